@@ -0,0 +1,153 @@
+//! Detection throughput across resolutions, face counts and input layouts.
+//!
+//! Uses the same `sample.jpg` as the unit tests: three faces staggered in distance, of
+//! which two are detected at its native resolution.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::{imageops::FilterType, DynamicImage, GenericImage, GenericImageView};
+use rusty_yunet::{
+    available_backends, DetectorConfig, FaceDetector, FrameBuffer, FrameLayout, ImageView,
+};
+
+fn load_sample() -> DynamicImage {
+    image::open("sample.jpg").expect("benchmarks run from the crate root")
+}
+
+fn bgr(image: &DynamicImage) -> (Vec<u8>, usize, usize) {
+    (
+        image.to_bgr8().into_raw(),
+        image.width() as usize,
+        image.height() as usize,
+    )
+}
+
+/// Tiles the sample `n` by `n` times, multiplying the number of detectable faces.
+fn mosaic(sample: &DynamicImage, n: u32) -> DynamicImage {
+    let (w, h) = sample.dimensions();
+    let mut mosaic = DynamicImage::new_rgb8(w * n, h * n);
+    for ty in 0..n {
+        for tx in 0..n {
+            mosaic.copy_from(sample, tx * w, ty * h).unwrap();
+        }
+    }
+    mosaic
+}
+
+fn resolutions(c: &mut Criterion) {
+    let sample = load_sample();
+    let mut group = c.benchmark_group("resolution");
+    group.sample_size(20);
+    for width in [320, 640, 806, 1280, 1920] {
+        let height = width * sample.height() / sample.width();
+        let (bytes, width, height) = bgr(&sample.resize_exact(width, height, FilterType::Triangle));
+        group.throughput(Throughput::Elements((width * height) as u64));
+        let mut detector = FaceDetector::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{width}x{height}")),
+            &bytes,
+            |b, bytes| b.iter(|| detector.detect(bytes, width, height).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn face_counts(c: &mut Criterion) {
+    let sample = load_sample();
+    let mut group = c.benchmark_group("face_count");
+    group.sample_size(20);
+    let blank = DynamicImage::new_rgb8(sample.width() * 2, sample.height() * 2);
+    for (name, image) in [("none", blank), ("mosaic_2x2", mosaic(&sample, 2))] {
+        let (bytes, width, height) = bgr(&image);
+        let mut detector = FaceDetector::new();
+        let faces = detector.detect(&bytes, width, height).unwrap().len();
+        group.bench_with_input(
+            BenchmarkId::new(name, format!("{faces}_faces")),
+            &bytes,
+            |b, bytes| b.iter(|| detector.detect(bytes, width, height).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn input_layouts(c: &mut Criterion) {
+    let (bytes, width, height) = bgr(&load_sample());
+    let mut group = c.benchmark_group("input_layout");
+    group.sample_size(20);
+    let mut detector = FaceDetector::new();
+
+    group.bench_function("packed_bgr", |b| {
+        b.iter(|| detector.detect(&bytes, width, height).unwrap())
+    });
+
+    let layout = FrameLayout {
+        width,
+        height,
+        stride: (3 * width).next_multiple_of(64),
+    };
+    let mut padded = vec![0u8; layout.stride * height];
+    for (src, dst) in bytes
+        .chunks(3 * width)
+        .zip(padded.chunks_mut(layout.stride))
+    {
+        dst[..src.len()].copy_from_slice(src);
+    }
+    let frame = unsafe { FrameBuffer::new(padded.as_ptr(), padded.len(), layout) }.unwrap();
+    group.bench_function("strided_frame_buffer", |b| {
+        b.iter(|| detector.detect_frame(&frame).unwrap())
+    });
+
+    let rgb = load_sample().to_rgb8().into_raw();
+    group.bench_function("rgb_converted_per_frame", |b| {
+        b.iter(|| {
+            let bgr: Vec<u8> = rgb.chunks(3).flat_map(|p| [p[2], p[1], p[0]]).collect();
+            detector.detect(&bgr, width, height).unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn downscaling(c: &mut Criterion) {
+    let (bytes, width, height) = bgr(&mosaic(&load_sample(), 3));
+    let image = ImageView::new(&bytes, width, height).unwrap();
+    let mut group = c.benchmark_group("max_side");
+    group.sample_size(10);
+    for (max_side, refine_landmarks) in [(None, false), (Some(1280), false), (Some(1280), true)] {
+        let mut config = DetectorConfig::default();
+        config.max_side = max_side;
+        config.refine_landmarks = refine_landmarks;
+        let mut detector = FaceDetector::with_config(config).unwrap();
+        let name = match max_side {
+            Some(side) if refine_landmarks => format!("{side}_refined"),
+            Some(side) => side.to_string(),
+            None => "native".to_owned(),
+        };
+        group.bench_function(name, |b| b.iter(|| detector.detect_image(&image).unwrap()));
+    }
+    group.finish();
+}
+
+fn backends(c: &mut Criterion) {
+    let (bytes, width, height) = bgr(&load_sample());
+    let mut group = c.benchmark_group("backend");
+    group.sample_size(10);
+    for (backend, target) in available_backends() {
+        let mut config = DetectorConfig::default();
+        config.backend = backend;
+        config.target = target;
+        let mut detector = FaceDetector::with_config(config).unwrap();
+        group.bench_function(format!("{backend:?}_{target:?}"), |b| {
+            b.iter(|| detector.detect(&bytes, width, height).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    resolutions,
+    face_counts,
+    input_layouts,
+    downscaling,
+    backends
+);
+criterion_main!(benches);
@@ -1,31 +1,229 @@
+const WEIGHTS_SOURCE: &str = "src/libfacedetection/facedetectcnn-data.cpp";
+
 fn main() {
-    let mut build = cxx_build::bridge("src/lib.rs");
+    #[cfg(feature = "libfacedetection")]
+    compile_libfacedetection();
+
+    #[cfg(feature = "native")]
+    convert_weights();
+
+    #[cfg(feature = "grpc")]
+    compile_protos();
+
+    #[cfg(feature = "proto")]
+    compile_detections();
+
+    hash_weights();
+    println!("cargo:rerun-if-changed={WEIGHTS_SOURCE}");
+}
+
+/// Exposes a 64-bit FNV-1a hash of the bundled weights as `YUNET_MODEL_HASH`, identifying
+/// the model in run manifests whichever backend runs it.
+fn hash_weights() {
+    let source = std::fs::read(WEIGHTS_SOURCE).expect("bundled weights are readable");
+    let hash = source.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    println!("cargo:rustc-env=YUNET_MODEL_HASH={hash:016x}");
+}
+
+#[cfg(feature = "libfacedetection")]
+fn compile_libfacedetection() {
+    let mut build = cxx_build::bridge("src/detector/network/libfacedetection.rs");
     let build = build
         .include("src/libfacedetection")
-        .file("src/libfacedetection/facedetectcnn-data.cpp")
+        .file(WEIGHTS_SOURCE)
         .file("src/libfacedetection/facedetectcnn-model.cpp")
         .file("src/libfacedetection/facedetectcnn.cpp")
         .file("src/bridge_wrapper.cpp")
         .flag_if_supported("-std=c++11")
         .flag("-O3");
 
+    // The SIMD extensions of the target, which `cfg!(target_feature)` here would give for the
+    // host the build script runs on instead, such as when cross-compiling for a Raspberry Pi.
+    let features = std::env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+    let has = |feature: &str| features.split(',').any(|f| f == feature);
+
     // AVX (advanced vector extensions) support
-    #[cfg(target_feature = "avx2")]
-    let build = build.flag("-mavx2").define("_ENABLE_AVX2", None);
+    if has("avx2") {
+        build.flag("-mavx2").define("_ENABLE_AVX2", None);
+    }
 
     // Fused multiply-add instruction support.
-    #[cfg(target_feature = "fma")]
-    let build = build.flag("-mfma");
+    if has("fma") {
+        build.flag("-mfma");
+    }
+
+    if has("neon") {
+        build.define("_ENABLE_NEON", None);
+        // 32-bit ARM compilers only emit NEON instructions when asked to; aarch64 always has
+        // them.
+        if std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("arm") {
+            build.flag("-mfpu=neon");
+        }
+    }
+    let simd = if has("neon") {
+        "neon"
+    } else if has("avx2") {
+        "avx2"
+    } else {
+        "none"
+    };
+    println!("cargo:rustc-env=YUNET_SIMD={simd}");
 
-    #[cfg(target_feature = "neon")]
-    let build = build.define("_ENABLE_NEON", None);
+    #[cfg(feature = "static-cxx")]
+    link_cxx_runtime_statically(build);
 
     build.compile("rusty-yunet");
 
-    println!("cargo:rerun-if-changed=src/libfacedetection/facedetectcnn-data.cpp");
     println!("cargo:rerun-if-changed=src/libfacedetection/facedetectcnn-model.cpp");
     println!("cargo:rerun-if-changed=src/libfacedetection/facedetectcnn.cpp");
     println!("cargo:rerun-if-changed=src/libfacedetection/facedetectcnn.h");
     println!("cargo:rerun-if-changed=src/bridge_wrapper.h");
     println!("cargo:rerun-if-changed=src/bridge_wrapper.cpp");
+
+    compile_fault_bridge();
+}
+
+/// Builds the bridge the tests throw exceptions through into a library of its own, without
+/// telling Cargo to link it, so that only the tests, which link it themselves, carry it.
+#[cfg(feature = "libfacedetection")]
+fn compile_fault_bridge() {
+    cxx_build::bridge("src/detector/network/libfacedetection/faults.rs")
+        .include("src/libfacedetection")
+        .file("src/bridge_faults.cpp")
+        .flag_if_supported("-std=c++11")
+        .cargo_metadata(false)
+        .compile("rusty-yunet-faults");
+    println!("cargo:rerun-if-changed=src/bridge_faults.h");
+    println!("cargo:rerun-if-changed=src/bridge_faults.cpp");
+}
+
+/// Links the archive of the C++ standard library instead of the shared library, so that
+/// binaries run on machines without the toolchain's C++ runtime, such as minimal containers
+/// or older distributions. libfacedetection needs nothing else: no OpenCV, no system
+/// libraries beyond libc.
+#[cfg(feature = "static-cxx")]
+fn link_cxx_runtime_statically(build: &mut cc::Build) {
+    let target = std::env::var("TARGET").unwrap_or_default();
+    if target.contains("msvc") {
+        // The MSVC runtime follows `-C target-feature=+crt-static` instead.
+        return;
+    }
+    let library = if target.contains("apple") || target.contains("freebsd") {
+        "c++"
+    } else {
+        "stdc++"
+    };
+    let archive = format!("lib{library}.a");
+    let output = build
+        .get_compiler()
+        .to_command()
+        .arg(format!("-print-file-name={archive}"))
+        .output()
+        .expect("the C++ compiler runs");
+    let path = std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    // Compilers print the bare name back when they have no such archive.
+    let Some(dir) = path
+        .parent()
+        .filter(|_| path.is_absolute() && path.exists())
+    else {
+        panic!("static-cxx: the C++ compiler has no {archive}; install its static runtime");
+    };
+    build.cpp_link_stdlib(None);
+    println!("cargo:rustc-link-search=native={}", dir.display());
+    println!("cargo:rustc-link-lib=static={library}");
+}
+
+/// Generates the messages and service of the gRPC interface, with a vendored `protoc` so
+/// that none needs installing.
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    const PROTO: &str = "proto/rusty_yunet.proto";
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc is vendored");
+    std::env::set_var("PROTOC", protoc);
+    tonic_build::compile_protos(PROTO).expect("protos compile");
+    println!("cargo:rerun-if-changed={PROTO}");
+}
+
+/// Generates the messages of the `proto` feature, with the same vendored `protoc`.
+#[cfg(feature = "proto")]
+fn compile_detections() {
+    const PROTO: &str = "proto/detections.proto";
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc is vendored");
+    std::env::set_var("PROTOC", protoc);
+    prost_build::compile_protos(&[PROTO], &["proto"]).expect("protos compile");
+    println!("cargo:rerun-if-changed={PROTO}");
+}
+
+/// Extracts the network parameters from libfacedetection's generated C++ source into the
+/// binary layout read by the native backend (see `src/detector/network/native.rs`), so that
+/// no C++ toolchain is needed to use them.
+#[cfg(feature = "native")]
+fn convert_weights() {
+    use std::collections::HashMap;
+
+    let source = std::fs::read_to_string(WEIGHTS_SOURCE).expect("bundled weights are readable");
+    let mut arrays: HashMap<&str, Vec<f32>> = HashMap::new();
+    let mut layers = Vec::new();
+    for statement in source.split(';') {
+        let (Some(open), Some(close)) = (statement.find('{'), statement.rfind('}')) else {
+            continue;
+        };
+        let body = &statement[open + 1..close];
+        if statement.contains("ConvInfoStruct") {
+            for entry in body.split('}') {
+                let fields: Vec<&str> = entry
+                    .trim_start_matches([',', '{', ' ', '\t', '\r', '\n'])
+                    .split(',')
+                    .map(str::trim)
+                    .collect();
+                if let [channels, filters, depthwise, _pointwise, relu, weights, biases] =
+                    fields[..]
+                {
+                    layers.push((channels, filters, depthwise, relu, weights, biases));
+                }
+            }
+        } else if let Some(start) = statement.find("float ") {
+            let declaration = &statement[start + "float ".len()..open];
+            let name = declaration[..declaration.find('[').expect("array declaration")].trim();
+            let values = body
+                .split(',')
+                .map(|value| {
+                    let value = value.trim().trim_end_matches('f');
+                    value
+                        .parse::<f32>()
+                        .unwrap_or_else(|_| panic!("invalid weight {value:?} in {name}"))
+                })
+                .collect();
+            arrays.insert(name, values);
+        }
+    }
+
+    let mut out = b"YNW1".to_vec();
+    out.extend((layers.len() as u32).to_le_bytes());
+    for (channels, filters, depthwise, relu, weights, biases) in layers {
+        let channels: u32 = channels.parse().expect("channel count");
+        let filters: u32 = filters.parse().expect("filter count");
+        let depthwise = depthwise == "true";
+        let weights = &arrays[weights];
+        let biases = &arrays[biases];
+        let expected = if depthwise {
+            9 * channels
+        } else {
+            filters * channels
+        };
+        assert_eq!(expected as usize, weights.len(), "weight count");
+        assert_eq!(filters as usize, biases.len(), "bias count");
+
+        out.extend(channels.to_le_bytes());
+        out.extend(filters.to_le_bytes());
+        out.push(u8::from(depthwise) | u8::from(relu == "true") << 1);
+        for value in weights.iter().chain(biases) {
+            out.extend(value.to_le_bytes());
+        }
+    }
+
+    let path = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("yunet.weights");
+    std::fs::write(path, out).expect("weights are writable");
 }
@@ -0,0 +1,25 @@
+//! Arbitrary geometry over arbitrary bytes, mostly invalid, through the checked entry point.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input<'a> {
+    width: usize,
+    height: usize,
+    stride: usize,
+    channels: usize,
+    bytes: &'a [u8],
+}
+
+fuzz_target!(|input: Input| {
+    let _ = rusty_yunet::detect_faces_checked(
+        input.bytes,
+        input.width,
+        input.height,
+        input.stride,
+        input.channels,
+    );
+});
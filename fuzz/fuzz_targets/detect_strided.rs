@@ -0,0 +1,25 @@
+//! Small, mostly valid images with odd sizes and unaligned strides, so that the network runs.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input<'a> {
+    width: u8,
+    height: u8,
+    padding: u8,
+    gray: bool,
+    bytes: &'a [u8],
+}
+
+fuzz_target!(|input: Input| {
+    let channels = if input.gray { 1 } else { 3 };
+    let (width, height) = (input.width as usize, input.height as usize);
+    let stride = width * channels + input.padding as usize;
+    let mut bytes = input.bytes.to_vec();
+    bytes.resize(stride * height, 0);
+    let result = rusty_yunet::detect_faces_checked(&bytes, width, height, stride, channels);
+    assert_eq!(width == 0 || height == 0, result.is_err());
+});
@@ -0,0 +1,87 @@
+#ifndef RUSTY_YUNET_H
+#define RUSTY_YUNET_H
+
+/* Generated by cbindgen from src/capi.rs. Don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum YuNetStatus {
+  YU_NET_STATUS_OK = 0,
+  /**
+   * A required pointer was null.
+   */
+  YU_NET_STATUS_NULL_POINTER = 1,
+  /**
+   * The image buffer doesn't match its dimensions, or the image is too large.
+   */
+  YU_NET_STATUS_INVALID_IMAGE = 2,
+  YU_NET_STATUS_DETECTION_FAILED = 3,
+  YU_NET_STATUS_PANIC = 4,
+} YuNetStatus;
+
+/**
+ * Opaque handle to a detector, created by [`yunet_detector_new`].
+ */
+typedef struct YuNetDetector YuNetDetector;
+
+/**
+ * A detected face, in pixel coordinates of the input image.
+ */
+typedef struct YuNetFace {
+  float confidence;
+  float x;
+  float y;
+  float width;
+  float height;
+  /**
+   * Right eye, left eye, nose, right and left mouth corners, as x, y pairs.
+   */
+  float landmarks[10];
+} YuNetFace;
+
+/**
+ * Creates a detector with the default configuration, or returns null if that panicked.
+ * Release it with [`yunet_detector_free`].
+ */
+struct YuNetDetector *yunet_detector_new(void);
+
+/**
+ * # Safety
+ *
+ * `detector` must be null or a pointer returned by [`yunet_detector_new`] that wasn't
+ * freed yet.
+ */
+void yunet_detector_free(struct YuNetDetector *detector);
+
+/**
+ * Detects faces in a BGR image whose rows start `stride` bytes apart. On success, stores an
+ * array of `*count` faces in `*faces`, to be released with [`yunet_faces_free`]; otherwise
+ * stores null and zero.
+ *
+ * # Safety
+ *
+ * `detector` must come from [`yunet_detector_new`] and not be used by another thread during
+ * the call. `bgr` must be valid for reads of `stride * (height - 1) + 3 * width` bytes, and
+ * `faces` and `count` for writes.
+ */
+YuNetStatus yunet_detect(struct YuNetDetector *detector,
+                         const uint8_t *bgr,
+                         size_t width,
+                         size_t height,
+                         size_t stride,
+                         struct YuNetFace **faces,
+                         size_t *count);
+
+/**
+ * # Safety
+ *
+ * `faces` and `count` must be null and zero, or as stored by one call to [`yunet_detect`]
+ * that wasn't freed yet.
+ */
+void yunet_faces_free(struct YuNetFace *faces, size_t count);
+
+#endif /* RUSTY_YUNET_H */
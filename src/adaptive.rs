@@ -0,0 +1,263 @@
+//! Keeping up with a camera on hardware too slow to detect every frame at full resolution,
+//! such as a single-board computer, by skipping frames and then lowering the resolution
+//! detection runs at, as detection latency demands.
+//!
+//! On skipped frames, the faces of the last detection are moved along their tracks by the
+//! velocity of each, so that overlays and sinks still get a face per tracked person every
+//! frame.
+
+use std::time::Duration;
+
+use crate::pipeline::FrameResult;
+use crate::tracking::{Tracker, TrackerConfig};
+use crate::{Face, FaceDetector, ImageView, YuNetError};
+
+/// Tuning knobs for an [`AdaptiveScheduler`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct AdaptiveConfig {
+    /// The frame rate frames arrive at and must be processed at.
+    pub target_fps: f32,
+    /// The most frames skipped in a row, bounding how far predicted faces may drift. Only
+    /// once detection can't keep up skipping this many is the resolution lowered.
+    pub max_skip: u32,
+    /// The lowest resolution, as the longer side of the frame, detection is lowered to.
+    pub min_side: usize,
+    /// Weight (0..1) of the latest detection in the running average of detection latency.
+    pub smoothing: f64,
+    pub tracker: TrackerConfig,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            target_fps: 15.0,
+            max_skip: 3,
+            min_side: 160,
+            smoothing: 0.3,
+            tracker: TrackerConfig::default(),
+        }
+    }
+}
+
+/// A frame processed by an [`AdaptiveScheduler`].
+#[derive(Debug, Clone)]
+pub struct AdaptiveFrame {
+    pub result: FrameResult,
+    /// Whether faces were detected in this frame, rather than predicted from earlier ones.
+    pub detected: bool,
+}
+
+/// Detects and tracks faces over consecutive frames, skipping detection on as many frames
+/// and at as low a resolution as needed to keep up with [`AdaptiveConfig::target_fps`].
+pub struct AdaptiveScheduler {
+    detector: FaceDetector,
+    config: AdaptiveConfig,
+    tracker: Tracker,
+    /// The detector's own `max_side`, which detection never exceeds.
+    full_side: Option<usize>,
+    /// The longer side detection currently runs at, if lowered.
+    side: Option<usize>,
+    /// Running average of detection latency in seconds, at the current resolution.
+    latency: Option<f64>,
+    skipped: u32,
+    frame: u64,
+}
+
+impl AdaptiveScheduler {
+    pub fn new(detector: FaceDetector, config: AdaptiveConfig) -> Self {
+        Self {
+            full_side: detector.config().max_side,
+            tracker: Tracker::new(config.tracker.clone()),
+            detector,
+            config,
+            side: None,
+            latency: None,
+            skipped: 0,
+            frame: 0,
+        }
+    }
+
+    /// Processes the next frame, presented at `timestamp`, which must not decrease from one
+    /// call to the next.
+    pub fn process(
+        &mut self,
+        frame: &ImageView,
+        timestamp: Duration,
+    ) -> Result<AdaptiveFrame, YuNetError> {
+        let index = self.frame;
+        self.frame += 1;
+        if self.tracker.frame() > 0 && self.skipped < self.skip_allowance() {
+            self.skipped += 1;
+            let (track_ids, faces) = self.predict(timestamp);
+            return Ok(AdaptiveFrame {
+                result: FrameResult {
+                    index,
+                    timestamp: Some(timestamp),
+                    faces,
+                    track_ids,
+                },
+                detected: false,
+            });
+        }
+        self.skipped = 0;
+
+        let frame_side = frame.width().max(frame.height());
+        let full = self
+            .full_side
+            .map_or(frame_side, |side| side.min(frame_side));
+        let side = self.side.map_or(full, |side| side.min(full));
+        let mut config = self.detector.config().clone();
+        config.max_side = if side < full {
+            Some(side)
+        } else {
+            self.full_side
+        };
+        if config != *self.detector.config() {
+            self.detector.set_config(config)?;
+        }
+        let (faces, stats) = self.detector.detect_image_with_stats(frame)?;
+        let seconds = stats.total_ms() / 1000.0;
+        let latency = match self.latency {
+            Some(average) => average + (seconds - average) * self.config.smoothing,
+            None => seconds,
+        };
+        self.latency = Some(latency);
+        self.adapt_resolution(side, full, latency);
+
+        let track_ids = self.tracker.update_at(&faces, Some(timestamp));
+        Ok(AdaptiveFrame {
+            result: FrameResult {
+                index,
+                timestamp: Some(timestamp),
+                faces,
+                track_ids,
+            },
+            detected: true,
+        })
+    }
+
+    /// How many frames are skipped after each detection at the current latency.
+    pub fn skip_allowance(&self) -> u32 {
+        let budget = 1.0 / self.config.target_fps as f64;
+        match self.latency {
+            Some(latency) => ((latency / budget).ceil() as u32)
+                .saturating_sub(1)
+                .min(self.config.max_skip),
+            None => 0,
+        }
+    }
+
+    /// The longer side detection currently runs at, if lowered from the detector's own
+    /// [`max_side`](crate::DetectorConfig::max_side).
+    pub fn max_side(&self) -> Option<usize> {
+        self.side
+    }
+
+    /// The running average of detection latency at the current resolution.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency.map(Duration::from_secs_f64)
+    }
+
+    pub fn detector(&self) -> &FaceDetector {
+        &self.detector
+    }
+
+    pub fn tracker(&self) -> &Tracker {
+        &self.tracker
+    }
+
+    /// Lowers the resolution if detection can't keep up even skipping the most frames, and
+    /// raises it back once it keeps up with time to spare. The latency average is scaled
+    /// with the pixel count, so that it doesn't take several detections to catch up.
+    fn adapt_resolution(&mut self, side: usize, full: usize, latency: f64) {
+        let budget = 1.0 / self.config.target_fps as f64;
+        let capacity = budget * (self.config.max_skip + 1) as f64;
+        let new_side = if latency > capacity && side > self.config.min_side {
+            (side * 4 / 5).max(self.config.min_side)
+        } else if latency * 2.0 < capacity && side < full {
+            (side * 5 / 4).min(full)
+        } else {
+            return;
+        };
+        let scale = new_side as f64 / side as f64;
+        self.latency = Some(latency * scale * scale);
+        self.side = (new_side < full).then_some(new_side);
+    }
+
+    /// The faces of the tracks seen in the last detection, moved by their velocity to
+    /// `timestamp`.
+    fn predict(&self, timestamp: Duration) -> (Vec<u64>, Vec<Face>) {
+        let last = self.tracker.frame() - 1;
+        self.tracker
+            .tracks()
+            .iter()
+            .filter(|track| track.last_frame() == last)
+            .map(|track| {
+                let face = track.face();
+                let elapsed = self
+                    .tracker
+                    .timestamp()
+                    .map_or(0.0, |seen| timestamp.saturating_sub(seen).as_secs_f32());
+                let offset = track.velocity().unwrap_or_default() * elapsed;
+                let face = face.mapped(|p| p + offset, face.detection_dimensions());
+                (track.id(), face)
+            })
+            .unzip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_frames_then_lowers_resolution() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let frame = ImageView::new(image.as_raw(), 806, 605).unwrap();
+
+        // Keeping up, every frame is detected at full resolution.
+        let mut relaxed = AdaptiveScheduler::new(
+            FaceDetector::new(),
+            AdaptiveConfig {
+                target_fps: 0.1,
+                ..Default::default()
+            },
+        );
+        for second in 0..3 {
+            let frame = relaxed
+                .process(&frame, Duration::from_secs(second))
+                .unwrap();
+            assert!(frame.detected);
+            assert_eq!(2, frame.result.faces.len());
+        }
+        assert_eq!(None, relaxed.max_side());
+
+        // Falling behind, frames are skipped with the last faces carried along their tracks,
+        // and the resolution lowered.
+        let mut strained = AdaptiveScheduler::new(
+            FaceDetector::new(),
+            AdaptiveConfig {
+                target_fps: 1000.0,
+                max_skip: 2,
+                min_side: 320,
+                ..Default::default()
+            },
+        );
+        let frames: Vec<AdaptiveFrame> = (0..7)
+            .map(|ms| strained.process(&frame, Duration::from_millis(ms)).unwrap())
+            .collect();
+        assert_eq!(
+            vec![true, false, false, true, false, false, true],
+            frames.iter().map(|f| f.detected).collect::<Vec<_>>()
+        );
+        assert_eq!(frames[0].result.track_ids, frames[1].result.track_ids);
+        assert_eq!(
+            frames[0].result.faces[0].rectangle(),
+            frames[2].result.faces[0].rectangle()
+        );
+        assert_eq!(6, frames[6].result.index);
+        assert!(strained.max_side().unwrap() < 806);
+        assert_eq!(2, strained.skip_allowance());
+    }
+}
@@ -0,0 +1,198 @@
+//! Hour-of-day and day-of-week occupancy profiles, the usual report for signage and retail
+//! installations. Export them as CSV, or as JSON with the `serde_support` feature.
+
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Occupancy aggregated over one hour of the day or one day of the week.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OccupancyBucket {
+    /// Frames recorded.
+    pub samples: u64,
+    /// Faces summed over those frames.
+    pub faces: u64,
+    /// The most faces in a single frame.
+    pub peak_faces: u64,
+    /// New visitors, such as tracks started.
+    pub arrivals: u64,
+}
+
+impl OccupancyBucket {
+    /// Average faces per frame.
+    pub fn mean_faces(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.faces as f64 / self.samples as f64
+        }
+    }
+}
+
+/// Face counts and arrivals rolled up by local hour of the day and day of the week.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccupancyProfile {
+    utc_offset_secs: i64,
+    /// Indexed by hour, from midnight.
+    hours: [OccupancyBucket; 24],
+    /// Indexed by day, from Monday.
+    weekdays: [OccupancyBucket; 7],
+}
+
+impl OccupancyProfile {
+    /// A profile in local time `utc_offset_secs` ahead of UTC, such as 3600 for CET.
+    pub fn new(utc_offset_secs: i64) -> Self {
+        Self {
+            utc_offset_secs,
+            hours: [OccupancyBucket::default(); 24],
+            weekdays: [OccupancyBucket::default(); 7],
+        }
+    }
+
+    /// Records the number of faces in a frame captured at `at`.
+    pub fn record(&mut self, at: SystemTime, faces: usize) {
+        self.record_unix(unix_secs(at), faces);
+    }
+
+    /// Like [`record`](Self::record), with the capture time in seconds since the Unix epoch.
+    pub fn record_unix(&mut self, unix_secs: i64, faces: usize) {
+        let faces = faces as u64;
+        for bucket in self.buckets(unix_secs) {
+            bucket.samples += 1;
+            bucket.faces += faces;
+            bucket.peak_faces = bucket.peak_faces.max(faces);
+        }
+    }
+
+    /// Records a visitor arriving at `at`, such as a new track.
+    pub fn record_arrival(&mut self, at: SystemTime) {
+        self.record_arrival_unix(unix_secs(at));
+    }
+
+    /// Like [`record_arrival`](Self::record_arrival), in seconds since the Unix epoch.
+    pub fn record_arrival_unix(&mut self, unix_secs: i64) {
+        for bucket in self.buckets(unix_secs) {
+            bucket.arrivals += 1;
+        }
+    }
+
+    pub fn hours(&self) -> &[OccupancyBucket; 24] {
+        &self.hours
+    }
+
+    /// From Monday to Sunday.
+    pub fn weekdays(&self) -> &[OccupancyBucket; 7] {
+        &self.weekdays
+    }
+
+    /// One row per hour, then per weekday, under the header
+    /// `period,bucket,samples,mean_faces,peak_faces,arrivals`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("period,bucket,samples,mean_faces,peak_faces,arrivals\n");
+        let hours = self
+            .hours
+            .iter()
+            .enumerate()
+            .map(|(hour, b)| ("hour", format!("{hour:02}"), b));
+        let days = WEEKDAYS
+            .iter()
+            .zip(&self.weekdays)
+            .map(|(day, b)| ("weekday", day.to_string(), b));
+        for (period, bucket, b) in hours.chain(days) {
+            let _ = writeln!(
+                csv,
+                "{period},{bucket},{},{:.3},{},{}",
+                b.samples,
+                b.mean_faces(),
+                b.peak_faces,
+                b.arrivals
+            );
+        }
+        csv
+    }
+
+    fn buckets(&mut self, unix_secs: i64) -> [&mut OccupancyBucket; 2] {
+        let local = unix_secs + self.utc_offset_secs;
+        let days = local.div_euclid(86_400);
+        let hour = local.rem_euclid(86_400) / 3600;
+        // The epoch was a Thursday.
+        let weekday = (days + 3).rem_euclid(7);
+        [
+            &mut self.hours[hour as usize],
+            &mut self.weekdays[weekday as usize],
+        ]
+    }
+}
+
+impl Default for OccupancyProfile {
+    /// A profile in UTC.
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+fn unix_secs(at: SystemTime) -> i64 {
+    match at.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs_f64().ceil() as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_up_by_hour_and_weekday() {
+        // Monday 2024-01-01 09:30 UTC, and the Sunday before at 23:59 UTC.
+        let monday = 1_704_101_400;
+        let sunday = 1_704_067_140;
+        let mut profile = OccupancyProfile::default();
+        profile.record_unix(monday, 2);
+        profile.record_unix(monday + 60, 4);
+        profile.record_arrival_unix(monday);
+        profile.record_unix(sunday, 1);
+
+        let nine = profile.hours()[9];
+        assert_eq!(
+            (2, 3.0, 4, 1),
+            (
+                nine.samples,
+                nine.mean_faces(),
+                nine.peak_faces,
+                nine.arrivals
+            )
+        );
+        assert_eq!(1, profile.hours()[23].samples);
+        assert_eq!(2, profile.weekdays()[0].samples);
+        assert_eq!(1, profile.weekdays()[6].samples);
+
+        // An hour ahead, Sunday's midnight minute falls on Monday.
+        let mut local = OccupancyProfile::new(3600);
+        local.record(
+            UNIX_EPOCH + std::time::Duration::from_secs(sunday as u64),
+            1,
+        );
+        assert_eq!(1, local.weekdays()[0].samples);
+        assert_eq!(1, local.hours()[0].samples);
+
+        let csv = profile.to_csv();
+        assert_eq!(1 + 24 + 7, csv.lines().count());
+        assert!(csv.contains("hour,09,2,3.000,4,1\n"));
+        assert!(csv.ends_with("weekday,Sunday,1,1.000,1,0\n"));
+    }
+}
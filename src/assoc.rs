@@ -0,0 +1,17 @@
+//! Pairing up two sets of boxes one to one by how much they overlap, as the
+//! [`Tracker`](crate::Tracker) pairs faces with tracks, such as to fuse faces with the
+//! detections of a person or body detector.
+//!
+//! ```
+//! # use rusty_yunet::assoc::{associate, associate_by};
+//! # use rusty_yunet::Rect;
+//! let faces = [Rect::with_size(110.0, 40.0, 40.0, 50.0)];
+//! let people = [Rect::with_size(400.0, 20.0, 90.0, 300.0), Rect::with_size(90.0, 30.0, 80.0, 280.0)];
+//! // Faces barely overlap the bodies they belong to, so score by how much of the face each
+//! // body covers rather than by IoU.
+//! let pairs = associate_by(&faces, &people, 0.8, |face, body| face.intersection(body) / face.area());
+//! assert_eq!(vec![(0, 1)], pairs);
+//! assert!(associate(&faces, &people, 0.3).is_empty());
+//! ```
+
+pub use rusty_yunet_types::assoc::{associate, associate_by, greedy_matches};
@@ -0,0 +1,193 @@
+//! A Bevy plugin running detection and tracking on a background thread, so that frames
+//! submitted by the app come back as [`FaceDetected`] events without stalling the schedule.
+//!
+//! Frames are submitted as BGR bytes, from whatever captures them: a camera crate, a video
+//! decoder, or pixels read back from a render target.
+
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+
+use bevy_app::{App, Plugin, PreUpdate};
+use bevy_ecs::prelude::*;
+
+use crate::pipeline::{FrameResult, Pipeline};
+use crate::tracking::TrackerConfig;
+use crate::{DetectorConfig, Face, FaceDetector, ImageView, YuNetError};
+
+/// Adds the [`FaceDetection`] resource and the [`FaceDetected`] and
+/// [`FaceDetectionFailed`] events.
+#[derive(Debug, Clone, Default)]
+pub struct FaceDetectionPlugin {
+    pub detector: DetectorConfig,
+    pub tracker: TrackerConfig,
+}
+
+impl Plugin for FaceDetectionPlugin {
+    fn build(&self, app: &mut App) {
+        let detector = FaceDetector::with_config(self.detector.clone())
+            .expect("detector backend is available in this build");
+        app.insert_resource(FaceDetection::spawn(Pipeline::new(
+            detector,
+            self.tracker.clone(),
+        )))
+        .add_event::<FaceDetected>()
+        .add_event::<FaceDetectionFailed>()
+        .add_systems(PreUpdate, publish_results);
+    }
+}
+
+/// One face found in a submitted frame, sent in [`PreUpdate`] of the first update after
+/// detection finished.
+#[derive(Event, Debug, Clone)]
+pub struct FaceDetected {
+    /// Index of the frame among those accepted by [`FaceDetection::submit`].
+    pub frame: u64,
+    pub track_id: u64,
+    pub face: Face,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct FaceDetectionFailed {
+    pub error: YuNetError,
+}
+
+struct Frame {
+    bgr: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+/// The handle to the detection thread, which stops once this is dropped.
+#[derive(Resource)]
+pub struct FaceDetection {
+    frames: SyncSender<Frame>,
+    results: Mutex<Receiver<Result<FrameResult, YuNetError>>>,
+    latest: Option<FrameResult>,
+    dropped: u64,
+}
+
+impl FaceDetection {
+    fn spawn(mut pipeline: Pipeline) -> Self {
+        // A single slot: frames arriving while one waits are dropped rather than queued,
+        // so that results never lag further behind than one detection.
+        let (frames, frame_receiver) = mpsc::sync_channel::<Frame>(1);
+        let (result_sender, results) = mpsc::channel();
+        thread::Builder::new()
+            .name("rusty-yunet".to_owned())
+            .spawn(move || {
+                for frame in frame_receiver {
+                    let result = ImageView::new(&frame.bgr, frame.width, frame.height)
+                        .and_then(|view| pipeline.process(&view));
+                    if result_sender.send(result).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn the detection thread");
+        Self {
+            frames,
+            results: Mutex::new(results),
+            latest: None,
+            dropped: 0,
+        }
+    }
+
+    /// Queues a tightly packed BGR frame for detection. Returns false, dropping the frame,
+    /// if the thread is still busy with earlier ones.
+    pub fn submit(&mut self, bgr: Vec<u8>, width: usize, height: usize) -> bool {
+        match self.frames.try_send(Frame { bgr, width, height }) {
+            Ok(()) => true,
+            Err(_) => {
+                self.dropped += 1;
+                false
+            }
+        }
+    }
+
+    /// The most recent frame detected in.
+    pub fn latest(&self) -> Option<&FrameResult> {
+        self.latest.as_ref()
+    }
+
+    /// Frames [`submit`](Self::submit) dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+fn publish_results(
+    mut detection: ResMut<FaceDetection>,
+    mut detected: EventWriter<FaceDetected>,
+    mut failed: EventWriter<FaceDetectionFailed>,
+) {
+    let results: Vec<_> = detection
+        .results
+        .get_mut()
+        .expect("detection results aren't poisoned")
+        .try_iter()
+        .collect();
+    for result in results {
+        match result {
+            Ok(result) => {
+                detected.send_batch(result.faces.iter().zip(&result.track_ids).map(
+                    |(face, &track_id)| FaceDetected {
+                        frame: result.index,
+                        track_id,
+                        face: face.clone(),
+                    },
+                ));
+                detection.latest = Some(result);
+            }
+            Err(error) => {
+                failed.send(FaceDetectionFailed { error });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy_ecs::event::Events;
+
+    use super::*;
+
+    #[test]
+    fn publishes_faces_from_submitted_frames() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut app = App::new();
+        app.add_plugins(FaceDetectionPlugin::default());
+        let submit = |app: &mut App, bgr| {
+            let mut detection = app.world_mut().resource_mut::<FaceDetection>();
+            assert!(detection.submit(bgr, width, height));
+        };
+        let (mut faces, mut errors) = (Vec::new(), 0);
+        let mut update = |app: &mut App| {
+            thread::sleep(Duration::from_millis(10));
+            app.update();
+            let world = app.world();
+            let detected = world.resource::<Events<FaceDetected>>();
+            faces.extend(detected.iter_current_update_events().cloned());
+            let failed = world.resource::<Events<FaceDetectionFailed>>();
+            errors += failed.iter_current_update_events().count();
+            (faces.len(), errors)
+        };
+
+        let started = Instant::now();
+        submit(&mut app, image.into_raw());
+        while update(&mut app).0 == 0 {
+            assert!(started.elapsed() < Duration::from_secs(30));
+        }
+        submit(&mut app, vec![0; 3]);
+        while update(&mut app).1 == 0 {
+            assert!(started.elapsed() < Duration::from_secs(30));
+        }
+        assert_eq!(2, faces.len());
+        assert_eq!(0, faces[0].frame);
+        let detection = app.world().resource::<FaceDetection>();
+        assert_eq!(2, detection.latest().unwrap().faces.len());
+    }
+}
@@ -0,0 +1,11 @@
+#include "bridge_faults.h"
+#include "rusty-yunet/src/detector/network/libfacedetection/faults.rs.h"
+
+#include <stdexcept>
+
+void throw_fault(bool standard) {
+    if (standard) {
+        throw std::runtime_error("injected fault");
+    }
+    throw uint8_t(2);
+}
@@ -0,0 +1,10 @@
+#pragma once
+
+// Only for tests, and built into a library of its own that only they link: throws through
+// a bridge with the same exception handling as the detector's, so that turning exceptions
+// into errors can be tested without a hook in the detector itself.
+#include "bridge_wrapper.h"
+
+#include <cstdint>
+
+void throw_fault(bool standard);
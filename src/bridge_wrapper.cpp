@@ -1,22 +1,101 @@
 #include "bridge_wrapper.h"
+#include "rusty-yunet/src/detector/network/libfacedetection.rs.h"
 
-rust::Vec<BridgeFace> wrapper_detect_faces(const unsigned char* rgbImageData, int width, int height, int step) {
+// Counted so that long-running processes can check that handles, and the filters they
+// own, are released.
+static std::atomic<size_t> created_handles{0};
+static std::atomic<size_t> released_handles{0};
+
+FaceDetectorHandle::FaceDetectorHandle() {
+    init_parameters(filters);
+    created_handles++;
+}
+
+FaceDetectorHandle::~FaceDetectorHandle() {
+    released_handles++;
+}
+
+static BridgeFace to_bridge(const FaceRect& f) {
+    BridgeFace bridge_face = BridgeFace {
+        .score = f.score,
+        .x = f.x,
+        .y = f.y,
+        .w = f.w,
+        .h = f.h,
+        .lm = {}
+    };
+
+    std::copy(std::begin(f.lm), std::end(f.lm), bridge_face.lm.begin());
+    return bridge_face;
+}
+
+rust::Vec<BridgeFace> FaceDetectorHandle::detect(const unsigned char* rgbImageData, int width, int height, int step, int channels) const {
+    return run(rgbImageData, width, height, step, channels, true);
+}
+
+size_t FaceDetectorHandle::detect_into(const unsigned char* rgbImageData, int width, int height, int step, int channels, rust::Slice<BridgeFace> faces) const {
+    std::vector<FaceRect> found = objectdetect_cnn(filters, rgbImageData, width, height, step, true, channels);
+    size_t count = std::min(found.size(), faces.size());
+    for (size_t i = 0; i < count; i++) {
+        faces[i] = to_bridge(found[i]);
+    }
+
+    return found.size();
+}
+
+rust::Vec<BridgeFace> FaceDetectorHandle::detect_candidates(const unsigned char* rgbImageData, int width, int height, int step, int channels) const {
+    return run(rgbImageData, width, height, step, channels, false);
+}
+
+static rust::Vec<float> to_rust(const CDataBlob<float>& vector) {
+    rust::Vec<float> values;
+    values.reserve(vector.channels);
+    const float* data = vector.ptr(0, 0);
+    for (int i = 0; i < vector.channels; i++) {
+        values.push_back(data[i]);
+    }
+    return values;
+}
+
+rust::Vec<BridgeFace> FaceDetectorHandle::detect_raw(const unsigned char* rgbImageData, int width, int height, int step, int channels, bool suppress, rust::Vec<BridgeLevel>& levels) const {
+    HeadOutputs heads[3];
+    rust::Vec<BridgeFace> faces = run(rgbImageData, width, height, step, channels, suppress, heads);
+
+    levels.clear();
+    for (const HeadOutputs& head: heads) {
+        levels.push_back(BridgeLevel {
+            .stride = head.stride,
+            .rows = head.rows,
+            .cols = head.cols,
+            .cls = to_rust(head.cls),
+            .obj = to_rust(head.obj),
+            .reg = to_rust(head.reg),
+            .kps = to_rust(head.kps),
+        });
+    }
+
+    return faces;
+}
+
+rust::Vec<BridgeFace> FaceDetectorHandle::run(const unsigned char* rgbImageData, int width, int height, int step, int channels, bool suppress, HeadOutputs* heads) const {
     rust::Vec<BridgeFace> rust_faces;
-    std::vector<FaceRect> faces = objectdetect_cnn(rgbImageData, width, height, step); 
-
-    for (FaceRect f: faces) {
-        BridgeFace bridge_face = BridgeFace {
-            .score = f.score,
-            .x = f.x,
-            .y = f.y,
-            .w = f.w,
-            .h = f.h,
-            .lm = {}
-        };
-
-        std::copy(std::begin(f.lm), std::end(f.lm), bridge_face.lm.begin());
-        rust_faces.push_back(bridge_face);
+    std::vector<FaceRect> faces = objectdetect_cnn(filters, rgbImageData, width, height, step, suppress, channels, heads);
+
+    for (const FaceRect& f: faces) {
+        rust_faces.push_back(to_bridge(f));
     }
 
     return rust_faces;
 }
+
+std::unique_ptr<FaceDetectorHandle> new_face_detector() {
+    return std::unique_ptr<FaceDetectorHandle>(new FaceDetectorHandle());
+}
+
+size_t face_detectors_created() {
+    return created_handles;
+}
+
+size_t face_detectors_released() {
+    return released_handles;
+}
@@ -1,9 +1,50 @@
 #pragma once
 
 #include "rusty-yunet/src/libfacedetection/facedetectcnn.h"
-#include "rusty-yunet/src/lib.rs.h"
 #include "rust/cxx.h"
 
+#include <atomic>
+#include <exception>
+#include <memory>
 #include <vector>
 
-rust::Vec<BridgeFace> wrapper_detect_faces(const unsigned char* rgbImageData, int width, int height, int step);
+// Turns every exception thrown by a bridge function declared to return a Result into an
+// error on the Rust side, rather than only those derived from std::exception: none may
+// unwind into Rust.
+namespace rust {
+namespace behavior {
+template <typename Try, typename Fail>
+static void trycatch(Try &&func, Fail &&fail) noexcept try {
+    func();
+} catch (const std::exception &e) {
+    fail(e.what());
+} catch (...) {
+    fail("unknown C++ exception");
+}
+} // namespace behavior
+} // namespace rust
+
+struct BridgeFace;
+struct BridgeLevel;
+
+// Owns its own copy of the network parameters, so that separate instances never
+// share mutable state and may be driven from separate threads.
+class FaceDetectorHandle {
+public:
+    FaceDetectorHandle();
+    ~FaceDetectorHandle();
+
+    rust::Vec<BridgeFace> detect(const unsigned char* rgbImageData, int width, int height, int step, int channels) const;
+    size_t detect_into(const unsigned char* rgbImageData, int width, int height, int step, int channels, rust::Slice<BridgeFace> faces) const;
+    rust::Vec<BridgeFace> detect_candidates(const unsigned char* rgbImageData, int width, int height, int step, int channels) const;
+    rust::Vec<BridgeFace> detect_raw(const unsigned char* rgbImageData, int width, int height, int step, int channels, bool suppress, rust::Vec<BridgeLevel>& levels) const;
+
+private:
+    rust::Vec<BridgeFace> run(const unsigned char* rgbImageData, int width, int height, int step, int channels, bool suppress, HeadOutputs* heads = nullptr) const;
+
+    Filters<float> filters[NUM_CONV_LAYER];
+};
+
+std::unique_ptr<FaceDetectorHandle> new_face_detector();
+size_t face_detectors_created();
+size_t face_detectors_released();
@@ -0,0 +1,177 @@
+//! Broadcasting faces as compact JSON over UDP, one datagram per frame, for creative-coding
+//! environments such as TouchDesigner, Unity, openFrameworks or p5.js on the same network.
+//!
+//! Each datagram holds one object:
+//!
+//! ```json
+//! {"frame":12,"faces":[{"id":3,"confidence":0.912,"box":[0.41,0.22,0.13,0.17],"landmarks":[...]}]}
+//! ```
+//!
+//! with the box as x, y, width and height and the landmarks as x, y pairs in the order of
+//! [`FaceLandmarks`], normalized to 0..1 of the frame, y down, unless the broadcaster is
+//! given another convention with [`with_convention`](JsonBroadcaster::with_convention).
+//!
+//! [`FaceLandmarks`]: crate::FaceLandmarks
+
+use std::fmt::Write;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::pipeline::FrameResult;
+use crate::{CoordinateConvention, Face, Origin, Units};
+
+/// A UDP socket sending each frame's faces as JSON, at most at a given rate.
+#[derive(Debug)]
+pub struct JsonBroadcaster {
+    socket: UdpSocket,
+    target: SocketAddr,
+    min_interval: Duration,
+    last_sent: Option<Instant>,
+    convention: CoordinateConvention,
+    buffer: String,
+}
+
+impl JsonBroadcaster {
+    /// Sends to `target`, which may be a broadcast address such as `"255.255.255.255:9000"`
+    /// or `"192.168.1.255:9000"` to reach every host on the LAN.
+    pub fn new(target: impl ToSocketAddrs) -> io::Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send to"))?;
+        let any = match target {
+            SocketAddr::V4(_) => "0.0.0.0:0",
+            SocketAddr::V6(_) => "[::]:0",
+        };
+        let socket = UdpSocket::bind(any)?;
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            target,
+            min_interval: Duration::ZERO,
+            last_sent: None,
+            convention: CoordinateConvention::new(Origin::TopLeft, Units::Normalized),
+            buffer: String::new(),
+        })
+    }
+
+    /// Sends at most `rate` frames per second, skipping the frames in between. Receivers
+    /// polling at their own frame rate rarely need more than it.
+    pub fn with_max_rate(mut self, rate: f32) -> Self {
+        self.min_interval = Duration::from_secs_f32(1.0 / rate.max(f32::MIN_POSITIVE));
+        self
+    }
+
+    /// Sends rectangles and landmarks in `convention`, such as in pixels, rather than
+    /// normalized from the top-left corner.
+    pub fn with_convention(mut self, convention: CoordinateConvention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Sends the faces of a pipeline frame with their track IDs, unless one was sent too
+    /// recently. Returns whether it was sent.
+    pub fn send(&mut self, result: &FrameResult) -> io::Result<bool> {
+        self.send_frame(
+            result.index,
+            &result.faces,
+            result.track_ids.iter().copied(),
+        )
+    }
+
+    /// Like [`send`](Self::send), for faces without tracking, with their positions in `faces`
+    /// as IDs.
+    pub fn send_faces(&mut self, frame: u64, faces: &[Face]) -> io::Result<bool> {
+        self.send_frame(frame, faces, 0..)
+    }
+
+    fn send_frame(
+        &mut self,
+        frame: u64,
+        faces: &[Face],
+        ids: impl Iterator<Item = u64>,
+    ) -> io::Result<bool> {
+        let now = Instant::now();
+        if self
+            .last_sent
+            .is_some_and(|last| now.duration_since(last) < self.min_interval)
+        {
+            return Ok(false);
+        }
+        to_json(&mut self.buffer, frame, faces, ids, self.convention);
+        self.socket.send_to(self.buffer.as_bytes(), self.target)?;
+        self.last_sent = Some(now);
+        Ok(true)
+    }
+}
+
+fn to_json(
+    json: &mut String,
+    frame: u64,
+    faces: &[Face],
+    ids: impl Iterator<Item = u64>,
+    convention: CoordinateConvention,
+) {
+    json.clear();
+    let _ = write!(json, r#"{{"frame":{frame},"faces":["#);
+    for (i, (face, id)) in faces.iter().zip(ids).enumerate() {
+        let rect = face.rectangle_in(convention);
+        let _ = write!(
+            json,
+            r#"{}{{"id":{id},"confidence":{:.3},"box":[{:.4},{:.4},{:.4},{:.4}],"landmarks":["#,
+            if i == 0 { "" } else { "," },
+            face.confidence(),
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+        );
+        for (j, p) in face.landmarks_in(convention).as_array().iter().enumerate() {
+            let separator = if j == 0 { "" } else { "," };
+            let _ = write!(json, "{separator}{:.4},{:.4}", p.x, p.y);
+        }
+        json.push_str("]}");
+    }
+    json.push_str("]}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    #[test]
+    fn broadcasts_json_at_most_at_rate() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut broadcaster = JsonBroadcaster::new(receiver.local_addr().unwrap())
+            .unwrap()
+            .with_max_rate(1.0);
+        let raw = RawFace {
+            score: 0.5,
+            x: 10.0,
+            y: 20.0,
+            w: 30.0,
+            h: 40.0,
+            lm: [50.0; 10],
+        };
+        let result = FrameResult {
+            index: 7,
+            timestamp: None,
+            faces: vec![Face::from_raw_face(&raw, (100, 200))],
+            track_ids: vec![3],
+        };
+        assert!(broadcaster.send(&result).unwrap());
+        assert!(!broadcaster.send(&result).unwrap());
+
+        let mut packet = [0; 1024];
+        let len = receiver.recv(&mut packet).unwrap();
+        let landmarks = ["0.5000,0.2500"; 5].join(",");
+        assert_eq!(
+            format!(
+                r#"{{"frame":7,"faces":[{{"id":3,"confidence":0.500,"box":[0.1000,0.1000,0.3000,0.2000],"landmarks":[{landmarks}]}}]}}"#
+            ),
+            std::str::from_utf8(&packet[..len]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Mapping YuNet's scores to calibrated probabilities, fitted on detections labeled as true
+//! or false positives, such as those of a validation set matched against its ground truth.
+//!
+//! YuNet's confidence ranks faces well but isn't a probability: a face scored 0.8 is not
+//! right four times out of five. Once attached through [`DetectorConfig::calibration`],
+//! [`Face::confidence`](crate::Face::confidence) reports the calibrated value instead.
+//!
+//! ```no_run
+//! # use rusty_yunet::calibration::Calibration;
+//! # use rusty_yunet::{DetectorConfig, FaceDetector};
+//! // (score, whether the detection was a face), from a labeled validation set.
+//! let samples = [(0.95, true), (0.9, true), (0.85, false), (0.7, true), (0.6, false)];
+//! let mut config = DetectorConfig::default();
+//! config.calibration = Calibration::platt(&samples);
+//! let mut detector = FaceDetector::with_config(config).unwrap();
+//! ```
+//!
+//! [`DetectorConfig::calibration`]: crate::DetectorConfig::calibration
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Newton iterations of the Platt fit, which converges in a handful.
+const PLATT_ITERATIONS: usize = 100;
+
+/// A monotonic mapping from raw scores to calibrated probabilities (0..1). Fitted
+/// parameters serialize with the `serde` feature, to be fitted once and shipped with an
+/// application.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Calibration {
+    /// Platt scaling: the logistic `1 / (1 + exp(a * score + b))`. Smooth, and needs few
+    /// samples.
+    Platt { a: f32, b: f32 },
+    /// Isotonic regression: linear interpolation between `(score, probability)` points,
+    /// in increasing order of both, clamped beyond the first and last. Fits any monotonic
+    /// shape, given enough samples.
+    Isotonic { points: Vec<(f32, f32)> },
+}
+
+impl Calibration {
+    /// Fits Platt scaling to `(score, is_face)` samples, with Platt's smoothing of the
+    /// labels against overfitting. `None` unless there are both faces and non-faces.
+    pub fn platt(samples: &[(f32, bool)]) -> Option<Self> {
+        let faces = samples.iter().filter(|&&(_, face)| face).count() as f64;
+        let others = samples.len() as f64 - faces;
+        if faces == 0.0 || others == 0.0 {
+            return None;
+        }
+        let hi = (faces + 1.0) / (faces + 2.0);
+        let lo = 1.0 / (others + 2.0);
+        let target = |face: bool| if face { hi } else { lo };
+
+        // Minimizes the cross-entropy by Newton's method, halving steps that don't improve
+        // it. `p` is written in terms of `exp(-f)` or `exp(f)`, whichever doesn't overflow.
+        let loss = |a: f64, b: f64| -> f64 {
+            samples
+                .iter()
+                .map(|&(score, face)| {
+                    let f = a * score as f64 + b;
+                    let t = target(face);
+                    if f >= 0.0 {
+                        t * f + (-f).exp().ln_1p()
+                    } else {
+                        (t - 1.0) * f + f.exp().ln_1p()
+                    }
+                })
+                .sum()
+        };
+        let (mut a, mut b) = (0.0, ((others + 1.0) / (faces + 1.0)).ln());
+        let mut current = loss(a, b);
+        for _ in 0..PLATT_ITERATIONS {
+            let (mut g1, mut g2, mut h11, mut h22, mut h21) = (0.0, 0.0, 1e-12, 1e-12, 0.0);
+            for &(score, face) in samples {
+                let score = score as f64;
+                let f = a * score + b;
+                let p = if f >= 0.0 {
+                    (-f).exp() / (1.0 + (-f).exp())
+                } else {
+                    1.0 / (1.0 + f.exp())
+                };
+                let d1 = target(face) - p;
+                let d2 = p * (1.0 - p);
+                g1 += score * d1;
+                g2 += d1;
+                h11 += score * score * d2;
+                h22 += d2;
+                h21 += score * d2;
+            }
+            if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+                break;
+            }
+            let det = h11 * h22 - h21 * h21;
+            let da = -(h22 * g1 - h21 * g2) / det;
+            let db = -(-h21 * g1 + h11 * g2) / det;
+            let mut step = 1.0;
+            while step > 1e-10 {
+                let next = loss(a + step * da, b + step * db);
+                if next < current {
+                    (a, b, current) = (a + step * da, b + step * db, next);
+                    break;
+                }
+                step /= 2.0;
+            }
+            if step <= 1e-10 {
+                break;
+            }
+        }
+        Some(Calibration::Platt {
+            a: a as f32,
+            b: b as f32,
+        })
+    }
+
+    /// Fits isotonic regression to `(score, is_face)` samples by pooling adjacent
+    /// violators. `None` if there are no samples.
+    pub fn isotonic(samples: &[(f32, bool)]) -> Option<Self> {
+        let mut sorted: Vec<(f32, f32)> = samples
+            .iter()
+            .map(|&(score, face)| (score, if face { 1.0 } else { 0.0 }))
+            .collect();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Blocks of (score sum, label sum, count), tied scores starting out in one, merged
+        // until their means increase.
+        let mut tied: Vec<(f64, f64, usize)> = Vec::new();
+        let mut previous = None;
+        for (score, label) in sorted {
+            match tied.last_mut() {
+                Some((s, l, n)) if previous == Some(score) => {
+                    (*s, *l, *n) = (*s + score as f64, *l + label as f64, *n + 1)
+                }
+                _ => tied.push((score as f64, label as f64, 1)),
+            }
+            previous = Some(score);
+        }
+        let mut blocks: Vec<(f64, f64, usize)> = Vec::new();
+        for block in tied {
+            blocks.push(block);
+            while let [.., (s0, l0, n0), (s1, l1, n1)] = blocks[..] {
+                if l0 / (n0 as f64) < l1 / (n1 as f64) {
+                    break;
+                }
+                blocks.pop();
+                *blocks.last_mut().unwrap() = (s0 + s1, l0 + l1, n0 + n1);
+            }
+        }
+        if blocks.is_empty() {
+            return None;
+        }
+        let points = blocks
+            .into_iter()
+            .map(|(score, label, n)| ((score / n as f64) as f32, (label / n as f64) as f32))
+            .collect();
+        Some(Calibration::Isotonic { points })
+    }
+
+    /// The calibrated probability of a face scored `score`.
+    pub fn apply(&self, score: f32) -> f32 {
+        match self {
+            Calibration::Platt { a, b } => 1.0 / (1.0 + (a * score + b).exp()),
+            Calibration::Isotonic { points } => {
+                let i = points.partition_point(|&(s, _)| s < score);
+                match (i.checked_sub(1).map(|i| points[i]), points.get(i)) {
+                    (Some((s0, p0)), Some(&(s1, p1))) if s1 > s0 => {
+                        p0 + (p1 - p0) * (score - s0) / (s1 - s0)
+                    }
+                    (_, Some(&(_, p))) | (Some((_, p)), None) => p,
+                    (None, None) => score,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calibrates_overconfident_scores() {
+        // Faces score high, but only three in four above 0.8 are faces, one in four below.
+        let samples: Vec<(f32, bool)> = (0..400)
+            .map(|i| {
+                let score = 0.6 + 0.4 * (i % 100) as f32 / 100.0;
+                (score, (i / 100 == 0) ^ (score >= 0.8))
+            })
+            .collect();
+
+        let platt = Calibration::platt(&samples).unwrap();
+        let isotonic = Calibration::isotonic(&samples).unwrap();
+        for calibration in [&platt, &isotonic] {
+            assert!(calibration.apply(0.95) < 0.9, "{calibration:?}");
+            assert!(calibration.apply(0.65) > 0.1, "{calibration:?}");
+            assert!(
+                calibration.apply(0.65) < calibration.apply(0.95),
+                "{calibration:?}"
+            );
+        }
+        assert!((isotonic.apply(0.95) - 0.75).abs() < 0.01);
+        assert!((isotonic.apply(0.65) - 0.25).abs() < 0.01);
+        assert_eq!(isotonic.apply(0.0), isotonic.apply(0.6));
+
+        assert_eq!(None, Calibration::platt(&[(0.9, true)]));
+        assert_eq!(None, Calibration::isotonic(&[]));
+    }
+}
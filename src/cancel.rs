@@ -0,0 +1,74 @@
+//! Aborting long batch and tiled detections from another thread, such as when the user of
+//! a GUI navigates away from a background scan.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::YuNetError;
+
+/// A flag shared between the thread running a detection and those that may cancel it.
+/// Clones share the flag.
+///
+/// Cancellation is checked between units of work, such as tiles, images or frames, so a
+/// detection already running on one finishes first.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every detection given this token or a clone of it, now and in the future.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fails with [`YuNetError::Cancelled`] once cancelled.
+    pub(crate) fn check(&self) -> Result<(), YuNetError> {
+        if self.is_cancelled() {
+            Err(YuNetError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FaceDetector, ImageView, Pipeline, TileConfig, TiledDetector, TrackerConfig};
+
+    #[test]
+    fn cancels_between_units_of_work() {
+        let blank = vec![128; 3 * 200 * 100];
+        let image = ImageView::new(&blank, 200, 100).unwrap();
+        let config = TileConfig {
+            tile_size: 64,
+            overlap: 16,
+            ..TileConfig::default()
+        };
+        let mut tiled = TiledDetector::new(FaceDetector::new(), config);
+        let cancel = CancellationToken::new();
+        assert!(tiled.detect_image_with_cancel(&image, &cancel).is_ok());
+
+        cancel.clone().cancel();
+        assert!(cancel.is_cancelled());
+        assert!(matches!(
+            tiled.detect_image_with_cancel(&image, &cancel),
+            Err(YuNetError::Cancelled)
+        ));
+        let mut pipeline = Pipeline::new(FaceDetector::new(), TrackerConfig::default());
+        assert!(matches!(
+            pipeline.run_with_cancel([image, image], &cancel),
+            Err(YuNetError::Cancelled)
+        ));
+        assert_eq!(0, pipeline.checkpoint().next_frame());
+    }
+}
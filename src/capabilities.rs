@@ -0,0 +1,292 @@
+//! What this build can do, for applications to adapt to builds with fewer cargo features
+//! and to explain why something they need is missing instead of failing opaquely.
+//!
+//! ```no_run
+//! let capabilities = rusty_yunet::capabilities();
+//! if !capabilities.is_usable("ndi") {
+//!     eprintln!("{capabilities}");
+//! }
+//! ```
+
+use std::fmt;
+use std::net::UdpSocket;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// The role of a [`Capability`].
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CapabilityKind {
+    /// An inference engine, see [`Backend`](crate::Backend).
+    Backend,
+    /// An output faces are sent to.
+    Sink,
+    /// An input frames are received from.
+    Source,
+    /// Support for running models of your own on detected faces.
+    Stage,
+    /// Input decoding, output formats and helpers.
+    Support,
+    /// An API for another language or framework.
+    Binding,
+}
+
+/// Whether a [`Capability`] can be used, and why not.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Availability {
+    Usable,
+    /// The cargo feature of the capability wasn't enabled.
+    NotCompiled,
+    /// Compiled in, but missing something at run time, such as a library or the network.
+    Unusable(String),
+}
+
+/// A feature of the crate and whether this build can use it.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+    pub name: &'static str,
+    pub kind: CapabilityKind,
+    /// The cargo feature compiling it in.
+    pub feature: &'static str,
+    pub availability: Availability,
+}
+
+impl Capability {
+    pub fn is_usable(&self) -> bool {
+        self.availability == Availability::Usable
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({:?}): ", self.name, self.kind)?;
+        match &self.availability {
+            Availability::Usable => write!(f, "usable"),
+            Availability::NotCompiled => {
+                write!(f, "not compiled in, enable the `{}` feature", self.feature)
+            }
+            Availability::Unusable(reason) => write!(f, "unusable, {reason}"),
+        }
+    }
+}
+
+/// The outcome of [`capabilities`].
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub capabilities: Vec<Capability>,
+}
+
+impl Capabilities {
+    pub fn get(&self, name: &str) -> Option<&Capability> {
+        self.capabilities
+            .iter()
+            .find(|capability| capability.name == name)
+    }
+
+    /// Whether the capability called `name` is usable, false for unknown names.
+    pub fn is_usable(&self, name: &str) -> bool {
+        self.get(name).is_some_and(Capability::is_usable)
+    }
+
+    /// The usable capabilities of a kind.
+    pub fn usable(&self, kind: CapabilityKind) -> impl Iterator<Item = &Capability> {
+        self.capabilities
+            .iter()
+            .filter(move |capability| capability.kind == kind && capability.is_usable())
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for capability in &self.capabilities {
+            writeln!(f, "{capability}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes every optional backend, sink and integration of the crate and whether this
+/// build can use it, checking what is needed at run time, such as the NDI runtime and UDP
+/// sockets.
+pub fn capabilities() -> Capabilities {
+    let compiled = |compiled: bool| {
+        if compiled {
+            Availability::Usable
+        } else {
+            Availability::NotCompiled
+        }
+    };
+    let udp = |compiled: bool| {
+        if !compiled {
+            return Availability::NotCompiled;
+        }
+        match UdpSocket::bind("0.0.0.0:0") {
+            Ok(_) => Availability::Usable,
+            Err(e) => Availability::Unusable(format!("UDP sockets can't be opened: {e}")),
+        }
+    };
+    #[cfg(feature = "ndi")]
+    let ndi = match crate::ndi::runtime_available() {
+        Ok(()) => Availability::Usable,
+        Err(e) => Availability::Unusable(e.to_string()),
+    };
+    #[cfg(not(feature = "ndi"))]
+    let ndi = Availability::NotCompiled;
+    #[cfg(feature = "rtsp")]
+    let rtsp = match crate::rtsp::ffmpeg_available() {
+        Ok(()) => Availability::Usable,
+        Err(e) => Availability::Unusable(format!("ffmpeg can't be started: {e}")),
+    };
+    #[cfg(not(feature = "rtsp"))]
+    let rtsp = Availability::NotCompiled;
+
+    let capability = |name, kind, feature, availability| Capability {
+        name,
+        kind,
+        feature,
+        availability,
+    };
+    use CapabilityKind::*;
+    Capabilities {
+        capabilities: vec![
+            capability(
+                "libfacedetection",
+                Backend,
+                "libfacedetection",
+                compiled(cfg!(feature = "libfacedetection")),
+            ),
+            capability(
+                "native",
+                Backend,
+                "native",
+                compiled(cfg!(feature = "native")),
+            ),
+            capability("osc", Sink, "osc", udp(cfg!(feature = "osc"))),
+            capability(
+                "broadcast",
+                Sink,
+                "broadcast",
+                udp(cfg!(feature = "broadcast")),
+            ),
+            capability("ndi", Sink, "ndi", ndi),
+            capability("ipc", Source, "ipc", compiled(cfg!(feature = "ipc"))),
+            capability("rtsp", Source, "rtsp", rtsp),
+            capability(
+                "gstreamer",
+                Source,
+                "gstreamer",
+                compiled(cfg!(feature = "gstreamer")),
+            ),
+            capability("mqtt", Sink, "mqtt", compiled(cfg!(feature = "mqtt"))),
+            capability("grpc", Sink, "grpc", compiled(cfg!(feature = "grpc"))),
+            capability("proto", Support, "proto", compiled(cfg!(feature = "proto"))),
+            capability("server", Sink, "server", compiled(cfg!(feature = "server"))),
+            capability(
+                "face crops",
+                Stage,
+                "image",
+                compiled(cfg!(feature = "image")),
+            ),
+            capability(
+                "custom models",
+                Stage,
+                "native",
+                compiled(cfg!(feature = "native")),
+            ),
+            capability("store", Support, "store", compiled(cfg!(feature = "store"))),
+            capability(
+                "config files",
+                Support,
+                "config",
+                compiled(cfg!(feature = "config")),
+            ),
+            capability(
+                "testing",
+                Support,
+                "testing",
+                compiled(cfg!(feature = "testing")),
+            ),
+            capability(
+                "metrics",
+                Support,
+                "metrics",
+                compiled(cfg!(feature = "metrics")),
+            ),
+            capability(
+                "tracing",
+                Support,
+                "tracing",
+                compiled(cfg!(feature = "tracing")),
+            ),
+            capability("image", Support, "image", compiled(cfg!(feature = "image"))),
+            capability(
+                "viewer",
+                Support,
+                "viewer",
+                compiled(cfg!(feature = "viewer")),
+            ),
+            capability(
+                "static C++ runtime",
+                Support,
+                "static-cxx",
+                compiled(cfg!(feature = "static-cxx")),
+            ),
+            capability(
+                "text labels",
+                Support,
+                "ab_glyph",
+                compiled(cfg!(feature = "ab_glyph")),
+            ),
+            capability(
+                "parallel",
+                Support,
+                "rayon",
+                compiled(cfg!(feature = "rayon")),
+            ),
+            capability(
+                "serde",
+                Support,
+                "serde_support",
+                compiled(cfg!(feature = "serde")),
+            ),
+            capability("bevy", Binding, "bevy", compiled(cfg!(feature = "bevy"))),
+            capability("capi", Binding, "capi", compiled(cfg!(feature = "capi"))),
+            capability(
+                "python",
+                Binding,
+                "python",
+                compiled(cfg!(feature = "python")),
+            ),
+            capability("wasm", Binding, "wasm", compiled(cfg!(feature = "wasm"))),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::available_backends;
+
+    #[test]
+    fn describes_the_build() {
+        let capabilities = capabilities();
+        assert_eq!(
+            available_backends().len(),
+            capabilities.usable(CapabilityKind::Backend).count()
+        );
+        assert_eq!(cfg!(feature = "image"), capabilities.is_usable("image"));
+        assert!(!capabilities.is_usable("opencv"));
+        if !cfg!(feature = "osc") {
+            let osc = capabilities.get("osc").unwrap();
+            assert_eq!(Availability::NotCompiled, osc.availability);
+            assert_eq!(
+                "osc (Sink): not compiled in, enable the `osc` feature",
+                osc.to_string()
+            );
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! A plain C interface for hosts that can't link Rust directly, declared in
+//! `include/rusty_yunet.h`.
+//!
+//! Every function catches panics and reports them as [`YuNetStatus::Panic`] instead of
+//! unwinding into the caller.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{Face, FaceDetector, ImageView, YuNetError};
+
+/// Opaque handle to a detector, created by [`yunet_detector_new`].
+pub struct YuNetDetector(FaceDetector);
+
+/// A detected face, in pixel coordinates of the input image.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YuNetFace {
+    pub confidence: f32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Right eye, left eye, nose, right and left mouth corners, as x, y pairs.
+    pub landmarks: [f32; 10],
+}
+
+impl From<&Face> for YuNetFace {
+    fn from(face: &Face) -> Self {
+        let rect = face.rectangle();
+        let landmarks = face.landmarks();
+        let points = landmarks.as_array();
+        Self {
+            confidence: face.confidence(),
+            x: rect.x,
+            y: rect.y,
+            width: rect.w,
+            height: rect.h,
+            landmarks: std::array::from_fn(|i| points[i / 2][i % 2]),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuNetStatus {
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// The image buffer doesn't match its dimensions, or the image is too large.
+    InvalidImage = 2,
+    DetectionFailed = 3,
+    Panic = 4,
+}
+
+impl From<YuNetError> for YuNetStatus {
+    fn from(error: YuNetError) -> Self {
+        match error {
+            YuNetError::InvalidImage | YuNetError::ImageTooLarge { .. } => {
+                YuNetStatus::InvalidImage
+            }
+            _ => YuNetStatus::DetectionFailed,
+        }
+    }
+}
+
+/// Creates a detector with the default configuration, or returns null if that panicked.
+/// Release it with [`yunet_detector_free`].
+#[no_mangle]
+pub extern "C" fn yunet_detector_new() -> *mut YuNetDetector {
+    catch_unwind(|| Box::into_raw(Box::new(YuNetDetector(FaceDetector::new()))))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+///
+/// `detector` must be null or a pointer returned by [`yunet_detector_new`] that wasn't
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn yunet_detector_free(detector: *mut YuNetDetector) {
+    if !detector.is_null() {
+        // Never unwind into C, even if a destructor panics.
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(detector))));
+    }
+}
+
+/// Detects faces in a BGR image whose rows start `stride` bytes apart. On success, stores an
+/// array of `*count` faces in `*faces`, to be released with [`yunet_faces_free`]; otherwise
+/// stores null and zero.
+///
+/// # Safety
+///
+/// `detector` must come from [`yunet_detector_new`] and not be used by another thread during
+/// the call. `bgr` must be valid for reads of `stride * (height - 1) + 3 * width` bytes, and
+/// `faces` and `count` for writes.
+#[no_mangle]
+pub unsafe extern "C" fn yunet_detect(
+    detector: *mut YuNetDetector,
+    bgr: *const u8,
+    width: usize,
+    height: usize,
+    stride: usize,
+    faces: *mut *mut YuNetFace,
+    count: *mut usize,
+) -> YuNetStatus {
+    if detector.is_null() || bgr.is_null() || faces.is_null() || count.is_null() {
+        return YuNetStatus::NullPointer;
+    }
+    *faces = ptr::null_mut();
+    *count = 0;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("yunet_detect", width, height, stride).entered();
+
+    let detect = || -> Result<Box<[YuNetFace]>, YuNetError> {
+        // Check the layout before building a slice over the caller's memory.
+        if width == 0 || height == 0 || stride < width.saturating_mul(3) {
+            return Err(YuNetError::InvalidImage);
+        }
+        let len = stride
+            .checked_mul(height - 1)
+            .and_then(|rows| rows.checked_add(3 * width))
+            .filter(|&len| len <= isize::MAX as usize)
+            .ok_or(YuNetError::InvalidImage)?;
+        let image =
+            ImageView::with_stride(std::slice::from_raw_parts(bgr, len), width, height, stride)?;
+        let found = (*detector).0.detect_image(&image)?;
+        Ok(found.iter().map(YuNetFace::from).collect())
+    };
+    match catch_unwind(AssertUnwindSafe(detect)) {
+        Ok(Ok(found)) => {
+            *count = found.len();
+            *faces = Box::into_raw(found).cast();
+            YuNetStatus::Ok
+        }
+        Ok(Err(error)) => error.into(),
+        Err(_) => {
+            #[cfg(feature = "tracing")]
+            tracing::error!("detection panicked");
+            YuNetStatus::Panic
+        }
+    }
+}
+
+/// # Safety
+///
+/// `faces` and `count` must be null and zero, or as stored by one call to [`yunet_detect`]
+/// that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn yunet_faces_free(faces: *mut YuNetFace, count: usize) {
+    if !faces.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(faces, count)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_through_the_c_interface() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let expected = FaceDetector::new()
+            .detect(image.as_raw(), width, height)
+            .unwrap();
+
+        unsafe {
+            let detector = yunet_detector_new();
+            let mut faces = ptr::null_mut();
+            let mut count = 0;
+            let status = yunet_detect(
+                detector,
+                image.as_ptr(),
+                width,
+                height,
+                3 * width,
+                &mut faces,
+                &mut count,
+            );
+            assert_eq!(YuNetStatus::Ok, status);
+            let found = std::slice::from_raw_parts(faces, count);
+            assert_eq!(
+                expected.iter().map(YuNetFace::from).collect::<Vec<_>>(),
+                found
+            );
+            yunet_faces_free(faces, count);
+
+            let status = yunet_detect(
+                detector,
+                image.as_ptr(),
+                width,
+                height,
+                width,
+                &mut faces,
+                &mut count,
+            );
+            assert_eq!(YuNetStatus::InvalidImage, status);
+            assert!(faces.is_null());
+            yunet_detector_free(detector);
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! The settings of a detection daemon or command-line tool built on this crate, read from a
+//! TOML or JSON file rather than from ever longer argument lists.
+//!
+//! ```toml
+//! [source]
+//! ipc = "/dev/shm/rusty-yunet"   # or directory = "photos/", or file = "frame.jpg",
+//!                                # or rtsp = "rtsp://camera/stream"
+//!
+//! [detector]
+//! backend = "native"
+//! model = "yunet-finetuned.bin"  # weights for the native backend
+//! min_confidence = 0.7
+//! min_face_size = 40             # pixels, or min_face_fraction = 0.05
+//! max_side = 640
+//!
+//! [presence]
+//! min_confidence = 0.8
+//! appear_after = 0.5             # seconds
+//! leave_after = 2
+//! primary = "closest_to_center"
+//!
+//! [[sinks]]
+//! kind = "osc"
+//! address = "127.0.0.1:9000"
+//! ```
+//!
+//! Every section is optional. Errors name the offending field, such as
+//! `detector.min_confidence: expected a number`, and unknown fields are rejected rather than
+//! ignored, so that typos don't go unnoticed.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+
+use crate::detector::FaceSize;
+use crate::primary::PrimaryPolicy;
+use crate::{Backend, DetectorConfig, FaceDetector, PresenceConfig, ResizeStrategy, YuNetError};
+
+/// The settings read from a configuration file.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct AppConfig {
+    pub source: Option<InputSource>,
+    pub detector: DetectorConfig,
+    /// Weights for the native backend, in the format of
+    /// [`NATIVE_MODEL`](crate::NATIVE_MODEL), instead of the bundled ones.
+    pub model: Option<PathBuf>,
+    pub presence: Option<PresenceConfig>,
+    pub sinks: Vec<SinkConfig>,
+}
+
+/// Where frames come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// A shared-memory ring written by a capture process; see [`ipc`](crate::ipc).
+    Ipc(PathBuf),
+    /// The images in a directory tree; see [`scan`](crate::scan).
+    Directory(PathBuf),
+    /// A single image file.
+    File(PathBuf),
+    /// The URL of an IP camera's stream; see [`rtsp`](crate::rtsp).
+    Rtsp(String),
+}
+
+/// Where results go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SinkConfig {
+    pub kind: SinkKind,
+    /// The address to send to or listen on, or for NDI the name of the source.
+    pub address: String,
+}
+
+impl SinkConfig {
+    pub fn new(kind: SinkKind, address: impl Into<String>) -> Self {
+        Self {
+            kind,
+            address: address.into(),
+        }
+    }
+}
+
+/// The outputs a [`SinkConfig`] can name, each behind the feature of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SinkKind {
+    Osc,
+    Broadcast,
+    Mqtt,
+    Ndi,
+    Server,
+    Grpc,
+}
+
+const SINK_KINDS: [(&str, SinkKind); 6] = [
+    ("osc", SinkKind::Osc),
+    ("broadcast", SinkKind::Broadcast),
+    ("mqtt", SinkKind::Mqtt),
+    ("ndi", SinkKind::Ndi),
+    ("server", SinkKind::Server),
+    ("grpc", SinkKind::Grpc),
+];
+
+impl AppConfig {
+    /// Reads a file, as JSON if its extension is `.json` and as TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, YuNetError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| YuNetError::Io(e.to_string()))?;
+        let json = path.extension().is_some_and(|ext| ext == "json");
+        let config = if json {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        };
+        config.map_err(|error| error.in_config(path.display()))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, YuNetError> {
+        Self::from_value(&parse_toml(text)?)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, YuNetError> {
+        let value: Value = serde_json::from_str(text).map_err(|e| YuNetError::Config {
+            message: "invalid JSON".to_owned(),
+            source: Some(Arc::new(e)),
+        })?;
+        Self::from_value(&value)
+    }
+
+    fn from_value(value: &Value) -> Result<Self, YuNetError> {
+        let root = Fields::new(value, "")?;
+        let mut config = AppConfig::default();
+        if let Some(source) = root.section("source")? {
+            let kinds = [
+                ("ipc", InputSource::Ipc as fn(PathBuf) -> InputSource),
+                ("directory", InputSource::Directory),
+                ("file", InputSource::File),
+            ];
+            for (key, kind) in kinds {
+                if let Some(path) = source.string(key)? {
+                    if config.source.is_some() {
+                        return Err(source.error(key, "only one source may be given"));
+                    }
+                    config.source = Some(kind(path.into()));
+                }
+            }
+            if let Some(url) = source.string("rtsp")? {
+                if config.source.is_some() {
+                    return Err(source.error("rtsp", "only one source may be given"));
+                }
+                config.source = Some(InputSource::Rtsp(url));
+            }
+            source.finish()?;
+            if config.source.is_none() {
+                return Err(root.error("source", "expected one of ipc, directory, file or rtsp"));
+            }
+        }
+        if let Some(detector) = root.section("detector")? {
+            read_detector(&detector, &mut config)?;
+        }
+        if let Some(presence) = root.section("presence")? {
+            config.presence = Some(read_presence(&presence)?);
+        }
+        for sink in root.sections("sinks")? {
+            let kind = sink.required_string("kind")?;
+            let Some(&(_, kind)) = SINK_KINDS.iter().find(|(name, _)| *name == kind) else {
+                let names: Vec<&str> = SINK_KINDS.iter().map(|(name, _)| *name).collect();
+                return Err(sink.error("kind", &format!("expected one of {}", names.join(", "))));
+            };
+            config
+                .sinks
+                .push(SinkConfig::new(kind, sink.required_string("address")?));
+            sink.finish()?;
+        }
+        root.finish()?;
+        Ok(config)
+    }
+
+    /// A detector as configured, with the weights of [`model`](Self::model) if given.
+    pub fn build_detector(&self) -> Result<FaceDetector, YuNetError> {
+        match &self.model {
+            #[cfg(feature = "native")]
+            Some(path) => {
+                let model = std::fs::read(path).map_err(|e| YuNetError::Io(e.to_string()))?;
+                FaceDetector::with_native_model(self.detector.clone(), &model)
+            }
+            #[cfg(not(feature = "native"))]
+            Some(_) => Err(YuNetError::UnsupportedBackend {
+                backend: Backend::Native,
+                target: self.detector.target,
+            }),
+            None => FaceDetector::with_config(self.detector.clone()),
+        }
+    }
+}
+
+/// Applies the fields of a TOML file to a copy of `base`, as those of the `[detector]`
+/// section of an [`AppConfig`] file, but at the top level and without a `model`. For
+/// [`ConfigWatcher`](crate::ConfigWatcher).
+pub(crate) fn detector_from_toml(
+    base: &DetectorConfig,
+    text: &str,
+) -> Result<DetectorConfig, YuNetError> {
+    let mut detector = base.clone();
+    apply_detector(&Fields::new(&parse_toml(text)?, "")?, &mut detector)?;
+    Ok(detector)
+}
+
+fn parse_toml(text: &str) -> Result<Value, YuNetError> {
+    let document: toml_edit::DocumentMut =
+        text.parse()
+            .map_err(|e: toml_edit::TomlError| YuNetError::Config {
+                message: "invalid TOML".to_owned(),
+                source: Some(Arc::new(e)),
+            })?;
+    Ok(table_to_json(document.as_table()))
+}
+
+fn read_detector(fields: &Fields, config: &mut AppConfig) -> Result<(), YuNetError> {
+    config.model = fields.string("model")?.map(PathBuf::from);
+    apply_detector(fields, &mut config.detector)
+}
+
+/// Sets the fields given on `detector`, leaving the others as they were.
+fn apply_detector(fields: &Fields, detector: &mut DetectorConfig) -> Result<(), YuNetError> {
+    if let Some(backend) = fields.string("backend")? {
+        detector.backend = match backend.as_str() {
+            "libfacedetection" => Backend::LibFaceDetection,
+            "native" => Backend::Native,
+            _ => return Err(fields.error("backend", "expected libfacedetection or native")),
+        };
+    }
+    if let Some(min_confidence) = fields.unit("min_confidence")? {
+        detector.min_confidence = Some(min_confidence);
+    }
+    match (
+        fields.positive("min_face_size")?,
+        fields.unit("min_face_fraction")?,
+    ) {
+        (Some(_), Some(_)) => {
+            return Err(fields.error(
+                "min_face_fraction",
+                "only one of min_face_size and min_face_fraction may be given",
+            ))
+        }
+        (Some(pixels), None) => detector.min_face_size = Some(FaceSize::Pixels(pixels)),
+        (None, Some(fraction)) => detector.min_face_size = Some(FaceSize::Fraction(fraction)),
+        (None, None) => {}
+    }
+    if let Some(max_side) = fields.count("max_side")? {
+        detector.max_side = Some(max_side);
+    }
+    if let Some(size) = fields.array("input_size")? {
+        match size[..] {
+            [Value::Number(ref w), Value::Number(ref h)] => {
+                let (Some(w), Some(h)) = (w.as_u64(), h.as_u64()) else {
+                    return Err(fields.error("input_size", "expected whole numbers"));
+                };
+                detector.input_size = Some((w as usize, h as usize));
+            }
+            _ => return Err(fields.error("input_size", "expected [width, height]")),
+        }
+    }
+    if let Some(resize) = fields.string("resize")? {
+        detector.resize = match resize.as_str() {
+            "stretch" => ResizeStrategy::Stretch,
+            "letterbox" => ResizeStrategy::Letterbox,
+            "crop" => ResizeStrategy::Crop,
+            _ => return Err(fields.error("resize", "expected stretch, letterbox or crop")),
+        };
+    }
+    if let Some(mirror) = fields.bool("mirror")? {
+        detector.mirror = mirror;
+    }
+    if let Some(refine) = fields.bool("refine_landmarks")? {
+        detector.refine_landmarks = refine;
+    }
+    fields.finish()
+}
+
+fn read_presence(fields: &Fields) -> Result<PresenceConfig, YuNetError> {
+    let mut presence = PresenceConfig::default();
+    if let Some(min) = fields.unit("min_confidence")? {
+        presence.min_confidence = min;
+    }
+    presence.min_face_size = fields.positive("min_face_size")?.map(FaceSize::Pixels);
+    for (key, duration) in [
+        ("appear_after", &mut presence.appear_after),
+        ("leave_after", &mut presence.leave_after),
+        ("report_interval", &mut presence.report_interval),
+    ] {
+        if let Some(seconds) = fields.number(key)? {
+            *duration = Duration::try_from_secs_f64(seconds)
+                .map_err(|_| fields.error(key, "expected a duration in seconds"))?;
+        }
+    }
+    if let Some(primary) = fields.string("primary")? {
+        presence.primary = match primary.as_str() {
+            "largest" => PrimaryPolicy::Largest,
+            "closest_to_center" => PrimaryPolicy::ClosestToCenter,
+            "longest_dwelling" => PrimaryPolicy::LongestDwelling,
+            "sticky" => PrimaryPolicy::Sticky,
+            _ => {
+                return Err(fields.error(
+                    "primary",
+                    "expected largest, closest_to_center, longest_dwelling or sticky",
+                ))
+            }
+        };
+    }
+    fields.finish()?;
+    Ok(presence)
+}
+
+/// The fields of one table of the file, read by name, remembering which were read so that
+/// the rest can be reported as unknown.
+struct Fields<'a> {
+    map: &'a Map<String, Value>,
+    /// Where the table is, such as `sinks[1]`, empty at the root.
+    path: String,
+    read: RefCell<Vec<&'a str>>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value, path: &str) -> Result<Self, YuNetError> {
+        match value {
+            Value::Object(map) => Ok(Self {
+                map,
+                path: path.to_owned(),
+                read: RefCell::new(Vec::new()),
+            }),
+            _ => Err(YuNetError::config(format!("{path}: expected a table"))),
+        }
+    }
+
+    fn error(&self, key: &str, message: &str) -> YuNetError {
+        if self.path.is_empty() {
+            YuNetError::config(format!("{key}: {message}"))
+        } else {
+            YuNetError::config(format!("{}.{key}: {message}", self.path))
+        }
+    }
+
+    fn get(&self, key: &'a str) -> Option<&'a Value> {
+        self.read.borrow_mut().push(key);
+        self.map.get(key)
+    }
+
+    fn section(&self, key: &'a str) -> Result<Option<Fields<'a>>, YuNetError> {
+        let path = self.child(key);
+        self.get(key)
+            .map(|value| Fields::new(value, &path))
+            .transpose()
+    }
+
+    fn sections(&self, key: &'a str) -> Result<Vec<Fields<'a>>, YuNetError> {
+        match self.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| Fields::new(item, &format!("{}[{i}]", self.child(key))))
+                .collect(),
+            Some(_) => Err(self.error(key, "expected an array of tables")),
+        }
+    }
+
+    fn child(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{key}", self.path)
+        }
+    }
+
+    fn string(&self, key: &'a str) -> Result<Option<String>, YuNetError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(self.error(key, "expected a string")),
+        }
+    }
+
+    fn required_string(&self, key: &'a str) -> Result<String, YuNetError> {
+        self.string(key)?
+            .ok_or_else(|| self.error(key, "missing field"))
+    }
+
+    fn bool(&self, key: &'a str) -> Result<Option<bool>, YuNetError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::Bool(b)) => Ok(Some(*b)),
+            Some(_) => Err(self.error(key, "expected true or false")),
+        }
+    }
+
+    fn number(&self, key: &'a str) -> Result<Option<f64>, YuNetError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::Number(n)) => Ok(n.as_f64()),
+            Some(_) => Err(self.error(key, "expected a number")),
+        }
+    }
+
+    /// A number in 0..=1.
+    fn unit(&self, key: &'a str) -> Result<Option<f32>, YuNetError> {
+        match self.number(key)? {
+            Some(n) if !(0.0..=1.0).contains(&n) => Err(self.error(key, "expected 0 to 1")),
+            n => Ok(n.map(|n| n as f32)),
+        }
+    }
+
+    fn positive(&self, key: &'a str) -> Result<Option<f32>, YuNetError> {
+        match self.number(key)? {
+            Some(n) if n <= 0.0 => Err(self.error(key, "expected a positive number")),
+            n => Ok(n.map(|n| n as f32)),
+        }
+    }
+
+    fn count(&self, key: &'a str) -> Result<Option<usize>, YuNetError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::Number(n)) => match n.as_u64() {
+                Some(n) if n > 0 => Ok(Some(n as usize)),
+                _ => Err(self.error(key, "expected a positive whole number")),
+            },
+            Some(_) => Err(self.error(key, "expected a positive whole number")),
+        }
+    }
+
+    fn array(&self, key: &'a str) -> Result<Option<&'a Vec<Value>>, YuNetError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::Array(items)) => Ok(Some(items)),
+            Some(_) => Err(self.error(key, "expected an array")),
+        }
+    }
+
+    /// Fails on the first field that wasn't read.
+    fn finish(&self) -> Result<(), YuNetError> {
+        let read = self.read.borrow();
+        match self.map.keys().find(|key| !read.contains(&key.as_str())) {
+            Some(key) => Err(self.error(key, "unknown field")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn table_to_json(table: &toml_edit::Table) -> Value {
+    Value::Object(
+        table
+            .iter()
+            .map(|(key, item)| (key.to_owned(), item_to_json(item)))
+            .collect(),
+    )
+}
+
+fn item_to_json(item: &toml_edit::Item) -> Value {
+    match item {
+        toml_edit::Item::None => Value::Null,
+        toml_edit::Item::Value(value) => value_to_json(value),
+        toml_edit::Item::Table(table) => table_to_json(table),
+        toml_edit::Item::ArrayOfTables(tables) => {
+            Value::Array(tables.iter().map(table_to_json).collect())
+        }
+    }
+}
+
+fn value_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::String(d.value().to_string()),
+        toml_edit::Value::Array(items) => Value::Array(items.iter().map(value_to_json).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_owned(), value_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_toml_and_json_alike() {
+        let toml = r#"
+            [source]
+            ipc = "/dev/shm/frames"
+
+            [detector]
+            min_confidence = 0.7
+            min_face_size = 40
+            input_size = [320, 320]
+            resize = "crop"
+
+            [presence]
+            appear_after = 0.25
+            primary = "sticky"
+
+            [[sinks]]
+            kind = "osc"
+            address = "127.0.0.1:9000"
+        "#;
+        let config = AppConfig::from_toml(toml).unwrap();
+        assert_eq!(
+            Some(InputSource::Ipc("/dev/shm/frames".into())),
+            config.source
+        );
+        assert_eq!(Some(0.7), config.detector.min_confidence);
+        assert_eq!(Some(FaceSize::Pixels(40.0)), config.detector.min_face_size);
+        assert_eq!(Some((320, 320)), config.detector.input_size);
+        assert_eq!(ResizeStrategy::Crop, config.detector.resize);
+        let presence = config.presence.as_ref().unwrap();
+        assert_eq!(Duration::from_millis(250), presence.appear_after);
+        assert_eq!(PrimaryPolicy::Sticky, presence.primary);
+        assert_eq!(
+            vec![SinkConfig::new(SinkKind::Osc, "127.0.0.1:9000")],
+            config.sinks
+        );
+        assert!(config.build_detector().is_ok());
+
+        let json = r#"{
+            "source": {"ipc": "/dev/shm/frames"},
+            "detector": {"min_confidence": 0.7, "min_face_size": 40,
+                         "input_size": [320, 320], "resize": "crop"},
+            "presence": {"appear_after": 0.25, "primary": "sticky"},
+            "sinks": [{"kind": "osc", "address": "127.0.0.1:9000"}]
+        }"#;
+        assert_eq!(config, AppConfig::from_json(json).unwrap());
+
+        let error = |text: &str| AppConfig::from_toml(text).unwrap_err().to_string();
+        assert!(error("[detector]\nmin_confidence = \"high\"")
+            .contains("detector.min_confidence: expected a number"));
+        assert!(
+            error("[detector]\nmin_confidnce = 0.5").contains("detector.min_confidnce: unknown")
+        );
+        assert!(error("[[sinks]]\nkind = \"osc\"").contains("sinks[0].address: missing"));
+        assert_eq!(
+            Some(InputSource::Rtsp("rtsp://door/live".to_owned())),
+            AppConfig::from_toml("[source]\nrtsp = \"rtsp://door/live\"")
+                .unwrap()
+                .source
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Converting the YUV frames cameras deliver, and the 16-bit and floating point images of
+//! scientific and HDR pipelines, into the 8-bit images the detector takes, without a
+//! dependency on a conversion crate.
+//!
+//! YUV frames are taken as tightly packed and in the limited ("video") range of BT.601, as most
+//! webcams and capture cards deliver them. The inner loops use fixed-point arithmetic only,
+//! so that the compiler vectorizes them.
+//!
+//! ```no_run
+//! # use rusty_yunet::{convert::YuvFormat, FaceDetector, ImageView};
+//! # let (frame, width, height) = (vec![0u8; 640 * 480 * 3 / 2], 640, 480);
+//! let mut detector = FaceDetector::new();
+//! let mut bgr = Vec::new();
+//! let image = ImageView::from_yuv(YuvFormat::Nv12, &frame, width, height, &mut bgr)?;
+//! let faces = detector.detect_image(&image)?;
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{ImageView, YuNetError};
+
+/// The memory layout of a YUV frame.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YuvFormat {
+    /// A Y plane followed by a plane of interleaved U and V at half resolution, as from most
+    /// hardware decoders and Android cameras.
+    Nv12,
+    /// Like [`Nv12`](Self::Nv12), with V before U.
+    Nv21,
+    /// Packed 4:2:2, Y0 U Y1 V for each pair of pixels, as from most UVC webcams.
+    Yuyv,
+    /// Y, U and V planes, U and V at half resolution, also known as YUV420p.
+    I420,
+}
+
+impl YuvFormat {
+    /// The size in bytes of a frame of the given dimensions.
+    pub fn frame_len(&self, width: usize, height: usize) -> usize {
+        let chroma = width.div_ceil(2) * height.div_ceil(2);
+        match self {
+            YuvFormat::Nv12 | YuvFormat::Nv21 | YuvFormat::I420 => width * height + 2 * chroma,
+            YuvFormat::Yuyv => 4 * width.div_ceil(2) * height,
+        }
+    }
+}
+
+/// Converts a YUV frame into tightly packed BGR, replacing the contents of `bgr`.
+///
+/// Fails with [`YuNetError::InvalidImage`] if either dimension is zero or `data` is shorter
+/// than [`YuvFormat::frame_len`].
+pub fn to_bgr(
+    format: YuvFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    bgr: &mut Vec<u8>,
+) -> Result<(), YuNetError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("yuv_to_bgr", ?format, width, height).entered();
+    if width == 0 || height == 0 || data.len() < format.frame_len(width, height) {
+        return Err(YuNetError::InvalidImage);
+    }
+    bgr.clear();
+    bgr.resize(3 * width * height, 0);
+    let (luma, chroma) = data.split_at(width * height);
+    let chroma_width = width.div_ceil(2);
+    let chroma_len = chroma_width * height.div_ceil(2);
+    for (y, out) in bgr.chunks_exact_mut(3 * width).enumerate() {
+        let luma_row = &luma[y * width..][..width];
+        let c = y / 2;
+        match format {
+            YuvFormat::Nv12 | YuvFormat::Nv21 => {
+                let uv = &chroma[2 * c * chroma_width..][..2 * chroma_width];
+                let (u, v) = if format == YuvFormat::Nv12 {
+                    (uv, &uv[1..])
+                } else {
+                    (&uv[1..], uv)
+                };
+                convert_row(luma_row, 1, u, v, 2, out);
+            }
+            YuvFormat::I420 => {
+                let u = &chroma[c * chroma_width..][..chroma_width];
+                let v = &chroma[chroma_len + c * chroma_width..][..chroma_width];
+                convert_row(luma_row, 1, u, v, 1, out);
+            }
+            YuvFormat::Yuyv => {
+                let row = &data[4 * chroma_width * y..][..4 * chroma_width];
+                convert_row(row, 2, &row[1..], &row[3..], 4, out);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Converts one row of pixels, reading the luma of pixel `x` at `x * luma_step` and its
+/// chroma at `x / 2 * chroma_step`.
+fn convert_row(
+    luma: &[u8],
+    luma_step: usize,
+    u: &[u8],
+    v: &[u8],
+    chroma_step: usize,
+    out: &mut [u8],
+) {
+    for (x, pixel) in out.chunks_exact_mut(3).enumerate() {
+        let c = x / 2 * chroma_step;
+        pixel.copy_from_slice(&yuv_to_bgr(luma[x * luma_step], u[c], v[c]));
+    }
+}
+
+/// BT.601 limited range, in 8.8 fixed point.
+#[inline(always)]
+fn yuv_to_bgr(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let c = 298 * (y as i32 - 16) + 128;
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+    let clamp = |value: i32| (value >> 8).clamp(0, 255) as u8;
+    [
+        clamp(c + 516 * d),
+        clamp(c - 100 * d - 208 * e),
+        clamp(c + 409 * e),
+    ]
+}
+
+/// How samples of more than 8 bits are brought down to 8 for the network.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ToneMapping {
+    /// Scales the full range of the type, 0..65535 for `u16` and 0..1 for `f32`, to 0..255,
+    /// clamping floats outside it.
+    #[default]
+    Full,
+    /// Stretches the darkest to the brightest sample of each image to 0..255, for sensors
+    /// using part of the range only, such as 10- or 12-bit ones. Exposure then varies with
+    /// the content of each frame.
+    Stretch,
+    /// Compresses the highlights of scene-linear HDR images with Reinhard's `v / (1 + v)`
+    /// and gamma encodes the result, keeping faces in the shadows visible.
+    Reinhard,
+}
+
+/// Samples of more than 8 bits.
+trait Sample: Copy {
+    /// The value of full intensity.
+    const FULL: f32;
+
+    fn value(self) -> f32;
+}
+
+impl Sample for u16 {
+    const FULL: f32 = u16::MAX as f32;
+
+    fn value(self) -> f32 {
+        self as f32
+    }
+}
+
+impl Sample for f32 {
+    const FULL: f32 = 1.0;
+
+    fn value(self) -> f32 {
+        self
+    }
+}
+
+/// Reduces 16-bit samples to 8 bits, replacing the contents of `out`.
+pub fn reduce_u16(samples: &[u16], mapping: ToneMapping, out: &mut Vec<u8>) {
+    reduce(samples, mapping, out);
+}
+
+/// Reduces floating point samples to 8 bits, replacing the contents of `out`. NaNs become
+/// black.
+pub fn reduce_f32(samples: &[f32], mapping: ToneMapping, out: &mut Vec<u8>) {
+    reduce(samples, mapping, out);
+}
+
+fn reduce<S: Sample>(samples: &[S], mapping: ToneMapping, out: &mut Vec<u8>) {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("reduce", samples = samples.len(), ?mapping).entered();
+    let (offset, scale) = match mapping {
+        ToneMapping::Full | ToneMapping::Reinhard => (0.0, 1.0 / S::FULL),
+        ToneMapping::Stretch => {
+            let (min, max) = samples
+                .iter()
+                .map(|s| s.value())
+                .filter(|v| v.is_finite())
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                });
+            (min, if max > min { 1.0 / (max - min) } else { 0.0 })
+        }
+    };
+    out.clear();
+    out.extend(samples.iter().map(|s| {
+        let v = (s.value() - offset) * scale;
+        let v = match mapping {
+            ToneMapping::Reinhard => (v.max(0.0) / (1.0 + v.max(0.0))).powf(1.0 / 2.2),
+            ToneMapping::Full | ToneMapping::Stretch => v,
+        };
+        // Saturating casts turn NaN into 0.
+        (v * 255.0 + 0.5).clamp(0.0, 255.0) as u8
+    }));
+}
+
+impl<'a> ImageView<'a> {
+    /// Reduces a tightly packed 16-bit BGR (`channels` 3) or grayscale (`channels` 1) image
+    /// into `buffer`, which is reused across frames to avoid reallocating, and views it.
+    ///
+    /// Fails with [`YuNetError::InvalidImage`] if `channels` is neither, either dimension is
+    /// zero or `samples` is too short.
+    pub fn from_u16(
+        samples: &[u16],
+        width: usize,
+        height: usize,
+        channels: usize,
+        mapping: ToneMapping,
+        buffer: &'a mut Vec<u8>,
+    ) -> Result<Self, YuNetError> {
+        let samples = packed_samples(samples, width, height, channels)?;
+        reduce_u16(samples, mapping, buffer);
+        ImageView::packed(buffer, width, height, channels)
+    }
+
+    /// Like [`from_u16`](Self::from_u16), for floating point samples.
+    pub fn from_f32(
+        samples: &[f32],
+        width: usize,
+        height: usize,
+        channels: usize,
+        mapping: ToneMapping,
+        buffer: &'a mut Vec<u8>,
+    ) -> Result<Self, YuNetError> {
+        let samples = packed_samples(samples, width, height, channels)?;
+        reduce_f32(samples, mapping, buffer);
+        ImageView::packed(buffer, width, height, channels)
+    }
+
+    /// A 16-bit grayscale image from the `image` crate, reduced into `buffer`.
+    #[cfg(feature = "image")]
+    pub fn from_luma16(
+        image: &image::ImageBuffer<image::Luma<u16>, Vec<u16>>,
+        mapping: ToneMapping,
+        buffer: &'a mut Vec<u8>,
+    ) -> Result<Self, YuNetError> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        Self::from_u16(image.as_raw(), width, height, 1, mapping, buffer)
+    }
+
+    /// A floating point RGB image from the `image` crate, reduced into `buffer` and turned
+    /// into BGR.
+    #[cfg(feature = "image")]
+    pub fn from_rgb32f(
+        image: &image::ImageBuffer<image::Rgb<f32>, Vec<f32>>,
+        mapping: ToneMapping,
+        buffer: &'a mut Vec<u8>,
+    ) -> Result<Self, YuNetError> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let samples = packed_samples(image.as_raw(), width, height, 3)?;
+        reduce_f32(samples, mapping, buffer);
+        for pixel in buffer.chunks_exact_mut(3) {
+            pixel.swap(0, 2);
+        }
+        ImageView::new(buffer, width, height)
+    }
+
+    /// Converts a YUV frame into `bgr`, which is reused across frames to avoid reallocating,
+    /// and views it. See [`to_bgr`] for the failure cases.
+    pub fn from_yuv(
+        format: YuvFormat,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        bgr: &'a mut Vec<u8>,
+    ) -> Result<Self, YuNetError> {
+        to_bgr(format, data, width, height, bgr)?;
+        ImageView::new(bgr, width, height)
+    }
+
+    /// The Y plane of a planar YUV frame as a grayscale image, without converting anything,
+    /// for when color doesn't matter as much as speed. Fails with
+    /// [`YuNetError::InvalidImage`] for [`YuvFormat::Yuyv`], whose luma is interleaved.
+    pub fn yuv_luma(
+        format: YuvFormat,
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Self, YuNetError> {
+        match format {
+            YuvFormat::Nv12 | YuvFormat::Nv21 | YuvFormat::I420 => {
+                ImageView::gray(data, width, height)
+            }
+            YuvFormat::Yuyv => Err(YuNetError::InvalidImage),
+        }
+    }
+}
+
+/// The samples of a tightly packed image, checking its layout.
+fn packed_samples<S>(
+    samples: &[S],
+    width: usize,
+    height: usize,
+    channels: usize,
+) -> Result<&[S], YuNetError> {
+    let len = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .filter(|&len| len > 0 && (channels == 1 || channels == 3))
+        .ok_or(YuNetError::InvalidImage)?;
+    samples.get(..len).ok_or(YuNetError::InvalidImage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_yuv_formats() {
+        // An 8x2 frame of black, white, red and blue 2x2 blocks, as encoded by BT.601.
+        let (width, height) = (8, 2);
+        let luma_row = [16, 16, 235, 235, 81, 81, 41, 41];
+        let (u, v) = ([128, 128, 90, 240], [128, 128, 240, 110]);
+        let luma = [luma_row, luma_row].concat();
+        let uv: Vec<u8> = u.iter().zip(&v).flat_map(|(&u, &v)| [u, v]).collect();
+        let vu: Vec<u8> = u.iter().zip(&v).flat_map(|(&u, &v)| [v, u]).collect();
+        let yuyv_row: Vec<u8> = (0..4)
+            .flat_map(|i| [luma_row[2 * i], u[i], luma_row[2 * i + 1], v[i]])
+            .collect();
+        let frames = [
+            (YuvFormat::Nv12, [&luma[..], &uv].concat()),
+            (YuvFormat::Nv21, [&luma[..], &vu].concat()),
+            (YuvFormat::I420, [&luma[..], &u, &v].concat()),
+            (YuvFormat::Yuyv, [&yuyv_row[..], &yuyv_row].concat()),
+        ];
+
+        let bgr_row = [[0, 0, 0], [255, 255, 255], [0, 0, 255], [255, 0, 0]]
+            .iter()
+            .flat_map(|pixel| [*pixel; 2])
+            .flatten()
+            .collect::<Vec<u8>>();
+        let mut bgr = Vec::new();
+        for (format, data) in &frames {
+            assert_eq!(data.len(), format.frame_len(width, height));
+            let image = ImageView::from_yuv(*format, data, width, height, &mut bgr).unwrap();
+            assert_eq!(3, image.channels());
+            assert_eq!([&bgr_row[..], &bgr_row].concat(), bgr, "{format:?}");
+            let short = &data[..data.len() - 1];
+            assert!(to_bgr(*format, short, width, height, &mut bgr).is_err());
+        }
+
+        let luma_view = ImageView::yuv_luma(YuvFormat::Nv12, &frames[0].1, width, height).unwrap();
+        assert_eq!(1, luma_view.channels());
+        assert_eq!(&luma[..], &luma_view.data()[..luma.len()]);
+        assert!(ImageView::yuv_luma(YuvFormat::Yuyv, &frames[3].1, width, height).is_err());
+    }
+
+    #[test]
+    fn reduces_bit_depth() {
+        let mut out = Vec::new();
+        reduce_u16(&[0, 257, 32896, 65535], ToneMapping::Full, &mut out);
+        assert_eq!(vec![0, 1, 128, 255], out);
+        // A 12-bit sensor only reaches 4095.
+        reduce_u16(&[100, 2100, 4095], ToneMapping::Stretch, &mut out);
+        assert_eq!(vec![0, 128, 255], out);
+        reduce_f32(&[-1.0, 0.5, 2.0, f32::NAN], ToneMapping::Full, &mut out);
+        assert_eq!(vec![0, 128, 255, 0], out);
+        // Reinhard maps 1 to a half, before gamma, and never saturates.
+        reduce_f32(&[0.0, 1.0, 1000.0], ToneMapping::Reinhard, &mut out);
+        assert_eq!(
+            vec![0, (0.5f32.powf(1.0 / 2.2) * 255.0).round() as u8, 255],
+            out
+        );
+
+        let mut buffer = Vec::new();
+        #[cfg(feature = "image")]
+        {
+            let image = image::ImageBuffer::from_raw(2, 1, vec![1.0, 0.5, 0.0, 0.0, 0.0, 1.0f32]);
+            let view = ImageView::from_rgb32f(&image.unwrap(), ToneMapping::Full, &mut buffer);
+            assert_eq!(3, view.unwrap().channels());
+            assert_eq!(vec![0, 128, 255, 255, 0, 0], buffer);
+        }
+        assert!(ImageView::from_u16(&[0; 5], 3, 2, 1, ToneMapping::Full, &mut buffer).is_err());
+        assert!(ImageView::from_u16(&[0; 12], 2, 2, 2, ToneMapping::Full, &mut buffer).is_err());
+        let view = ImageView::from_u16(&[0; 6], 3, 2, 1, ToneMapping::Full, &mut buffer).unwrap();
+        assert_eq!((3, 2), view.dimensions());
+    }
+}
@@ -0,0 +1,185 @@
+//! Detections as flat CSV, a row per face, for spreadsheets and data frames rather than
+//! nested JSON.
+//!
+//! ```no_run
+//! # use rusty_yunet::csv::CsvWriter;
+//! # use rusty_yunet::scan::{scan_directory, ScanOptions};
+//! let mut csv = CsvWriter::new(std::fs::File::create("faces.csv")?)?;
+//! for (path, faces) in scan_directory("photos", ScanOptions::default())? {
+//!     if let Ok(faces) = faces {
+//!         csv.write_faces(&path.display().to_string(), 0, &faces)?;
+//!     }
+//! }
+//! csv.flush()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The columns are the source, such as a file path, the index of the frame in it, zero for
+//! images, the confidence, the rectangle as `x`, `y`, `w` and `h`, the x and y of each
+//! landmark, such as `right_eye_x`, and the `width` and `height` the face was detected at.
+//! Coordinates are top-left pixels unless the writer is given another
+//! [`CoordinateConvention`] with [`with_convention`](CsvWriter::with_convention).
+
+use std::io::{self, BufWriter, Seek, Write};
+
+use crate::{CoordinateConvention, Face, Landmark};
+
+/// Writes detections as CSV with a header row, quoting sources as RFC 4180 does.
+pub struct CsvWriter<W: Write> {
+    out: BufWriter<W>,
+    rows: u64,
+    convention: CoordinateConvention,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Writes the header row to `out`.
+    pub fn new(out: W) -> io::Result<Self> {
+        let mut out = BufWriter::new(out);
+        let landmarks = Landmark::ALL
+            .iter()
+            .map(|landmark| format!("{0}_x,{0}_y", landmark.name()))
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(
+            out,
+            "source,index,confidence,x,y,w,h,{landmarks},width,height"
+        )?;
+        Ok(Self {
+            out,
+            rows: 0,
+            convention: CoordinateConvention::default(),
+        })
+    }
+
+    /// Continues a file the header and earlier rows were written to, such as one
+    /// [`Checkpoint::reopen_sink`](crate::Checkpoint::reopen_sink) returns, without writing
+    /// the header again. [`rows`](Self::rows) counts from zero.
+    pub fn resume(out: W) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            rows: 0,
+            convention: CoordinateConvention::default(),
+        }
+    }
+
+    /// Writes rectangles and landmarks in `convention`, such as normalized for a UI laid out
+    /// in fractions of the screen. Rectangles keep their corner with the smallest
+    /// coordinates, as [`CoordinateConvention::rect`] says.
+    pub fn with_convention(mut self, convention: CoordinateConvention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Writes a row per face of frame `index` of `source`. Frames without faces add no rows.
+    pub fn write_faces(&mut self, source: &str, index: u64, faces: &[Face]) -> io::Result<()> {
+        let source = quote(source);
+        for face in faces {
+            let rect = face.rectangle_in(self.convention);
+            write!(
+                self.out,
+                "{source},{index},{},{},{},{},{}",
+                face.confidence(),
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h
+            )?;
+            for (_, point) in face.landmarks_in(self.convention).iter() {
+                write!(self.out, ",{},{}", point.x, point.y)?;
+            }
+            let (width, height) = face.detection_dimensions();
+            writeln!(self.out, ",{width},{height}")?;
+            self.rows += 1;
+        }
+        Ok(())
+    }
+
+    /// Rows written so far, without the header.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Flushes and returns the bytes written to the underlying writer, for
+    /// [`Checkpoint::with_sink`](crate::Checkpoint::with_sink).
+    pub fn position(&mut self) -> io::Result<u64>
+    where
+        W: Seek,
+    {
+        self.out.flush()?;
+        self.out.get_mut().stream_position()
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(self) -> io::Result<W> {
+        self.out.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// `field` quoted if it holds a comma, quote or line break, its quotes doubled.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, Origin, Rect, Units};
+
+    #[test]
+    fn writes_a_row_per_face() {
+        let face = mock::face(Rect::with_size(10.0, 20.5, 30.0, 40.0), 0.75);
+        let mut csv = CsvWriter::new(Vec::new()).unwrap();
+        csv.write_faces("photos/a.jpg", 0, &[face.clone(), face])
+            .unwrap();
+        csv.write_faces("photos/empty.jpg", 0, &[]).unwrap();
+        csv.write_faces(
+            "photos/\"b\", c.jpg",
+            3,
+            &[mock::face(Rect::with_size(0.0, 0.0, 8.0, 8.0), 0.5)],
+        )
+        .unwrap();
+        assert_eq!(3, csv.rows());
+
+        let text = String::from_utf8(csv.into_inner().unwrap()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(4, lines.len());
+        let header: Vec<&str> = lines[0].split(',').collect();
+        assert_eq!(19, header.len());
+        assert_eq!(["right_eye_x", "right_eye_y"], header[7..9]);
+        assert!(lines[1].starts_with("photos/a.jpg,0,0.75,10,20.5,30,40,"));
+        assert!(lines[1].ends_with(",640,480"));
+        assert!(lines[3].starts_with("\"photos/\"\"b\"\", c.jpg\",3,0.5,"));
+
+        let convention = CoordinateConvention::new(Origin::BottomLeft, Units::Normalized);
+        let mut csv = CsvWriter::new(Vec::new())
+            .unwrap()
+            .with_convention(convention);
+        csv.write_faces(
+            "a.jpg",
+            0,
+            &[mock::face(Rect::with_size(64.0, 96.0, 32.0, 48.0), 0.5)],
+        )
+        .unwrap();
+        let text = String::from_utf8(csv.into_inner().unwrap()).unwrap();
+        let row: Vec<f32> = text
+            .lines()
+            .nth(1)
+            .unwrap()
+            .split(',')
+            .skip(3)
+            .take(4)
+            .map(|v| v.parse().unwrap())
+            .collect();
+        for (expected, actual) in [0.1, 0.7, 0.05, 0.1].into_iter().zip(row) {
+            assert!((expected - actual).abs() < 1e-6, "{text}");
+        }
+    }
+}
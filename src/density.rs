@@ -0,0 +1,252 @@
+//! Face counts per cell of a grid over the frame, for crowd-level occupancy estimates in
+//! wide shots where following individual faces is impractical, and accumulated over time
+//! for heatmaps of where people stand.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+#[cfg(feature = "image")]
+use image::{Rgb, RgbImage};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::Face;
+
+/// How many faces have their center in each cell of a `columns` x `rows` grid laid over the
+/// frame they were detected in.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DensityGrid {
+    columns: usize,
+    rows: usize,
+    counts: Vec<u32>,
+}
+
+impl DensityGrid {
+    /// Both dimensions are raised to at least one cell.
+    pub fn new(faces: &[Face], columns: usize, rows: usize) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let mut counts = vec![0; columns * rows];
+        for face in faces {
+            let center = face.normalized_rectangle().center();
+            let cell = |v: f32, cells: usize| ((v * cells as f32) as usize).min(cells - 1);
+            counts[cell(center.y, rows) * columns + cell(center.x, columns)] += 1;
+        }
+        Self {
+            columns,
+            rows,
+            counts,
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Faces in the cell at the given column and row, starting from the top left.
+    pub fn count(&self, column: usize, row: usize) -> u32 {
+        assert!(
+            column < self.columns && row < self.rows,
+            "cell out of the grid"
+        );
+        self.counts[row * self.columns + column]
+    }
+
+    /// All counts, row by row.
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// The count of the most crowded cell.
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Fraction (0..1) of the cells holding at least one face.
+    pub fn occupancy(&self) -> f32 {
+        let occupied = self.counts.iter().filter(|&&count| count > 0).count();
+        occupied as f32 / self.counts.len() as f32
+    }
+}
+
+/// Where faces were over a stretch of frames: how many frames, and how long, a face had its
+/// center in each cell of a `columns` x `rows` grid, for the position and dwell analytics
+/// of retail and museum installations. Export it as CSV or, with the `image` feature, as
+/// a heatmap.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occupancy {
+    columns: usize,
+    rows: usize,
+    frames: u64,
+    /// Frames with a face in each cell, summed over faces, row by row.
+    face_frames: Vec<u64>,
+    /// Time with a face in each cell, summed over faces, row by row.
+    dwell: Vec<Duration>,
+}
+
+impl Occupancy {
+    /// Both dimensions are raised to at least one cell.
+    pub fn new(columns: usize, rows: usize) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        Self {
+            columns,
+            rows,
+            frames: 0,
+            face_frames: vec![0; columns * rows],
+            dwell: vec![Duration::ZERO; columns * rows],
+        }
+    }
+
+    /// Adds the faces of a frame standing for `duration` of the stream, such as the
+    /// interval to the next frame.
+    pub fn record(&mut self, faces: &[Face], duration: Duration) {
+        let grid = DensityGrid::new(faces, self.columns, self.rows);
+        for ((frames, dwell), &count) in self
+            .face_frames
+            .iter_mut()
+            .zip(&mut self.dwell)
+            .zip(grid.counts())
+        {
+            *frames += count as u64;
+            *dwell += duration * count;
+        }
+        self.frames += 1;
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Frames recorded.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Frames with a face in the cell at the given column and row, starting from the top
+    /// left, counting each face.
+    pub fn face_frames(&self, column: usize, row: usize) -> u64 {
+        self.face_frames[self.index(column, row)]
+    }
+
+    /// Time faces spent in the cell at the given column and row, summed over faces.
+    pub fn dwell(&self, column: usize, row: usize) -> Duration {
+        self.dwell[self.index(column, row)]
+    }
+
+    /// Clears the counts, such as at the start of a new reporting period.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.columns, self.rows);
+    }
+
+    /// One line per cell, row by row, after a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("column,row,face_frames,dwell_secs\n");
+        for (i, (frames, dwell)) in self.face_frames.iter().zip(&self.dwell).enumerate() {
+            let (column, row) = (i % self.columns, i / self.columns);
+            let dwell = dwell.as_secs_f64();
+            let _ = writeln!(csv, "{column},{row},{frames},{dwell:.3}");
+        }
+        csv
+    }
+
+    /// The dwell of each cell as an image of the given size, interpolated between cell
+    /// centers, from black for none through red and yellow to white for the most.
+    #[cfg(feature = "image")]
+    pub fn heatmap(&self, width: u32, height: u32) -> RgbImage {
+        let max = self.dwell.iter().max().copied().unwrap_or_default();
+        let max = max.as_secs_f32().max(f32::MIN_POSITIVE);
+        let value = |column: usize, row: usize| self.dwell(column, row).as_secs_f32() / max;
+        // Cell coordinates of a pixel center, clamped to the outermost cell centers.
+        let cell = |v: u32, pixels: u32, cells: usize| -> (usize, usize, f32) {
+            let at = ((v as f32 + 0.5) / pixels as f32 * cells as f32 - 0.5)
+                .clamp(0.0, (cells - 1) as f32);
+            let low = at.floor() as usize;
+            (low, (low + 1).min(cells - 1), at - low as f32)
+        };
+        RgbImage::from_fn(width, height, |x, y| {
+            let (c0, c1, tx) = cell(x, width, self.columns);
+            let (r0, r1, ty) = cell(y, height, self.rows);
+            let top = value(c0, r0) * (1.0 - tx) + value(c1, r0) * tx;
+            let bottom = value(c0, r1) * (1.0 - tx) + value(c1, r1) * tx;
+            heat(top * (1.0 - ty) + bottom * ty)
+        })
+    }
+
+    fn index(&self, column: usize, row: usize) -> usize {
+        assert!(
+            column < self.columns && row < self.rows,
+            "cell out of the grid"
+        );
+        row * self.columns + column
+    }
+}
+
+/// Black, red, yellow and white at 0, 1/3, 2/3 and 1.
+#[cfg(feature = "image")]
+fn heat(v: f32) -> Rgb<u8> {
+    let channel = |from: f32| ((v * 3.0 - from).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb([channel(0.0), channel(1.0), channel(2.0)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(x: i32, y: i32) -> Face {
+        test_face(0.9, [x as f32, y as f32, 10.0, 10.0], (100, 100), None)
+    }
+
+    #[test]
+    fn counts_faces_by_center() {
+        // Centers at (5, 5), (15, 5), (80, 95) and (99, 99), the last two on the edges.
+        let faces = [face(0, 0), face(10, 0), face(75, 90), face(94, 94)];
+        let grid = DensityGrid::new(&faces, 4, 2);
+        assert_eq!(&[2, 0, 0, 0, 0, 0, 0, 2], grid.counts());
+        assert_eq!(2, grid.count(3, 1));
+        assert_eq!((4, 2, 2), (grid.total(), grid.max(), grid.rows()));
+        assert_eq!(0.25, grid.occupancy());
+    }
+
+    #[test]
+    fn accumulates_dwell_per_cell() {
+        let mut occupancy = Occupancy::new(2, 2);
+        let second = Duration::from_secs(1);
+        occupancy.record(&[face(0, 0), face(90, 90)], second);
+        occupancy.record(&[face(0, 0)], second / 2);
+        occupancy.record(&[], second);
+        assert_eq!(3, occupancy.frames());
+        assert_eq!(2, occupancy.face_frames(0, 0));
+        assert_eq!(second * 3 / 2, occupancy.dwell(0, 0));
+        assert_eq!(Duration::ZERO, occupancy.dwell(1, 0));
+
+        let csv = occupancy.to_csv();
+        assert_eq!(5, csv.lines().count());
+        assert!(csv.contains("0,0,2,1.500\n"));
+        assert!(csv.ends_with("1,1,1,1.000\n"));
+
+        #[cfg(feature = "image")]
+        {
+            let heatmap = occupancy.heatmap(40, 40);
+            assert_eq!(Rgb([255, 255, 255]), heatmap[(0, 0)]);
+            assert_eq!(Rgb([0, 0, 0]), heatmap[(39, 0)]);
+            assert_eq!(Rgb([255, 255, 0]), heatmap[(39, 39)]);
+        }
+
+        occupancy.reset();
+        assert_eq!(Occupancy::new(2, 2), occupancy);
+    }
+}
@@ -0,0 +1,1762 @@
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+#[cfg(feature = "image")]
+use std::path::Path;
+use std::sync::Arc;
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::calibration::Calibration;
+use crate::convert::ToneMapping;
+#[cfg(feature = "image")]
+use crate::exif;
+use crate::filter::FaceFilter;
+use crate::geometry::{CoordinateMapper, Fit};
+use crate::io::{FrameBuffer, ImageView};
+use crate::orientation::{Orientation, Rotation};
+#[cfg(feature = "native")]
+use crate::provenance::fnv1a;
+use crate::provenance::{ModelInfo, Provenance};
+use crate::selection::ResultOrder;
+#[cfg(feature = "metrics")]
+use crate::telemetry;
+use crate::{resample, Face, FaceBox, Rect, YuNetError};
+
+/// Context around a face included in its refinement crop, relative to the face size.
+const REFINEMENT_MARGIN: f32 = 0.5;
+/// How well a re-detected face must overlap the original to lend it its landmarks.
+const REFINEMENT_MIN_IOU: f32 = 0.5;
+
+/// The most pixels the network takes in one frame, beyond which libfacedetection's buffer
+/// sizes overflow. Larger frames fail with [`YuNetError::ImageTooLarge`] unless
+/// [`max_side`](DetectorConfig::max_side) downscales them below it.
+pub const MAX_INPUT_PIXELS: usize = 1 << 25;
+
+mod backend;
+#[cfg(feature = "image")]
+mod batch;
+mod buffer;
+mod lenient;
+mod network;
+mod nms;
+mod pipelined;
+mod pool;
+mod raw;
+mod request;
+#[cfg(feature = "image")]
+mod self_test;
+mod stats;
+mod tiled;
+#[cfg(feature = "config")]
+mod watch;
+pub use backend::{available_backends, Backend, DetectionBackend, Target};
+pub use buffer::FaceBuffer;
+pub use lenient::{DetectionOutcome, DetectionWarning};
+#[cfg(feature = "native")]
+pub(crate) use network::variant_of;
+use network::Network;
+pub(crate) use network::RawFace;
+#[cfg(feature = "native")]
+pub use network::BUNDLED_WEIGHTS as NATIVE_MODEL;
+pub use nms::NmsStrategy;
+pub use pipelined::PipelinedDetector;
+pub use pool::{FaceDetectorPool, PooledDetector};
+pub use raw::{RawLevel, RawOutput};
+pub use request::DetectionRequest;
+#[cfg(feature = "image")]
+pub use self_test::{ReferenceMatch, SelfTestConfig, SelfTestReport};
+pub use stats::{DetectionStats, DetectorStats, STATS_WINDOW};
+use stats::{Instant, StatsAccumulator};
+pub use tiled::{TileConfig, TiledDetector};
+#[cfg(feature = "config")]
+pub use watch::{ConfigWatcher, WATCH_INTERVAL};
+
+/// Tuning knobs for a [`FaceDetector`].
+///
+/// Like the other configurations of this crate, it's non-exhaustive, so that knobs can be
+/// added in minor releases: start from [`Default`] and set the fields needed.
+///
+/// ```
+/// # use rusty_yunet::DetectorConfig;
+/// let mut config = DetectorConfig::default();
+/// config.max_side = Some(1280);
+/// config.min_confidence = Some(0.8);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct DetectorConfig {
+    /// Downscale frames so that neither side exceeds this many pixels before running the
+    /// network. Faces are still reported in the coordinates of the original frame.
+    pub max_side: Option<usize>,
+    /// Run the network at exactly this resolution (width, height), fitting every frame to it
+    /// as [`resize`](Self::resize) says, such as for the same latency whatever the camera.
+    /// Overrides [`max_side`](Self::max_side), which then only bounds landmark refinement.
+    /// Faces are still reported in the coordinates of the original frame.
+    pub input_size: Option<(usize, usize)>,
+    /// How frames are fitted to [`input_size`](Self::input_size) when their proportions
+    /// differ.
+    pub resize: ResizeStrategy,
+    /// When a frame was downscaled, re-detect each face on a full-resolution crop and take
+    /// its landmarks from there, so that alignment doesn't suffer from the downscaling.
+    pub refine_landmarks: bool,
+    /// Mirror frames horizontally before detection, such as those of a selfie preview.
+    /// Faces are still reported in the coordinates of the original frame.
+    pub mirror: bool,
+    /// Rotate frames clockwise by this, after any mirroring, before detection, such as to
+    /// turn the frames of a camera mounted sideways upright. Faces are still reported in the
+    /// coordinates of the original frame.
+    pub rotation: Rotation,
+    /// Merge overlapping candidates with this strategy instead of the network's own hard
+    /// suppression.
+    pub nms: Option<NmsStrategy>,
+    /// Drop faces whose shorter side is smaller than this, before any landmark refinement.
+    pub min_face_size: Option<FaceSize>,
+    /// Drop faces less confident than this, after any [`calibration`](Self::calibration).
+    pub min_confidence: Option<f32>,
+    /// The order faces are returned in, whichever the backend.
+    pub result_order: ResultOrder,
+    /// Record which model and backend produced each face; see [`Face::provenance`].
+    pub provenance: bool,
+    /// Report calibrated probabilities as [`Face::confidence`] rather than YuNet's raw
+    /// scores, from the start of post-processing on, so that [`nms`](Self::nms) and
+    /// filters see them too.
+    pub calibration: Option<Calibration>,
+    /// Must be one of [`available_backends`].
+    ///
+    /// Defaults to [`Backend::LibFaceDetection`] when the `libfacedetection` feature is
+    /// enabled anywhere in the dependency graph, and to [`Backend::Native`] otherwise. Set
+    /// it explicitly to pin the backend regardless of features.
+    pub backend: Backend,
+    pub target: Target,
+}
+
+/// How frames are fitted to a fixed [`DetectorConfig::input_size`] of other proportions.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ResizeStrategy {
+    /// Scaled to the input size, distorting faces the more the proportions differ, which
+    /// costs accuracy at extreme aspect ratios.
+    Stretch,
+    /// Scaled to fit and padded with black, keeping faces undistorted.
+    #[default]
+    Letterbox,
+    /// Scaled to cover and cut to the center, keeping faces undistorted but missing those
+    /// near two edges.
+    Crop,
+}
+
+impl ResizeStrategy {
+    fn fit(self) -> Fit {
+        match self {
+            ResizeStrategy::Stretch => Fit::Stretch,
+            ResizeStrategy::Letterbox => Fit::Contain,
+            ResizeStrategy::Crop => Fit::Cover,
+        }
+    }
+}
+
+/// A face size threshold, compared with the shorter side of the face rectangle.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaceSize {
+    /// Pixels of the original frame, whatever the resolution the network ran at.
+    Pixels(f32),
+    /// A fraction (0..1) of the frame, as in [`Face::size`].
+    Fraction(f32),
+}
+
+impl FaceSize {
+    pub(crate) fn admits(&self, face: &Face) -> bool {
+        match *self {
+            FaceSize::Pixels(min) => {
+                let rect = face.rectangle();
+                rect.w.min(rect.h) >= min
+            }
+            FaceSize::Fraction(min) => face.size() >= min,
+        }
+    }
+}
+
+/// A YuNet face detector owning its own copy of the network state.
+///
+/// Detectors are `Send` but not `Sync`: move one into each worker thread, or share a
+/// [`FaceDetectorPool`] between threads instead.
+pub struct FaceDetector {
+    network: Network,
+    config: DetectorConfig,
+    stats: StatsAccumulator,
+    filter: FaceFilter,
+    /// The file configuration is reloaded from, with the generation last applied.
+    #[cfg(feature = "config")]
+    watcher: Option<(ConfigWatcher, u64)>,
+    /// The hash of the parameters passed to `with_native_model`, unless bundled.
+    custom_model: Option<u64>,
+    buffers: ScratchFrames,
+    /// Keeps the auto traits the same whichever backends are compiled in.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// Intermediate frames kept from one detection to the next, reused while the frames keep
+/// their dimensions.
+#[derive(Default)]
+struct ScratchFrames {
+    /// The frame rotated or mirrored.
+    oriented: Vec<u8>,
+    /// The frame resized to the network's input.
+    resized: Vec<u8>,
+}
+
+/// How [`prepare`] fitted a frame to the network, for [`FaceDetector::finish`] to map the
+/// faces back.
+#[derive(Debug, Clone, Copy)]
+struct Prepared {
+    orientation: Orientation,
+    input_size: (usize, usize),
+    /// From the oriented frame to the network's input.
+    mapper: CoordinateMapper,
+    /// Whether faces get [`refine_landmarks`](DetectorConfig::refine_landmarks).
+    refine_landmarks: bool,
+}
+
+/// What a single detection does differently from the configuration, without changing it,
+/// so that a [`ConfigWatcher`] reloading it meanwhile isn't undone afterwards.
+#[derive(Debug, Clone, Copy, Default)]
+struct Overrides {
+    /// Rotation and mirroring in place of the configured ones, such as by EXIF.
+    orientation: Option<(Rotation, bool)>,
+    /// Skip [`refine_landmarks`](DetectorConfig::refine_landmarks) whatever the
+    /// configuration.
+    skip_refinement: bool,
+}
+
+impl Prepared {
+    fn is_downscaled(&self) -> bool {
+        self.input_size != self.orientation.oriented_dimensions()
+    }
+
+    /// The oriented frame and the network's input, each either in `buffers` or `original`
+    /// itself.
+    fn views<'a>(
+        &self,
+        original: &ImageView<'a>,
+        buffers: &'a ScratchFrames,
+    ) -> (ImageView<'a>, ImageView<'a>) {
+        let image = self.oriented(original, &buffers.oriented);
+        let input = if self.is_downscaled() {
+            let (width, height) = self.input_size;
+            ImageView::packed(&buffers.resized, width, height, original.channels())
+                .expect("resized buffer is packed")
+        } else {
+            image
+        };
+        (image, input)
+    }
+
+    fn oriented<'a>(&self, original: &ImageView<'a>, oriented: &'a [u8]) -> ImageView<'a> {
+        if self.orientation.is_identity() {
+            return *original;
+        }
+        let (width, height) = self.orientation.oriented_dimensions();
+        ImageView::packed(oriented, width, height, original.channels())
+            .expect("oriented buffer is packed")
+    }
+}
+
+/// The part of a detection before the network runs: orients `image` and fits it to the
+/// network's input as `config` and `overrides` say, into `buffers`. Needs no detector, so
+/// that it can run on another thread than the network; see [`PipelinedDetector`].
+fn prepare(
+    config: &DetectorConfig,
+    overrides: Overrides,
+    image: &ImageView,
+    buffers: &mut ScratchFrames,
+) -> Result<Prepared, YuNetError> {
+    let (rotation, mirror) = overrides
+        .orientation
+        .unwrap_or((config.rotation, config.mirror));
+    let orientation = Orientation {
+        rotation,
+        mirror,
+        dimensions: image.dimensions(),
+    };
+    if !orientation.is_identity() {
+        orientation.apply_into(image, &mut buffers.oriented);
+    }
+    let (width, height) = orientation.oriented_dimensions();
+    let (input_size, fit) = match (config.input_size, config.max_side) {
+        (Some(input_size), _) => (input_size, config.resize.fit()),
+        (None, Some(max_side)) => (resample::fit_within(width, height, max_side), Fit::Stretch),
+        (None, None) => ((width, height), Fit::Stretch),
+    };
+    let mapper = CoordinateMapper::new((width, height), input_size, fit);
+    let stride = if input_size == (width, height) {
+        if orientation.is_identity() {
+            image.stride()
+        } else {
+            width * image.channels()
+        }
+    } else {
+        input_size.0 * image.channels()
+    };
+    if input_size.0.saturating_mul(input_size.1) > MAX_INPUT_PIXELS || stride > i32::MAX as usize {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            width = input_size.0,
+            height = input_size.1,
+            "frame too large for the network"
+        );
+        return Err(YuNetError::ImageTooLarge {
+            width: input_size.0,
+            height: input_size.1,
+        });
+    }
+    let prepared = Prepared {
+        orientation,
+        input_size,
+        mapper,
+        refine_landmarks: config.refine_landmarks && !overrides.skip_refinement,
+    };
+    if prepared.is_downscaled() {
+        let oriented = prepared.oriented(image, &buffers.oriented);
+        resample::fit_into(&oriented, &mapper, &mut buffers.resized);
+    }
+    Ok(prepared)
+}
+
+impl FaceDetector {
+    pub fn new() -> Self {
+        Self::with_config(DetectorConfig::default()).expect("default backend is always available")
+    }
+
+    /// Fails if the configured backend and target aren't available in this build.
+    pub fn with_config(config: DetectorConfig) -> Result<Self, YuNetError> {
+        check_backend(&config)?;
+        Ok(Self {
+            network: Network::new(config.backend)?,
+            config,
+            stats: StatsAccumulator::default(),
+            filter: FaceFilter::new(),
+            #[cfg(feature = "config")]
+            watcher: None,
+            custom_model: None,
+            buffers: ScratchFrames::default(),
+            _not_sync: PhantomData,
+        })
+    }
+
+    /// Runs the native backend on parameters passed as bytes, such as a copy of
+    /// [`NATIVE_MODEL`] fetched at runtime by a browser build, or weights fine-tuned without
+    /// changing the architecture. `config.backend` is ignored.
+    ///
+    /// Parameters quantized with [`quantize_native_model`] run as
+    /// [`ModelVariant::Yunet2023MarInt8`], which [`model_info`](Self::model_info) reports.
+    ///
+    /// Fails with [`YuNetError::ModelLoad`] if the bytes aren't in the format of
+    /// [`NATIVE_MODEL`] or describe layers of other shapes.
+    #[cfg(feature = "native")]
+    pub fn with_native_model(mut config: DetectorConfig, model: &[u8]) -> Result<Self, YuNetError> {
+        config.backend = Backend::Native;
+        check_backend(&config)?;
+        Ok(Self {
+            network: Network::native_from_bytes(model)?,
+            config,
+            stats: StatsAccumulator::default(),
+            filter: FaceFilter::new(),
+            #[cfg(feature = "config")]
+            watcher: None,
+            custom_model: (model != NATIVE_MODEL).then(|| fnv1a(model)),
+            buffers: ScratchFrames::default(),
+            _not_sync: PhantomData,
+        })
+    }
+
+    pub fn config(&self) -> &DetectorConfig {
+        &self.config
+    }
+
+    /// Which model this detector runs, on which backend. With
+    /// [`provenance`](DetectorConfig::provenance) set, faces record the same in their
+    /// [`Provenance`].
+    pub fn model_info(&self) -> ModelInfo {
+        ModelInfo::new(
+            self.custom_model,
+            self.network.variant(),
+            self.config.input_size,
+            self.config.backend,
+        )
+    }
+
+    /// Replaces the configuration. Changing the backend replaces the network, which fails if
+    /// the new backend isn't available in this build, and drops any model passed to
+    /// [`with_native_model`](Self::with_native_model).
+    pub fn set_config(&mut self, config: DetectorConfig) -> Result<(), YuNetError> {
+        if (config.backend, config.target) != (self.config.backend, self.config.target) {
+            check_backend(&config)?;
+            self.network = Network::new(config.backend)?;
+            self.custom_model = None;
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    /// Detects faces in a tightly packed BGR image of the given dimensions, in the configured
+    /// [`result_order`](DetectorConfig::result_order).
+    pub fn detect(
+        &mut self,
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image(&ImageView::new(bytes, width, height)?)
+    }
+
+    /// Like [`detect`](Self::detect), also reporting where the time went.
+    pub fn detect_with_stats(
+        &mut self,
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<(Vec<Face>, DetectionStats), YuNetError> {
+        self.detect_image_with_stats(&ImageView::new(bytes, width, height)?)
+    }
+
+    /// Like [`detect`](Self::detect), on a tightly packed 8-bit grayscale image, such as a
+    /// frame of an IR camera.
+    pub fn detect_gray(
+        &mut self,
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image(&ImageView::gray(bytes, width, height)?)
+    }
+
+    /// Detects faces in a BGR or grayscale image.
+    pub fn detect_image(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image_with_stats(image).map(|(faces, _)| faces)
+    }
+
+    /// Like [`detect_image`](Self::detect_image), dropping faces the network got wrong, such
+    /// as with a negative width, and flagging doubtful ones, such as with a landmark far
+    /// outside the frame, as [`DetectionWarning`]s rather than passing them on silently.
+    pub fn detect_lenient(&mut self, image: &ImageView) -> Result<DetectionOutcome, YuNetError> {
+        self.detect_image(image).map(lenient::inspect)
+    }
+
+    /// Like [`detect_image`](Self::detect_image), returning only where the faces are, for
+    /// uses that don't need landmarks. Skips
+    /// [`refine_landmarks`](DetectorConfig::refine_landmarks), which runs the network again
+    /// around every face, whatever the configuration.
+    pub fn detect_boxes(&mut self, image: &ImageView) -> Result<Vec<FaceBox>, YuNetError> {
+        let overrides = Overrides {
+            skip_refinement: true,
+            ..Default::default()
+        };
+        let faces = self.detect_overridden(image, overrides)?;
+        Ok(faces.iter().map(FaceBox::from).collect())
+    }
+
+    /// Decodes an image file and detects faces in it, turning JPEGs upright by their EXIF
+    /// orientation first in place of the configured [`mirror`](DetectorConfig::mirror) and
+    /// [`rotation`](DetectorConfig::rotation). Faces are reported in the coordinates of the
+    /// decoded pixels, as stored in the file and as returned by `image::open`.
+    #[cfg(feature = "image")]
+    pub fn detect_file(&mut self, path: impl AsRef<Path>) -> Result<Vec<Face>, YuNetError> {
+        let bytes = std::fs::read(path).map_err(|e| YuNetError::Decode(e.to_string()))?;
+        self.detect_encoded(&bytes)
+    }
+
+    /// Like [`detect_file`](Self::detect_file), on the contents of an image file, such as an
+    /// upload.
+    #[cfg(feature = "image")]
+    pub fn detect_encoded(&mut self, bytes: &[u8]) -> Result<Vec<Face>, YuNetError> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| YuNetError::Decode(e.to_string()))?
+            .to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let overrides = Overrides {
+            orientation: Some(exif::jpeg_orientation(bytes).unwrap_or_default()),
+            ..Default::default()
+        };
+        self.detect_overridden(&ImageView::new(image.as_raw(), width, height)?, overrides)
+    }
+
+    /// Like [`detect_image`](Self::detect_image), with `overrides` for this frame only.
+    fn detect_overridden(
+        &mut self,
+        image: &ImageView,
+        overrides: Overrides,
+    ) -> Result<Vec<Face>, YuNetError> {
+        let mut faces = Vec::new();
+        let result = self
+            .run_into(image, true, &mut faces, Output::Allocated, overrides)
+            .map(|(stats, _)| stats);
+        #[cfg(feature = "metrics")]
+        self.record_metrics(result.as_ref());
+        result.map(|_| faces)
+    }
+
+    /// Like [`detect_image`](Self::detect_image), also reporting where the time went.
+    pub fn detect_image_with_stats(
+        &mut self,
+        image: &ImageView,
+    ) -> Result<(Vec<Face>, DetectionStats), YuNetError> {
+        let result = self.run(image, true);
+        #[cfg(feature = "metrics")]
+        self.record_metrics(result.as_ref().map(|(_, stats)| stats));
+        result
+    }
+
+    /// Like [`detect_image_with_stats`](Self::detect_image_with_stats), writing the faces
+    /// into `faces`, cleared first, so that a vector kept from frame to frame is reused
+    /// rather than one allocated per frame, such as on constrained devices.
+    pub fn detect_into(
+        &mut self,
+        image: &ImageView,
+        faces: &mut Vec<Face>,
+    ) -> Result<DetectionStats, YuNetError> {
+        let result = self
+            .run_into(image, true, faces, Output::Allocated, Overrides::default())
+            .map(|(stats, _)| stats);
+        #[cfg(feature = "metrics")]
+        self.record_metrics(result.as_ref());
+        result
+    }
+
+    /// Like [`detect_into`](Self::detect_into), into the fixed slots of `buffer`, which the
+    /// C++ network writes its faces into directly. Faces beyond its capacity are dropped,
+    /// least confident first; see [`FaceBuffer::truncated`]. Only custom
+    /// [`nms`](DetectorConfig::nms) and landmark refinement still allocate.
+    pub fn detect_buffered(
+        &mut self,
+        image: &ImageView,
+        buffer: &mut FaceBuffer,
+    ) -> Result<DetectionStats, YuNetError> {
+        let FaceBuffer { raw, faces, found } = buffer;
+        let result = self.run_into(image, true, faces, Output::Slots(raw), Overrides::default());
+        faces.truncate(raw.len());
+        *found = result.as_ref().map_or(0, |&(_, found)| found);
+        let result = result.map(|(stats, _)| stats);
+        #[cfg(feature = "metrics")]
+        self.record_metrics(result.as_ref());
+        result
+    }
+
+    /// Like [`detect_image`](Self::detect_image), also returning the undecoded outputs of
+    /// the network's heads, for decoding, calibration or confidence re-mapping of your own.
+    pub fn detect_with_raw_output(
+        &mut self,
+        image: &ImageView,
+    ) -> Result<(Vec<Face>, RawOutput), YuNetError> {
+        let mut faces = Vec::new();
+        let mut raw = RawOutput::default();
+        let result = self
+            .run_into(
+                image,
+                true,
+                &mut faces,
+                Output::Raw(&mut raw),
+                Overrides::default(),
+            )
+            .map(|(stats, _)| stats);
+        #[cfg(feature = "metrics")]
+        self.record_metrics(result.as_ref());
+        result.map(|_| (faces, raw))
+    }
+
+    /// Like [`detect`](Self::detect), returning every candidate the network scored above its
+    /// confidence threshold, in the configured [`ResultOrder`], before non-maximum suppression merges
+    /// overlapping ones. For custom suppression, such as soft-NMS, or for studying the
+    /// network. Landmarks aren't refined.
+    pub fn detect_candidates(
+        &mut self,
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image_candidates(&ImageView::new(bytes, width, height)?)
+    }
+
+    /// Like [`detect_candidates`](Self::detect_candidates), on a BGR image.
+    pub fn detect_image_candidates(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        let result = self.run(image, false);
+        #[cfg(feature = "metrics")]
+        self.record_metrics(result.as_ref().map(|(_, stats)| stats));
+        result.map(|(faces, _)| faces)
+    }
+
+    /// Records a frame, or its failure, in the [`telemetry`](crate::telemetry) metrics.
+    #[cfg(feature = "metrics")]
+    fn record_metrics(&self, result: Result<&DetectionStats, &YuNetError>) {
+        match result {
+            Ok(stats) => telemetry::record_frame(self.config.backend, stats),
+            Err(error) => telemetry::record_error(self.config.backend, error),
+        }
+    }
+
+    fn run(
+        &mut self,
+        image: &ImageView,
+        suppress: bool,
+    ) -> Result<(Vec<Face>, DetectionStats), YuNetError> {
+        let mut faces = Vec::new();
+        let (stats, _) = self.run_into(
+            image,
+            suppress,
+            &mut faces,
+            Output::Allocated,
+            Overrides::default(),
+        )?;
+        Ok((faces, stats))
+    }
+
+    /// Detects into `faces`, having the network write where `output` says, and returns how
+    /// many faces the network found.
+    fn run_into(
+        &mut self,
+        image: &ImageView,
+        suppress: bool,
+        faces: &mut Vec<Face>,
+        output: Output,
+        overrides: Overrides,
+    ) -> Result<(DetectionStats, usize), YuNetError> {
+        faces.clear();
+        #[cfg(feature = "config")]
+        self.reload_config();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "detect",
+            width = image.width(),
+            height = image.height(),
+            channels = image.channels(),
+            backend = ?self.config.backend,
+        )
+        .entered();
+        let started = Instant::now();
+        // Taken for the frame and put back after it, so that frames of the same dimensions
+        // reuse the allocations. A failed frame drops them.
+        let mut buffers = std::mem::take(&mut self.buffers);
+        let prepared = prepare(&self.config, overrides, image, &mut buffers)?;
+        let preprocess_ms = stats::millis(started.elapsed());
+        let result = self.finish(
+            image,
+            &buffers,
+            &prepared,
+            preprocess_ms,
+            suppress,
+            faces,
+            output,
+        )?;
+        self.buffers = buffers;
+        Ok(result)
+    }
+
+    /// The part of a detection after [`prepare`]: runs the network on the prepared input
+    /// and maps its faces back onto `original`.
+    #[allow(clippy::too_many_arguments)]
+    fn finish(
+        &mut self,
+        original: &ImageView,
+        buffers: &ScratchFrames,
+        prepared: &Prepared,
+        preprocess_ms: f64,
+        suppress: bool,
+        faces: &mut Vec<Face>,
+        output: Output,
+    ) -> Result<(DetectionStats, usize), YuNetError> {
+        let preprocessed = Instant::now();
+        let Prepared {
+            orientation,
+            input_size,
+            mapper,
+            refine_landmarks,
+        } = *prepared;
+        let (image, input) = prepared.views(original, buffers);
+        let suppressed = suppress && self.config.nms.is_none();
+        let allocated;
+        let (raw_faces, found): (&[RawFace], usize) = match output {
+            Output::Slots(slots) if suppressed => {
+                let found = self.network.infer_into(&input, slots)?;
+                (&slots[..found.min(slots.len())], found)
+            }
+            Output::Raw(raw) => {
+                raw.input_size = input_size;
+                allocated = self.network.infer_raw(&input, suppressed, raw)?;
+                (&allocated, allocated.len())
+            }
+            _ => {
+                allocated = if suppressed {
+                    self.network.infer(&input)?
+                } else {
+                    self.network.infer_candidates(&input)?
+                };
+                (&allocated, allocated.len())
+            }
+        };
+        let inferred = Instant::now();
+
+        let restore = mapper.inverse();
+        faces.extend(
+            raw_faces
+                .iter()
+                .map(|f| restore.face(&Face::from_raw_face(f, input_size))),
+        );
+        if let Some(calibration) = &self.config.calibration {
+            for face in faces.iter_mut() {
+                face.set_confidence(calibration.apply(face.confidence()));
+            }
+        }
+        if let Some(nms) = self.config.nms.filter(|_| suppress) {
+            *faces = nms.suppress(std::mem::take(faces));
+        }
+        if let Some(min_size) = self.config.min_face_size {
+            faces.retain(|face| min_size.admits(face));
+        }
+        if let Some(min_confidence) = self.config.min_confidence {
+            faces.retain(|face| face.confidence() >= min_confidence);
+        }
+        if let Some(max_side) = self
+            .config
+            .max_side
+            .filter(|_| suppress && refine_landmarks && prepared.is_downscaled())
+        {
+            for face in faces.iter_mut() {
+                self.refine_landmarks(face, &image, max_side);
+            }
+        }
+        if !orientation.is_identity() {
+            for face in faces.iter_mut() {
+                *face = face.mapped(|p| orientation.restore(p), orientation.dimensions);
+            }
+        }
+        if suppress {
+            self.filter.apply(original, faces);
+        }
+        self.config.result_order.sort(faces);
+        if self.config.provenance {
+            let provenance = Arc::new(Provenance::current(input_size, &self.model_info()));
+            for face in faces.iter_mut() {
+                face.set_provenance(Arc::clone(&provenance));
+            }
+        }
+
+        let stats = DetectionStats {
+            preprocess_ms,
+            inference_ms: stats::millis(inferred - preprocessed),
+            postprocess_ms: stats::millis(inferred.elapsed()),
+            faces_found: faces.len(),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            faces = stats.faces_found,
+            input_width = input_size.0,
+            input_height = input_size.1,
+            preprocess_ms = stats.preprocess_ms,
+            inference_ms = stats.inference_ms,
+            postprocess_ms = stats.postprocess_ms,
+            "detected"
+        );
+        self.stats.record(stats);
+        Ok((stats, found))
+    }
+
+    /// Runs a detection on a blank frame of the given dimensions, so that one-off costs such
+    /// as loading the bundled weights and allocating buffers are paid before the first real
+    /// frame rather than during it. Pass the dimensions of the frames to come. The run isn't
+    /// counted in [`stats`](Self::stats); its own timings are returned.
+    pub fn warm_up(&mut self, width: usize, height: usize) -> Result<DetectionStats, YuNetError> {
+        let blank = vec![128; width.saturating_mul(height).saturating_mul(3)];
+        let image = ImageView::new(&blank, width, height)?;
+        let stats = std::mem::take(&mut self.stats);
+        let result = self.run(&image, true);
+        self.stats = stats;
+        result.map(|(_, stats)| stats)
+    }
+
+    /// Drops the faces that fail `filter` from every detection, though not from
+    /// [`detect_candidates`](Self::detect_candidates).
+    pub fn with_filter(mut self, filter: FaceFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Takes the configuration from `watcher`, now and whenever its file changes. Changes
+    /// apply between detections, never during one.
+    #[cfg(feature = "config")]
+    pub fn with_config_watcher(mut self, watcher: ConfigWatcher) -> Self {
+        self.watcher = Some((watcher, u64::MAX));
+        self.reload_config();
+        self
+    }
+
+    /// Applies the watched configuration if it changed since last applied.
+    #[cfg(feature = "config")]
+    fn reload_config(&mut self) {
+        let Some((watcher, generation)) = &self.watcher else {
+            return;
+        };
+        if let Some((config, generation)) = watcher.update_since(*generation) {
+            if let Err(_error) = self.set_config(config) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(%_error, "watched configuration not applied");
+            }
+            if let Some((_, applied)) = &mut self.watcher {
+                *applied = generation;
+            }
+        }
+    }
+
+    pub fn filter(&self) -> &FaceFilter {
+        &self.filter
+    }
+
+    /// Cumulative statistics over all detections run by this detector.
+    pub fn stats(&self) -> DetectorStats {
+        self.stats.snapshot()
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = StatsAccumulator::default();
+    }
+
+    /// Releases the rotated and resized copies of frames kept for the next frame, such as
+    /// when the resolution is about to change for good or the detector goes idle.
+    pub fn reset_buffers(&mut self) {
+        self.buffers = ScratchFrames::default();
+    }
+
+    /// Releases the network, and the model and buffers it owns, returning the statistics
+    /// of the detector's lifetime. Dropping a detector releases the same resources; `close`
+    /// only makes the point of teardown explicit, such as on shutdown. Every backend runs on
+    /// the CPU, so there are no device contexts to release beyond that.
+    pub fn close(self) -> DetectorStats {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("closing detector");
+        self.stats()
+    }
+
+    /// Detects faces in the current contents of a caller-managed frame buffer, reading it in
+    /// place.
+    pub fn detect_frame(&mut self, frame: &FrameBuffer) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image(&frame.view())
+    }
+
+    /// Re-detects a face within a full-resolution crop around it, adopting the landmarks of
+    /// the matching detection. Faces that can't be matched keep their original landmarks.
+    fn refine_landmarks(&self, face: &mut Face, image: &ImageView, max_side: usize) {
+        let rect = face.rectangle();
+        let margin = rect.w.max(rect.h) * REFINEMENT_MARGIN;
+        let x0 = (rect.x - margin).floor().max(0.0) as usize;
+        let y0 = (rect.y - margin).floor().max(0.0) as usize;
+        let x1 = (rect.x + rect.w + margin).ceil().max(0.0) as usize;
+        let y1 = (rect.y + rect.h + margin).ceil().max(0.0) as usize;
+        let Some(crop) = image.crop(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0)) else {
+            return;
+        };
+
+        // Very large faces can produce crops that are more expensive than the frame itself.
+        let (fit_width, fit_height) = resample::fit_within(crop.width(), crop.height(), max_side);
+        let candidates = if (fit_width, fit_height) == crop.dimensions() {
+            self.run_network(&crop)
+        } else {
+            self.run_network_resized(&crop, fit_width, fit_height)
+        };
+
+        let offset = Vec2::new(x0 as f32, y0 as f32);
+        let local = Rect::new(Vec2::new(rect.x, rect.y) - offset, rect.w, rect.h);
+        let best = candidates
+            .iter()
+            .map(|candidate| (candidate.rectangle().iou(&local), candidate))
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some((iou, candidate)) = best {
+            if iou >= REFINEMENT_MIN_IOU {
+                face.set_landmarks(candidate.landmarks().map(|p| p + offset));
+            }
+        }
+    }
+
+    /// Runs the network on a downscaled copy of `image`, reporting faces in the coordinates
+    /// of `image` itself.
+    fn run_network_resized(&self, image: &ImageView, width: usize, height: usize) -> Vec<Face> {
+        let small = resample::resize(image, width, height);
+        let small = ImageView::packed(&small, width, height, image.channels())
+            .expect("resized buffer is packed");
+        let scale = Vec2::new(
+            image.width() as f32 / width as f32,
+            image.height() as f32 / height as f32,
+        );
+        self.run_network(&small)
+            .iter()
+            .map(|face| face.rescaled(scale, image.dimensions()))
+            .collect()
+    }
+
+    /// Refinement is best effort: a crop the network fails on has no candidates, leaving
+    /// the landmarks of its face as they were.
+    fn run_network(&self, image: &ImageView) -> Vec<Face> {
+        self.network
+            .infer(image)
+            .unwrap_or_default()
+            .iter()
+            .map(|f| Face::from_raw_face(f, image.dimensions()))
+            .collect()
+    }
+}
+
+/// Where [`FaceDetector::run_into`] has the network write its faces.
+enum Output<'a> {
+    /// Into a vector allocated per frame.
+    Allocated,
+    /// Into fixed slots, when suppressing in the network.
+    Slots(&'a mut [RawFace]),
+    /// Into a vector allocated per frame, along with the outputs of the heads.
+    Raw(&'a mut RawOutput),
+}
+
+fn check_backend(config: &DetectorConfig) -> Result<(), YuNetError> {
+    if available_backends().contains(&(config.backend, config.target)) {
+        Ok(())
+    } else {
+        Err(YuNetError::UnsupportedBackend {
+            backend: config.backend,
+            target: config.target,
+        })
+    }
+}
+
+impl Default for FaceDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Quantizes float parameters in the format of [`NATIVE_MODEL`], such as [`NATIVE_MODEL`]
+/// itself, to int8, for [`FaceDetector::with_native_model`] to run as
+/// [`ModelVariant::Yunet2023MarInt8`](crate::ModelVariant::Yunet2023MarInt8). Each filter
+/// gets a scale mapping its largest weight to 127.
+///
+/// Fails with [`YuNetError::ModelLoad`] if the bytes aren't float YuNet parameters.
+#[cfg(feature = "native")]
+pub fn quantize_native_model(model: &[u8]) -> Result<Vec<u8>, YuNetError> {
+    network::quantize(model)
+}
+
+/// The networks currently alive in the process, over every detector, pool and thread-local
+/// detector, for checking that a long-running process releases them.
+pub fn live_networks() -> usize {
+    let (created, released) = network::counts();
+    created.saturating_sub(released)
+}
+
+thread_local! {
+    static THREAD_DETECTOR: RefCell<FaceDetector> = RefCell::new(FaceDetector::new());
+}
+
+/// Detects faces using a lazily created detector private to the calling thread.
+pub fn detect_faces(bytes: &[u8], width: usize, height: usize) -> Result<Vec<Face>, YuNetError> {
+    THREAD_DETECTOR.with(|detector| detector.borrow_mut().detect(bytes, width, height))
+}
+
+/// Like [`detect_faces`], for untrusted input: a BGR (`channels` 3) or grayscale (1) image
+/// whose rows start `stride` bytes apart. Zero sizes, strides shorter than a row, buffers
+/// shorter than the image, dimensions whose byte size overflows and images beyond
+/// [`MAX_INPUT_PIXELS`] are rejected before reaching a backend. The fuzz targets in `fuzz/`
+/// exercise it.
+pub fn detect_faces_checked(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    channels: usize,
+) -> Result<Vec<Face>, YuNetError> {
+    let image = match channels {
+        3 => ImageView::with_stride(bytes, width, height, stride)?,
+        1 => ImageView::gray_with_stride(bytes, width, height, stride)?,
+        _ => return Err(YuNetError::InvalidImage),
+    };
+    THREAD_DETECTOR.with(|detector| detector.borrow_mut().detect_image(&image))
+}
+
+/// Like [`detect_faces_checked`], guaranteeing that neither a panic nor a C++ exception
+/// unwinds out of detection, for hosts such as plugin runtimes that can't survive an
+/// abort. Exceptions already become [`YuNetError::Backend`] sources at the bridge; panics
+/// become [`YuNetError::Panicked`], and replace the calling thread's detector, in case it
+/// was left half-updated.
+pub fn detect_faces_catching(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    channels: usize,
+) -> Result<Vec<Face>, YuNetError> {
+    let detect = || detect_faces_checked(bytes, width, height, stride, channels);
+    std::panic::catch_unwind(detect).unwrap_or_else(|payload| {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map_or("unknown panic", |message| message)
+                .to_owned(),
+        };
+        THREAD_DETECTOR.with(|detector| *detector.borrow_mut() = FaceDetector::new());
+        Err(YuNetError::Panicked { message })
+    })
+}
+
+/// Like [`detect_faces`], for a tightly packed 16-bit grayscale image such as those of
+/// scientific cameras, reduced to 8 bits by `mapping` first.
+pub fn detect_faces_u16(
+    samples: &[u16],
+    width: usize,
+    height: usize,
+    mapping: ToneMapping,
+) -> Result<Vec<Face>, YuNetError> {
+    let mut buffer = Vec::new();
+    let image = ImageView::from_u16(samples, width, height, 1, mapping, &mut buffer)?;
+    THREAD_DETECTOR.with(|detector| detector.borrow_mut().detect_image(&image))
+}
+
+/// Like [`detect_faces`], returning every candidate before non-maximum suppression; see
+/// [`FaceDetector::detect_candidates`].
+pub fn detect_faces_raw(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<Vec<Face>, YuNetError> {
+    THREAD_DETECTOR.with(|detector| {
+        detector
+            .borrow_mut()
+            .detect_candidates(bytes, width, height)
+    })
+}
+
+/// Like [`detect_faces`], also returning the undecoded outputs of the network's heads; see
+/// [`FaceDetector::detect_with_raw_output`].
+pub fn detect_faces_with_raw_output(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<(Vec<Face>, RawOutput), YuNetError> {
+    let image = ImageView::new(bytes, width, height)?;
+    THREAD_DETECTOR.with(|detector| detector.borrow_mut().detect_with_raw_output(&image))
+}
+
+/// Detects faces in an image file using the calling thread's detector, as
+/// [`FaceDetector::detect_file`] does.
+#[cfg(feature = "image")]
+pub fn detect_faces_from_path(path: impl AsRef<Path>) -> Result<Vec<Face>, YuNetError> {
+    THREAD_DETECTOR.with(|detector| detector.borrow_mut().detect_file(path))
+}
+
+/// Detects faces in every image in parallel, using a process-wide detector pool.
+///
+/// The pool is created on the first call, with one detector per thread of the rayon pool
+/// that call runs in, and is never resized. If the first call runs inside a small custom
+/// `ThreadPool`, later calls are limited to that many detectors too; use a
+/// [`FaceDetectorPool`] of your own to control the parallelism.
+#[cfg(feature = "rayon")]
+pub fn detect_faces_parallel(images: &[ImageView]) -> Vec<Result<Vec<Face>, YuNetError>> {
+    static POOL: std::sync::OnceLock<FaceDetectorPool> = std::sync::OnceLock::new();
+    POOL.get_or_init(|| FaceDetectorPool::new(rayon::current_num_threads()))
+        .detect_parallel(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::FrameLayout;
+    use crate::ModelVariant;
+
+    fn load_sample() -> (Vec<u8>, usize, usize) {
+        let image = image::open("sample.jpg").unwrap();
+        (
+            image.to_bgr8().to_vec(),
+            image::GenericImageView::width(&image) as usize,
+            image::GenericImageView::height(&image) as usize,
+        )
+    }
+
+    #[test]
+    fn detect_sample_faces() {
+        // Loads a sample with three faces clearly staggered in distance. Detecting the biggest
+        // face with high confidence should be completely expected. Detecting the mid-sized face
+        // is good, as it probably stretches what we consider "presence" in front of a normal
+        // installation. Detecting the smallest face is very unrealistic and unnecessary.
+        //
+        // Detecting two faces with this test at this resolution can be considered a good result.
+        let image = image::open("sample.jpg").unwrap();
+        let bytes = image.to_bgr8().to_vec();
+        let faces = detect_faces(
+            &bytes,
+            image::GenericImageView::width(&image) as usize,
+            image::GenericImageView::height(&image) as usize,
+        )
+        .unwrap();
+        assert_eq!(2, faces.len());
+    }
+
+    #[test]
+    fn rejects_invalid_geometry() {
+        let invalid = |bytes: &[u8], width, height, stride, channels| {
+            matches!(
+                detect_faces_checked(bytes, width, height, stride, channels),
+                Err(YuNetError::InvalidImage)
+            )
+        };
+        let bytes = vec![100; 101 * 20];
+        assert!(invalid(&[], 0, 10, 0, 3));
+        assert!(invalid(&bytes, 33, 0, 101, 3));
+        assert!(invalid(&bytes, 33, 20, 98, 3));
+        assert!(invalid(&bytes, 33, 21, 101, 3));
+        assert!(invalid(&bytes, 33, 20, 101, 4));
+        assert!(invalid(&bytes, usize::MAX / 2, 3, usize::MAX, 3));
+        // Unaligned strides are fine.
+        assert!(detect_faces_checked(&bytes, 33, 20, 101, 3).is_ok());
+        assert!(detect_faces_checked(&bytes, 33, 20, 101, 1).is_ok());
+
+        // Never touched, so never paged in.
+        let huge = vec![0; 6000 * 6000];
+        assert!(matches!(
+            detect_faces_checked(&huge, 6000, 6000, 6000, 1),
+            Err(YuNetError::ImageTooLarge {
+                width: 6000,
+                height: 6000
+            })
+        ));
+    }
+
+    #[test]
+    fn exposes_candidates_before_suppression() {
+        let (bytes, width, height) = load_sample();
+        for (backend, target) in available_backends() {
+            let config = DetectorConfig {
+                backend,
+                target,
+                ..DetectorConfig::default()
+            };
+            let mut detector = FaceDetector::with_config(config).unwrap();
+            let faces = detector.detect(&bytes, width, height).unwrap();
+            let candidates = detector.detect_candidates(&bytes, width, height).unwrap();
+            assert!(candidates.len() > faces.len());
+            assert!(candidates
+                .windows(2)
+                .all(|pair| pair[0].confidence() >= pair[1].confidence()));
+            for face in &faces {
+                assert!(candidates.iter().any(|c| c.rectangle() == face.rectangle()));
+            }
+        }
+    }
+
+    #[test]
+    fn detects_in_gray_frames() {
+        let gray = image::open("sample.jpg").unwrap().to_luma8();
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+        let expanded: Vec<u8> = gray.as_raw().iter().flat_map(|&v| [v; 3]).collect();
+        for (backend, target) in available_backends() {
+            for (mirror, max_side) in [(false, None), (true, Some(400))] {
+                let config = DetectorConfig {
+                    mirror,
+                    max_side,
+                    backend,
+                    target,
+                    ..DetectorConfig::default()
+                };
+                let mut detector = FaceDetector::with_config(config).unwrap();
+                let faces = detector.detect_gray(gray.as_raw(), width, height).unwrap();
+                let expected = detector.detect(&expanded, width, height).unwrap();
+                let rectangles =
+                    |faces: &[Face]| faces.iter().map(Face::rectangle).collect::<Vec<_>>();
+                assert!(!faces.is_empty());
+                assert_eq!(rectangles(&expected), rectangles(&faces));
+            }
+        }
+    }
+
+    #[test]
+    fn filters_small_faces() {
+        let (bytes, width, height) = load_sample();
+        let all = FaceDetector::new().detect(&bytes, width, height).unwrap();
+        let (small, large) = if all[0].size() < all[1].size() {
+            (&all[0], &all[1])
+        } else {
+            (&all[1], &all[0])
+        };
+        let pixels = |face: &Face| face.rectangle().w.min(face.rectangle().h);
+
+        for min_face_size in [
+            FaceSize::Fraction((small.size() + large.size()) / 2.0),
+            FaceSize::Pixels((pixels(small) + pixels(large)) / 2.0),
+        ] {
+            let config = DetectorConfig {
+                min_face_size: Some(min_face_size),
+                ..Default::default()
+            };
+            let faces = FaceDetector::with_config(config)
+                .unwrap()
+                .detect(&bytes, width, height)
+                .unwrap();
+            assert_eq!(1, faces.len());
+            assert_eq!(large.rectangle(), faces[0].rectangle());
+        }
+    }
+
+    #[test]
+    fn detects_in_rotated_and_mirrored_frames() {
+        let image = image::open("sample.jpg").unwrap();
+        let (width, height) = image::GenericImageView::dimensions(&image);
+        let (width, height) = (width as f32, height as f32);
+        let detect = |image: &image::DynamicImage, rotation, mirror| {
+            let config = DetectorConfig {
+                rotation,
+                mirror,
+                ..Default::default()
+            };
+            let bgr = image.to_bgr8();
+            let (w, h) = (bgr.width() as usize, bgr.height() as usize);
+            let mut faces = FaceDetector::with_config(config)
+                .unwrap()
+                .detect(bgr.as_raw(), w, h)
+                .unwrap();
+            faces.sort_by(|a, b| a.rectangle().x.total_cmp(&b.rectangle().x));
+            faces
+        };
+        let reference = detect(&image, Rotation::None, false);
+
+        // A camera mounted sideways, turned upright by the detector.
+        let sideways = image.rotate270();
+        let faces = detect(&sideways, Rotation::Cw90, false);
+        assert_eq!(
+            (height as usize, width as usize),
+            faces[0].detection_dimensions()
+        );
+        let mut expected: Vec<Rect> = reference
+            .iter()
+            .map(|face| {
+                let r = face.rectangle();
+                Rect::with_size(r.y, width - r.x - r.w, r.h, r.w)
+            })
+            .collect();
+        expected.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert_eq!(expected.len(), faces.len());
+        for (face, expected) in faces.iter().zip(&expected) {
+            assert!(face.rectangle().iou(expected) > 0.9);
+        }
+
+        // A mirrored preview, with landmarks keeping their natural sense.
+        let mirrored = image.fliph();
+        let faces = detect(&mirrored, Rotation::None, true);
+        let (face, reference) = (&faces[faces.len() - 1], &reference[0]);
+        let right_eye = reference.landmarks().right_eye;
+        let expected = Vec2::new(width - right_eye.x, right_eye.y);
+        assert!(face.landmarks().right_eye.distance(expected) < 2.0);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn detects_files_by_exif_orientation() {
+        let image = image::open("sample.jpg").unwrap();
+        let width = image::GenericImageView::width(&image) as f32;
+        let reference = detect_faces_from_path("sample.jpg").unwrap();
+
+        // A phone photo stored sideways, tagged to be turned a quarter clockwise.
+        let mut jpeg = Vec::new();
+        image
+            .rotate270()
+            .write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(95))
+            .unwrap();
+        let mut tagged = crate::exif::tests::exif_segment(6, false);
+        tagged.extend_from_slice(&jpeg[2..]);
+        let path = std::env::temp_dir().join("rusty-yunet-exif-orientation.jpg");
+        std::fs::write(&path, tagged).unwrap();
+        let faces = detect_faces_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let faces = faces.unwrap();
+        // Recompression may surface another marginal face, but none may go missing.
+        for face in &reference {
+            let r = face.rectangle();
+            let stored = Rect::with_size(r.y, width - r.x - r.w, r.h, r.w);
+            assert!(faces.iter().any(|f| f.rectangle().iou(&stored) > 0.9));
+        }
+        assert!(matches!(
+            detect_faces_from_path("missing.jpg"),
+            Err(YuNetError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn releases_networks_on_drop() {
+        let released = network::counts().1;
+        for _ in 0..2000 {
+            drop(FaceDetector::new());
+        }
+        let mut detector = FaceDetector::new();
+        let (bytes, width, height) = load_sample();
+        detector.detect(&bytes, width, height).unwrap();
+        assert_eq!(1, detector.close().detections);
+        // Other tests create and drop detectors of their own meanwhile.
+        assert!(network::counts().1 - released >= 2001);
+    }
+
+    #[test]
+    fn catches_panics_and_exceptions() {
+        let (bytes, width, height) = load_sample();
+        let detect = || detect_faces_catching(&bytes, width, height, width * 3, 3);
+        network::inject_panic();
+        assert!(matches!(
+            detect(),
+            Err(YuNetError::Panicked { message }) if message == "injected panic"
+        ));
+        // The replacement detector works.
+        assert_eq!(2, detect().unwrap().len());
+    }
+
+    #[test]
+    fn detector_is_send() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+        assert_send::<FaceDetector>();
+        assert_sync::<FaceDetectorPool>();
+
+        // Fails to compile, as ambiguous, if `FaceDetector` is `Sync`.
+        trait AmbiguousIfSync<A> {
+            fn check() {}
+        }
+        impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+        impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+        <FaceDetector as AmbiguousIfSync<_>>::check();
+    }
+
+    #[test]
+    fn pool_detects_from_many_threads() {
+        let (bytes, width, height) = load_sample();
+        let pool = FaceDetectorPool::new(3);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..4 {
+                        let faces = pool.detect(&bytes, width, height).unwrap();
+                        assert_eq!(2, faces.len());
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn free_function_is_thread_safe() {
+        let (bytes, width, height) = load_sample();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let faces = detect_faces(&bytes, width, height).unwrap();
+                    assert_eq!(2, faces.len());
+                });
+            }
+        });
+    }
+
+    /// Summed distance between the landmarks of two detections of the same face.
+    fn landmark_error(a: &Face, b: &Face) -> f32 {
+        let (a, b) = (a.landmarks(), b.landmarks());
+        a.iter()
+            .map(|(landmark, p)| p.distance(b.get(landmark)))
+            .sum()
+    }
+
+    #[test]
+    fn downscaled_detection_refines_landmarks() {
+        let (bytes, width, height) = load_sample();
+        let reference = FaceDetector::new().detect(&bytes, width, height).unwrap();
+        let mut config = DetectorConfig {
+            max_side: Some(width / 2),
+            ..Default::default()
+        };
+        let coarse = FaceDetector::with_config(config.clone())
+            .unwrap()
+            .detect(&bytes, width, height)
+            .unwrap();
+        config.refine_landmarks = true;
+        let mut detector = FaceDetector::with_config(config).unwrap();
+        let refined = detector.detect(&bytes, width, height).unwrap();
+
+        assert!(refined[0].rectangle().iou(&reference[0].rectangle()) > 0.7);
+        assert!(
+            landmark_error(&reference[0], &refined[0]) < landmark_error(&reference[0], &coarse[0])
+        );
+
+        // Boxes skip the refinement, but not the configuration's.
+        let boxes = detector
+            .detect_boxes(&ImageView::new(&bytes, width, height).unwrap())
+            .unwrap();
+        let coarse: Vec<FaceBox> = coarse.iter().map(FaceBox::from).collect();
+        assert_eq!(coarse, boxes);
+        assert!(detector.config().refine_landmarks);
+    }
+
+    #[test]
+    fn fits_frames_to_fixed_input_size() {
+        let (bytes, width, height) = load_sample();
+        let reference = FaceDetector::new().detect(&bytes, width, height).unwrap();
+        let detect = |resize| {
+            let config = DetectorConfig {
+                input_size: Some((640, 640)),
+                resize,
+                ..Default::default()
+            };
+            let mut detector = FaceDetector::with_config(config).unwrap();
+            detector.detect(&bytes, width, height).unwrap()
+        };
+
+        // Letterboxed, both faces are found where they are in the frame.
+        let letterboxed = detect(ResizeStrategy::Letterbox);
+        assert_eq!(2, letterboxed.len());
+        for (face, reference) in letterboxed.iter().zip(&reference) {
+            assert!(face.rectangle().iou(&reference.rectangle()) > 0.7);
+            assert_eq!((width, height), face.detection_dimensions());
+        }
+        // Cropped to the central 605x605, only faces within it can be found; both are.
+        let margin = (width - height) as f32 / 2.0;
+        let cropped = detect(ResizeStrategy::Crop);
+        for rect in cropped.iter().map(Face::rectangle) {
+            assert!(rect.x >= margin - 10.0 && rect.x + rect.w <= width as f32 - margin + 10.0);
+        }
+        for reference in &reference {
+            assert!(cropped
+                .iter()
+                .any(|face| face.rectangle().iou(&reference.rectangle()) > 0.7));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn detects_in_parallel() {
+        let (bytes, width, height) = load_sample();
+        let image = ImageView::new(&bytes, width, height).unwrap();
+        let results = detect_faces_parallel(&[image; 6]);
+        assert_eq!(6, results.len());
+        for faces in results {
+            assert_eq!(2, faces.unwrap().len());
+        }
+
+        // Fewer detectors than images and rayon threads.
+        let results = FaceDetectorPool::new(2).detect_parallel(&[image; 5]);
+        assert_eq!(5, results.len());
+        assert!(results
+            .iter()
+            .all(|faces| faces.as_ref().unwrap().len() == 2));
+    }
+
+    #[test]
+    fn provenance_is_opt_in() {
+        let (bytes, width, height) = load_sample();
+        let faces = FaceDetector::new().detect(&bytes, width, height).unwrap();
+        assert!(faces[0].provenance().is_none());
+
+        let config = DetectorConfig {
+            max_side: Some(400),
+            provenance: true,
+            ..Default::default()
+        };
+        let faces = FaceDetector::with_config(config)
+            .unwrap()
+            .detect(&bytes, width, height)
+            .unwrap();
+        let provenance = faces[0].provenance().unwrap();
+        assert_eq!(crate::provenance::MODEL_VERSION, provenance.model_version);
+        assert_eq!(crate::provenance::MODEL_HASH, provenance.model_hash);
+        assert_eq!((400, 300), provenance.input_size);
+    }
+
+    #[test]
+    fn reports_model_info() {
+        let info = FaceDetector::new().model_info();
+        assert_eq!(crate::provenance::MODEL_NAME, info.name);
+        assert_eq!(crate::provenance::MODEL_HASH, info.hash);
+        assert_eq!(16, info.hash.len());
+        assert_eq!(ModelVariant::Yunet2023Mar, info.variant);
+        assert_eq!(None, info.input_size);
+
+        let config = DetectorConfig {
+            input_size: Some((640, 640)),
+            ..Default::default()
+        };
+        let info = FaceDetector::with_config(config).unwrap().model_info();
+        assert_eq!(Some((640, 640)), info.input_size);
+
+        #[cfg(feature = "native")]
+        {
+            let info = FaceDetector::with_native_model(Default::default(), NATIVE_MODEL)
+                .unwrap()
+                .model_info();
+            assert_eq!(
+                ("native-cpu", crate::provenance::MODEL_HASH),
+                (&*info.backend, &*info.hash)
+            );
+            let mut model = NATIVE_MODEL.to_vec();
+            let last = model.len() - 4;
+            model[last..].copy_from_slice(&0.5f32.to_le_bytes());
+            let info = FaceDetector::with_native_model(Default::default(), &model)
+                .unwrap()
+                .model_info();
+            assert_eq!("custom", info.version);
+            assert_eq!(format!("{:016x}", fnv1a(&model)), info.hash);
+
+            let model = crate::quantize_native_model(NATIVE_MODEL).unwrap();
+            assert!(model.len() < NATIVE_MODEL.len() / 3);
+            let info = FaceDetector::with_native_model(Default::default(), &model)
+                .unwrap()
+                .model_info();
+            assert_eq!(ModelVariant::Yunet2023MarInt8, info.variant);
+            assert_eq!(Some(info.variant), ModelVariant::of_native_model(&model));
+        }
+    }
+
+    #[test]
+    fn detects_in_strided_frame_buffer() {
+        let (bytes, width, height) = load_sample();
+        let layout = FrameLayout {
+            width,
+            height,
+            stride: 3 * width + 64,
+        };
+        let mut padded = vec![0u8; layout.stride * height];
+        for (src, dst) in bytes
+            .chunks(3 * width)
+            .zip(padded.chunks_mut(layout.stride))
+        {
+            dst[..src.len()].copy_from_slice(src);
+        }
+
+        let frame = unsafe { FrameBuffer::new(padded.as_ptr(), padded.len(), layout) }.unwrap();
+        let faces = FaceDetector::new().detect_frame(&frame).unwrap();
+        assert_eq!(2, faces.len());
+        assert!(unsafe { FrameBuffer::new(padded.as_ptr(), 100, layout) }.is_err());
+        assert!(unsafe { FrameBuffer::new(padded.as_ptr(), usize::MAX, layout) }.is_err());
+    }
+
+    #[test]
+    fn accumulates_stats() {
+        let (bytes, width, height) = load_sample();
+        let mut detector = FaceDetector::new();
+        let (faces, stats) = detector.detect_with_stats(&bytes, width, height).unwrap();
+        assert_eq!(faces.len(), stats.faces_found);
+        assert!(stats.inference_ms > 0.0);
+        detector.detect(&bytes, width, height).unwrap();
+
+        let cumulative = detector.stats();
+        assert_eq!(2, cumulative.detections);
+        assert_eq!(4, cumulative.faces_found);
+        assert_eq!(2.0, cumulative.mean_faces_found);
+        detector.reset_stats();
+        assert_eq!(0, detector.stats().detections);
+    }
+
+    #[test]
+    fn reuses_frame_buffers() {
+        let (bytes, width, height) = load_sample();
+        let image = ImageView::new(&bytes, width, height).unwrap();
+        let mut detector = FaceDetector::with_config(DetectorConfig {
+            max_side: Some(width / 2),
+            mirror: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let first = detector.detect_image(&image).unwrap();
+        let buffers = (
+            detector.buffers.oriented.as_ptr(),
+            detector.buffers.resized.as_ptr(),
+        );
+        assert_eq!(width * height * 3, detector.buffers.oriented.len());
+        let second = detector.detect_image(&image).unwrap();
+        assert_eq!(
+            buffers,
+            (
+                detector.buffers.oriented.as_ptr(),
+                detector.buffers.resized.as_ptr()
+            )
+        );
+        let rects = |faces: &[Face]| faces.iter().map(Face::rectangle).collect::<Vec<_>>();
+        assert_eq!(rects(&first), rects(&second));
+
+        detector.reset_buffers();
+        assert_eq!(0, detector.buffers.resized.capacity());
+    }
+
+    #[test]
+    fn detects_into_reused_buffer() {
+        let (bytes, width, height) = load_sample();
+        let image = ImageView::new(&bytes, width, height).unwrap();
+        let mut detector = FaceDetector::new();
+        let mut faces = Vec::with_capacity(16);
+        let buffer = faces.as_ptr();
+        for _ in 0..2 {
+            let stats = detector.detect_into(&image, &mut faces).unwrap();
+            assert_eq!(2, stats.faces_found);
+            assert_eq!(2, faces.len());
+        }
+        assert_eq!(buffer, faces.as_ptr());
+        let huge = vec![0; 6000 * 6000];
+        let huge = ImageView::gray(&huge, 6000, 6000).unwrap();
+        assert!(detector.detect_into(&huge, &mut faces).is_err());
+        assert!(faces.is_empty());
+    }
+
+    #[test]
+    fn detects_into_fixed_buffer() {
+        let (bytes, width, height) = load_sample();
+        let image = ImageView::new(&bytes, width, height).unwrap();
+        let mut detector = FaceDetector::new();
+        let expected = detector.detect_image(&image).unwrap();
+        let rects = |faces: &[Face]| faces.iter().map(Face::rectangle).collect::<Vec<_>>();
+
+        let mut buffer = FaceBuffer::with_capacity(8);
+        detector.detect_buffered(&image, &mut buffer).unwrap();
+        assert_eq!(rects(&expected), rects(buffer.faces()));
+        assert_eq!((2, 0), (buffer.found(), buffer.truncated()));
+
+        // The least confident faces are dropped for lack of slots.
+        let mut small = FaceBuffer::with_capacity(1);
+        detector.detect_buffered(&image, &mut small).unwrap();
+        assert_eq!((2, 1), (small.found(), small.truncated()));
+        let best = expected
+            .iter()
+            .max_by(|a, b| a.confidence().total_cmp(&b.confidence()))
+            .unwrap();
+        assert_eq!(vec![best.rectangle()], rects(small.faces()));
+    }
+
+    #[test]
+    fn decodes_raw_output_into_faces() {
+        let (bytes, width, height) = load_sample();
+        let (faces, raw) = detect_faces_with_raw_output(&bytes, width, height).unwrap();
+        assert_eq!((width, height), raw.input_size);
+        assert_eq!(
+            vec![8, 16, 32],
+            raw.levels.iter().map(|l| l.stride).collect::<Vec<_>>()
+        );
+        for level in &raw.levels {
+            assert_eq!(level.anchors(), level.cls.len());
+            assert_eq!(level.anchors() * 10, level.kps.len());
+        }
+
+        // Every face decodes from a confident anchor.
+        let decoded: Vec<Rect> = raw
+            .levels
+            .iter()
+            .flat_map(|level| {
+                (0..level.anchors())
+                    .filter(|&i| level.score(i) >= 0.5)
+                    .map(|i| level.decode(i).0)
+            })
+            .collect();
+        for face in &faces {
+            let best = decoded
+                .iter()
+                .map(|rect| rect.iou(&face.rectangle()))
+                .fold(0.0, f32::max);
+            assert!(best > 0.9, "{best}");
+        }
+    }
+
+    /// The C++ network is built with NEON on ARM targets that have it, such as the
+    /// Raspberry Pi 3 and later.
+    #[cfg(all(
+        feature = "libfacedetection",
+        any(
+            target_arch = "aarch64",
+            all(target_arch = "arm", target_feature = "neon")
+        )
+    ))]
+    #[test]
+    fn builds_with_neon_on_arm() {
+        assert_eq!("neon", env!("YUNET_SIMD"));
+    }
+
+    #[test]
+    fn warms_up_outside_stats() {
+        let mut detector = FaceDetector::new();
+        let stats = detector.warm_up(640, 480).unwrap();
+        assert_eq!(0, stats.faces_found);
+        assert_eq!(0, detector.stats().detections);
+        assert!(detector.warm_up(0, 480).is_err());
+        FaceDetectorPool::new(2).warm_up(64, 48).unwrap();
+    }
+
+    #[cfg(not(feature = "native"))]
+    #[test]
+    fn rejects_unavailable_backends() {
+        let config = DetectorConfig {
+            backend: Backend::Native,
+            ..Default::default()
+        };
+        assert!(matches!(
+            FaceDetector::with_config(config.clone()),
+            Err(YuNetError::UnsupportedBackend { .. })
+        ));
+        assert!(FaceDetector::new().set_config(config).is_err());
+    }
+
+    #[cfg(all(feature = "native", feature = "libfacedetection"))]
+    #[test]
+    fn switches_backends() {
+        let (bytes, width, height) = load_sample();
+        let mut detector = FaceDetector::new();
+        for backend in [Backend::Native, Backend::LibFaceDetection] {
+            detector
+                .set_config(DetectorConfig {
+                    backend,
+                    ..Default::default()
+                })
+                .unwrap();
+            assert_eq!(2, detector.detect(&bytes, width, height).unwrap().len());
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn traces_detections() {
+        use std::sync::Mutex;
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        /// Records the names of spans and the messages of events.
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes) -> Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name().to_owned());
+                Id::from_u64(names.len() as u64)
+            }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(event.metadata().name().to_owned());
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let (bytes, width, height) = load_sample();
+        let recorder = Arc::new(Recorder::default());
+        tracing::subscriber::with_default(Arc::clone(&recorder), || {
+            FaceDetector::new().detect(&bytes, width, height).unwrap();
+        });
+        let recorded = recorder.0.lock().unwrap();
+        assert_eq!("detect", recorded[0]);
+        assert!(recorded
+            .iter()
+            .any(|name| name.starts_with("event src/detector.rs")));
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{Face, FaceDetector, ImageView, YuNetError};
+
+/// Inference engines a [`FaceDetector`](crate::FaceDetector) can run YuNet on.
+///
+/// Both run the same bundled libfacedetection model and are compiled in by the cargo
+/// feature of the same name. OpenCV DNN is not a dependency, so its backends cannot be
+/// selected.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// libfacedetection's C++ kernels, the default where available. Since cargo features
+    /// are unified, another crate enabling `libfacedetection` changes the default.
+    #[cfg_attr(feature = "libfacedetection", default)]
+    LibFaceDetection,
+    /// A plain Rust port of the same network, for builds without a C++ toolchain.
+    #[cfg_attr(not(feature = "libfacedetection"), default)]
+    Native,
+}
+
+/// The device a [`Backend`] computes on. Every backend currently runs on the CPU only.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Target {
+    /// Scalar or SIMD (AVX2, NEON) CPU kernels, as chosen at compile time.
+    #[default]
+    Cpu,
+}
+
+/// The backend and target combinations usable in this build, fastest first.
+pub fn available_backends() -> Vec<(Backend, Target)> {
+    vec![
+        #[cfg(feature = "libfacedetection")]
+        (Backend::LibFaceDetection, Target::Cpu),
+        #[cfg(feature = "native")]
+        (Backend::Native, Target::Cpu),
+    ]
+}
+
+/// Something that finds faces in frames: a [`FaceDetector`](crate::FaceDetector), or a
+/// stand-in such as [`ScriptedBackend`](crate::mock::ScriptedBackend) for testing the
+/// tracking and presence logic built on top of detection without images or a model.
+pub trait DetectionBackend {
+    /// The faces in `image`, in its pixel coordinates.
+    fn detect_image(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError>;
+}
+
+impl DetectionBackend for FaceDetector {
+    fn detect_image(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        FaceDetector::detect_image(self, image)
+    }
+}
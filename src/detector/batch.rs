@@ -0,0 +1,90 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::FaceDetector;
+use crate::progress::ProgressReporter;
+use crate::scan::{Walker, IMAGE_EXTENSIONS};
+use crate::{Face, YuNetError};
+
+/// A file and the faces detected in it.
+type FileFaces = (PathBuf, Result<Vec<Face>, YuNetError>);
+
+impl FaceDetector {
+    /// Detects faces in every file as [`detect_file`](Self::detect_file) does, reporting
+    /// progress after each. A file failing doesn't stop the others.
+    pub fn detect_files<P: AsRef<Path>>(
+        &mut self,
+        paths: &[P],
+        progress: &mut impl ProgressReporter,
+    ) -> Vec<Result<Vec<Face>, YuNetError>> {
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let faces = self.detect_file(path);
+                progress.report(i + 1, paths.len(), Some(path.as_ref()));
+                faces
+            })
+            .collect()
+    }
+
+    /// Detects faces in every image file under `dir` and its subdirectories, by extension,
+    /// in path order. Fails only if a directory can't be listed.
+    pub fn detect_dir(
+        &mut self,
+        dir: impl AsRef<Path>,
+        progress: &mut impl ProgressReporter,
+    ) -> io::Result<Vec<FileFaces>> {
+        let extensions = IMAGE_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+        let paths = Walker::new(dir.as_ref(), extensions, true)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|(_, e)| e)?;
+        let results = self.detect_files(&paths, progress);
+        Ok(paths.into_iter().zip(results).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DetectorConfig;
+
+    #[test]
+    fn scans_directories() {
+        let dir = std::env::temp_dir().join(format!("rusty-yunet-scan-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::copy("sample.jpg", dir.join("nested/sample.jpg")).unwrap();
+        std::fs::write(dir.join("broken.png"), b"not an image").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"skipped").unwrap();
+
+        let config = DetectorConfig {
+            max_side: Some(320),
+            ..DetectorConfig::default()
+        };
+        let mut detector = FaceDetector::with_config(config).unwrap();
+        let mut reports = Vec::new();
+        let results = detector
+            .detect_dir(&dir, &mut |done, total, current: Option<&Path>| {
+                reports.push((done, total, current.map(Path::to_path_buf)))
+            })
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            vec![dir.join("broken.png"), dir.join("nested/sample.jpg")],
+            results
+                .iter()
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(results[0].1.is_err());
+        assert!(!results[1].1.as_ref().unwrap().is_empty());
+        assert_eq!(
+            vec![
+                (1, 2, Some(dir.join("broken.png"))),
+                (2, 2, Some(dir.join("nested/sample.jpg")))
+            ],
+            reports
+        );
+    }
+}
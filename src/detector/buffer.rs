@@ -0,0 +1,44 @@
+use super::network::RawFace;
+use crate::Face;
+
+/// A fixed number of slots for faces, reused from frame to frame by
+/// [`FaceDetector::detect_buffered`](crate::FaceDetector::detect_buffered), so that
+/// detections allocate nothing for their results, not even across the boundary to the C++
+/// network.
+#[derive(Debug, Clone)]
+pub struct FaceBuffer {
+    pub(crate) raw: Box<[RawFace]>,
+    pub(crate) faces: Vec<Face>,
+    pub(crate) found: usize,
+}
+
+impl FaceBuffer {
+    /// Slots for up to `capacity` faces per frame.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            raw: vec![RawFace::default(); capacity].into_boxed_slice(),
+            faces: Vec::with_capacity(capacity),
+            found: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// The faces of the last frame, at most [`capacity`](Self::capacity) of them.
+    pub fn faces(&self) -> &[Face] {
+        &self.faces
+    }
+
+    /// How many faces the network found in the last frame, before the detector's own
+    /// filtering. More than the capacity if faces were dropped for lack of slots.
+    pub fn found(&self) -> usize {
+        self.found
+    }
+
+    /// How many faces of the last frame were dropped for lack of slots.
+    pub fn truncated(&self) -> usize {
+        self.found.saturating_sub(self.capacity())
+    }
+}
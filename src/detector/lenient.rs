@@ -0,0 +1,150 @@
+use glam::Vec2;
+
+use crate::{Face, Landmark};
+
+/// The faces of a [`detect_lenient`](super::FaceDetector::detect_lenient) call, with what
+/// was wrong with the ones that didn't come out cleanly.
+#[derive(Debug, Clone, Default)]
+pub struct DetectionOutcome {
+    pub faces: Vec<Face>,
+    pub warnings: Vec<DetectionWarning>,
+}
+
+impl DetectionOutcome {
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// A recoverable problem with one face of a detection. `index` is the position of the face
+/// in what [`detect_image`](super::FaceDetector::detect_image) would have returned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetectionWarning {
+    /// The network reported a box of zero or negative width or height, which YuNet does,
+    /// rarely. The face was dropped.
+    NegativeSize {
+        index: usize,
+        width: f32,
+        height: f32,
+    },
+    /// The box or a landmark isn't a finite number. The face was dropped.
+    NonFinite { index: usize },
+    /// A landmark lies further outside the frame than the size of its face, so it likely
+    /// belongs to no real feature. The face was kept.
+    LandmarkOutOfBounds {
+        index: usize,
+        landmark: Landmark,
+        point: Vec2,
+    },
+}
+
+impl DetectionWarning {
+    pub fn index(&self) -> usize {
+        match *self {
+            DetectionWarning::NegativeSize { index, .. }
+            | DetectionWarning::NonFinite { index }
+            | DetectionWarning::LandmarkOutOfBounds { index, .. } => index,
+        }
+    }
+
+    /// Whether the face was left out of [`DetectionOutcome::faces`].
+    pub fn dropped(&self) -> bool {
+        !matches!(self, DetectionWarning::LandmarkOutOfBounds { .. })
+    }
+}
+
+/// Sorts `faces` into the ones to keep and warnings about the rest.
+pub(crate) fn inspect(faces: Vec<Face>) -> DetectionOutcome {
+    let mut outcome = DetectionOutcome::default();
+    for (index, face) in faces.into_iter().enumerate() {
+        let rect = face.rectangle();
+        let points = face.landmarks().as_array();
+        let finite = [rect.x, rect.y, rect.w, rect.h]
+            .iter()
+            .all(|v| v.is_finite())
+            && points.iter().all(|p| p.is_finite());
+        if !finite {
+            outcome.warnings.push(DetectionWarning::NonFinite { index });
+            continue;
+        }
+        if rect.w <= 0.0 || rect.h <= 0.0 {
+            outcome.warnings.push(DetectionWarning::NegativeSize {
+                index,
+                width: rect.w,
+                height: rect.h,
+            });
+            continue;
+        }
+        let (width, height) = face.detection_dimensions();
+        let margin = rect.w.max(rect.h);
+        let (min, max) = (
+            Vec2::splat(-margin),
+            Vec2::new(width as f32, height as f32) + margin,
+        );
+        for landmark in Landmark::ALL {
+            let point = face.landmarks().get(landmark);
+            if point.cmplt(min).any() || point.cmpgt(max).any() {
+                outcome
+                    .warnings
+                    .push(DetectionWarning::LandmarkOutOfBounds {
+                        index,
+                        landmark,
+                        point,
+                    });
+            }
+        }
+        outcome.faces.push(face);
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    #[test]
+    fn warns_about_broken_faces() {
+        let raw = |w: f32, lm: [f32; 10]| RawFace {
+            score: 0.9,
+            x: 10.0,
+            y: 10.0,
+            w,
+            h: 20.0,
+            lm,
+        };
+        let lm = [15.0, 15.0, 25.0, 15.0, 20.0, 20.0, 16.0, 25.0, 24.0, 25.0];
+        let mut stray = lm;
+        stray[8] = 140.0;
+        let faces = [
+            raw(20.0, lm),
+            raw(-4.0, lm),
+            raw(20.0, stray),
+            raw(f32::NAN, lm),
+        ]
+        .map(|raw| Face::from_raw_face(&raw, (100, 100)));
+
+        let outcome = inspect(faces.to_vec());
+        let rects: Vec<_> = outcome.faces.iter().map(Face::rectangle).collect();
+        assert_eq!(vec![faces[0].rectangle(), faces[2].rectangle()], rects);
+        assert_eq!(
+            vec![
+                DetectionWarning::NegativeSize {
+                    index: 1,
+                    width: -4.0,
+                    height: 20.0
+                },
+                DetectionWarning::LandmarkOutOfBounds {
+                    index: 2,
+                    landmark: Landmark::MouthLeft,
+                    point: Vec2::new(140.0, 25.0),
+                },
+                DetectionWarning::NonFinite { index: 3 },
+            ],
+            outcome.warnings
+        );
+        assert!(outcome.warnings[0].dropped());
+        assert!(!outcome.warnings[1].dropped());
+        assert!(inspect(vec![faces[0].clone()]).is_clean());
+    }
+}
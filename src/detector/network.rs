@@ -0,0 +1,163 @@
+use super::Backend;
+use crate::io::ImageView;
+use crate::YuNetError;
+
+#[cfg(feature = "libfacedetection")]
+mod libfacedetection;
+#[cfg(feature = "native")]
+mod native;
+#[cfg(feature = "native")]
+pub use native::BUNDLED_WEIGHTS;
+#[cfg(feature = "native")]
+pub(crate) use native::{quantize, variant_of};
+
+#[cfg(test)]
+thread_local! {
+    static PANIC_NEXT: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Makes the next inference on this thread panic, for testing that panics are caught.
+#[cfg(test)]
+pub(crate) fn inject_panic() {
+    PANIC_NEXT.set(true);
+}
+
+fn raise_injected_panic() {
+    #[cfg(test)]
+    if PANIC_NEXT.replace(false) {
+        panic!("injected panic");
+    }
+}
+
+/// A detection as reported by the network, in the pixel coordinates of its input, to
+/// subpixel precision.
+///
+/// Laid out as the C++ bridge's `BridgeFace`, so that the C++ side can write faces into a
+/// buffer of them in place.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct RawFace {
+    pub(crate) score: f32,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) w: f32,
+    pub(crate) h: f32,
+    /// Right eye, left eye, nose, right and left mouth corners, as x, y pairs.
+    pub(crate) lm: [f32; 10],
+}
+
+/// Networks (created, released) over the life of the process, by every compiled-in
+/// backend.
+pub(crate) fn counts() -> (usize, usize) {
+    let counts = [
+        #[cfg(feature = "libfacedetection")]
+        libfacedetection::counts(),
+        #[cfg(feature = "native")]
+        native::counts(),
+    ];
+    counts.iter().fold((0, 0), |(c, r), &(created, released)| {
+        (c + created, r + released)
+    })
+}
+
+/// The state of one instance of the network on one of the compiled-in backends.
+pub(crate) enum Network {
+    #[cfg(feature = "libfacedetection")]
+    LibFaceDetection(libfacedetection::Network),
+    #[cfg(feature = "native")]
+    Native(native::Network),
+}
+
+impl Network {
+    /// The backend must be one of [`available_backends`](super::available_backends).
+    pub(crate) fn new(backend: Backend) -> Result<Self, YuNetError> {
+        match backend {
+            #[cfg(feature = "libfacedetection")]
+            Backend::LibFaceDetection => {
+                libfacedetection::Network::new().map(Network::LibFaceDetection)
+            }
+            #[cfg(feature = "native")]
+            Backend::Native => Ok(Network::Native(native::Network::new())),
+            #[allow(unreachable_patterns)]
+            backend => unreachable!("{backend:?} is not compiled in"),
+        }
+    }
+
+    /// The native backend running caller-supplied parameters.
+    #[cfg(feature = "native")]
+    pub(crate) fn native_from_bytes(model: &[u8]) -> Result<Self, YuNetError> {
+        native::Network::from_bytes(model).map(Network::Native)
+    }
+
+    /// The release of the model the network runs.
+    pub(crate) fn variant(&self) -> crate::ModelVariant {
+        match self {
+            #[cfg(feature = "libfacedetection")]
+            Network::LibFaceDetection(_) => crate::ModelVariant::Yunet2023Mar,
+            #[cfg(feature = "native")]
+            Network::Native(network) => network.variant(),
+        }
+    }
+
+    /// Runs the network on a BGR image, returning faces after non-maximum suppression.
+    pub(crate) fn infer(&self, image: &ImageView) -> Result<Vec<RawFace>, YuNetError> {
+        raise_injected_panic();
+        match self {
+            #[cfg(feature = "libfacedetection")]
+            Network::LibFaceDetection(network) => network.infer(image),
+            #[cfg(feature = "native")]
+            Network::Native(network) => Ok(network.infer(image)),
+        }
+    }
+
+    /// Like [`infer`](Self::infer), writing the first `out.len()` faces into `out` and
+    /// returning how many the network found, which may be more.
+    pub(crate) fn infer_into(
+        &self,
+        image: &ImageView,
+        out: &mut [RawFace],
+    ) -> Result<usize, YuNetError> {
+        raise_injected_panic();
+        match self {
+            #[cfg(feature = "libfacedetection")]
+            Network::LibFaceDetection(network) => network.infer_into(image, out),
+            #[cfg(feature = "native")]
+            Network::Native(network) => {
+                let faces = network.infer(image);
+                for (slot, face) in out.iter_mut().zip(&faces) {
+                    *slot = *face;
+                }
+                Ok(faces.len())
+            }
+        }
+    }
+
+    /// Like [`infer`](Self::infer), or [`infer_candidates`](Self::infer_candidates) unless
+    /// `suppress`, also storing the outputs of the heads in `raw`.
+    pub(crate) fn infer_raw(
+        &self,
+        image: &ImageView,
+        suppress: bool,
+        raw: &mut super::RawOutput,
+    ) -> Result<Vec<RawFace>, YuNetError> {
+        raise_injected_panic();
+        match self {
+            #[cfg(feature = "libfacedetection")]
+            Network::LibFaceDetection(network) => network.infer_raw(image, suppress, raw),
+            #[cfg(feature = "native")]
+            Network::Native(network) => Ok(network.infer_raw(image, suppress, raw)),
+        }
+    }
+
+    /// Runs the network on a BGR image, returning every candidate above the confidence
+    /// threshold, most confident first.
+    pub(crate) fn infer_candidates(&self, image: &ImageView) -> Result<Vec<RawFace>, YuNetError> {
+        raise_injected_panic();
+        match self {
+            #[cfg(feature = "libfacedetection")]
+            Network::LibFaceDetection(network) => network.infer_candidates(image),
+            #[cfg(feature = "native")]
+            Network::Native(network) => Ok(network.infer_candidates(image)),
+        }
+    }
+}
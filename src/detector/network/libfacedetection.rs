@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use super::RawFace;
+use crate::detector::{Backend, RawLevel, RawOutput};
+use crate::io::ImageView;
+use crate::YuNetError;
+
+#[cfg(test)]
+mod faults;
+
+/// libfacedetection's C++ implementation, with SIMD kernels chosen at compile time.
+pub(crate) struct Network {
+    handle: cxx::UniquePtr<ffi::FaceDetectorHandle>,
+}
+
+/// Networks (created, released) over the life of the process, as the C++ side counts them
+/// in the constructor and destructor of its handle.
+pub(crate) fn counts() -> (usize, usize) {
+    // Released first, like the native backend's counts.
+    let released = ffi::face_detectors_released();
+    (ffi::face_detectors_created(), released)
+}
+
+impl Network {
+    pub(crate) fn new() -> Result<Self, YuNetError> {
+        let handle = ffi::new_face_detector().map_err(|e| YuNetError::ModelLoad {
+            reason: "the C++ backend failed to set up its parameters".to_owned(),
+            source: Some(Arc::new(backend_error(e))),
+        })?;
+        Ok(Self { handle })
+    }
+
+    pub(crate) fn infer(&self, image: &ImageView) -> Result<Vec<RawFace>, YuNetError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "libfacedetection",
+            width = image.width(),
+            height = image.height(),
+            stride = image.stride(),
+            suppress = true,
+        )
+        .entered();
+        let faces = unsafe {
+            self.handle.detect(
+                image.data().as_ptr(),
+                image.width() as i32,
+                image.height() as i32,
+                image.stride() as i32,
+                image.channels() as i32,
+            )
+        };
+        faces.map(from_bridge).map_err(inference_error)
+    }
+
+    /// Like [`infer`](Self::infer), with the C++ side writing the first `out.len()` faces
+    /// straight into `out` rather than into a vector allocated per call, returning how many
+    /// it found.
+    pub(crate) fn infer_into(
+        &self,
+        image: &ImageView,
+        out: &mut [RawFace],
+    ) -> Result<usize, YuNetError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "libfacedetection",
+            width = image.width(),
+            height = image.height(),
+            stride = image.stride(),
+            suppress = true,
+            capacity = out.len(),
+        )
+        .entered();
+        // SAFETY: `RawFace` is `repr(C)` with the fields of `BridgeFace`, as cxx lays shared
+        // structs out, in the same order; their sizes and alignments are checked below.
+        let out = unsafe {
+            std::slice::from_raw_parts_mut(out.as_mut_ptr().cast::<ffi::BridgeFace>(), out.len())
+        };
+        unsafe {
+            self.handle.detect_into(
+                image.data().as_ptr(),
+                image.width() as i32,
+                image.height() as i32,
+                image.stride() as i32,
+                image.channels() as i32,
+                out,
+            )
+        }
+        .map_err(inference_error)
+    }
+
+    pub(crate) fn infer_candidates(&self, image: &ImageView) -> Result<Vec<RawFace>, YuNetError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "libfacedetection",
+            width = image.width(),
+            height = image.height(),
+            stride = image.stride(),
+            suppress = false,
+        )
+        .entered();
+        let faces = unsafe {
+            self.handle.detect_candidates(
+                image.data().as_ptr(),
+                image.width() as i32,
+                image.height() as i32,
+                image.stride() as i32,
+                image.channels() as i32,
+            )
+        };
+        faces.map(from_bridge).map_err(inference_error)
+    }
+
+    /// Like [`infer`](Self::infer), or [`infer_candidates`](Self::infer_candidates) unless
+    /// `suppress`, also storing the outputs of the heads in `raw`.
+    pub(crate) fn infer_raw(
+        &self,
+        image: &ImageView,
+        suppress: bool,
+        raw: &mut RawOutput,
+    ) -> Result<Vec<RawFace>, YuNetError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "libfacedetection",
+            width = image.width(),
+            height = image.height(),
+            stride = image.stride(),
+            suppress,
+            raw = true,
+        )
+        .entered();
+        let mut levels = Vec::new();
+        let faces = unsafe {
+            self.handle.detect_raw(
+                image.data().as_ptr(),
+                image.width() as i32,
+                image.height() as i32,
+                image.stride() as i32,
+                image.channels() as i32,
+                suppress,
+                &mut levels,
+            )
+        }
+        .map_err(inference_error)?;
+        raw.levels.clear();
+        raw.levels.extend(levels.into_iter().map(|level| RawLevel {
+            stride: level.stride as usize,
+            rows: level.rows as usize,
+            cols: level.cols as usize,
+            cls: level.cls,
+            obj: level.obj,
+            reg: level.reg,
+            kps: level.kps,
+        }));
+        Ok(from_bridge(faces))
+    }
+}
+
+/// An exception the C++ side threw, caught at the bridge.
+fn backend_error(exception: cxx::Exception) -> YuNetError {
+    YuNetError::Backend {
+        message: exception.what().to_owned(),
+    }
+}
+
+fn inference_error(exception: cxx::Exception) -> YuNetError {
+    YuNetError::Inference {
+        backend: Backend::LibFaceDetection,
+        source: Arc::new(backend_error(exception)),
+    }
+}
+
+fn from_bridge(faces: Vec<ffi::BridgeFace>) -> Vec<RawFace> {
+    faces
+        .into_iter()
+        .map(|f| RawFace {
+            score: f.score,
+            x: f.x,
+            y: f.y,
+            w: f.w,
+            h: f.h,
+            lm: f.lm,
+        })
+        .collect()
+}
+
+const _: () = assert!(
+    std::mem::size_of::<RawFace>() == std::mem::size_of::<ffi::BridgeFace>()
+        && std::mem::align_of::<RawFace>() == std::mem::align_of::<ffi::BridgeFace>()
+);
+
+// SAFETY: the C++ handle owns all of its state and holds no thread-local resources. It
+// is deliberately not `Sync`, as detection is only ever driven through `&mut self`.
+unsafe impl Send for ffi::FaceDetectorHandle {}
+
+#[cxx::bridge]
+#[allow(clippy::too_many_arguments)]
+mod ffi {
+    // Shared type visible from both C++ and Rust
+    #[derive(Debug)]
+    struct BridgeFace {
+        score: f32,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        lm: [f32; 10],
+    }
+
+    /// The undecoded outputs of the heads on one level of the feature pyramid.
+    struct BridgeLevel {
+        stride: i32,
+        rows: i32,
+        cols: i32,
+        cls: Vec<f32>,
+        obj: Vec<f32>,
+        reg: Vec<f32>,
+        kps: Vec<f32>,
+    }
+
+    unsafe extern "C++" {
+        include!("rusty-yunet/src/bridge_wrapper.h");
+
+        type FaceDetectorHandle;
+
+        fn new_face_detector() -> Result<UniquePtr<FaceDetectorHandle>>;
+
+        /// Handles constructed and destroyed over the life of the process.
+        fn face_detectors_created() -> usize;
+        fn face_detectors_released() -> usize;
+
+        unsafe fn detect(
+            self: &FaceDetectorHandle,
+            rgb_image_data: *const u8,
+            width: i32,
+            height: i32,
+            step: i32,
+            channels: i32,
+        ) -> Result<Vec<BridgeFace>>;
+
+        /// Like `detect`, writing the first `faces.len()` faces into `faces` and returning
+        /// how many were found.
+        unsafe fn detect_into(
+            self: &FaceDetectorHandle,
+            rgb_image_data: *const u8,
+            width: i32,
+            height: i32,
+            step: i32,
+            channels: i32,
+            faces: &mut [BridgeFace],
+        ) -> Result<usize>;
+
+        /// Like `detect`, or `detect_candidates` unless `suppress`, also storing the outputs
+        /// of the heads in `levels`.
+        unsafe fn detect_raw(
+            self: &FaceDetectorHandle,
+            rgb_image_data: *const u8,
+            width: i32,
+            height: i32,
+            step: i32,
+            channels: i32,
+            suppress: bool,
+            levels: &mut Vec<BridgeLevel>,
+        ) -> Result<Vec<BridgeFace>>;
+
+        /// Like `detect`, without non-maximum suppression.
+        unsafe fn detect_candidates(
+            self: &FaceDetectorHandle,
+            rgb_image_data: *const u8,
+            width: i32,
+            height: i32,
+            step: i32,
+            channels: i32,
+        ) -> Result<Vec<BridgeFace>>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catches_every_exception() {
+        for (standard, message) in [(true, "injected fault"), (false, "unknown C++ exception")] {
+            let exception = faults::throw_fault(standard).unwrap_err();
+            assert_eq!(message, exception.what());
+        }
+    }
+
+    #[test]
+    fn reports_exceptions_as_inference_errors() {
+        let exception = faults::throw_fault(true).unwrap_err();
+        let YuNetError::Inference { backend, source } = inference_error(exception) else {
+            panic!("the exception wasn't reported");
+        };
+        assert_eq!(Backend::LibFaceDetection, backend);
+        assert_eq!("Backend error: injected fault", source.to_string());
+    }
+}
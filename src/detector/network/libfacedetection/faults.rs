@@ -0,0 +1,17 @@
+//! A bridge that only throws, for testing that exceptions become errors. Its C++ side is a
+//! library of its own that only the tests link.
+
+#[link(name = "rusty-yunet-faults", kind = "static")]
+extern "C" {}
+
+#[cxx::bridge]
+mod ffi {
+    unsafe extern "C++" {
+        include!("rusty-yunet/src/bridge_faults.h");
+
+        /// Throws a `std::exception` if `standard`, and something else otherwise.
+        fn throw_fault(standard: bool) -> Result<()>;
+    }
+}
+
+pub(super) use ffi::throw_fault;
@@ -0,0 +1,718 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use super::RawFace;
+use crate::detector::{RawLevel, RawOutput};
+use crate::io::ImageView;
+use crate::{ModelVariant, YuNetError};
+
+/// The bundled parameters, converted from libfacedetection's C++ source by the build script.
+///
+/// All values are little-endian: the magic `YNW1` and the `u32` number of layers, then for
+/// each layer its `u32` input channels and filter count, a flags byte (bit 0: a 3x3
+/// depthwise rather than a 1x1 pointwise convolution, bit 1: followed by a ReLU), its `f32`
+/// weights and finally its biases.
+///
+/// Quantized parameters, as [`quantize`] writes them, start with `YNQ1` instead, and each
+/// layer's weights are an `f32` scale per filter, or per channel when depthwise, followed by
+/// the weights as `i8` multiples of their scale.
+pub const BUNDLED_WEIGHTS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/yunet.weights"));
+const NUM_LAYERS: usize = 53;
+
+/// Post-processing parameters, as hardcoded in libfacedetection.
+const CONFIDENCE_THRESHOLD: f32 = 0.5;
+const NMS_THRESHOLD: f32 = 0.3;
+const TOP_K: usize = 1000;
+const KEEP_TOP_K: usize = 100;
+
+/// libfacedetection's network and post-processing ported to plain Rust. Slower than the
+/// SIMD C++ kernels, but builds without a C++ toolchain.
+pub(crate) struct Network {
+    model: Arc<Model>,
+}
+
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+static RELEASED: AtomicUsize = AtomicUsize::new(0);
+
+/// Networks (created, released) over the life of the process.
+pub(crate) fn counts() -> (usize, usize) {
+    // Released first, so that a network created and released in between is counted as
+    // created too.
+    let released = RELEASED.load(Ordering::SeqCst);
+    (CREATED.load(Ordering::SeqCst), released)
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        RELEASED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Network {
+    pub(crate) fn new() -> Self {
+        Self::with_model(Arc::clone(bundled_model()))
+    }
+
+    fn with_model(model: Arc<Model>) -> Self {
+        CREATED.fetch_add(1, Ordering::SeqCst);
+        Self { model }
+    }
+
+    /// Runs parameters in the format of [`BUNDLED_WEIGHTS`] instead. Only their values may
+    /// differ from the bundled ones: the layers must have the same shapes.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, YuNetError> {
+        let model = Model::from_bytes(bytes)?;
+        let shape = |layer: &Layer| (layer.channels, layer.filters, layer.depthwise, layer.relu);
+        if !model
+            .layers
+            .iter()
+            .map(shape)
+            .eq(bundled_model().layers.iter().map(shape))
+        {
+            return Err(invalid("layers differ in shape from the bundled model"));
+        }
+        Ok(Self::with_model(Arc::new(model)))
+    }
+
+    pub(crate) fn variant(&self) -> ModelVariant {
+        self.model.variant
+    }
+
+    pub(crate) fn infer(&self, image: &ImageView) -> Vec<RawFace> {
+        if image.width() == 0 || image.height() == 0 {
+            return Vec::new();
+        }
+        finish(decode(&self.model.heads(image)), true)
+    }
+
+    /// Like [`infer`](Self::infer), without non-maximum suppression.
+    pub(crate) fn infer_candidates(&self, image: &ImageView) -> Vec<RawFace> {
+        if image.width() == 0 || image.height() == 0 {
+            return Vec::new();
+        }
+        finish(decode(&self.model.heads(image)), false)
+    }
+
+    /// Like [`infer`](Self::infer), or [`infer_candidates`](Self::infer_candidates) unless
+    /// `suppress`, also storing the outputs of the heads in `raw`.
+    pub(crate) fn infer_raw(
+        &self,
+        image: &ImageView,
+        suppress: bool,
+        raw: &mut RawOutput,
+    ) -> Vec<RawFace> {
+        raw.levels.clear();
+        if image.width() == 0 || image.height() == 0 {
+            return Vec::new();
+        }
+        let heads = self.model.heads(image);
+        raw.levels.extend(heads.iter().map(Head::to_raw));
+        finish(decode(&heads), suppress)
+    }
+}
+
+/// The candidates after non-maximum suppression if `suppress`, or all of them, most
+/// confident first.
+fn finish(mut candidates: Vec<Candidate>, suppress: bool) -> Vec<RawFace> {
+    if suppress {
+        candidates = self::suppress(candidates);
+    } else {
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+    candidates.iter().map(Candidate::to_raw).collect()
+}
+
+fn bundled_model() -> &'static Arc<Model> {
+    static BUNDLED: OnceLock<Arc<Model>> = OnceLock::new();
+    BUNDLED.get_or_init(|| {
+        Arc::new(Model::from_bytes(BUNDLED_WEIGHTS).expect("bundled weights are valid"))
+    })
+}
+
+/// Which release parameters in the format of [`BUNDLED_WEIGHTS`] are of, by their magic,
+/// if they are in it at all.
+pub(crate) fn variant_of(bytes: &[u8]) -> Option<ModelVariant> {
+    match bytes.get(..4)? {
+        b"YNW1" => Some(ModelVariant::Yunet2023Mar),
+        b"YNQ1" => Some(ModelVariant::Yunet2023MarInt8),
+        _ => None,
+    }
+}
+
+/// Quantizes float parameters in the format of [`BUNDLED_WEIGHTS`] to int8, with a scale
+/// per filter that maps its largest weight to 127.
+pub(crate) fn quantize(bytes: &[u8]) -> Result<Vec<u8>, YuNetError> {
+    let model = Model::from_bytes(bytes)?;
+    if model.variant != ModelVariant::Yunet2023Mar {
+        return Err(invalid("already quantized"));
+    }
+    let mut out = b"YNQ1".to_vec();
+    out.extend_from_slice(&(NUM_LAYERS as u32).to_le_bytes());
+    for layer in &model.layers {
+        let Weights::Float(weights) = &layer.weights else {
+            unreachable!("float models have float weights")
+        };
+        out.extend_from_slice(&(layer.channels as u32).to_le_bytes());
+        out.extend_from_slice(&(layer.filters as u32).to_le_bytes());
+        out.push(layer.depthwise as u8 | (layer.relu as u8) << 1);
+        // Pointwise filters are rows of weights, depthwise channels are columns.
+        let group = |i: usize| {
+            if layer.depthwise {
+                i % layer.channels
+            } else {
+                i / layer.channels
+            }
+        };
+        let mut scales = vec![0.0f32; layer.filters];
+        for (i, weight) in weights.iter().enumerate() {
+            scales[group(i)] = scales[group(i)].max(weight.abs() / 127.0);
+        }
+        for scale in &mut scales {
+            if *scale == 0.0 {
+                *scale = 1.0;
+            }
+            out.extend_from_slice(&scale.to_le_bytes());
+        }
+        for (i, weight) in weights.iter().enumerate() {
+            out.push((weight / scales[group(i)]).round().clamp(-127.0, 127.0) as i8 as u8);
+        }
+        for bias in &layer.biases {
+            out.extend_from_slice(&bias.to_le_bytes());
+        }
+    }
+    Ok(out)
+}
+
+struct Model {
+    variant: ModelVariant,
+    layers: Vec<Layer>,
+}
+
+impl Model {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, YuNetError> {
+        let variant = variant_of(bytes).ok_or_else(|| invalid("not YuNet weights"))?;
+        let mut reader = Reader(&bytes[4..]);
+        if reader.u32()? as usize != NUM_LAYERS {
+            return Err(invalid("not YuNet weights"));
+        }
+        let quantized = variant == ModelVariant::Yunet2023MarInt8;
+        let layers = (0..NUM_LAYERS)
+            .map(|_| Layer::read(&mut reader, quantized))
+            .collect::<Result<_, _>>()?;
+        if !reader.0.is_empty() {
+            return Err(invalid("trailing bytes after the last layer"));
+        }
+        Ok(Self { variant, layers })
+    }
+
+    /// Runs the backbone, neck and heads, returning the outputs of the heads on each level
+    /// of the feature pyramid.
+    fn heads(&self, image: &ImageView) -> [Head; 3] {
+        let x = self.apply(&input_blob(image), 0..3);
+        let x = max_pool(&x);
+        let x = self.apply(&x, 3..11);
+        let fb1 = self.apply(&max_pool(&x), 11..15);
+        let fb2 = self.apply(&max_pool(&fb1), 15..19);
+        let fb3 = self.apply(&max_pool(&fb2), 19..23);
+
+        // Feature pyramid, from the coarsest level down.
+        let fb3 = self.apply(&fb3, 27..29);
+        let fb2 = self.apply(&add(&upsample(&fb3), &fb2), 25..27);
+        let fb1 = self.apply(&add(&upsample(&fb2), &fb1), 23..25);
+
+        let levels = [(fb1, 8), (fb2, 16), (fb3, 32)];
+        std::array::from_fn(|level| {
+            let (features, stride) = &levels[level];
+            let head =
+                |first: usize| self.apply(features, first + 2 * level..first + 2 * level + 2);
+            Head {
+                stride: *stride,
+                cls: head(29),
+                reg: head(35),
+                obj: head(41),
+                kps: head(47),
+            }
+        })
+    }
+
+    fn apply(&self, input: &Blob, layers: Range<usize>) -> Blob {
+        let (first, rest) = self.layers[layers]
+            .split_first()
+            .expect("at least one layer");
+        rest.iter()
+            .fold(first.apply(input), |blob, layer| layer.apply(&blob))
+    }
+}
+
+/// The outputs of the classification, box, objectness and landmark heads on one level of
+/// the feature pyramid.
+struct Head {
+    stride: usize,
+    cls: Blob,
+    reg: Blob,
+    obj: Blob,
+    kps: Blob,
+}
+
+impl Head {
+    fn to_raw(&self) -> RawLevel {
+        RawLevel {
+            stride: self.stride,
+            rows: self.cls.rows,
+            cols: self.cls.cols,
+            cls: self.cls.data.clone(),
+            obj: self.obj.data.clone(),
+            reg: self.reg.data.clone(),
+            kps: self.kps.data.clone(),
+        }
+    }
+}
+
+/// Every anchor that passes the confidence threshold.
+fn decode(heads: &[Head]) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for Head {
+        stride,
+        cls,
+        reg,
+        obj,
+        kps,
+    } in heads
+    {
+        let stride = *stride as f32;
+        for r in 0..cls.rows {
+            for c in 0..cls.cols {
+                let i = r * cls.cols + c;
+                let score = (sigmoid(cls.data[i]) * sigmoid(obj.data[i])).sqrt();
+                if score < CONFIDENCE_THRESHOLD {
+                    continue;
+                }
+                let prior = (c as f32 * stride, r as f32 * stride);
+                let b = reg.pixel(r, c);
+                let (cx, cy) = (b[0] * stride + prior.0, b[1] * stride + prior.1);
+                let (w, h) = (b[2].exp() * stride, b[3].exp() * stride);
+                let k = kps.pixel(r, c);
+                candidates.push(Candidate {
+                    score,
+                    bbox: [cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0],
+                    landmarks: std::array::from_fn(|j| {
+                        k[j] * stride + if j % 2 == 0 { prior.0 } else { prior.1 }
+                    }),
+                });
+            }
+        }
+    }
+    candidates
+}
+
+/// A 1x1 pointwise or 3x3 depthwise convolution.
+struct Layer {
+    channels: usize,
+    filters: usize,
+    depthwise: bool,
+    relu: bool,
+    /// `filters` rows of `channels` weights when pointwise, 9 rows of `channels` when
+    /// depthwise.
+    weights: Weights,
+    biases: Vec<f32>,
+}
+
+enum Weights {
+    Float(Vec<f32>),
+    /// Pointwise weights of a quantized model, `channels` rows of `filters`, with the scale
+    /// of each filter. The inputs are quantized per pixel in turn, so that the sums are of
+    /// integers. Depthwise weights, a small share of the work, are restored to floats on
+    /// loading.
+    Int8 {
+        weights: Vec<i8>,
+        scales: Vec<f32>,
+    },
+}
+
+impl Layer {
+    fn read(reader: &mut Reader, quantized: bool) -> Result<Self, YuNetError> {
+        let channels = reader.u32()? as usize;
+        let filters = reader.u32()? as usize;
+        let flags = reader.take(1)?[0];
+        let depthwise = flags & 1 != 0;
+        if depthwise && channels != filters {
+            return Err(invalid("depthwise layer with more filters than channels"));
+        }
+        let weight_count = if depthwise { 9 } else { filters };
+        let weight_count = weight_count.checked_mul(channels);
+        let weights = if !quantized {
+            Weights::Float(reader.f32s(weight_count)?)
+        } else {
+            let scales = reader.f32s(Some(if depthwise { channels } else { filters }))?;
+            let weights = reader.i8s(weight_count)?;
+            if depthwise {
+                Weights::Float(
+                    (weights.iter().enumerate())
+                        .map(|(i, &w)| w as f32 * scales[i % channels])
+                        .collect(),
+                )
+            } else {
+                // Transposed, so that each input accumulates into every filter at once.
+                let weights = (0..channels * filters)
+                    .map(|i| weights[(i % filters) * channels + i / filters])
+                    .collect();
+                Weights::Int8 { weights, scales }
+            }
+        };
+        Ok(Self {
+            channels,
+            filters,
+            depthwise,
+            relu: flags & 2 != 0,
+            weights,
+            biases: reader.f32s(Some(filters))?,
+        })
+    }
+
+    fn apply(&self, input: &Blob) -> Blob {
+        assert_eq!(self.channels, input.channels, "layer input channels");
+        let mut out = Blob::zeros(input.rows, input.cols, self.filters);
+        match &self.weights {
+            Weights::Float(weights) if self.depthwise => self.depthwise(weights, input, &mut out),
+            Weights::Float(weights) => self.pointwise(weights, input, &mut out),
+            Weights::Int8 { weights, scales } => {
+                self.pointwise_int8(weights, scales, input, &mut out)
+            }
+        }
+        if self.relu {
+            for v in &mut out.data {
+                *v = v.max(0.0);
+            }
+        }
+        out
+    }
+
+    fn pointwise(&self, weights: &[f32], input: &Blob, out: &mut Blob) {
+        let pixels = input.data.chunks_exact(self.channels);
+        for (src, dst) in pixels.zip(out.data.chunks_exact_mut(self.filters)) {
+            let filters = weights.chunks_exact(self.channels).zip(&self.biases);
+            for (value, (weights, bias)) in dst.iter_mut().zip(filters) {
+                *value = src.iter().zip(weights).map(|(a, b)| a * b).sum::<f32>() + bias;
+            }
+        }
+    }
+
+    fn pointwise_int8(&self, weights: &[i8], scales: &[f32], input: &Blob, out: &mut Blob) {
+        let mut sums = vec![0i32; self.filters];
+        let pixels = input.data.chunks_exact(self.channels);
+        for (src, dst) in pixels.zip(out.data.chunks_exact_mut(self.filters)) {
+            let max = src.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+            let scale = if max == 0.0 { 1.0 } else { max / 127.0 };
+            sums.fill(0);
+            for (v, row) in src.iter().zip(weights.chunks_exact(self.filters)) {
+                // Rounded half away from zero; most inputs follow a ReLU and are zero.
+                let q = (v / scale + 0.5f32.copysign(*v)) as i16;
+                if q != 0 {
+                    for (sum, &w) in sums.iter_mut().zip(row) {
+                        *sum += (q * w as i16) as i32;
+                    }
+                }
+            }
+            let filters = sums.iter().zip(scales.iter().zip(&self.biases));
+            for (value, (&sum, (filter_scale, bias))) in dst.iter_mut().zip(filters) {
+                *value = sum as f32 * scale * filter_scale + bias;
+            }
+        }
+    }
+
+    fn depthwise(&self, weights: &[f32], input: &Blob, out: &mut Blob) {
+        let channels = self.channels;
+        for r in 0..input.rows {
+            for c in 0..input.cols {
+                let dst = &mut out.data[(r * input.cols + c) * channels..][..channels];
+                for sr in r.saturating_sub(1)..(r + 2).min(input.rows) {
+                    for sc in c.saturating_sub(1)..(c + 2).min(input.cols) {
+                        let tap = (sr + 1 - r) * 3 + (sc + 1 - c);
+                        let weights = &weights[tap * channels..][..channels];
+                        for ((value, s), w) in dst.iter_mut().zip(input.pixel(sr, sc)).zip(weights)
+                        {
+                            *value += s * w;
+                        }
+                    }
+                }
+                for (value, bias) in dst.iter_mut().zip(&self.biases) {
+                    *value += bias;
+                }
+            }
+        }
+    }
+}
+
+/// A feature map, stored row by row with interleaved channels.
+struct Blob {
+    rows: usize,
+    cols: usize,
+    channels: usize,
+    data: Vec<f32>,
+}
+
+impl Blob {
+    fn zeros(rows: usize, cols: usize, channels: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            channels,
+            data: vec![0.0; rows * cols * channels],
+        }
+    }
+
+    fn pixel(&self, r: usize, c: usize) -> &[f32] {
+        &self.data[(r * self.cols + c) * self.channels..][..self.channels]
+    }
+
+    fn pixel_mut(&mut self, r: usize, c: usize) -> &mut [f32] {
+        &mut self.data[(r * self.cols + c) * self.channels..][..self.channels]
+    }
+}
+
+/// Unrolls every 3x3, stride 2 neighbourhood of the image (padded to a multiple of 32) into
+/// the channels of one pixel, so that the first convolution can be pointwise. 27 of the 32
+/// channels are used.
+fn input_blob(image: &ImageView) -> Blob {
+    let (width, height) = image.dimensions();
+    let mut blob = Blob::zeros(height.div_ceil(32) * 16, width.div_ceil(32) * 16, 32);
+    for r in 0..blob.rows {
+        for c in 0..blob.cols {
+            let dst = blob.pixel_mut(r, c);
+            for dy in 0..3 {
+                let Some(y) = (2 * r + dy).checked_sub(1).filter(|&y| y < height) else {
+                    continue;
+                };
+                for dx in 0..3 {
+                    let Some(x) = (2 * c + dx).checked_sub(1).filter(|&x| x < width) else {
+                        continue;
+                    };
+                    let channels = image.channels();
+                    let src = &image.data()[y * image.stride() + channels * x..][..channels];
+                    let offset = (dy * 3 + dx) * 3;
+                    for (i, value) in dst[offset..offset + 3].iter_mut().enumerate() {
+                        // Gray pixels fill every color channel.
+                        *value = src[i % channels] as f32;
+                    }
+                }
+            }
+        }
+    }
+    blob
+}
+
+fn max_pool(input: &Blob) -> Blob {
+    let mut out = Blob::zeros(input.rows / 2, input.cols / 2, input.channels);
+    for r in 0..out.rows {
+        for c in 0..out.cols {
+            let dst = out.pixel_mut(r, c);
+            dst.copy_from_slice(input.pixel(2 * r, 2 * c));
+            for (sr, sc) in [
+                (2 * r, 2 * c + 1),
+                (2 * r + 1, 2 * c),
+                (2 * r + 1, 2 * c + 1),
+            ] {
+                if sr < input.rows && sc < input.cols {
+                    for (value, s) in dst.iter_mut().zip(input.pixel(sr, sc)) {
+                        *value = value.max(*s);
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn upsample(input: &Blob) -> Blob {
+    let mut out = Blob::zeros(input.rows * 2, input.cols * 2, input.channels);
+    for r in 0..out.rows {
+        for c in 0..out.cols {
+            out.pixel_mut(r, c)
+                .copy_from_slice(input.pixel(r / 2, c / 2));
+        }
+    }
+    out
+}
+
+fn add(a: &Blob, b: &Blob) -> Blob {
+    assert_eq!(
+        (a.rows, a.cols, a.channels),
+        (b.rows, b.cols, b.channels),
+        "added feature maps must have the same shape"
+    );
+    Blob {
+        data: a.data.iter().zip(&b.data).map(|(a, b)| a + b).collect(),
+        ..*a
+    }
+}
+
+fn sigmoid(v: f32) -> f32 {
+    let v = v.clamp(-88.376_26, 88.376_26);
+    1.0 / (1.0 + (-v).exp())
+}
+
+struct Candidate {
+    score: f32,
+    /// Left, top, right, bottom.
+    bbox: [f32; 4],
+    landmarks: [f32; 10],
+}
+
+impl Candidate {
+    fn to_raw(&self) -> RawFace {
+        RawFace {
+            score: self.score,
+            x: self.bbox[0],
+            y: self.bbox[1],
+            w: self.bbox[2] - self.bbox[0],
+            h: self.bbox[3] - self.bbox[1],
+            lm: self.landmarks,
+        }
+    }
+}
+
+/// Greedy non-maximum suppression, keeping the most confident of overlapping candidates.
+fn suppress(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(TOP_K);
+    let mut kept: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        if kept.len() == KEEP_TOP_K {
+            break;
+        }
+        if kept
+            .iter()
+            .all(|k| overlap(&k.bbox, &candidate.bbox) <= NMS_THRESHOLD)
+        {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+fn overlap(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let w = a[2].min(b[2]) - a[0].max(b[0]);
+    let h = a[3].min(b[3]) - a[1].max(b[1]);
+    if w <= 0.0 || h <= 0.0 {
+        return 0.0;
+    }
+    let area = |r: &[f32; 4]| (r[2] - r[0]) * (r[3] - r[1]);
+    let intersection = w * h;
+    intersection / (area(a) + area(b) - intersection)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+fn invalid(reason: &str) -> YuNetError {
+    YuNetError::ModelLoad {
+        reason: reason.to_owned(),
+        source: None,
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], YuNetError> {
+        if self.0.len() < len {
+            return Err(invalid("truncated"));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, YuNetError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Reads `count` bytes as signed integers; `None` stands for a count that overflowed.
+    fn i8s(&mut self, count: Option<usize>) -> Result<Vec<i8>, YuNetError> {
+        let len = count.ok_or_else(|| invalid("layer too large"))?;
+        Ok(self.take(len)?.iter().map(|&byte| byte as i8).collect())
+    }
+
+    /// Reads `count` floats; `None` stands for a count that overflowed.
+    fn f32s(&mut self, count: Option<usize>) -> Result<Vec<f32>, YuNetError> {
+        let len = count
+            .and_then(|count| count.checked_mul(4))
+            .ok_or_else(|| invalid("layer too large"))?;
+        Ok(self
+            .take(len)?
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_malformed_weights() {
+        assert!(Model::from_bytes(b"YNW1").is_err());
+        assert!(Model::from_bytes(&BUNDLED_WEIGHTS[..BUNDLED_WEIGHTS.len() - 4]).is_err());
+        assert!(Model::from_bytes(BUNDLED_WEIGHTS).is_ok());
+    }
+
+    #[test]
+    fn loads_weights_of_the_same_architecture() {
+        assert!(Network::from_bytes(BUNDLED_WEIGHTS).is_ok());
+        // The first layer's flags, after the header and its channel and filter counts.
+        let mut without_relu = BUNDLED_WEIGHTS.to_vec();
+        without_relu[16] &= !2;
+        assert!(Model::from_bytes(&without_relu).is_ok());
+        assert!(Network::from_bytes(&without_relu).is_err());
+    }
+
+    #[test]
+    fn runs_quantized_weights() {
+        let quantized = quantize(BUNDLED_WEIGHTS).unwrap();
+        assert_eq!(Some(ModelVariant::Yunet2023MarInt8), variant_of(&quantized));
+        assert!(quantize(&quantized).is_err());
+        let network = Network::from_bytes(&quantized).unwrap();
+        assert_eq!(ModelVariant::Yunet2023MarInt8, network.variant());
+
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = image.dimensions();
+        let view = ImageView::new(image.as_raw(), width as usize, height as usize).unwrap();
+        let float = Network::new().infer(&view);
+        let int8 = network.infer(&view);
+        assert_eq!(float.len(), int8.len());
+        for (a, b) in float.iter().zip(&int8) {
+            assert!((a.score - b.score).abs() < 0.02, "{a:?} vs {b:?}");
+            for (a, b) in [a.x, a.y, a.w, a.h].iter().zip([b.x, b.y, b.w, b.h]) {
+                assert!((a - b).abs() < 2.0, "{a} vs {b}");
+            }
+        }
+    }
+
+    #[cfg(feature = "libfacedetection")]
+    #[test]
+    fn matches_libfacedetection() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = image.dimensions();
+        let view = ImageView::new(image.as_raw(), width as usize, height as usize).unwrap();
+
+        let native = Network::new().infer(&view);
+        let reference = super::super::libfacedetection::Network::new()
+            .unwrap()
+            .infer(&view)
+            .unwrap();
+        assert_eq!(reference.len(), native.len());
+        for (native, reference) in native.iter().zip(&reference) {
+            assert!((native.score - reference.score).abs() < 1e-3);
+            let (a, b) = (
+                [native.x, native.y, native.w, native.h],
+                [reference.x, reference.y, reference.w, reference.h],
+            );
+            for (a, b) in a
+                .iter()
+                .chain(&native.lm)
+                .zip(b.iter().chain(&reference.lm))
+            {
+                assert!((a - b).abs() < 0.5, "{native:?} vs {reference:?}");
+            }
+        }
+    }
+}
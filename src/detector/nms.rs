@@ -0,0 +1,102 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::Face;
+
+/// How overlapping candidates are merged, in place of the network's own hard suppression at
+/// an IoU of 0.3. Soft strategies lower the confidence of overlapping candidates instead of
+/// dropping them, so that adjacent faces in a crowd survive.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NmsStrategy {
+    /// Drop candidates overlapping a more confident one by more than `iou`.
+    Hard { iou: f32 },
+    /// Scale the confidence of candidates overlapping a more confident one by more than
+    /// `iou` by one minus the overlap, then drop those below `min_confidence`.
+    SoftLinear { iou: f32, min_confidence: f32 },
+    /// Scale the confidence of every candidate by `exp(-iou² / sigma)` of its overlap with
+    /// each more confident one, then drop those below `min_confidence`.
+    SoftGaussian { sigma: f32, min_confidence: f32 },
+}
+
+impl NmsStrategy {
+    /// Suppresses overlapping faces, returning the survivors most confident first.
+    pub fn suppress(&self, mut candidates: Vec<Face>) -> Vec<Face> {
+        let mut kept: Vec<Face> = Vec::new();
+        while let Some(best) = candidates
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.confidence().total_cmp(&b.confidence()))
+            .map(|(i, _)| i)
+        {
+            let best = candidates.swap_remove(best);
+            let rect = best.rectangle();
+            candidates.retain_mut(|candidate| {
+                let iou = rect.iou(&candidate.rectangle());
+                let decay = match *self {
+                    NmsStrategy::Hard { iou: threshold } => return iou <= threshold,
+                    NmsStrategy::SoftLinear { iou: threshold, .. } if iou > threshold => 1.0 - iou,
+                    NmsStrategy::SoftLinear { .. } => 1.0,
+                    NmsStrategy::SoftGaussian { sigma, .. } => (-iou * iou / sigma).exp(),
+                };
+                candidate.set_confidence(candidate.confidence() * decay);
+                candidate.confidence() >= self.min_confidence()
+            });
+            kept.push(best);
+        }
+        kept
+    }
+
+    fn min_confidence(&self) -> f32 {
+        match *self {
+            NmsStrategy::Hard { .. } => 0.0,
+            NmsStrategy::SoftLinear { min_confidence, .. }
+            | NmsStrategy::SoftGaussian { min_confidence, .. } => min_confidence,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(score: f32, x: i32) -> Face {
+        test_face(score, [x as f32, 0.0, 20.0, 20.0], (100, 100), None)
+    }
+
+    #[test]
+    fn suppresses_by_strategy() {
+        // Two adjacent faces overlapping by half their width, and one apart.
+        let candidates = vec![face(0.8, 10), face(0.9, 0), face(0.7, 60)];
+        let confidences = |strategy: NmsStrategy| -> Vec<f32> {
+            strategy
+                .suppress(candidates.clone())
+                .iter()
+                .map(Face::confidence)
+                .collect()
+        };
+        let iou = 1.0 / 3.0;
+
+        assert_eq!(vec![0.9, 0.7], confidences(NmsStrategy::Hard { iou: 0.3 }));
+        assert_eq!(
+            vec![0.9, 0.8, 0.7],
+            confidences(NmsStrategy::Hard { iou: 0.5 })
+        );
+        let linear = confidences(NmsStrategy::SoftLinear {
+            iou: 0.3,
+            min_confidence: 0.5,
+        });
+        assert_eq!(3, linear.len());
+        assert_eq!([0.9, 0.7], linear[..2]);
+        assert!((linear[2] - 0.8 * (1.0 - iou)).abs() < 1e-6);
+        let gaussian = |min_confidence| {
+            confidences(NmsStrategy::SoftGaussian {
+                sigma: 0.5,
+                min_confidence,
+            })
+        };
+        assert!((gaussian(0.6)[2] - 0.8 * (-iou * iou / 0.5f32).exp()).abs() < 1e-6);
+        assert_eq!(2, gaussian(0.65).len());
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use super::stats::{self, Instant};
+use super::{prepare, Output, Overrides, Prepared, ScratchFrames};
+use crate::{DetectorConfig, Face, FaceDetector, ImageView, YuNetError};
+
+/// A [`FaceDetector`] on a thread of its own, so that the next frame is copied, oriented and
+/// resized on the caller's thread while the network runs on the last one. On machines with
+/// a core to spare, this nearly doubles the frames per second of a stream whose frames need
+/// preparing, such as downscaling with [`max_side`](DetectorConfig::max_side), at the cost of
+/// `depth` frames of latency. Faces are the same as those of the detector itself.
+///
+/// The detector's configuration is fixed for the life of the pipeline; a
+/// [`ConfigWatcher`](crate::ConfigWatcher) it was given is ignored.
+pub struct PipelinedDetector {
+    config: DetectorConfig,
+    jobs: Option<SyncSender<Job>>,
+    done: Receiver<Done>,
+    worker: Option<JoinHandle<FaceDetector>>,
+    depth: usize,
+    in_flight: usize,
+    /// Buffers of finished frames, reused by the next ones.
+    spare: Vec<(Vec<u8>, ScratchFrames)>,
+}
+
+/// A prepared frame on its way to the network.
+struct Job {
+    /// The frame, tightly packed.
+    pixels: Vec<u8>,
+    dimensions: (usize, usize),
+    channels: usize,
+    buffers: ScratchFrames,
+    prepared: Prepared,
+    preprocess_ms: f64,
+}
+
+/// A frame back from the network, with its buffers for reuse.
+struct Done {
+    faces: Result<Vec<Face>, YuNetError>,
+    pixels: Vec<u8>,
+    buffers: ScratchFrames,
+}
+
+impl PipelinedDetector {
+    /// Moves `detector` to a thread of its own. Up to `depth` frames, raised to at least
+    /// one, are in flight before [`detect`](Self::detect) waits for the oldest; one is
+    /// double buffering.
+    pub fn new(mut detector: FaceDetector, depth: usize) -> Self {
+        let depth = depth.max(1);
+        let config = detector.config().clone();
+        let (jobs, queue) = mpsc::sync_channel::<Job>(depth);
+        let (finished, done) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            for job in queue {
+                let (width, height) = job.dimensions;
+                let image = ImageView::packed(&job.pixels, width, height, job.channels)
+                    .expect("queued frames are packed");
+                let mut faces = Vec::new();
+                let result = detector.finish(
+                    &image,
+                    &job.buffers,
+                    &job.prepared,
+                    job.preprocess_ms,
+                    true,
+                    &mut faces,
+                    Output::Allocated,
+                );
+                #[cfg(feature = "metrics")]
+                detector.record_metrics(result.as_ref().map(|(stats, _)| stats));
+                let done = Done {
+                    faces: result.map(|_| faces),
+                    pixels: job.pixels,
+                    buffers: job.buffers,
+                };
+                if finished.send(done).is_err() {
+                    break;
+                }
+            }
+            detector
+        });
+        Self {
+            config,
+            jobs: Some(jobs),
+            done,
+            worker: Some(worker),
+            depth,
+            in_flight: 0,
+            spare: Vec::new(),
+        }
+    }
+
+    /// Queues `frame`, and once more than `depth` frames are in flight, waits for the oldest
+    /// and returns its faces. Frames come back in the order they were queued.
+    ///
+    /// Errors of the frame returned are returned; a frame too large for the network fails
+    /// right away, without being queued.
+    pub fn detect(&mut self, frame: &ImageView) -> Result<Option<Vec<Face>>, YuNetError> {
+        self.submit(frame)?;
+        if self.in_flight > self.depth {
+            self.next().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Waits for every frame in flight, such as at the end of a stream, and returns their
+    /// faces in order, or the first error among them.
+    pub fn flush(&mut self) -> Result<Vec<Vec<Face>>, YuNetError> {
+        let results: Vec<_> = (0..self.in_flight).map(|_| self.next()).collect();
+        results.into_iter().collect()
+    }
+
+    /// Frames queued and not yet returned.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    pub fn config(&self) -> &DetectorConfig {
+        &self.config
+    }
+
+    /// Stops the thread and returns the detector, dropping the results of frames in
+    /// flight.
+    pub fn into_inner(mut self) -> FaceDetector {
+        self.jobs = None;
+        let worker = self.worker.take().expect("detector thread is running");
+        worker.join().expect("detection panicked")
+    }
+
+    fn submit(&mut self, frame: &ImageView) -> Result<(), YuNetError> {
+        let started = Instant::now();
+        let (mut pixels, mut buffers) = self.spare.pop().unwrap_or_default();
+        let (width, height) = frame.dimensions();
+        let row = width * frame.channels();
+        pixels.clear();
+        for y in 0..height {
+            pixels.extend_from_slice(&frame.data()[y * frame.stride()..][..row]);
+        }
+        let image = ImageView::packed(&pixels, width, height, frame.channels())
+            .expect("copied frames are packed");
+        let prepared = prepare(&self.config, Overrides::default(), &image, &mut buffers)?;
+        let job = Job {
+            dimensions: (width, height),
+            channels: frame.channels(),
+            prepared,
+            preprocess_ms: stats::millis(started.elapsed()),
+            pixels,
+            buffers,
+        };
+        self.jobs
+            .as_ref()
+            .expect("detector thread is running")
+            .send(job)
+            .expect("detection panicked");
+        self.in_flight += 1;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Vec<Face>, YuNetError> {
+        let done = self.done.recv().expect("detection panicked");
+        self.in_flight -= 1;
+        self.spare.push((done.pixels, done.buffers));
+        done.faces
+    }
+}
+
+impl Drop for PipelinedDetector {
+    fn drop(&mut self) {
+        self.jobs = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_detector_in_order() {
+        let sample = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (sample.width() as usize, sample.height() as usize);
+        let blank = vec![0; width * height * 3];
+        let frames = [sample.as_raw(), &blank, sample.as_raw(), sample.as_raw()]
+            .map(|bytes| ImageView::new(bytes, width, height).unwrap());
+        let config = DetectorConfig {
+            max_side: Some(320),
+            mirror: true,
+            ..Default::default()
+        };
+
+        let mut direct = FaceDetector::with_config(config.clone()).unwrap();
+        let expected: Vec<Vec<Face>> = frames
+            .iter()
+            .map(|frame| direct.detect_image(frame).unwrap())
+            .collect();
+        let mut pipelined = PipelinedDetector::new(FaceDetector::with_config(config).unwrap(), 1);
+        let mut detected = Vec::new();
+        for frame in &frames {
+            detected.extend(pipelined.detect(frame).unwrap());
+            assert!(pipelined.in_flight() <= 1);
+        }
+        assert_eq!(3, detected.len());
+        detected.extend(pipelined.flush().unwrap());
+        assert_eq!(0, pipelined.in_flight());
+
+        assert!(!expected[0].is_empty() && expected[1].is_empty());
+        for (faces, expected) in detected.iter().zip(&expected) {
+            assert_eq!(expected.len(), faces.len());
+            for (face, expected) in faces.iter().zip(expected) {
+                assert_eq!(expected.rectangle(), face.rectangle());
+                assert_eq!(expected.landmarks(), face.landmarks());
+            }
+        }
+        assert_eq!(4, pipelined.into_inner().stats().detections);
+    }
+}
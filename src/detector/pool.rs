@@ -0,0 +1,191 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+
+use super::{DetectorConfig, FaceDetector};
+#[cfg(feature = "rayon")]
+use crate::CancellationToken;
+use crate::{Face, ImageView, YuNetError};
+
+/// A fixed-size set of [`FaceDetector`]s that can be shared between threads.
+///
+/// Each detector is only ever used by one thread at a time; callers block until one
+/// becomes available.
+pub struct FaceDetectorPool {
+    idle: Mutex<Vec<FaceDetector>>,
+    returned: Condvar,
+    size: usize,
+}
+
+impl FaceDetectorPool {
+    /// Creates a pool of `size` detectors. A size of zero is rounded up to one.
+    pub fn new(size: usize) -> Self {
+        Self::with_config(size, DetectorConfig::default())
+            .expect("default backend is always available")
+    }
+
+    /// Creates a pool of `size` detectors sharing the same configuration.
+    pub fn with_config(size: usize, config: DetectorConfig) -> Result<Self, YuNetError> {
+        let size = size.max(1);
+        let detectors = (0..size)
+            .map(|_| FaceDetector::with_config(config.clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            idle: Mutex::new(detectors),
+            returned: Condvar::new(),
+            size,
+        })
+    }
+
+    /// The number of detectors owned by the pool.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Warms up every idle detector for frames of the given dimensions, as
+    /// [`FaceDetector::warm_up`] does, typically right after creating the pool.
+    pub fn warm_up(&self, width: usize, height: usize) -> Result<(), YuNetError> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        for detector in idle.iter_mut() {
+            detector.warm_up(width, height)?;
+        }
+        Ok(())
+    }
+
+    /// Checks out a detector, blocking until one is idle. It returns to the pool on drop.
+    pub fn get(&self) -> PooledDetector<'_> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(detector) = idle.pop() {
+                return PooledDetector {
+                    pool: self,
+                    config: detector.config().clone(),
+                    detector: Some(detector),
+                };
+            }
+            idle = self.returned.wait(idle).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Detects faces in a tightly packed BGR image using the next idle detector.
+    pub fn detect(
+        &self,
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<Face>, YuNetError> {
+        self.get().detect(bytes, width, height)
+    }
+
+    /// Detects faces in a BGR image using the next idle detector.
+    pub fn detect_image(&self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        self.get().detect_image(image)
+    }
+
+    /// Detects faces in every image, spreading the work over the current rayon thread pool.
+    ///
+    /// The images are split into at most [`size`](Self::size) contiguous chunks, each
+    /// processed by one detector, so rayon workers don't sit blocked waiting for a detector
+    /// when the pool is smaller than the thread pool. They still block if other threads are
+    /// using the pool at the same time.
+    #[cfg(feature = "rayon")]
+    pub fn detect_parallel(&self, images: &[ImageView]) -> Vec<Result<Vec<Face>, YuNetError>> {
+        self.detect_parallel_with_cancel(images, &CancellationToken::new())
+    }
+
+    /// Like [`detect_parallel`](Self::detect_parallel), failing the images not yet started
+    /// with [`YuNetError::Cancelled`] once `cancel` is cancelled.
+    #[cfg(feature = "rayon")]
+    pub fn detect_parallel_with_cancel(
+        &self,
+        images: &[ImageView],
+        cancel: &CancellationToken,
+    ) -> Vec<Result<Vec<Face>, YuNetError>> {
+        use rayon::prelude::*;
+
+        if images.is_empty() {
+            return Vec::new();
+        }
+        images
+            .par_chunks(images.len().div_ceil(self.size))
+            .flat_map_iter(|chunk| {
+                let mut detector = self.get();
+                chunk
+                    .iter()
+                    .map(|image| cancel.check().and_then(|()| detector.detect_image(image)))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// A detector checked out of a [`FaceDetectorPool`].
+///
+/// Configuration changed through [`set_config`](FaceDetector::set_config) holds until the
+/// detector returns to the pool, which puts back the configuration it was checked out with.
+pub struct PooledDetector<'a> {
+    pool: &'a FaceDetectorPool,
+    /// The configuration at checkout, restored on return.
+    config: DetectorConfig,
+    detector: Option<FaceDetector>,
+}
+
+impl Deref for PooledDetector<'_> {
+    type Target = FaceDetector;
+
+    fn deref(&self) -> &FaceDetector {
+        self.detector
+            .as_ref()
+            .expect("detector is present until drop")
+    }
+}
+
+impl DerefMut for PooledDetector<'_> {
+    fn deref_mut(&mut self) -> &mut FaceDetector {
+        self.detector
+            .as_mut()
+            .expect("detector is present until drop")
+    }
+}
+
+impl Drop for PooledDetector<'_> {
+    fn drop(&mut self) {
+        if let Some(mut detector) = self.detector.take() {
+            if detector.config() != &self.config
+                && detector.set_config(self.config.clone()).is_err()
+            {
+                // Going back to the backend the detector was created with only fails if its
+                // network can't be created again; start over rather than lend the borrower's.
+                if let Ok(fresh) = FaceDetector::with_config(self.config.clone()) {
+                    detector = fresh;
+                }
+            }
+            self.pool
+                .idle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(detector);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_the_configuration_on_return() {
+        let pool = FaceDetectorPool::new(1);
+        {
+            let mut detector = pool.get();
+            let config = DetectorConfig {
+                min_confidence: Some(0.99),
+                mirror: true,
+                ..Default::default()
+            };
+            detector.set_config(config.clone()).unwrap();
+            assert_eq!(&config, detector.config());
+        }
+        assert_eq!(&DetectorConfig::default(), pool.get().config());
+    }
+}
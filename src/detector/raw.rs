@@ -0,0 +1,65 @@
+use glam::Vec2;
+
+use crate::{FaceLandmarks, Rect};
+
+/// The undecoded outputs of YuNet's heads for one frame, for experimenting with decoding,
+/// calibration or confidence re-mapping of your own without patching the backends.
+///
+/// Coordinates are in pixels of the network input: the frame after the rotation, mirroring
+/// and downscaling of the detector's configuration, of `input_size`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawOutput {
+    pub input_size: (usize, usize),
+    /// The levels of the feature pyramid, with strides 8, 16 and 32.
+    pub levels: Vec<RawLevel>,
+}
+
+/// The outputs of the heads on one level of the feature pyramid: one anchor per cell of a
+/// `rows` by `cols` grid, in row-major order, its prior at `(col, row) * stride`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawLevel {
+    pub stride: usize,
+    pub rows: usize,
+    pub cols: usize,
+    /// The classification logit of each anchor.
+    pub cls: Vec<f32>,
+    /// The objectness logit of each anchor.
+    pub obj: Vec<f32>,
+    /// The box of each anchor as 4 values: the offset of its center from the prior and the
+    /// logarithm of its width and height, in strides.
+    pub reg: Vec<f32>,
+    /// The landmarks of each anchor as 10 values: the offsets of each from the prior as x, y
+    /// pairs in the order of [`FaceLandmarks`], in strides.
+    pub kps: Vec<f32>,
+}
+
+impl RawLevel {
+    pub fn anchors(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// The confidence of anchor `i` as YuNet scores it, the geometric mean of its
+    /// classification and objectness probabilities. Faces score at least 0.5.
+    pub fn score(&self, i: usize) -> f32 {
+        (sigmoid(self.cls[i]) * sigmoid(self.obj[i])).sqrt()
+    }
+
+    /// The rectangle and landmarks of anchor `i` as YuNet decodes them, in pixels of the
+    /// network input.
+    pub fn decode(&self, i: usize) -> (Rect, FaceLandmarks) {
+        let stride = self.stride as f32;
+        let prior = Vec2::new((i % self.cols) as f32, (i / self.cols) as f32) * stride;
+        let b = &self.reg[4 * i..4 * i + 4];
+        let center = Vec2::new(b[0], b[1]) * stride + prior;
+        let size = Vec2::new(b[2].exp(), b[3].exp()) * stride;
+        let k = &self.kps[10 * i..10 * i + 10];
+        let point = |j: usize| Vec2::new(k[2 * j], k[2 * j + 1]) * stride + prior;
+        let landmarks = FaceLandmarks::from_array([0, 1, 2, 3, 4].map(point));
+        (Rect::new(center - size / 2.0, size.x, size.y), landmarks)
+    }
+}
+
+fn sigmoid(v: f32) -> f32 {
+    let v = v.clamp(-88.376_26, 88.376_26);
+    1.0 / (1.0 + (-v).exp())
+}
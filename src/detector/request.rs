@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use glam::Vec2;
+
+use super::{FaceDetector, FaceDetectorPool};
+use crate::{DetectionContext, Face, ImageView, Rect, YuNetError};
+
+enum Runner<'d> {
+    Detector(&'d mut FaceDetector),
+    Pool(&'d FaceDetectorPool),
+}
+
+/// Options for a single detection, applied on top of the detector's configuration without
+/// changing it. Start one with [`FaceDetector::request`] or [`FaceDetectorPool::request`].
+///
+/// ```no_run
+/// # use rusty_yunet::{FaceDetector, ImageView, Rect};
+/// # let bytes = vec![0u8; 3 * 640 * 480];
+/// # let image = ImageView::new(&bytes, 640, 480)?;
+/// let mut detector = FaceDetector::new();
+/// let faces = detector
+///     .request(&image)
+///     .roi(Rect::with_size(160.0, 0.0, 320.0, 480.0))
+///     .min_confidence(0.7)
+///     .max_faces(3)
+///     .run()?;
+/// # Ok::<(), rusty_yunet::YuNetError>(())
+/// ```
+#[must_use = "a request does nothing until run"]
+pub struct DetectionRequest<'d, 'i> {
+    runner: Runner<'d>,
+    image: ImageView<'i>,
+    roi: Option<Rect>,
+    min_confidence: f32,
+    max_faces: Option<usize>,
+    context: Option<Arc<DetectionContext>>,
+}
+
+impl<'d, 'i> DetectionRequest<'d, 'i> {
+    fn new(runner: Runner<'d>, image: &ImageView<'i>) -> Self {
+        Self {
+            runner,
+            image: *image,
+            roi: None,
+            min_confidence: 0.0,
+            max_faces: None,
+            context: None,
+        }
+    }
+
+    /// Only detects within this region of the image, in pixels, clamped to the image. Faces
+    /// are still reported in the coordinates of the whole image.
+    pub fn roi(mut self, roi: Rect) -> Self {
+        self.roi = Some(roi);
+        self
+    }
+
+    /// Drops faces with less confidence than this.
+    pub fn min_confidence(mut self, min_confidence: f32) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Keeps at most this many faces, the first in the detector's
+    /// [`result_order`](super::DetectorConfig::result_order).
+    pub fn max_faces(mut self, max_faces: usize) -> Self {
+        self.max_faces = Some(max_faces);
+        self
+    }
+
+    /// Records which frame of which source the image is in every face; see
+    /// [`Face::context`].
+    pub fn context(mut self, context: DetectionContext) -> Self {
+        self.context = Some(Arc::new(context));
+        self
+    }
+
+    pub fn run(self) -> Result<Vec<Face>, YuNetError> {
+        let (region, offset) = match self.roi {
+            Some(roi) => {
+                let x = roi.x.max(0.0) as usize;
+                let y = roi.y.max(0.0) as usize;
+                let width = (roi.x + roi.w).max(0.0) as usize;
+                let height = (roi.y + roi.h).max(0.0) as usize;
+                let Some(region) =
+                    self.image
+                        .crop(x, y, width.saturating_sub(x), height.saturating_sub(y))
+                else {
+                    return Ok(Vec::new());
+                };
+                (region, Some(Vec2::new(x as f32, y as f32)))
+            }
+            None => (self.image, None),
+        };
+        let mut faces = match self.runner {
+            Runner::Detector(detector) => detector.detect_image(&region)?,
+            Runner::Pool(pool) => pool.detect_image(&region)?,
+        };
+        if let Some(offset) = offset {
+            let dimensions = self.image.dimensions();
+            faces = faces
+                .iter()
+                .map(|face| face.mapped(|p| p + offset, dimensions))
+                .collect();
+        }
+        faces.retain(|face| face.confidence() >= self.min_confidence);
+        if let Some(max_faces) = self.max_faces {
+            faces.truncate(max_faces);
+        }
+        if let Some(context) = self.context {
+            for face in faces.iter_mut() {
+                face.set_context(Arc::clone(&context));
+            }
+        }
+        Ok(faces)
+    }
+}
+
+impl FaceDetector {
+    /// Starts a detection in `image` with per-call options.
+    pub fn request<'i>(&mut self, image: &ImageView<'i>) -> DetectionRequest<'_, 'i> {
+        DetectionRequest::new(Runner::Detector(self), image)
+    }
+}
+
+impl FaceDetectorPool {
+    /// Starts a detection in `image` with per-call options, run by the next idle detector.
+    pub fn request<'i>(&self, image: &ImageView<'i>) -> DetectionRequest<'_, 'i> {
+        DetectionRequest::new(Runner::Pool(self), image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_per_call_options() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let image = ImageView::new(image.as_raw(), width, height).unwrap();
+        let pool = FaceDetectorPool::new(1);
+        let all = pool.detect_image(&image).unwrap();
+        assert_eq!(2, all.len());
+
+        let top = pool.request(&image).max_faces(1).run().unwrap();
+        assert_eq!(all[0].rectangle(), top[0].rectangle());
+        let confident = pool
+            .request(&image)
+            .min_confidence(all[1].confidence() + 0.001)
+            .run()
+            .unwrap();
+        assert_eq!(1, confident.len());
+
+        // A region around the second face alone, reported in whole-image coordinates.
+        let rect = all[1].rectangle();
+        let roi = Rect::with_size(rect.x - rect.w, rect.y - rect.h, 3.0 * rect.w, 3.0 * rect.h);
+        let mut detector = FaceDetector::new();
+        let faces = detector.request(&image).roi(roi).run().unwrap();
+        assert_eq!(1, faces.len());
+        assert_eq!((width, height), faces[0].detection_dimensions());
+        assert!(faces[0].rectangle().iou(&rect) > 0.8);
+        let outside = Rect::with_size(width as f32, 0.0, 10.0, 10.0);
+        let context = DetectionContext {
+            frame_index: Some(7),
+            timestamp: None,
+            source: Some("porch".to_owned()),
+        };
+        let faces = pool.request(&image).context(context.clone()).run().unwrap();
+        assert!(faces.iter().all(|face| face.context() == Some(&context)));
+        assert_eq!(None, all[0].context());
+        assert!(detector
+            .request(&image)
+            .roi(outside)
+            .run()
+            .unwrap()
+            .is_empty());
+    }
+}
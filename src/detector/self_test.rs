@@ -0,0 +1,207 @@
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::{DetectionStats, DetectorConfig, FaceDetector};
+use crate::{Face, ImageView, Rect, YuNetError};
+
+/// The sample image of the repository, embedded.
+const SAMPLE: &[u8] = include_bytes!("../../sample.jpg");
+/// The faces of [`SAMPLE`] and their confidences, as detected by the bundled model.
+const REFERENCE: [(Rect, f32); 2] = [
+    (
+        Rect {
+            x: 186.12,
+            y: 333.13,
+            w: 49.01,
+            h: 61.92,
+        },
+        0.924,
+    ),
+    (
+        Rect {
+            x: 183.09,
+            y: 253.76,
+            w: 20.98,
+            h: 28.95,
+        },
+        0.867,
+    ),
+];
+
+/// Tolerances of [`FaceDetector::self_test`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SelfTestConfig {
+    /// How well a detection must overlap a reference face to match it.
+    pub min_iou: f32,
+    /// How far the confidence of a match may stray from that of the reference face.
+    pub confidence_tolerance: f32,
+    /// Detections matching no reference face that are tolerated.
+    pub max_unexpected: usize,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self {
+            min_iou: 0.7,
+            confidence_tolerance: 0.1,
+            max_unexpected: 1,
+        }
+    }
+}
+
+/// How a reference face of the self-test fared.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReferenceMatch {
+    pub expected: Rect,
+    pub expected_confidence: f32,
+    /// IoU of the detection overlapping the reference face most, 0 if none does.
+    pub best_iou: f32,
+    /// Confidence of that detection.
+    pub confidence: Option<f32>,
+    pub passed: bool,
+}
+
+/// The outcome of [`FaceDetector::self_test`].
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    pub references: Vec<ReferenceMatch>,
+    /// Detections matching no reference face.
+    pub unexpected: usize,
+    pub max_unexpected: usize,
+    pub stats: DetectionStats,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.references.iter().all(|reference| reference.passed)
+            && self.unexpected <= self.max_unexpected
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let matched = self.references.iter().filter(|r| r.passed).count();
+        write!(
+            f,
+            "self-test {}: {matched} of {} reference faces matched, {} unexpected detections, {:.1} ms",
+            if self.passed() { "passed" } else { "failed" },
+            self.references.len(),
+            self.unexpected,
+            self.stats.total_ms(),
+        )
+    }
+}
+
+impl FaceDetector {
+    /// Runs an embedded image with known faces through this detector and compares the
+    /// detections with them, so that deployments can fail fast on broken builds or corrupted
+    /// models instead of silently detecting nothing.
+    ///
+    /// The detector's backend and network, including any model passed to
+    /// [`with_native_model`](Self::with_native_model), are tested with the default
+    /// configuration otherwise and without its [`filter`](Self::filter), and the run isn't
+    /// counted in its [`stats`](Self::stats).
+    /// Fails only if the embedded image can't be decoded.
+    ///
+    /// ```no_run
+    /// # use rusty_yunet::{FaceDetector, SelfTestConfig};
+    /// let mut detector = FaceDetector::new();
+    /// let report = detector.self_test(&SelfTestConfig::default())?;
+    /// assert!(report.passed(), "{report}");
+    /// # Ok::<(), rusty_yunet::YuNetError>(())
+    /// ```
+    pub fn self_test(&mut self, config: &SelfTestConfig) -> Result<SelfTestReport, YuNetError> {
+        let image = image::load_from_memory(SAMPLE)
+            .map_err(|e| YuNetError::Decode(e.to_string()))?
+            .to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let image = ImageView::new(image.as_raw(), width, height)?;
+
+        let neutral = DetectorConfig {
+            backend: self.config.backend,
+            target: self.config.target,
+            ..DetectorConfig::default()
+        };
+        let configured = std::mem::replace(&mut self.config, neutral);
+        let stats = std::mem::take(&mut self.stats);
+        let filter = std::mem::take(&mut self.filter);
+        let result = self.run(&image, true);
+        self.config = configured;
+        self.stats = stats;
+        self.filter = filter;
+        let (faces, stats) = result?;
+        Ok(compare(&faces, stats, config))
+    }
+}
+
+fn compare(faces: &[Face], stats: DetectionStats, config: &SelfTestConfig) -> SelfTestReport {
+    let references: Vec<ReferenceMatch> = REFERENCE
+        .iter()
+        .map(|&(expected, expected_confidence)| {
+            let best = faces
+                .iter()
+                .map(|face| (face.rectangle().iou(&expected), face.confidence()))
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .filter(|&(iou, _)| iou > 0.0);
+            let passed = best.is_some_and(|(iou, confidence)| {
+                iou >= config.min_iou
+                    && (confidence - expected_confidence).abs() <= config.confidence_tolerance
+            });
+            ReferenceMatch {
+                expected,
+                expected_confidence,
+                best_iou: best.map_or(0.0, |(iou, _)| iou),
+                confidence: best.map(|(_, confidence)| confidence),
+                passed,
+            }
+        })
+        .collect();
+    let unexpected = faces
+        .iter()
+        .filter(|face| {
+            REFERENCE
+                .iter()
+                .all(|(expected, _)| face.rectangle().iou(expected) < config.min_iou)
+        })
+        .count();
+    SelfTestReport {
+        references,
+        unexpected,
+        max_unexpected: config.max_unexpected,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::available_backends;
+
+    #[test]
+    fn passes_on_working_detectors() {
+        for (backend, target) in available_backends() {
+            let config = DetectorConfig {
+                backend,
+                target,
+                max_side: Some(160),
+                mirror: true,
+                ..DetectorConfig::default()
+            };
+            let mut detector = FaceDetector::with_config(config).unwrap();
+            let report = detector.self_test(&SelfTestConfig::default()).unwrap();
+            assert!(report.passed(), "{report}");
+            assert_eq!(0, detector.stats().detections);
+            assert_eq!(Some(160), detector.config().max_side);
+        }
+
+        // A detector finding nothing, as with a corrupted model.
+        let report = compare(&[], DetectionStats::default(), &SelfTestConfig::default());
+        assert!(!report.passed());
+        assert_eq!(0.0, report.references[0].best_iou);
+    }
+}
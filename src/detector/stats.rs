@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// How many of the most recent detections the rolling averages cover.
+pub const STATS_WINDOW: usize = 30;
+
+/// Where the time of a single detection went.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DetectionStats {
+    /// Validating and downscaling the frame.
+    pub preprocess_ms: f64,
+    /// Running the network, including its own non-maximum suppression.
+    pub inference_ms: f64,
+    /// Mapping results back to frame coordinates, landmark refinement and metadata.
+    pub postprocess_ms: f64,
+    pub faces_found: usize,
+}
+
+impl DetectionStats {
+    pub fn total_ms(&self) -> f64 {
+        self.preprocess_ms + self.inference_ms + self.postprocess_ms
+    }
+}
+
+/// Cumulative statistics of a [`FaceDetector`](crate::FaceDetector).
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DetectorStats {
+    /// Detections run since creation or the last reset.
+    pub detections: u64,
+    pub faces_found: u64,
+    pub total_ms: f64,
+    /// Averages over the last [`STATS_WINDOW`] detections.
+    pub mean_preprocess_ms: f64,
+    pub mean_inference_ms: f64,
+    pub mean_postprocess_ms: f64,
+    pub mean_faces_found: f64,
+}
+
+impl DetectorStats {
+    /// Rolling average of the total time per detection.
+    pub fn mean_total_ms(&self) -> f64 {
+        self.mean_preprocess_ms + self.mean_inference_ms + self.mean_postprocess_ms
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StatsAccumulator {
+    detections: u64,
+    faces_found: u64,
+    total_ms: f64,
+    recent: VecDeque<DetectionStats>,
+}
+
+impl StatsAccumulator {
+    pub(crate) fn record(&mut self, stats: DetectionStats) {
+        self.detections += 1;
+        self.faces_found += stats.faces_found as u64;
+        self.total_ms += stats.total_ms();
+        if self.recent.len() == STATS_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(stats);
+    }
+
+    pub(crate) fn snapshot(&self) -> DetectorStats {
+        let n = self.recent.len().max(1) as f64;
+        let mean = |f: fn(&DetectionStats) -> f64| self.recent.iter().map(f).sum::<f64>() / n;
+        DetectorStats {
+            detections: self.detections,
+            faces_found: self.faces_found,
+            total_ms: self.total_ms,
+            mean_preprocess_ms: mean(|s| s.preprocess_ms),
+            mean_inference_ms: mean(|s| s.inference_ms),
+            mean_postprocess_ms: mean(|s| s.postprocess_ms),
+            mean_faces_found: mean(|s| s.faces_found as f64),
+        }
+    }
+}
+
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) use std::time::Instant;
+
+/// `std::time::Instant` panics on `wasm32-unknown-unknown`, which has no clock of its own, so
+/// ask the JavaScript host instead.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Instant(f64);
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Instant {
+    pub(crate) fn now() -> Self {
+        Self(js_sys::Date::now())
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        Self::now() - *self
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl std::ops::Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Self) -> Duration {
+        Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+    }
+}
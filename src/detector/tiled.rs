@@ -0,0 +1,201 @@
+use glam::Vec2;
+
+use super::FaceDetector;
+use crate::{CancellationToken, Face, ImageView, YuNetError};
+
+/// How a [`TiledDetector`] splits frames.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TileConfig {
+    /// Side of the square tiles, in pixels of the original frame.
+    pub tile_size: usize,
+    /// Pixels shared by neighbouring tiles. Faces up to this size are whole in some tile.
+    pub overlap: usize,
+    /// Also run the network on the whole frame, downscaled to fit a tile, to find faces too
+    /// large for the tiles.
+    pub whole_frame: bool,
+    /// Of two overlapping faces, drop the less confident one if their intersection covers at
+    /// least this fraction (0..1) of the smaller, such as the half of a face cut by a tile
+    /// border.
+    pub merge_threshold: f32,
+}
+
+impl Default for TileConfig {
+    fn default() -> Self {
+        Self {
+            tile_size: 640,
+            overlap: 160,
+            whole_frame: true,
+            merge_threshold: 0.5,
+        }
+    }
+}
+
+/// Finds faces too small for a detection on the whole frame, such as those in the back of a
+/// crowd in 4K footage, by detecting in overlapping full-resolution tiles and merging the
+/// results.
+///
+/// Costs one detection per tile, and the whole-frame one if enabled.
+pub struct TiledDetector {
+    detector: FaceDetector,
+    config: TileConfig,
+}
+
+impl TiledDetector {
+    /// Detects with `detector` in each tile. Its [`max_side`](super::DetectorConfig::max_side)
+    /// only applies to the whole-frame pass, which otherwise fits the frame to a tile.
+    pub fn new(detector: FaceDetector, config: TileConfig) -> Self {
+        Self { detector, config }
+    }
+
+    pub fn config(&self) -> &TileConfig {
+        &self.config
+    }
+
+    pub fn detector(&mut self) -> &mut FaceDetector {
+        &mut self.detector
+    }
+
+    /// Detects faces in a tightly packed BGR image of the given dimensions.
+    pub fn detect(
+        &mut self,
+        bytes: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image(&ImageView::new(bytes, width, height)?)
+    }
+
+    /// Detects faces in a BGR image, in the coordinates of the whole image.
+    pub fn detect_image(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        self.detect_image_with_cancel(image, &CancellationToken::new())
+    }
+
+    /// Like [`detect_image`](Self::detect_image), failing with [`YuNetError::Cancelled`]
+    /// before the next tile once `cancel` is cancelled.
+    pub fn detect_image_with_cancel(
+        &mut self,
+        image: &ImageView,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Face>, YuNetError> {
+        cancel.check()?;
+        let (width, height) = image.dimensions();
+        let tile_size = self.config.tile_size.max(1);
+        if width <= tile_size && height <= tile_size {
+            return self.detector.detect_image(image);
+        }
+
+        let configured_max_side = self.detector.config.max_side.take();
+        let mut faces = Vec::new();
+        let mut result = Ok(());
+        'tiles: for y in tile_origins(height, tile_size, self.config.overlap) {
+            for x in tile_origins(width, tile_size, self.config.overlap) {
+                let tile = image
+                    .crop(x, y, tile_size.min(width), tile_size.min(height))
+                    .expect("tiles lie within the frame");
+                let offset = Vec2::new(x as f32, y as f32);
+                match cancel
+                    .check()
+                    .and_then(|()| self.detector.detect_image(&tile))
+                {
+                    Ok(found) => faces.extend(
+                        found
+                            .iter()
+                            .map(|face| face.mapped(|p| p + offset, (width, height))),
+                    ),
+                    Err(error) => {
+                        result = Err(error);
+                        break 'tiles;
+                    }
+                }
+            }
+        }
+        if result.is_ok() && self.config.whole_frame {
+            self.detector.config.max_side = Some(configured_max_side.unwrap_or(tile_size));
+            match cancel
+                .check()
+                .and_then(|()| self.detector.detect_image(image))
+            {
+                Ok(found) => faces.extend(found),
+                Err(error) => result = Err(error),
+            }
+        }
+        self.detector.config.max_side = configured_max_side;
+        result?;
+        let mut faces = merge(faces, self.config.merge_threshold);
+        self.detector.config.result_order.sort(&mut faces);
+        Ok(faces)
+    }
+}
+
+/// Starts of tiles of `tile` pixels covering `length`, at most `overlap` apart from a full
+/// stride, with the last one flush with the end.
+fn tile_origins(length: usize, tile: usize, overlap: usize) -> Vec<usize> {
+    if length <= tile {
+        return vec![0];
+    }
+    let stride = tile.saturating_sub(overlap).max(1);
+    let mut origins: Vec<usize> = (0..length - tile).step_by(stride).collect();
+    origins.push(length - tile);
+    origins
+}
+
+/// Greedy non-maximum suppression, keeping the most confident of overlapping faces.
+fn merge(mut faces: Vec<Face>, threshold: f32) -> Vec<Face> {
+    faces.sort_by(|a, b| b.confidence().total_cmp(&a.confidence()));
+    let mut kept: Vec<Face> = Vec::with_capacity(faces.len());
+    for face in faces {
+        let rect = face.rectangle();
+        let duplicate = kept.iter().any(|k| {
+            let smaller = rect.area().min(k.rectangle().area());
+            smaller > 0.0 && rect.intersection(&k.rectangle()) / smaller >= threshold
+        });
+        if !duplicate {
+            kept.push(face);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{imageops, Rgb, RgbImage};
+
+    use super::*;
+    use crate::DetectorConfig;
+
+    #[test]
+    fn finds_small_faces_in_tiles() {
+        assert_eq!(vec![0, 100, 200, 220], tile_origins(320, 100, 0));
+        assert_eq!(vec![0, 60, 120, 180, 220], tile_origins(320, 100, 40));
+        assert_eq!(vec![0], tile_origins(80, 100, 40));
+
+        // The sample pasted into a 4K-like frame, shrinking its faces when downscaled.
+        let sample = image::open("sample.jpg").unwrap().to_rgb8();
+        let (w, h) = sample.dimensions();
+        let mut frame = RgbImage::from_pixel(4 * w, 4 * h, Rgb([128, 128, 128]));
+        imageops::replace(&mut frame, &sample, 2 * w, h);
+        let bgr: Vec<u8> = frame.pixels().flat_map(|p| [p[2], p[1], p[0]]).collect();
+        let (width, height) = (4 * w as usize, 4 * h as usize);
+
+        let config = DetectorConfig {
+            max_side: Some(640),
+            ..DetectorConfig::default()
+        };
+        let mut downscaled = FaceDetector::with_config(config.clone()).unwrap();
+        let missed = downscaled.detect(&bgr, width, height).unwrap();
+        let full = FaceDetector::new().detect(&bgr, width, height).unwrap();
+        let detector = FaceDetector::with_config(config).unwrap();
+        let mut tiled = TiledDetector::new(detector, TileConfig::default());
+        let faces = tiled.detect(&bgr, width, height).unwrap();
+
+        assert!(faces.len() > missed.len());
+        assert_eq!(full.len(), faces.len());
+        for face in &faces {
+            assert_eq!((width, height), face.detection_dimensions());
+            assert!(full
+                .iter()
+                .any(|f| f.rectangle().iou(&face.rectangle()) > 0.9));
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use notify::{Event, EventKind, PollWatcher, RecursiveMode, Watcher};
+
+use super::DetectorConfig;
+use crate::{config, YuNetError};
+
+/// How often [`DetectorConfig::watch`] checks the file for changes where the operating
+/// system can't report them.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A [`DetectorConfig`] kept up to date with a file, for tuning an installation on site
+/// without restarting it, with the `config` feature. Attach it to detectors with [`FaceDetector::with_config_watcher`];
+/// clones share the same file, so one watcher can serve every detector of a pool.
+///
+/// The file is TOML with the fields of the `[detector]` section of an
+/// [`AppConfig`](crate::config::AppConfig) file, other than `model`, at the top level. They
+/// override the configuration [`watch`](DetectorConfig::watch) was called on; removing a line
+/// restores its value.
+///
+/// ```toml
+/// # Faces below this confidence are dropped.
+/// min_confidence = 0.7
+/// # The shorter side of faces, in pixels, or as a fraction of the frame.
+/// min_face_size = 40
+/// # min_face_fraction = 0.05
+/// max_side = 640
+/// refine_landmarks = true
+/// mirror = false
+/// ```
+///
+/// Changes are picked up through the operating system's notifications, watching the file's
+/// directory so that editors saving through a rename are seen too, by a thread that ends
+/// with the last clone. Where notifications aren't available, the file's contents are
+/// compared every [`WATCH_INTERVAL`] instead. A file that fails to parse leaves the last
+/// good configuration in place; see [`last_error`](Self::last_error).
+///
+/// [`FaceDetector::with_config_watcher`]: super::FaceDetector::with_config_watcher
+#[derive(Clone)]
+pub struct ConfigWatcher {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    path: PathBuf,
+    base: DetectorConfig,
+    state: Mutex<State>,
+}
+
+struct State {
+    config: DetectorConfig,
+    /// Counts the changes applied, so that detectors can tell a new configuration.
+    generation: u64,
+    error: Option<String>,
+}
+
+impl DetectorConfig {
+    /// Applies the overrides in the file at `path` on top of this configuration, and keeps
+    /// applying them whenever the file changes. Fails if the file can't be read or parsed
+    /// now.
+    pub fn watch(self, path: impl AsRef<Path>) -> Result<ConfigWatcher, YuNetError> {
+        self.watch_every(path, WATCH_INTERVAL)
+    }
+
+    /// Like [`watch`](Self::watch), checking the file every `interval` where change
+    /// notifications aren't available.
+    pub fn watch_every(
+        self,
+        path: impl AsRef<Path>,
+        interval: Duration,
+    ) -> Result<ConfigWatcher, YuNetError> {
+        let path = path.as_ref().to_owned();
+        let (sender, events) = mpsc::channel();
+        let watcher =
+            notifier(&path, interval, sender).map_err(|e| YuNetError::Io(e.to_string()))?;
+        let config = load(&self, &path)?;
+        let shared = Arc::new(Shared {
+            path,
+            base: self,
+            state: Mutex::new(State {
+                config,
+                generation: 0,
+                error: None,
+            }),
+        });
+        let weak = Arc::downgrade(&shared);
+        std::thread::Builder::new()
+            .name("rusty-yunet-config".to_owned())
+            .spawn(move || reload_on_change(weak, events, watcher, interval))
+            .map_err(|e| YuNetError::Io(e.to_string()))?;
+        Ok(ConfigWatcher { shared })
+    }
+}
+
+impl ConfigWatcher {
+    /// The configuration as of the last good version of the file.
+    pub fn config(&self) -> DetectorConfig {
+        self.state().config.clone()
+    }
+
+    /// Why the file last failed to read or parse, until it next loads.
+    pub fn last_error(&self) -> Option<String> {
+        self.state().error.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    /// The configuration, if it changed since `generation`, with its own generation.
+    pub(crate) fn update_since(&self, generation: u64) -> Option<(DetectorConfig, u64)> {
+        let state = self.state();
+        (state.generation != generation).then(|| (state.config.clone(), state.generation))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Reports changes to the file at `path`: through the operating system where it can, and by
+/// comparing the file's contents every `interval` otherwise.
+fn notifier(
+    path: &Path,
+    interval: Duration,
+    sender: mpsc::Sender<notify::Result<Event>>,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let watching = notify::recommended_watcher(sender.clone()).and_then(|mut watcher| {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    if let Ok(watcher) = watching {
+        return Ok(Box::new(watcher));
+    }
+    #[cfg(feature = "tracing")]
+    tracing::warn!(path = %path.display(), "polling detector configuration");
+    let config = notify::Config::default()
+        .with_poll_interval(interval)
+        .with_compare_contents(true);
+    let mut watcher = PollWatcher::new(sender, config)?;
+    watcher.watch(path, RecursiveMode::NonRecursive)?;
+    Ok(Box::new(watcher))
+}
+
+/// Reloads the file whenever `events` report a change to it, until the watcher is dropped.
+fn reload_on_change(
+    shared: Weak<Shared>,
+    events: Receiver<notify::Result<Event>>,
+    _watcher: Box<dyn Watcher + Send>,
+    interval: Duration,
+) {
+    loop {
+        let changed = match events.recv_timeout(interval) {
+            Ok(Ok(event)) => event,
+            Ok(Err(_)) | Err(RecvTimeoutError::Timeout) => {
+                if shared.strong_count() == 0 {
+                    return;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let name = shared.path.file_name();
+        if matches!(changed.kind, EventKind::Access(_))
+            || !changed.paths.iter().any(|p| p.file_name() == name)
+        {
+            continue;
+        }
+        // A save often arrives as several events; load once they settle.
+        while events.recv_timeout(Duration::from_millis(20)).is_ok() {}
+        let loaded = load(&shared.base, &shared.path);
+        let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        match loaded {
+            Ok(config) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!(path = %shared.path.display(), "reloaded detector configuration");
+                state.config = config;
+                state.generation += 1;
+                state.error = None;
+            }
+            Err(error) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(path = %shared.path.display(), %error, "kept detector configuration");
+                state.error = Some(error.to_string());
+            }
+        }
+    }
+}
+
+fn load(base: &DetectorConfig, path: &Path) -> Result<DetectorConfig, YuNetError> {
+    let text = std::fs::read_to_string(path).map_err(|e| YuNetError::Io(e.to_string()))?;
+    config::detector_from_toml(base, &text).map_err(|error| error.in_config(path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FaceDetector, FaceSize, ImageView};
+
+    #[test]
+    fn reloads_changed_file() {
+        let path = std::env::temp_dir().join(format!("rusty-yunet-{}.toml", std::process::id()));
+        std::fs::write(&path, "# tuned on site\nmin_confidence = 0.9\n").unwrap();
+        let watcher = DetectorConfig::default()
+            .watch_every(&path, Duration::from_millis(10))
+            .unwrap();
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let mut detector = FaceDetector::new().with_config_watcher(watcher.clone());
+        let detect =
+            |detector: &mut FaceDetector| detector.detect(image.as_raw(), 806, 605).unwrap().len();
+        assert_eq!(Some(0.9), detector.config().min_confidence);
+        assert_eq!(1, detect(&mut detector));
+
+        let reload = |contents: &str| {
+            std::fs::write(&path, contents).unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+        };
+        reload("min_confidence = 0.5\nmin_face_size = 10\n");
+        assert_eq!(2, detect(&mut detector));
+        assert_eq!(
+            Some(FaceSize::Pixels(10.0)),
+            detector.config().min_face_size
+        );
+
+        // A rewrite of the same length within the same second is still seen.
+        reload("min_confidence = 0.5\nmin_face_size = 20\n");
+        assert_eq!(2, detect(&mut detector));
+        assert_eq!(
+            Some(FaceSize::Pixels(20.0)),
+            detector.config().min_face_size
+        );
+
+        // A broken file keeps the last good configuration.
+        reload("min_confidence = \"high\"\n");
+        assert!(watcher
+            .last_error()
+            .unwrap()
+            .contains("min_confidence: expected a number"));
+        assert_eq!(2, detect(&mut detector));
+
+        // Reloads picked up in the middle of a detection with overrides stick.
+        #[cfg(feature = "image")]
+        {
+            reload("min_confidence = 0.5\nmirror = true\n");
+            let jpeg = std::fs::read("sample.jpg").unwrap();
+            assert_eq!(2, detector.detect_encoded(&jpeg).unwrap().len());
+            assert!(detector.config().mirror);
+        }
+        reload("min_confidence = 0.5\nrefine_landmarks = true\n");
+        let frame = ImageView::new(image.as_raw(), 806, 605).unwrap();
+        assert_eq!(2, detector.detect_boxes(&frame).unwrap().len());
+        assert!(detector.config().refine_landmarks);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+//! What changed between the faces of two frames, for event-driven logic such as starting a
+//! recording when someone walks in, without running a [`Tracker`](crate::Tracker).
+//!
+//! Faces are associated by overlap alone, as the tracker does from one frame to the next,
+//! so only consecutive frames, or frames close enough for faces to still overlap, compare
+//! meaningfully.
+
+use glam::Vec2;
+
+use crate::assoc::greedy_matches;
+use crate::geometry::iou_matrix;
+use crate::Face;
+
+/// The overlap (IoU) above which [`diff_detections`] takes two faces for the same person,
+/// as [`TrackerConfig::min_iou`](crate::tracking::TrackerConfig::min_iou) defaults to.
+pub const DIFF_MIN_IOU: f32 = 0.3;
+
+/// A face present in both frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovedFace {
+    /// Index of the face in the previous frame.
+    pub previous: usize,
+    /// Index of the face in the current frame.
+    pub current: usize,
+    /// How far the center of its rectangle moved, in pixels.
+    pub offset: Vec2,
+    pub iou: f32,
+}
+
+/// The outcome of [`diff_detections`], as indices into the faces compared, in increasing
+/// order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DetectionDiff {
+    /// Faces of the current frame matching none of the previous one.
+    pub appeared: Vec<usize>,
+    /// Faces of the previous frame matching none of the current one.
+    pub disappeared: Vec<usize>,
+    /// Faces in both frames, in the order of the current frame, whether or not they moved.
+    pub moved: Vec<MovedFace>,
+}
+
+impl DetectionDiff {
+    /// Whether any face appeared or disappeared.
+    pub fn changed(&self) -> bool {
+        !self.appeared.is_empty() || !self.disappeared.is_empty()
+    }
+}
+
+/// Compares the faces of two frames, taking faces overlapping by at least
+/// [`DIFF_MIN_IOU`] for the same person.
+pub fn diff_detections(previous: &[Face], current: &[Face]) -> DetectionDiff {
+    diff_detections_with(previous, current, DIFF_MIN_IOU)
+}
+
+/// Like [`diff_detections`], taking faces overlapping by at least `min_iou` for the same
+/// person, most overlapping pairs first.
+pub fn diff_detections_with(previous: &[Face], current: &[Face], min_iou: f32) -> DetectionDiff {
+    let mut moved: Vec<MovedFace> = greedy_matches(&iou_matrix(previous, current), min_iou)
+        .into_iter()
+        .map(|(p, c, iou)| MovedFace {
+            previous: p,
+            current: c,
+            offset: current[c].rectangle().center() - previous[p].rectangle().center(),
+            iou,
+        })
+        .collect();
+    moved.sort_by_key(|face| face.current);
+    let unmatched = |len: usize, matched: Vec<usize>| -> Vec<usize> {
+        (0..len).filter(|i| !matched.contains(i)).collect()
+    };
+    DetectionDiff {
+        appeared: unmatched(current.len(), moved.iter().map(|m| m.current).collect()),
+        disappeared: unmatched(previous.len(), moved.iter().map(|m| m.previous).collect()),
+        moved,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::face;
+    use crate::Rect;
+
+    #[test]
+    fn reports_entries_exits_and_moves() {
+        let at = |x: f32| face(Rect::with_size(x, 100.0, 50.0, 60.0), 0.9);
+        let previous = [at(0.0), at(200.0)];
+        let current = [at(400.0), at(210.0)];
+
+        let diff = diff_detections(&previous, &current);
+        assert_eq!(vec![0], diff.appeared);
+        assert_eq!(vec![0], diff.disappeared);
+        assert_eq!(1, diff.moved.len());
+        let moved = diff.moved[0];
+        assert_eq!((1, 1), (moved.previous, moved.current));
+        assert_eq!(Vec2::new(10.0, 0.0), moved.offset);
+        assert!(diff.changed());
+
+        assert!(!diff_detections(&current, &current).changed());
+        assert_eq!(vec![0, 1], diff_detections(&[], &current).appeared);
+        // A stricter threshold no longer takes the shifted face for the same person.
+        assert_eq!(
+            vec![0, 1],
+            diff_detections_with(&previous, &current, 0.9).appeared
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Rendering detections onto images: annotation overlays, redaction and contact sheets,
+//! and cutting faces out of them, as crops or avatars.
+
+use crate::Face;
+
+mod annotate;
+mod avatar;
+mod crop;
+mod layout;
+mod redact;
+mod report;
+
+#[cfg(feature = "viewer")]
+pub(crate) use annotate::fill_rect;
+pub use annotate::{annotate, annotate_faces, Annotation, Palette, Theme};
+pub use avatar::{avatar_png, make_avatar, AvatarShape, AvatarSpec};
+pub use crop::{crop_faces, save_crops, Crop, Sharpen};
+pub use layout::place_labels;
+pub use redact::{anonymize, redact_faces, Anonymization, Redaction};
+pub use report::{ContactSheet, SheetEntry};
+
+/// The pixel region (x, y, width, height) of `face` within an image of the given dimensions,
+/// grown by `margin` and clamped to the image. Faces detected at another resolution are
+/// scaled to fit.
+pub(crate) fn face_region(
+    face: &Face,
+    (width, height): (u32, u32),
+    margin: f32,
+) -> Option<(u32, u32, u32, u32)> {
+    let rect = face.normalized_rectangle();
+    let (mx, my) = (rect.w * margin, rect.h * margin);
+    let x0 = ((rect.x - mx) * width as f32).floor().max(0.0) as u32;
+    let y0 = ((rect.y - my) * height as f32).floor().max(0.0) as u32;
+    let x1 = (((rect.x + rect.w + mx) * width as f32).ceil().max(0.0) as u32).min(width);
+    let y1 = (((rect.y + rect.h + my) * height as f32).ceil().max(0.0) as u32).min(height);
+    (x1 > x0 && y1 > y0).then(|| (x0, y0, x1 - x0, y1 - y0))
+}
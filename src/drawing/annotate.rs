@@ -0,0 +1,435 @@
+use glam::Vec2;
+use image::{Rgb, RgbImage};
+
+#[cfg(feature = "ab_glyph")]
+use super::place_labels;
+use crate::tracking::Track;
+use crate::{Face, Rect};
+
+/// How box colors are chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Palette {
+    Fixed(Rgb<u8>),
+    /// `(min_confidence, color)` pairs; the first band whose threshold is reached wins, so
+    /// list them in descending order. Faces below every band use the last color.
+    ConfidenceBands(Vec<(f32, Rgb<u8>)>),
+    /// Colors picked by [`Annotation::id`], such as a track ID. Annotations without an ID
+    /// use the first color.
+    ById(Vec<Rgb<u8>>),
+    /// A color derived from a hash of [`Annotation::id`], so that each track keeps its color
+    /// for as long as it lives, and consecutive IDs get unrelated colors. Annotations
+    /// without an ID are white.
+    HashedId,
+}
+
+impl Palette {
+    fn color(&self, annotation: &Annotation) -> Rgb<u8> {
+        match self {
+            Palette::Fixed(color) => *color,
+            Palette::ConfidenceBands(bands) => bands
+                .iter()
+                .find(|(min, _)| annotation.confidence >= *min)
+                .or(bands.last())
+                .map_or(Rgb([255, 255, 255]), |(_, color)| *color),
+            Palette::ById(colors) => match (annotation.id, colors.is_empty()) {
+                (_, true) => Rgb([255, 255, 255]),
+                (Some(id), false) => colors[(id % colors.len() as u64) as usize],
+                (None, false) => colors[0],
+            },
+            Palette::HashedId => annotation.id.map_or(Rgb([255, 255, 255]), hashed_color),
+        }
+    }
+}
+
+/// A bright, saturated color whose hue is picked by a hash of `id`.
+fn hashed_color(id: u64) -> Rgb<u8> {
+    // The splitmix64 finalizer, spreading neighbouring IDs all over the hue circle.
+    let mut h = id.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^= h >> 31;
+    let hue = (h >> 40) as f32 / (1u64 << 24) as f32 * 6.0;
+    let (saturation, value) = (0.75, 0.95);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    Rgb([r, g, b].map(|c| ((c + m) * 255.0).round() as u8))
+}
+
+/// The look of annotated output.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub palette: Palette,
+    /// Box outline thickness in pixels.
+    pub thickness: u32,
+    /// Radius of landmark dots; zero hides them.
+    pub landmark_radius: u32,
+    pub label_color: Rgb<u8>,
+    /// Labels are only rendered when a font is set.
+    #[cfg(feature = "ab_glyph")]
+    pub font: Option<ab_glyph::FontArc>,
+    /// Label height in pixels.
+    pub font_size: f32,
+}
+
+impl Theme {
+    /// Green for confident faces, through yellow, to red for doubtful ones.
+    pub fn confidence_bands() -> Self {
+        Self {
+            palette: Palette::ConfidenceBands(vec![
+                (0.9, Rgb([0, 200, 0])),
+                (0.7, Rgb([230, 200, 0])),
+                (0.0, Rgb([230, 0, 0])),
+            ]),
+            ..Self::default()
+        }
+    }
+
+    /// A stable color per track, for annotations made with [`Annotation::from_track`].
+    pub fn by_track() -> Self {
+        Self {
+            palette: Palette::HashedId,
+            ..Self::default()
+        }
+    }
+
+    /// A distinct color per annotation ID.
+    pub fn by_id() -> Self {
+        Self {
+            palette: Palette::ById(vec![
+                Rgb([230, 25, 75]),
+                Rgb([60, 180, 75]),
+                Rgb([255, 225, 25]),
+                Rgb([0, 130, 200]),
+                Rgb([245, 130, 48]),
+                Rgb([145, 30, 180]),
+                Rgb([70, 240, 240]),
+                Rgb([240, 50, 230]),
+            ]),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            palette: Palette::Fixed(Rgb([0, 255, 0])),
+            thickness: 2,
+            landmark_radius: 2,
+            label_color: Rgb([0, 0, 0]),
+            #[cfg(feature = "ab_glyph")]
+            font: None,
+            font_size: 16.0,
+        }
+    }
+}
+
+/// Something to draw: a box in image pixel coordinates with optional landmarks and label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub rect: Rect,
+    /// Right eye, left eye, nose, right and left mouth corners.
+    pub landmarks: Option<[Vec2; 5]>,
+    pub confidence: f32,
+    pub id: Option<u64>,
+    pub label: Option<String>,
+}
+
+impl Annotation {
+    /// Annotates a face on an image of the given dimensions, scaling it from the resolution
+    /// it was detected at. Labelled with its confidence.
+    pub fn from_face(face: &Face, (width, height): (u32, u32)) -> Self {
+        let (dw, dh) = face.detection_dimensions();
+        let scale = Vec2::new(width as f32 / dw as f32, height as f32 / dh as f32);
+        let rect = face.rectangle();
+        let lm = face.landmarks();
+        Self {
+            rect: Rect::with_size(
+                rect.x * scale.x,
+                rect.y * scale.y,
+                rect.w * scale.x,
+                rect.h * scale.y,
+            ),
+            landmarks: Some(lm.as_array().map(|p| p * scale)),
+            confidence: face.confidence(),
+            id: None,
+            label: Some(format!("{:.2}", face.confidence())),
+        }
+    }
+
+    /// Annotates the latest face of a track, labelled with the track ID and how long it has
+    /// been followed: in seconds when its frames were timestamped or the stream's
+    /// `frame_rate` is known, in frames otherwise. Timestamps win, as they stay accurate
+    /// for variable frame rate video.
+    pub fn from_track(track: &Track, dimensions: (u32, u32), frame_rate: Option<f32>) -> Self {
+        let age = track.age();
+        let dwell = match (track.dwell(), frame_rate) {
+            (Some(dwell), _) => format!("{:.1}s", dwell.as_secs_f32()),
+            (None, Some(fps)) if fps > 0.0 => format!("{:.1}s", age as f32 / fps),
+            _ => format!("{age}f"),
+        };
+        Self::from_face(track.face(), dimensions)
+            .with_id(track.id())
+            .with_label(format!("#{} {dwell}", track.id()))
+    }
+
+    pub fn with_id(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+/// Draws every face with its confidence as label.
+pub fn annotate_faces(image: &mut RgbImage, faces: &[Face], theme: &Theme) {
+    let annotations: Vec<Annotation> = faces
+        .iter()
+        .map(|face| Annotation::from_face(face, image.dimensions()))
+        .collect();
+    annotate(image, &annotations, theme);
+}
+
+/// Draws boxes, landmarks and labels, laying labels out so that they don't cover each other
+/// or other boxes.
+pub fn annotate(image: &mut RgbImage, annotations: &[Annotation], theme: &Theme) {
+    for annotation in annotations {
+        let color = theme.palette.color(annotation);
+        draw_outline(image, &annotation.rect, theme.thickness, color);
+        if theme.landmark_radius > 0 {
+            for point in annotation.landmarks.iter().flatten() {
+                fill_circle(image, *point, theme.landmark_radius as f32, color);
+            }
+        }
+    }
+
+    #[cfg(feature = "ab_glyph")]
+    if let Some(font) = &theme.font {
+        let labelled: Vec<&Annotation> = annotations.iter().filter(|a| a.label.is_some()).collect();
+        let boxes: Vec<Rect> = labelled.iter().map(|a| a.rect).collect();
+        let texts: Vec<text::Text> = labelled
+            .iter()
+            .map(|a| {
+                text::Text::layout(
+                    font,
+                    theme.font_size,
+                    a.label.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect();
+        let sizes: Vec<(u32, u32)> = texts.iter().map(text::Text::padded_size).collect();
+        let positions = place_labels(image.dimensions(), &boxes, &sizes);
+        for ((annotation, text), (position, size)) in labelled
+            .iter()
+            .zip(&texts)
+            .zip(positions.iter().zip(&sizes))
+        {
+            let background = theme.palette.color(annotation);
+            fill_rect(image, position.0, position.1, size.0, size.1, background);
+            text.draw(image, *position, theme.label_color);
+        }
+    }
+}
+
+fn draw_outline(image: &mut RgbImage, rect: &Rect, thickness: u32, color: Rgb<u8>) {
+    let (x0, y0) = (rect.x.round() as i32, rect.y.round() as i32);
+    let (w, h) = (
+        rect.w.round().max(1.0) as u32,
+        rect.h.round().max(1.0) as u32,
+    );
+    let t = thickness.min(w).min(h);
+    fill_rect(image, x0, y0, w, t, color);
+    fill_rect(image, x0, y0 + (h - t) as i32, w, t, color);
+    fill_rect(image, x0, y0, t, h, color);
+    fill_rect(image, x0 + (w - t) as i32, y0, t, h, color);
+}
+
+pub(crate) fn fill_rect(image: &mut RgbImage, x: i32, y: i32, w: u32, h: u32, color: Rgb<u8>) {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    for py in y.max(0)..(y + h as i32).min(height) {
+        for px in x.max(0)..(x + w as i32).min(width) {
+            image.put_pixel(px as u32, py as u32, color);
+        }
+    }
+}
+
+fn fill_circle(image: &mut RgbImage, center: Vec2, radius: f32, color: Rgb<u8>) {
+    let r = radius.ceil() as i32;
+    let (cx, cy) = (center.x.round() as i32, center.y.round() as i32);
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let (x, y) = (cx + dx, cy + dy);
+            if ((dx * dx + dy * dy) as f32) <= radius * radius
+                && x >= 0
+                && y >= 0
+                && (x as u32) < image.width()
+                && (y as u32) < image.height()
+            {
+                image.put_pixel(x as u32, y as u32, color);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ab_glyph")]
+mod text {
+    use ab_glyph::{point, Font, FontArc, Glyph, PxScale, ScaleFont};
+    use image::{Rgb, RgbImage};
+
+    /// Padding around label text, in pixels.
+    const PADDING: u32 = 2;
+
+    /// A line of text laid out left to right from the origin.
+    pub(super) struct Text<'a> {
+        font: &'a FontArc,
+        glyphs: Vec<Glyph>,
+        width: f32,
+        height: f32,
+    }
+
+    impl<'a> Text<'a> {
+        pub(super) fn layout(font: &'a FontArc, size: f32, text: &str) -> Self {
+            let scaled = font.as_scaled(PxScale::from(size));
+            let mut caret = 0.0;
+            let mut previous = None;
+            let mut glyphs = Vec::with_capacity(text.len());
+            for c in text.chars() {
+                let id = scaled.glyph_id(c);
+                if let Some(previous) = previous {
+                    caret += scaled.kern(previous, id);
+                }
+                glyphs.push(id.with_scale_and_position(size, point(caret, scaled.ascent())));
+                caret += scaled.h_advance(id);
+                previous = Some(id);
+            }
+            Self {
+                font,
+                glyphs,
+                width: caret,
+                height: scaled.height(),
+            }
+        }
+
+        pub(super) fn padded_size(&self) -> (u32, u32) {
+            (
+                self.width.ceil() as u32 + 2 * PADDING,
+                self.height.ceil() as u32 + 2 * PADDING,
+            )
+        }
+
+        pub(super) fn draw(&self, image: &mut RgbImage, (x, y): (i32, i32), color: Rgb<u8>) {
+            let origin = (x + PADDING as i32, y + PADDING as i32);
+            for glyph in &self.glyphs {
+                let Some(outline) = self.font.outline_glyph(glyph.clone()) else {
+                    continue;
+                };
+                let bounds = outline.px_bounds();
+                outline.draw(|gx, gy, coverage| {
+                    let px = origin.0 + bounds.min.x as i32 + gx as i32;
+                    let py = origin.1 + bounds.min.y as i32 + gy as i32;
+                    if px < 0 || py < 0 || px as u32 >= image.width() || py as u32 >= image.height()
+                    {
+                        return;
+                    }
+                    let pixel = image.get_pixel_mut(px as u32, py as u32);
+                    for (channel, target) in pixel.0.iter_mut().zip(color.0) {
+                        let blended =
+                            *channel as f32 + (target as f32 - *channel as f32) * coverage;
+                        *channel = blended.round() as u8;
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_themed_boxes() {
+        let mut image = RgbImage::new(100, 100);
+        let annotation = Annotation {
+            rect: Rect::with_size(10.0, 10.0, 30.0, 30.0),
+            landmarks: Some([Vec2::new(25.0, 25.0); 5]),
+            confidence: 0.8,
+            id: None,
+            label: None,
+        };
+        annotate(&mut image, &[annotation], &Theme::confidence_bands());
+
+        let yellow = Rgb([230, 200, 0]);
+        assert_eq!(yellow, *image.get_pixel(10, 20));
+        assert_eq!(yellow, *image.get_pixel(25, 25));
+        assert_eq!(Rgb([0, 0, 0]), *image.get_pixel(20, 20));
+    }
+
+    #[test]
+    fn track_colors_are_stable_and_distinct() {
+        let annotation = |id| Annotation {
+            rect: Rect::with_size(0.0, 0.0, 1.0, 1.0),
+            landmarks: None,
+            confidence: 1.0,
+            id: Some(id),
+            label: None,
+        };
+        let palette = Palette::HashedId;
+        assert_eq!(palette.color(&annotation(7)), palette.color(&annotation(7)));
+        let colors: std::collections::HashSet<_> =
+            (0..16).map(|id| palette.color(&annotation(id)).0).collect();
+        assert_eq!(16, colors.len());
+    }
+
+    #[cfg(feature = "ab_glyph")]
+    #[test]
+    fn draws_labels_above_boxes() {
+        // Fira Mono, cut down to ASCII, under the OFL; see tests/fixtures/FiraMono-LICENSE.
+        let font = ab_glyph::FontArc::try_from_slice(include_bytes!(
+            "../../tests/fixtures/FiraMono-subset.ttf"
+        ))
+        .unwrap();
+        let theme = Theme {
+            font: Some(font.clone()),
+            ..Theme::default()
+        };
+        let label = "0.95";
+        let (w, h) = text::Text::layout(&font, theme.font_size, label).padded_size();
+        let mut image = RgbImage::new(200, 200);
+        let annotation = Annotation {
+            rect: Rect::with_size(50.0, 80.0, 40.0, 40.0),
+            landmarks: None,
+            confidence: 0.95,
+            id: None,
+            label: Some(label.to_owned()),
+        };
+        annotate(&mut image, &[annotation], &theme);
+
+        let green = Rgb([0, 255, 0]);
+        let top = 80 - h;
+        // The label's padded background sits right on top of the box...
+        assert_eq!(green, *image.get_pixel(51, top));
+        assert_eq!(green, *image.get_pixel(50 + w - 1, 79));
+        assert_eq!(Rgb([0, 0, 0]), *image.get_pixel(51, top - 1));
+        assert_eq!(Rgb([0, 0, 0]), *image.get_pixel(50 + w, top + 1));
+        // ...with dark text blended into it.
+        let text_pixels = (top..80)
+            .flat_map(|y| (50..50 + w).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.get_pixel(x, y)[1] < 128)
+            .count();
+        assert!(text_pixels > 10, "{text_pixels} text pixels");
+    }
+}
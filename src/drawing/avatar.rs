@@ -0,0 +1,126 @@
+use image::png::PngEncoder;
+use image::{imageops, ColorType, ImageResult, RgbImage, Rgba, RgbaImage};
+
+use crate::Face;
+
+/// The outline of an avatar made by [`make_avatar`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AvatarShape {
+    #[default]
+    Square,
+    /// A circle filling the square, transparent outside it.
+    Circle,
+}
+
+/// How [`make_avatar`] frames a face.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvatarSpec {
+    /// Width and height in pixels, at least one.
+    pub size: u32,
+    /// Space around the face on each side, relative to its larger side.
+    pub margin: f32,
+    pub shape: AvatarShape,
+    /// Fills the parts of the avatar beyond the edges of the image.
+    pub background: Rgba<u8>,
+}
+
+impl Default for AvatarSpec {
+    fn default() -> Self {
+        Self {
+            size: 256,
+            margin: 0.4,
+            shape: AvatarShape::Square,
+            background: Rgba([0, 0, 0, 0]),
+        }
+    }
+}
+
+/// A square picture of `face` from `image`, centered on it and padded with
+/// [`AvatarSpec::background`] where the face is close to an edge, so that it stays centered.
+/// Faces detected at another resolution are scaled to fit. Save it as PNG to keep the
+/// transparency of circles, or see [`avatar_png`].
+///
+/// Pairs with [`BestFrameSelector`](crate::BestFrameSelector), to make each person's
+/// avatar from their best shot.
+pub fn make_avatar(image: &RgbImage, face: &Face, spec: &AvatarSpec) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let rect = face.normalized_rectangle();
+    let center = rect.center();
+    let (cx, cy) = (center.x * width as f32, center.y * height as f32);
+    let side = (rect.w * width as f32).max(rect.h * height as f32) * (1.0 + 2.0 * spec.margin);
+    let side = side.round().max(1.0) as u32;
+
+    let (x0, y0) = (cx - side as f32 / 2.0, cy - side as f32 / 2.0);
+    let (x0, y0) = (x0.round() as i64, y0.round() as i64);
+    let crop = RgbaImage::from_fn(side, side, |x, y| {
+        let (sx, sy) = (x0 + x as i64, y0 + y as i64);
+        if (0..width as i64).contains(&sx) && (0..height as i64).contains(&sy) {
+            let [r, g, b] = image.get_pixel(sx as u32, sy as u32).0;
+            Rgba([r, g, b, 255])
+        } else {
+            spec.background
+        }
+    });
+    let size = spec.size.max(1);
+    let mut avatar = imageops::resize(&crop, size, size, imageops::FilterType::CatmullRom);
+
+    if spec.shape == AvatarShape::Circle {
+        let radius = size as f32 / 2.0;
+        for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+            let distance =
+                ((x as f32 + 0.5 - radius).powi(2) + (y as f32 + 0.5 - radius).powi(2)).sqrt();
+            // Anti-aliased over the pixel the edge crosses.
+            let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+            pixel.0[3] = (pixel.0[3] as f32 * coverage).round() as u8;
+        }
+    }
+    avatar
+}
+
+/// [`make_avatar`], encoded as PNG.
+pub fn avatar_png(image: &RgbImage, face: &Face, spec: &AvatarSpec) -> ImageResult<Vec<u8>> {
+    let avatar = make_avatar(image, face, spec);
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).encode(
+        avatar.as_raw(),
+        avatar.width(),
+        avatar.height(),
+        ColorType::Rgba8,
+    )?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::face_in;
+    use crate::Rect;
+
+    #[test]
+    fn centers_faces_near_edges() {
+        let image = RgbImage::from_pixel(100, 100, image::Rgb([200, 100, 50]));
+        // Against the left edge, so that the left of the avatar lies beyond it.
+        let face = face_in(Rect::with_size(0.0, 40.0, 20.0, 20.0), 0.9, (100, 100));
+        let spec = AvatarSpec {
+            size: 40,
+            margin: 0.5,
+            ..Default::default()
+        };
+        let avatar = make_avatar(&image, &face, &spec);
+        assert_eq!((40, 40), avatar.dimensions());
+        assert_eq!(&Rgba([0, 0, 0, 0]), avatar.get_pixel(2, 20));
+        assert_eq!(&Rgba([200, 100, 50, 255]), avatar.get_pixel(30, 20));
+
+        let circle = AvatarSpec {
+            shape: AvatarShape::Circle,
+            ..spec
+        };
+        let avatar = make_avatar(&image, &face, &circle);
+        assert_eq!(0, avatar.get_pixel(39, 0).0[3]);
+        assert_eq!(255, avatar.get_pixel(30, 20).0[3]);
+
+        let png = avatar_png(&image, &face, &circle).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(avatar, decoded);
+    }
+}
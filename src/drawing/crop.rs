@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use image::{imageops, ImageResult, RgbImage};
+
+use super::face_region;
+use crate::Face;
+
+/// An unsharp mask restoring some of the detail lost to blur and compression in low-quality
+/// frames, such as those of surveillance cameras.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sharpen {
+    /// Gaussian blur sigma of the mask, in pixels of the crop.
+    pub sigma: f32,
+    /// How much of the difference from the blurred crop is added back.
+    pub amount: f32,
+    /// Differences from the blurred crop below this are left alone, so that sensor noise
+    /// isn't amplified.
+    pub threshold: i32,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self {
+            sigma: 1.0,
+            amount: 1.0,
+            threshold: 2,
+        }
+    }
+}
+
+/// How faces are cut out by [`crop_faces`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Crop {
+    /// Extra area around the face rectangle to include, relative to its size.
+    pub margin: f32,
+    /// Applied to each crop after cutting it out, so that the surrounding image isn't read.
+    pub sharpen: Option<Sharpen>,
+}
+
+/// Cuts every face out of `image`, as input for second-stage models such as recognition or
+/// attribute classifiers. Faces entirely outside the image have no crop.
+pub fn crop_faces(image: &RgbImage, faces: &[Face], crop: &Crop) -> Vec<Option<RgbImage>> {
+    faces
+        .iter()
+        .map(|face| {
+            let (x, y, w, h) = face_region(face, image.dimensions(), crop.margin)?;
+            let cropped = imageops::crop_imm(image, x, y, w, h).to_image();
+            Some(match crop.sharpen {
+                Some(sharpen) => unsharp_mask(&cropped, &sharpen),
+                None => cropped,
+            })
+        })
+        .collect()
+}
+
+/// Writes the crop of every face in `image` to `dir`, creating it if needed, as
+/// `face-<index>-<confidence>.png`. Returns the paths written; faces outside the image are
+/// skipped.
+pub fn save_crops(
+    image: &RgbImage,
+    faces: &[Face],
+    crop: &Crop,
+    dir: impl AsRef<Path>,
+) -> ImageResult<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for (index, (face, cropped)) in faces.iter().zip(crop_faces(image, faces, crop)).enumerate() {
+        let Some(cropped) = cropped else {
+            continue;
+        };
+        let path = dir.join(format!("face-{index}-{:.2}.png", face.confidence()));
+        cropped.save(&path)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Adds back the difference from a blurred copy. `imageops::unsharpen` would add its
+/// absolute value instead, brightening both sides of every edge.
+fn unsharp_mask(image: &RgbImage, sharpen: &Sharpen) -> RgbImage {
+    let mut out = imageops::blur(image, sharpen.sigma);
+    for (blurred, original) in out.pixels_mut().zip(image.pixels()) {
+        for (b, &o) in blurred.0.iter_mut().zip(&original.0) {
+            let diff = o as i32 - *b as i32;
+            *b = if diff.abs() > sharpen.threshold {
+                (o as f32 + sharpen.amount * diff as f32)
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            } else {
+                o
+            };
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    #[test]
+    fn crops_and_sharpens_faces() {
+        // A soft vertical edge through the face.
+        let edge = RgbImage::from_fn(100, 100, |x, _| {
+            image::Rgb([if x < 30 { 80 } else { 160 }; 3])
+        });
+        let image = imageops::blur(&edge, 1.5);
+        let face = |x| {
+            Face::from_raw_face(
+                &RawFace {
+                    score: 0.9,
+                    x: x as f32,
+                    y: 20.0,
+                    w: 20.0,
+                    h: 20.0,
+                    lm: [0.0; 10],
+                },
+                (100, 100),
+            )
+        };
+
+        let plain = crop_faces(&image, &[face(20), face(200)], &Crop::default());
+        assert!(plain[1].is_none());
+        let plain = plain[0].as_ref().unwrap();
+        assert_eq!((20, 20), plain.dimensions());
+        assert_eq!(image.get_pixel(25, 30), plain.get_pixel(5, 10));
+        assert_eq!(Some(plain), face(20).crop(&image).as_ref());
+
+        let dir = std::env::temp_dir().join(format!("rusty-yunet-crops-{}", std::process::id()));
+        let paths = save_crops(&image, &[face(200), face(20)], &Crop::default(), &dir).unwrap();
+        assert_eq!(vec![dir.join("face-1-0.90.png")], paths);
+        assert_eq!(
+            (20, 20),
+            image::open(&paths[0]).unwrap().to_rgb8().dimensions()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let sharp = Crop {
+            sharpen: Some(Sharpen::default()),
+            ..Crop::default()
+        };
+        let sharp = crop_faces(&image, &[face(20)], &sharp).remove(0).unwrap();
+        // The edge got steeper on both sides.
+        assert!(sharp.get_pixel(8, 10)[0] < plain.get_pixel(8, 10)[0]);
+        assert!(sharp.get_pixel(11, 10)[0] > plain.get_pixel(11, 10)[0]);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::Rect;
+
+/// Picks a position for each label next to its box, avoiding previously placed labels,
+/// the other boxes, and the image edges where possible.
+///
+/// `labels[i]` is the (width, height) of the label for `boxes[i]`. Returns the top-left
+/// corner of each label. Labels that can't be placed without overlap fall back to sitting
+/// just above their box, clamped to the image.
+pub fn place_labels(
+    image_size: (u32, u32),
+    boxes: &[Rect],
+    labels: &[(u32, u32)],
+) -> Vec<(i32, i32)> {
+    let bounds = Rect::with_size(0.0, 0.0, image_size.0 as f32, image_size.1 as f32);
+    let mut placed: Vec<Rect> = Vec::with_capacity(labels.len());
+    let mut positions = Vec::with_capacity(labels.len());
+
+    for (i, (&rect, &(w, h))) in boxes.iter().zip(labels).enumerate() {
+        let (w, h) = (w as f32, h as f32);
+        let candidates = [
+            // Above, below, then inside the top of the box, then stacked further above.
+            (rect.x, rect.y - h),
+            (rect.x, rect.y + rect.h),
+            (rect.x, rect.y),
+            (rect.x, rect.y - 2.0 * h),
+            (rect.x, rect.y - 3.0 * h),
+            (rect.x, rect.y + rect.h + h),
+        ];
+        let fits = |&(x, y): &(f32, f32)| {
+            let label = Rect::with_size(x, y, w, h);
+            contains(&bounds, &label)
+                && placed.iter().all(|other| !intersects(&label, other))
+                && boxes
+                    .iter()
+                    .enumerate()
+                    .all(|(j, other)| j == i || !intersects(&label, other))
+        };
+        let (x, y) = candidates.into_iter().find(fits).unwrap_or((
+            rect.x.clamp(0.0, (bounds.w - w).max(0.0)),
+            (rect.y - h).clamp(0.0, (bounds.h - h).max(0.0)),
+        ));
+        placed.push(Rect::with_size(x, y, w, h));
+        positions.push((x.round() as i32, y.round() as i32));
+    }
+    positions
+}
+
+fn intersects(a: &Rect, b: &Rect) -> bool {
+    a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+}
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    inner.x >= outer.x
+        && inner.y >= outer.y
+        && inner.x + inner.w <= outer.x + outer.w
+        && inner.y + inner.h <= outer.y + outer.h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_avoid_each_other_and_edges() {
+        let boxes = [
+            // At the top edge, so its label can't go above.
+            Rect::with_size(10.0, 0.0, 40.0, 40.0),
+            // Directly below the first box, where its label would naturally go.
+            Rect::with_size(10.0, 50.0, 40.0, 40.0),
+        ];
+        let positions = place_labels((200, 200), &boxes, &[(30, 10), (30, 10)]);
+        assert_eq!((10, 40), positions[0]);
+        // Above would overlap the first label, below is free.
+        assert_eq!((10, 90), positions[1]);
+    }
+}
@@ -0,0 +1,162 @@
+use image::{imageops, Rgb, RgbImage};
+
+use super::face_region;
+use crate::{Face, Rect};
+
+/// How faces are obscured by [`redact_faces`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Redaction {
+    /// Gaussian blur sigma, relative to the larger side of the face rectangle.
+    pub strength: f32,
+    /// Extra area around the face rectangle to blur, relative to its size. Hair and ears
+    /// identify people too.
+    pub margin: f32,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            strength: 0.15,
+            margin: 0.2,
+        }
+    }
+}
+
+/// Blurs the identity-relevant texture of every face in `image`, returning the pixel region
+/// redacted for each face.
+///
+/// The faces themselves are left untouched, so their rectangles, landmarks and confidence
+/// can be stored alongside the redacted image to build privacy-safe datasets that still
+/// support layout and analytics work.
+pub fn redact_faces(image: &mut RgbImage, faces: &[Face], redaction: Redaction) -> Vec<Rect> {
+    faces
+        .iter()
+        .filter_map(|face| {
+            let rect = face_region(face, image.dimensions(), redaction.margin)?;
+            let (x, y, w, h) = rect;
+            let sigma = redaction.strength * w.max(h) as f32;
+            let blurred = imageops::blur(&imageops::crop_imm(image, x, y, w, h).to_image(), sigma);
+            imageops::replace(image, &blurred, x, y);
+            Some(Rect::with_size(x as f32, y as f32, w as f32, h as f32))
+        })
+        .collect()
+}
+
+/// Blocks across the larger side of a pixelated face.
+const PIXELATE_BLOCKS: u32 = 8;
+
+/// How faces are hidden by [`anonymize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anonymization {
+    /// As [`redact_faces`] with the default [`Redaction`].
+    GaussianBlur,
+    /// Coarse blocks of the average color beneath them.
+    Pixelate,
+    /// Solid black.
+    Blackout,
+}
+
+/// Hides every face in `image`, including the margin of the default [`Redaction`] around
+/// it, returning the pixel region anonymized for each face.
+pub fn anonymize(image: &mut RgbImage, faces: &[Face], anonymization: Anonymization) -> Vec<Rect> {
+    let margin = Redaction::default().margin;
+    if anonymization == Anonymization::GaussianBlur {
+        return redact_faces(image, faces, Redaction::default());
+    }
+    faces
+        .iter()
+        .filter_map(|face| {
+            let (x, y, w, h) = face_region(face, image.dimensions(), margin)?;
+            let block = match anonymization {
+                Anonymization::Pixelate => w.max(h).div_ceil(PIXELATE_BLOCKS).max(1),
+                _ => w.max(h),
+            };
+            for by in (y..y + h).step_by(block as usize) {
+                for bx in (x..x + w).step_by(block as usize) {
+                    let (bw, bh) = (block.min(x + w - bx), block.min(y + h - by));
+                    let color = match anonymization {
+                        Anonymization::Pixelate => average(image, bx, by, bw, bh),
+                        _ => Rgb([0, 0, 0]),
+                    };
+                    for py in by..by + bh {
+                        for px in bx..bx + bw {
+                            image.put_pixel(px, py, color);
+                        }
+                    }
+                }
+            }
+            Some(Rect::with_size(x as f32, y as f32, w as f32, h as f32))
+        })
+        .collect()
+}
+
+fn average(image: &RgbImage, x: u32, y: u32, w: u32, h: u32) -> Rgb<u8> {
+    let mut sum = [0u64; 3];
+    for pixel in imageops::crop_imm(image, x, y, w, h).to_image().pixels() {
+        for (s, &c) in sum.iter_mut().zip(&pixel.0) {
+            *s += c as u64;
+        }
+    }
+    let count = (w * h) as u64;
+    Rgb(sum.map(|s| ((s + count / 2) / count) as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    #[test]
+    fn anonymizes_faces() {
+        let gradient = RgbImage::from_fn(100, 100, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        let faces = [Face::from_raw_face(
+            &RawFace {
+                score: 0.9,
+                x: 20.0,
+                y: 20.0,
+                w: 40.0,
+                h: 40.0,
+                lm: [0.0; 10],
+            },
+            (100, 100),
+        )];
+
+        let mut image = gradient.clone();
+        let regions = anonymize(&mut image, &faces, Anonymization::Blackout);
+        assert_eq!(vec![Rect::with_size(12.0, 12.0, 56.0, 56.0)], regions);
+        assert_eq!(Rgb([0, 0, 0]), *image.get_pixel(40, 40));
+        assert_eq!(gradient.get_pixel(70, 70), image.get_pixel(70, 70));
+
+        let mut image = gradient.clone();
+        anonymize(&mut image, &faces, Anonymization::Pixelate);
+        // Blocks of 7 pixels, filled with their average.
+        assert_eq!(Rgb([15, 15, 0]), *image.get_pixel(12, 12));
+        assert_eq!(image.get_pixel(12, 12), image.get_pixel(18, 18));
+        assert_ne!(image.get_pixel(18, 18), image.get_pixel(19, 19));
+    }
+
+    #[test]
+    fn redaction_stays_within_face_region() {
+        let checkerboard = RgbImage::from_fn(100, 100, |x, y| {
+            image::Rgb([if (x + y) % 2 == 0 { 255 } else { 0 }; 3])
+        });
+        let face = Face::from_raw_face(
+            &RawFace {
+                score: 0.9,
+                x: 20.0,
+                y: 20.0,
+                w: 20.0,
+                h: 20.0,
+                lm: [30.0; 10],
+            },
+            (200, 200),
+        );
+
+        let mut image = checkerboard.clone();
+        let regions = redact_faces(&mut image, &[face], Redaction::default());
+        assert_eq!(vec![Rect::with_size(8.0, 8.0, 14.0, 14.0)], regions);
+        // The face was detected at twice the resolution of this image.
+        assert_ne!(checkerboard.get_pixel(15, 15), image.get_pixel(15, 15));
+        assert_eq!(checkerboard.get_pixel(30, 30), image.get_pixel(30, 30));
+    }
+}
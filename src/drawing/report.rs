@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use image::{imageops, Rgb, RgbImage};
+
+use super::{crop_faces, Crop};
+use crate::pipeline::FrameResult;
+use crate::Face;
+
+/// Height of the timeline bar under each thumbnail, in pixels.
+const TIMELINE_HEIGHT: u32 = 6;
+/// Space between cells of a rendered sheet, in pixels.
+const GAP: u32 = 4;
+
+/// What a [`ContactSheet`] knows about one track.
+#[derive(Debug, Clone)]
+pub struct SheetEntry {
+    pub track_id: u64,
+    /// The crop of the most confident detection, scaled to the sheet's thumbnail size.
+    pub best_crop: RgbImage,
+    pub best_confidence: f32,
+    /// Frames the track was detected in.
+    pub detections: u64,
+    pub first_frame: u64,
+    pub last_frame: u64,
+}
+
+/// A summary of a batch or video run for people who won't read its raw output: the best
+/// crop of each track, with how often and when it was seen.
+#[derive(Debug, Clone)]
+pub struct ContactSheet {
+    thumbnail: u32,
+    entries: BTreeMap<u64, SheetEntry>,
+    frames: Option<(u64, u64)>,
+}
+
+impl ContactSheet {
+    /// Crops are scaled to `thumbnail` pixels square, at least one.
+    pub fn new(thumbnail: u32) -> Self {
+        Self {
+            thumbnail: thumbnail.max(1),
+            entries: BTreeMap::new(),
+            frames: None,
+        }
+    }
+
+    /// Takes in the results of a pipeline for the frame `image`.
+    pub fn observe(&mut self, image: &RgbImage, result: &FrameResult) {
+        let (first, last) = self.frames.get_or_insert((result.index, result.index));
+        *first = (*first).min(result.index);
+        *last = (*last).max(result.index);
+
+        let crop = Crop {
+            margin: 0.2,
+            sharpen: None,
+        };
+        for (face, &id) in result.faces.iter().zip(&result.track_ids) {
+            let improves = self
+                .entries
+                .get(&id)
+                .is_none_or(|entry| face.confidence() > entry.best_confidence);
+            let best_crop = improves
+                .then(|| self.thumbnail_of(image, face, &crop))
+                .flatten();
+            let entry = self.entries.entry(id).or_insert_with(|| SheetEntry {
+                track_id: id,
+                best_crop: RgbImage::new(self.thumbnail, self.thumbnail),
+                best_confidence: f32::NEG_INFINITY,
+                detections: 0,
+                first_frame: result.index,
+                last_frame: result.index,
+            });
+            if let Some(best_crop) = best_crop {
+                entry.best_crop = best_crop;
+                entry.best_confidence = face.confidence();
+            }
+            entry.detections += 1;
+            entry.first_frame = entry.first_frame.min(result.index);
+            entry.last_frame = entry.last_frame.max(result.index);
+        }
+    }
+
+    /// One entry per track, by track ID.
+    pub fn entries(&self) -> impl Iterator<Item = &SheetEntry> {
+        self.entries.values()
+    }
+
+    /// Lays the thumbnails out in a grid of `columns`, each above a timeline bar marking
+    /// the span of the run the track was seen in.
+    pub fn render(&self, columns: u32) -> RgbImage {
+        let columns = columns.max(1);
+        let rows = (self.entries.len() as u32).div_ceil(columns);
+        let (cell_w, cell_h) = (self.thumbnail + GAP, self.thumbnail + TIMELINE_HEIGHT + GAP);
+        let mut sheet = RgbImage::from_pixel(
+            columns.min(self.entries.len() as u32).max(1) * cell_w + GAP,
+            rows * cell_h + GAP,
+            Rgb([32, 32, 32]),
+        );
+        let (first, last) = self.frames.unwrap_or_default();
+        let span = (last - first + 1) as f32;
+        for (i, entry) in self.entries.values().enumerate() {
+            let x = GAP + i as u32 % columns * cell_w;
+            let y = GAP + i as u32 / columns * cell_h;
+            imageops::replace(&mut sheet, &entry.best_crop, x, y);
+
+            let bar_y = y + self.thumbnail;
+            let to_x = |frame: u64| ((frame - first) as f32 / span * self.thumbnail as f32) as u32;
+            let (start, end) = (to_x(entry.first_frame), to_x(entry.last_frame + 1));
+            for dx in 0..self.thumbnail {
+                let seen = (start..end.max(start + 1)).contains(&dx);
+                let color = if seen {
+                    Rgb([0, 200, 0])
+                } else {
+                    Rgb([96, 96, 96])
+                };
+                for dy in 1..TIMELINE_HEIGHT {
+                    sheet.put_pixel(x + dx, bar_y + dy, color);
+                }
+            }
+        }
+        sheet
+    }
+
+    fn thumbnail_of(&self, image: &RgbImage, face: &Face, crop: &Crop) -> Option<RgbImage> {
+        let cropped = crop_faces(image, std::slice::from_ref(face), crop).pop()??;
+        Some(imageops::resize(
+            &cropped,
+            self.thumbnail,
+            self.thumbnail,
+            imageops::FilterType::Triangle,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(score: f32, x: i32) -> Face {
+        test_face(score, [x as f32, 10.0, 20.0, 20.0], (100, 50), None)
+    }
+
+    #[test]
+    fn summarizes_tracks() {
+        let image = RgbImage::from_fn(100, 50, |x, _| Rgb([x as u8 * 2, 0, 0]));
+        let mut sheet = ContactSheet::new(16);
+        for (index, faces, track_ids) in [
+            (0, vec![face(0.7, 10)], vec![0]),
+            (1, vec![face(0.9, 12), face(0.8, 60)], vec![0, 1]),
+            (2, vec![face(0.6, 14)], vec![0]),
+        ] {
+            let result = FrameResult {
+                index,
+                timestamp: None,
+                faces,
+                track_ids,
+            };
+            sheet.observe(&image, &result);
+        }
+
+        let entries: Vec<&SheetEntry> = sheet.entries().collect();
+        assert_eq!(2, entries.len());
+        assert_eq!(
+            (3, 0.9),
+            (entries[0].detections, entries[0].best_confidence)
+        );
+        assert_eq!((1, 1), (entries[1].first_frame, entries[1].last_frame));
+        // The second track is further right, so redder.
+        assert!(entries[1].best_crop.get_pixel(8, 8)[0] > entries[0].best_crop.get_pixel(8, 8)[0]);
+
+        let rendered = sheet.render(4);
+        assert_eq!((2 * 20 + 4, 26 + 4), rendered.dimensions());
+        // The second track's timeline is only lit for the middle third.
+        let bar_y = 4 + 16 + 2;
+        assert_eq!(Rgb([96, 96, 96]), *rendered.get_pixel(24 + 2, bar_y));
+        assert_eq!(Rgb([0, 200, 0]), *rendered.get_pixel(24 + 8, bar_y));
+    }
+}
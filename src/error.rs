@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::detector::{Backend, Target};
+
+/// The lower-level error a [`YuNetError`] was caused by, as returned by
+/// [`Error::source`](std::error::Error::source). Shared, so that errors stay `Clone`; to
+/// downcast a source, downcast it to `ErrorSource` first, then the error it holds.
+pub type ErrorSource = Arc<dyn std::error::Error + Send + Sync>;
+
+#[derive(Error, Debug, Clone)]
+pub enum YuNetError {
+    #[error("Invalid input file")]
+    InvalidFile,
+    /// An image file couldn't be read or decoded.
+    #[error("Failed to decode image: {0}")]
+    Decode(String),
+    /// A directory couldn't be listed, or a file couldn't be read outside of decoding.
+    #[error("I/O error: {0}")]
+    Io(String),
+    /// A [`DetectionStore`](crate::store::DetectionStore) query failed.
+    #[error("Detection store error: {0}")]
+    Store(String),
+    #[error("Image buffer doesn't match its dimensions")]
+    InvalidImage,
+    /// The frame the network would run on exceeds
+    /// [`MAX_INPUT_PIXELS`](crate::detector::MAX_INPUT_PIXELS).
+    #[error(
+        "Image of {width}x{height} pixels is too large for the network, downscale it with max_side"
+    )]
+    ImageTooLarge { width: usize, height: usize },
+    #[error("Backend {backend:?} on {target:?} is not available in this build")]
+    UnsupportedBackend { backend: Backend, target: Target },
+    /// The C++ backend threw an exception, caught at the bridge rather than unwinding into
+    /// Rust.
+    #[error("Backend error: {message}")]
+    Backend { message: String },
+    /// A network couldn't be created, such as from parameters passed to
+    /// [`FaceDetector::with_native_model`](crate::FaceDetector::with_native_model) that
+    /// aren't in the format of the bundled ones.
+    #[error("Failed to load the model: {reason}")]
+    ModelLoad {
+        reason: String,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+    /// The network failed while running on a frame.
+    #[error("Inference failed on {backend:?}")]
+    Inference {
+        backend: Backend,
+        #[source]
+        source: ErrorSource,
+    },
+    /// Data couldn't be converted to or from another representation, such as a fixture
+    /// from JSON.
+    #[error("Failed to convert {what}")]
+    Conversion {
+        what: String,
+        #[source]
+        source: ErrorSource,
+    },
+    /// A configuration file, such as one watched by a
+    /// [`ConfigWatcher`](crate::detector::ConfigWatcher), couldn't be parsed.
+    #[error("Invalid configuration: {message}")]
+    Config {
+        message: String,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+    #[error("Face detection failed")]
+    FaceDetectionFailed,
+    /// Detection panicked, caught by
+    /// [`detect_faces_catching`](crate::detector::detect_faces_catching).
+    #[error("Detection panicked: {message}")]
+    Panicked { message: String },
+    /// A [`CancellationToken`](crate::CancellationToken) was cancelled.
+    #[error("Detection was cancelled")]
+    Cancelled,
+}
+
+impl YuNetError {
+    /// A configuration error of its own, rather than caused by a parser.
+    #[cfg(feature = "config")]
+    pub(crate) fn config(message: impl Into<String>) -> Self {
+        YuNetError::Config {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// The same error with `context`, such as the path of a file, before its message.
+    #[cfg(feature = "config")]
+    pub(crate) fn in_config(self, context: impl std::fmt::Display) -> Self {
+        match self {
+            YuNetError::Config { message, source } => YuNetError::Config {
+                message: format!("{context}: {message}"),
+                source,
+            },
+            error => error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn chains_sources() {
+        let error = YuNetError::Inference {
+            backend: Backend::LibFaceDetection,
+            source: Arc::new(YuNetError::Backend {
+                message: "std::bad_alloc".to_owned(),
+            }),
+        };
+        let mut chain = vec![error.to_string()];
+        let mut source = error.source();
+        while let Some(error) = source {
+            chain.push(error.to_string());
+            source = error.source();
+        }
+        assert_eq!(
+            vec![
+                "Inference failed on LibFaceDetection",
+                "Backend error: std::bad_alloc"
+            ],
+            chain
+        );
+        let error = YuNetError::Config {
+            message: "min_confidence: expected a number".to_owned(),
+            source: None,
+        };
+        assert!(error.source().is_none());
+
+        let parse = "high".parse::<f32>().unwrap_err();
+        let error = YuNetError::Conversion {
+            what: "min_confidence".to_owned(),
+            source: Arc::new(parse.clone()),
+        }
+        .clone();
+        let source = error.source().unwrap().downcast_ref::<ErrorSource>();
+        assert_eq!(Some(&parse), source.unwrap().downcast_ref());
+    }
+}
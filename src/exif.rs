@@ -0,0 +1,123 @@
+//! Just enough of EXIF to read the orientation of JPEG photos, which cameras and phones
+//! store sideways with a tag saying how to turn them upright.
+
+use crate::orientation::Rotation;
+
+const ORIENTATION_TAG: u16 = 0x0112;
+
+/// The mirroring and clockwise rotation that turn a JPEG upright, by its EXIF orientation.
+/// `None` if it has no orientation tag, or isn't a JPEG.
+pub(crate) fn jpeg_orientation(jpeg: &[u8]) -> Option<(Rotation, bool)> {
+    let orientation = match read_orientation(jpeg)? {
+        1 => (Rotation::None, false),
+        2 => (Rotation::None, true),
+        3 => (Rotation::Cw180, false),
+        4 => (Rotation::Cw180, true),
+        5 => (Rotation::Cw270, true),
+        6 => (Rotation::Cw90, false),
+        7 => (Rotation::Cw90, true),
+        8 => (Rotation::Cw270, false),
+        _ => return None,
+    };
+    Some(orientation)
+}
+
+fn read_orientation(jpeg: &[u8]) -> Option<u16> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut rest = &jpeg[2..];
+    loop {
+        let (&[0xFF, marker], tail) = rest.split_first_chunk::<2>()? else {
+            return None;
+        };
+        // Start of scan: the image data follows, with no more metadata before it.
+        if marker == 0xDA {
+            return None;
+        }
+        let length = u16::from_be_bytes(*tail.first_chunk::<2>()?) as usize;
+        let segment = tail.get(2..length)?;
+        if marker == 0xE1 {
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return tiff_orientation(tiff);
+            }
+        }
+        rest = tail.get(length..)?;
+    }
+}
+
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| {
+        let bytes = *tiff.get(offset..)?.first_chunk::<2>()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |offset: usize| {
+        let bytes = *tiff.get(offset..)?.first_chunk::<4>()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    let ifd = u32_at(4)? as usize;
+    (0..u16_at(ifd)? as usize)
+        .map(|i| ifd + 2 + 12 * i)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The start of a JPEG with only an EXIF segment holding `orientation`.
+    pub(crate) fn exif_segment(orientation: u16, big_endian: bool) -> Vec<u8> {
+        let mut tiff: Vec<u8> = if big_endian { b"MM" } else { b"II" }.to_vec();
+        let mut push = |value: u32, bytes: usize| {
+            let be = value.to_be_bytes();
+            let le = value.to_le_bytes();
+            if big_endian {
+                tiff.extend_from_slice(&be[4 - bytes..]);
+            } else {
+                tiff.extend_from_slice(&le[..bytes]);
+            }
+        };
+        push(42, 2);
+        push(8, 4);
+        // One entry: the tag, of SHORT type, with one value padded to four bytes.
+        push(1, 2);
+        push(ORIENTATION_TAG as u32, 2);
+        push(3, 2);
+        push(1, 4);
+        push(orientation as u32, 2);
+        push(0, 2);
+        // No next IFD.
+        push(0, 4);
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&(2 + 6 + tiff.len() as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg
+    }
+
+    #[test]
+    fn reads_orientation() {
+        for big_endian in [false, true] {
+            let mut jpeg = exif_segment(6, big_endian);
+            jpeg.extend_from_slice(&[0xFF, 0xDA]);
+            assert_eq!(Some((Rotation::Cw90, false)), jpeg_orientation(&jpeg));
+        }
+        assert_eq!(None, jpeg_orientation(&[0xFF, 0xD8, 0xFF, 0xDA]));
+        assert_eq!(None, jpeg_orientation(b"\x89PNG"));
+    }
+}
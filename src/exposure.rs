@@ -0,0 +1,193 @@
+//! Suggesting where cameras should meter exposure and focus, so that faces stay well exposed
+//! against bright windows and backlit stages. Feed the hint to cameras supporting
+//! region-of-interest auto exposure, such as through UVC or V4L2 controls or Android's
+//! `CaptureRequest.CONTROL_AE_REGIONS`.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{Face, Rect};
+
+/// Tuning knobs for an [`ExposureHinter`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ExposureConfig {
+    /// Faces less confident than this are ignored.
+    pub min_confidence: f32,
+    /// Context added around each face, relative to its size, so that the metered region
+    /// includes some hair and neck rather than skin alone.
+    pub margin: f32,
+    /// Weight (0..1) of each new region in the reported one. Lower values keep the exposure
+    /// from pumping as faces move; 1 disables smoothing.
+    pub smoothing: f32,
+}
+
+impl Default for ExposureConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.6,
+            margin: 0.1,
+            smoothing: 0.3,
+        }
+    }
+}
+
+/// One face's share of the metering, for cameras taking several weighted regions.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeteringRegion {
+    /// In pixels of the frame, clamped to it.
+    pub rect: Rect,
+    /// Relative importance in 0..1, by area and confidence; the most important face has 1.
+    pub weight: f32,
+}
+
+/// A suggested exposure and focus region for one frame.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExposureHint {
+    /// The smoothed union of the faces, in pixels of the frame, for cameras taking a single
+    /// region.
+    pub region: Rect,
+    /// Each face on its own, most important first.
+    pub regions: Vec<MeteringRegion>,
+    /// Dimensions (width, height) of the frame.
+    pub dimensions: (usize, usize),
+}
+
+impl ExposureHint {
+    /// [`region`](Self::region) as a fraction (0..1) of the frame, as some camera APIs
+    /// expect.
+    pub fn normalized_region(&self) -> Rect {
+        let (width, height) = (self.dimensions.0 as f32, self.dimensions.1 as f32);
+        Rect::with_size(
+            self.region.x / width,
+            self.region.y / height,
+            self.region.w / width,
+            self.region.h / height,
+        )
+    }
+}
+
+/// Turns the faces of consecutive frames into exposure and focus hints.
+#[derive(Debug, Clone)]
+pub struct ExposureHinter {
+    config: ExposureConfig,
+    region: Option<Rect>,
+}
+
+impl ExposureHinter {
+    pub fn new(config: ExposureConfig) -> Self {
+        Self {
+            config,
+            region: None,
+        }
+    }
+
+    /// Feeds the faces of the next frame, returning the hint for it, or `None` when no face
+    /// is in view and the camera should meter as it normally would. Smoothing restarts when
+    /// faces come back into view.
+    pub fn update(&mut self, faces: &[Face]) -> Option<ExposureHint> {
+        let mut regions: Vec<MeteringRegion> = faces
+            .iter()
+            .filter(|face| face.confidence() >= self.config.min_confidence)
+            .map(|face| MeteringRegion {
+                rect: self.expanded(face),
+                weight: face.rectangle().area() * face.confidence(),
+            })
+            .collect();
+        regions.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+        let Some(union) = regions
+            .iter()
+            .map(|region| region.rect)
+            .reduce(|a, b| a.bounding(&b))
+        else {
+            self.region = None;
+            return None;
+        };
+        let heaviest = regions[0].weight;
+        for region in &mut regions {
+            region.weight = if heaviest > 0.0 {
+                region.weight / heaviest
+            } else {
+                1.0
+            };
+        }
+
+        let t = self.config.smoothing.clamp(0.0, 1.0);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let region = match self.region {
+            Some(previous) => Rect::with_size(
+                lerp(previous.x, union.x),
+                lerp(previous.y, union.y),
+                lerp(previous.w, union.w),
+                lerp(previous.h, union.h),
+            ),
+            None => union,
+        };
+        self.region = Some(region);
+        Some(ExposureHint {
+            region,
+            regions,
+            dimensions: faces[0].detection_dimensions(),
+        })
+    }
+
+    /// The face rectangle grown by the margin and clamped to the frame.
+    fn expanded(&self, face: &Face) -> Rect {
+        let (width, height) = face.detection_dimensions();
+        let rect = face.rectangle();
+        let margin = rect.w.max(rect.h) * self.config.margin;
+        let x0 = (rect.x - margin).clamp(0.0, width as f32);
+        let y0 = (rect.y - margin).clamp(0.0, height as f32);
+        let x1 = (rect.x + rect.w + margin).clamp(0.0, width as f32);
+        let y1 = (rect.y + rect.h + margin).clamp(0.0, height as f32);
+        Rect::with_size(x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+impl Default for ExposureHinter {
+    fn default() -> Self {
+        Self::new(ExposureConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(x: i32, size: i32, score: f32) -> Face {
+        let [x, size] = [x, size].map(|v| v as f32);
+        test_face(score, [x, 10.0, size, size], (200, 100), None)
+    }
+
+    #[test]
+    fn hints_exposure_regions() {
+        let mut hinter = ExposureHinter::new(ExposureConfig {
+            margin: 0.1,
+            smoothing: 0.5,
+            ..ExposureConfig::default()
+        });
+        // A face at the edge of the frame, a smaller one, and an unconfident one.
+        let faces = [face(0, 40, 0.9), face(100, 20, 0.9), face(150, 40, 0.3)];
+        let hint = hinter.update(&faces).unwrap();
+        assert_eq!(Rect::with_size(0.0, 6.0, 122.0, 48.0), hint.region);
+        assert_eq!(
+            Rect::with_size(0.0, 0.06, 0.61, 0.48),
+            hint.normalized_region()
+        );
+        assert_eq!(2, hint.regions.len());
+        assert_eq!(Rect::with_size(0.0, 6.0, 44.0, 48.0), hint.regions[0].rect);
+        assert_eq!(
+            [1.0, 0.25],
+            [hint.regions[0].weight, hint.regions[1].weight]
+        );
+
+        // The smaller face leaves; the region shrinks halfway towards the remaining one.
+        let hint = hinter.update(&faces[..1]).unwrap();
+        assert_eq!(Rect::with_size(0.0, 6.0, 83.0, 48.0), hint.region);
+        assert!(hinter.update(&faces[2..]).is_none());
+        assert!(hinter.update(&[]).is_none());
+    }
+}
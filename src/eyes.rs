@@ -0,0 +1,154 @@
+//! Whether eyes are open, such as for driver monitoring or picking the photo of a burst in
+//! which nobody blinks, judged from the pixels around the eye landmarks.
+//!
+//! YuNet locates eye centers only, not eyelids, so openness is estimated from how tall the
+//! dark of the iris and pupil is at each eye: a disc when open, a thin line of lashes when
+//! closed. It is a heuristic, unreliable for faces under about 40 pixels between the eyes,
+//! behind sunglasses or turned far from the camera.
+
+use glam::Vec2;
+
+use crate::{Face, ImageView};
+
+/// Samples per interocular distance.
+const RESOLUTION: f32 = 40.0;
+/// Half the width and height of the region analyzed around an eye, relative to the
+/// interocular distance.
+const HALF_REGION: Vec2 = Vec2::new(0.25, 0.15);
+/// The height of the dark of an eye wide open, relative to the interocular distance.
+const OPEN_HEIGHT: f32 = 0.15;
+/// Interocular distances in pixels below which no estimate is made.
+const MIN_DISTANCE: f32 = 12.0;
+
+/// How open each eye of a face is, from 0 (closed) to 1 (open).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EyeOpenness {
+    /// The person's right eye, seen on the left.
+    pub right: f32,
+    pub left: f32,
+}
+
+impl EyeOpenness {
+    /// Whether both eyes are at least `threshold` open; 0.5 suits most faces.
+    pub fn both_open(&self, threshold: f32) -> bool {
+        self.right >= threshold && self.left >= threshold
+    }
+}
+
+impl Face {
+    /// Estimates how open the eyes of this face are, from `image`, the frame it was detected
+    /// in. `None` if the eyes are too close together or too near the edges of the frame to
+    /// tell.
+    pub fn eye_openness(&self, image: &ImageView) -> Option<EyeOpenness> {
+        let landmarks = self.landmarks();
+        let axis = landmarks.left_eye - landmarks.right_eye;
+        let distance = axis.length();
+        if distance < MIN_DISTANCE {
+            return None;
+        }
+        let axis = axis / distance;
+        Some(EyeOpenness {
+            right: openness(image, landmarks.right_eye, axis, distance)?,
+            left: openness(image, landmarks.left_eye, axis, distance)?,
+        })
+    }
+}
+
+/// The height of the tallest run of dark samples through the middle of the eye at
+/// `center`, relative to that of an open eye.
+fn openness(image: &ImageView, center: Vec2, axis: Vec2, distance: f32) -> Option<f32> {
+    let step = distance / RESOLUTION;
+    let (columns, rows) = (
+        (2.0 * HALF_REGION.x * RESOLUTION) as usize,
+        (2.0 * HALF_REGION.y * RESOLUTION) as usize,
+    );
+    let mut samples = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            let offset = Vec2::new(
+                column as f32 - columns as f32 / 2.0,
+                row as f32 - rows as f32 / 2.0,
+            ) * step;
+            samples.push(luma(image, center + axis.rotate(offset))?);
+        }
+    }
+    let darkest = samples.iter().copied().fold(f32::MAX, f32::min);
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    let threshold = darkest + (mean - darkest) * 0.5;
+
+    // The middle third of columns, where the iris is.
+    let tallest = (columns / 3..columns - columns / 3)
+        .map(|column| {
+            let (mut run, mut longest) = (0, 0);
+            for row in 0..rows {
+                run = if samples[row * columns + column] < threshold {
+                    run + 1
+                } else {
+                    0
+                };
+                longest = longest.max(run);
+            }
+            longest
+        })
+        .max()
+        .unwrap_or(0);
+    Some((tallest as f32 * step / (OPEN_HEIGHT * distance)).min(1.0))
+}
+
+/// The luma at a point of a BGR or grayscale image, by its nearest pixel, `None` outside.
+fn luma(image: &ImageView, point: Vec2) -> Option<f32> {
+    let (x, y) = (point.x.round(), point.y.round());
+    let (width, height) = image.dimensions();
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+        return None;
+    }
+    let channels = image.channels();
+    let pixel = &image.data()[y as usize * image.stride() + x as usize * channels..][..channels];
+    Some(match pixel {
+        [b, g, r] => 0.114 * *b as f32 + 0.587 * *g as f32 + 0.299 * *r as f32,
+        [gray] => *gray as f32,
+        _ => unreachable!("images have 1 or 3 channels"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    #[test]
+    fn tells_open_from_closed_eyes() {
+        // Skin, with an open right eye, a dark disc, and a closed left eye, a line of
+        // lashes, 40 pixels apart.
+        let (width, height) = (120, 100);
+        let mut gray = vec![180u8; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (x as f32 - 40.0, y as f32 - 50.0);
+                if dx * dx + dy * dy <= 25.0 || (y == 50 && (72..88).contains(&x)) {
+                    gray[y * width + x] = 30;
+                }
+            }
+        }
+        let image = ImageView::gray(&gray, width, height).unwrap();
+        let face = |eyes: [i32; 4]| {
+            let raw = RawFace {
+                score: 0.9,
+                x: 20.0,
+                y: 20.0,
+                w: 80.0,
+                h: 80.0,
+                lm: [eyes[0], eyes[1], eyes[2], eyes[3], 60, 65, 45, 80, 75, 80].map(|v| v as f32),
+            };
+            Face::from_raw_face(&raw, (width, height))
+        };
+
+        let openness = face([40, 50, 80, 50]).eye_openness(&image).unwrap();
+        assert!(openness.right > 0.8, "{openness:?}");
+        assert!(openness.left < 0.3, "{openness:?}");
+        assert!(!openness.both_open(0.5));
+        // Too small to tell, and cut off by the frame.
+        assert_eq!(None, face([40, 50, 45, 50]).eye_openness(&image));
+        assert_eq!(None, face([2, 50, 42, 50]).eye_openness(&image));
+    }
+}
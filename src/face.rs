@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use rusty_yunet_types::{FaceBox, FaceLandmarks, FaceRecord, Landmark};
+
+use crate::detector::RawFace;
+use crate::geometry::{CoordinateConvention, Rect};
+use crate::hooks::Annotation;
+use crate::provenance::{fnv1a, DetectionContext, Provenance};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Face {
+    /// How confident (0..1) YuNet is that the rectangle represents a valid face.
+    confidence: f32,
+    /// Location of the face on absolute pixel coordinates. This may fall outside
+    /// of screen coordinates.
+    rectangle: Rect,
+    /// The resolution of the image in which this face was detected (width, height).
+    detection_dimensions: (usize, usize),
+    /// Coordinates of five face landmarks.
+    landmarks: FaceLandmarks,
+    /// What produced this detection, if the detector was configured to record it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    provenance: Option<Arc<Provenance>>,
+    /// Which frame of which source this face was detected in, if the caller said.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    context: Option<Arc<DetectionContext>>,
+    /// Outputs of the [`FaceHook`](crate::hooks::FaceHook)s that looked at this face.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    annotations: BTreeMap<String, Annotation>,
+}
+
+impl Face {
+    /// Conversion is fallible, as YuNet has been known to report faces with
+    /// negative dimensions, rarely; see
+    /// [`FaceDetector::detect_lenient`](crate::FaceDetector::detect_lenient).
+    pub(crate) fn from_raw_face(face_rect: &RawFace, detection_dimensions: (usize, usize)) -> Self {
+        Self {
+            confidence: face_rect.score,
+            rectangle: Rect::with_size(face_rect.x, face_rect.y, face_rect.w, face_rect.h),
+            landmarks: FaceLandmarks::from_array(
+                [0, 1, 2, 3, 4].map(|i| Vec2::new(face_rect.lm[2 * i], face_rect.lm[2 * i + 1])),
+            ),
+            detection_dimensions,
+            provenance: None,
+            context: None,
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    /// A face as stored elsewhere, such as in a [`DetectionStore`](crate::store::DetectionStore),
+    /// or made up for a test; see [`mock`](crate::mock).
+    pub fn new(
+        confidence: f32,
+        rectangle: Rect,
+        landmarks: FaceLandmarks,
+        detection_dimensions: (usize, usize),
+    ) -> Self {
+        Self {
+            confidence,
+            rectangle,
+            detection_dimensions,
+            landmarks,
+            provenance: None,
+            context: None,
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    /// Maps a face detected on a resized frame into the coordinates of the original frame.
+    pub(crate) fn rescaled(&self, scale: Vec2, detection_dimensions: (usize, usize)) -> Self {
+        let rect = self.rectangle;
+        Self {
+            confidence: self.confidence,
+            rectangle: Rect::with_size(
+                rect.x * scale.x,
+                rect.y * scale.y,
+                rect.w * scale.x,
+                rect.h * scale.y,
+            ),
+            detection_dimensions,
+            landmarks: self.landmarks.map(|p| p * scale),
+            provenance: self.provenance.clone(),
+            context: self.context.clone(),
+            annotations: self.annotations.clone(),
+        }
+    }
+
+    /// Maps a face through a transform of its frame, such as a rotation, into a frame of the
+    /// given dimensions. The rectangle becomes the bounds of its transformed corners.
+    pub(crate) fn mapped(
+        &self,
+        f: impl Fn(Vec2) -> Vec2,
+        detection_dimensions: (usize, usize),
+    ) -> Self {
+        let rect = self.rectangle;
+        let corners = [
+            Vec2::new(rect.x, rect.y),
+            Vec2::new(rect.x + rect.w, rect.y + rect.h),
+        ]
+        .map(&f);
+        let (min, max) = (corners[0].min(corners[1]), corners[0].max(corners[1]));
+        Self {
+            confidence: self.confidence,
+            rectangle: Rect::new(min, max.x - min.x, max.y - min.y),
+            detection_dimensions,
+            landmarks: self.landmarks.map(f),
+            provenance: self.provenance.clone(),
+            context: self.context.clone(),
+            annotations: self.annotations.clone(),
+        }
+    }
+
+    pub(crate) fn set_provenance(&mut self, provenance: Arc<Provenance>) {
+        self.provenance = Some(provenance);
+    }
+
+    pub(crate) fn set_context(&mut self, context: Arc<DetectionContext>) {
+        self.context = Some(context);
+    }
+
+    pub(crate) fn set_confidence(&mut self, confidence: f32) {
+        self.confidence = confidence;
+    }
+
+    pub(crate) fn set_landmarks(&mut self, landmarks: FaceLandmarks) {
+        self.landmarks = landmarks;
+    }
+
+    pub(crate) fn annotate(&mut self, name: &str, annotation: Annotation) {
+        self.annotations.insert(name.to_owned(), annotation);
+    }
+
+    /// How confident (0..1) YuNet is that the rectangle is a face.
+    pub fn confidence(&self) -> f32 {
+        self.confidence
+    }
+
+    /// Face rectangle in absolute pixel coordinates.
+    pub fn rectangle(&self) -> Rect {
+        self.rectangle
+    }
+
+    /// The minimum of normalized width and height.
+    pub fn size(&self) -> f32 {
+        let rect = self.normalized_rectangle();
+        rect.w.min(rect.h)
+    }
+
+    /// The resolution (width, height) of the image in which this face was detected.
+    pub fn detection_dimensions(&self) -> (usize, usize) {
+        self.detection_dimensions
+    }
+
+    /// Face rectangle in normalized 0..1 coordinates.
+    pub fn normalized_rectangle(&self) -> Rect {
+        Rect::with_size(
+            self.rectangle.x / self.detection_dimensions.0 as f32,
+            self.rectangle.y / self.detection_dimensions.1 as f32,
+            self.rectangle.w / self.detection_dimensions.0 as f32,
+            self.rectangle.h / self.detection_dimensions.1 as f32,
+        )
+    }
+
+    /// Coordinates of five face landmarks.
+    pub fn landmarks(&self) -> &FaceLandmarks {
+        &self.landmarks
+    }
+
+    /// Landmarks in normalized 0..1 coordinates.
+    pub fn normalized_landmarks(&self) -> FaceLandmarks {
+        let (width, height) = self.detection_dimensions;
+        let scale = Vec2::new(1.0 / width as f32, 1.0 / height as f32);
+        self.landmarks.map(|p| p * scale)
+    }
+
+    /// The face rectangle in another coordinate convention of the frame it was detected in.
+    pub fn rectangle_in(&self, convention: CoordinateConvention) -> Rect {
+        convention.rect(self.rectangle, self.detection_dimensions)
+    }
+
+    /// The landmarks in another coordinate convention of the frame they were detected in.
+    pub fn landmarks_in(&self, convention: CoordinateConvention) -> FaceLandmarks {
+        self.landmarks
+            .map(|p| convention.point(p, self.detection_dimensions))
+    }
+
+    /// The face a fraction `t` (0..1) of the way from this one to `other`, such as between
+    /// two detections of the same person. Confidence is blended too; the frame, provenance,
+    /// context and annotations are those of `other`.
+    pub fn lerp(&self, other: &Face, t: f32) -> Face {
+        let (a, b) = (self.rectangle, other.rectangle);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let landmarks = [self.landmarks.as_array(), other.landmarks.as_array()];
+        let point = |i: usize| landmarks[0][i].lerp(landmarks[1][i], t);
+        Face {
+            confidence: mix(self.confidence, other.confidence),
+            rectangle: Rect::with_size(mix(a.x, b.x), mix(a.y, b.y), mix(a.w, b.w), mix(a.h, b.h)),
+            detection_dimensions: other.detection_dimensions,
+            landmarks: FaceLandmarks::from_array([0, 1, 2, 3, 4].map(point)),
+            provenance: other.provenance.clone(),
+            context: other.context.clone(),
+            annotations: other.annotations.clone(),
+        }
+    }
+
+    /// Where the whole head is likely to be, hair and ears included, in absolute pixel
+    /// coordinates, from the proportions of an average adult head: the eyes halfway between
+    /// the top of the head and the chin, which is the bottom of the face rectangle, and the
+    /// head three quarters as wide as it is tall, centered between the eyes. Like the face
+    /// rectangle, it may extend beyond the frame.
+    pub fn estimated_head_rect(&self) -> Rect {
+        let rect = self.rectangle;
+        let eyes = (self.landmarks.right_eye + self.landmarks.left_eye) / 2.0;
+        let chin = rect.y + rect.h;
+        // Eyes at or below the chin can't be right; fall back on the middle of the face.
+        let eyes = if eyes.y < chin { eyes } else { rect.center() };
+        let height = 2.0 * (chin - eyes.y);
+        let width = 0.75 * height;
+        Rect::with_size(eyes.x - width / 2.0, chin - height, width, height)
+    }
+
+    /// Where the head, shoulders and torso down to the waist are likely to be, in absolute
+    /// pixel coordinates, for someone upright and facing the camera: three heads tall from
+    /// the top of the [head](Self::estimated_head_rect) and three heads wide, centered on
+    /// it. Its top half is a head-and-shoulders crop.
+    pub fn estimated_upper_body_rect(&self) -> Rect {
+        let head = self.estimated_head_rect();
+        let width = 3.0 * head.w;
+        Rect::with_size(head.center().x - width / 2.0, head.y, width, 3.0 * head.h)
+    }
+
+    /// Whether the face rectangle and every landmark lie within the frame it was detected
+    /// in, as opposed to being cut off at its edges.
+    pub fn fully_visible(&self) -> bool {
+        let (width, height) = self.detection_dimensions;
+        let rect = self.rectangle;
+        rect.x >= 0.0
+            && rect.y >= 0.0
+            && rect.x + rect.w <= width as f32
+            && rect.y + rect.h <= height as f32
+            && self.landmarks.visibility(self.detection_dimensions) == [true; 5]
+    }
+
+    /// A hash of the face rectangle, landmarks and frame dimensions, rounded to whole
+    /// pixels, as a key to deduplicate detections, such as of an image scanned twice or
+    /// stored under two paths. It is the same across platforms and releases; confidence,
+    /// provenance, context and annotations don't enter it.
+    pub fn fingerprint(&self) -> u64 {
+        let (width, height) = self.detection_dimensions;
+        let rect = self.rectangle;
+        let coordinates = [rect.x, rect.y, rect.w, rect.h].into_iter().chain(
+            self.landmarks
+                .as_array()
+                .into_iter()
+                .flat_map(|p| [p.x, p.y]),
+        );
+        let mut bytes = Vec::with_capacity(16 + 14 * 4);
+        bytes.extend((width as u64).to_le_bytes());
+        bytes.extend((height as u64).to_le_bytes());
+        for value in coordinates {
+            bytes.extend((value.round() as i32).to_le_bytes());
+        }
+        fnv1a(&bytes)
+    }
+
+    /// The output of the [`FaceHook`](crate::hooks::FaceHook) called `name`, if it
+    /// attached one to this face.
+    pub fn annotation(&self, name: &str) -> Option<&Annotation> {
+        self.annotations.get(name)
+    }
+
+    /// The outputs of every hook that attached one, by name.
+    pub fn annotations(&self) -> &BTreeMap<String, Annotation> {
+        &self.annotations
+    }
+
+    /// What produced this detection; only recorded when [`DetectorConfig::provenance`] is
+    /// enabled.
+    ///
+    /// [`DetectorConfig::provenance`]: crate::DetectorConfig::provenance
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_deref()
+    }
+
+    /// Which frame of which source this face was detected in; only recorded when the
+    /// detection was given a context, such as through
+    /// [`DetectionRequest::context`](crate::DetectionRequest::context).
+    pub fn context(&self) -> Option<&DetectionContext> {
+        self.context.as_deref()
+    }
+
+    /// The face rectangle cut out of `image`, clamped to it, scaling the rectangle if the
+    /// image isn't the one the face was detected in. `None` if the face lies outside.
+    #[cfg(feature = "image")]
+    pub fn crop(&self, image: &image::RgbImage) -> Option<image::RgbImage> {
+        let (x, y, w, h) = crate::drawing::face_region(self, image.dimensions(), 0.0)?;
+        Some(image::imageops::crop_imm(image, x, y, w, h).to_image())
+    }
+}
+
+/// The face without what only the detector's side has, for sending to receivers that
+/// decode it with the `rusty-yunet-types` crate alone.
+impl From<&Face> for FaceRecord {
+    fn from(face: &Face) -> Self {
+        FaceRecord {
+            confidence: face.confidence,
+            rectangle: face.rectangle,
+            detection_dimensions: face.detection_dimensions,
+            landmarks: face.landmarks.clone(),
+        }
+    }
+}
+
+impl From<&Face> for FaceBox {
+    fn from(face: &Face) -> Self {
+        FaceBox {
+            confidence: face.confidence,
+            rectangle: face.rectangle,
+            detection_dimensions: face.detection_dimensions,
+        }
+    }
+}
+
+impl From<FaceRecord> for Face {
+    fn from(record: FaceRecord) -> Self {
+        Face::new(
+            record.confidence,
+            record.rectangle,
+            record.landmarks,
+            record.detection_dimensions,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(x: i32, lm: [i32; 10]) -> Face {
+        let raw = RawFace {
+            score: 0.9,
+            x: x as f32,
+            y: 10.0,
+            w: 20.0,
+            h: 20.0,
+            lm: lm.map(|v| v as f32),
+        };
+        Face::from_raw_face(&raw, (100, 100))
+    }
+
+    #[test]
+    fn reports_landmark_visibility() {
+        let inside = face(10, [20; 10]);
+        assert!(inside.fully_visible());
+
+        // Cut off at the left edge, with the right eye and mouth corner extrapolated.
+        let cut_off = face(-5, [-2, 20, 8, 20, 3, 25, -1, 30, 6, 30]);
+        assert_eq!(
+            [false, true, true, false, true],
+            cut_off
+                .landmarks()
+                .visibility(cut_off.detection_dimensions())
+        );
+        assert!(!cut_off.fully_visible());
+        assert!(!face(90, [95; 10]).fully_visible());
+
+        let mut landmarks = cut_off.landmarks().clone();
+        assert_eq!(Vec2::new(-2.0, 20.0), landmarks.get(Landmark::RightEye));
+        *landmarks.get_mut(Landmark::Nose) = Vec2::ZERO;
+        assert_eq!(Vec2::ZERO, landmarks.nose);
+        assert_eq!(
+            landmarks.as_array().to_vec(),
+            landmarks.iter().map(|(_, p)| p).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some((Landmark::MouthLeft, Vec2::new(6.0, 30.0))),
+            landmarks.iter().last()
+        );
+    }
+
+    #[test]
+    fn estimates_head_and_body_around_the_face() {
+        // Eyes 10 pixels above the chin, halfway down a face from 10 to 30.
+        let face = face(10, [15, 20, 25, 20, 20, 24, 16, 27, 24, 27]);
+        let head = face.estimated_head_rect();
+        assert_eq!(Rect::with_size(12.5, 10.0, 15.0, 20.0), head);
+        assert_eq!(
+            Rect::with_size(-2.5, 10.0, 45.0, 60.0),
+            face.estimated_upper_body_rect()
+        );
+    }
+
+    #[test]
+    fn fingerprints_are_stable_and_distinct() {
+        let original = face(10, [20; 10]);
+        assert_eq!(0x7231_6a90_4cac_fae5, original.fingerprint());
+        // Refinement jitter and other confidences don't change it.
+        let mut jittered = original.rescaled(Vec2::splat(1.01), (100, 100));
+        jittered.set_confidence(0.5);
+        assert_eq!(original.fingerprint(), jittered.fingerprint());
+
+        // Every position, size and landmark of a grid of faces, and other frame sizes.
+        let mut seen = std::collections::HashSet::new();
+        for x in 0..40 {
+            for size in 10..40 {
+                for nose in 0..20 {
+                    let raw = RawFace {
+                        score: 0.9,
+                        x: x as f32,
+                        y: 10.0,
+                        w: size as f32,
+                        h: size as f32,
+                        lm: [20, 20, 30, 20, 25, nose, 20, 40, 30, 40].map(|v| v as f32),
+                    };
+                    for dimensions in [(100, 100), (100, 101)] {
+                        let face = Face::from_raw_face(&raw, dimensions);
+                        assert!(seen.insert(face.fingerprint()));
+                    }
+                }
+            }
+        }
+    }
+}
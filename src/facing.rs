@@ -0,0 +1,83 @@
+//! Whether people face the camera, such as for exhibits that should react only to visitors
+//! looking at the screen, judged from the symmetry of the landmarks alone.
+//!
+//! A face turned sideways brings its nose and mouth towards one eye; one tilted up or down
+//! moves its nose towards the line of the eyes or that of the mouth. Both are measured in
+//! the frame of the eyes, so a head merely tilted to the side still faces the camera. The
+//! eyes themselves aren't looked at: someone facing the camera may be looking elsewhere.
+
+use glam::Vec2;
+
+use crate::Face;
+
+/// Where the nose sits between the line of the eyes and that of the mouth, as a fraction of
+/// the distance between them, on a face seen straight on.
+const FRONTAL_NOSE_DEPTH: f32 = 0.5;
+
+impl Face {
+    /// How directly the face is turned towards the camera, from 0 (in profile, or far up or
+    /// down) to 1 (straight on).
+    pub fn facing(&self) -> f32 {
+        let landmarks = self.landmarks();
+        let axis = landmarks.left_eye - landmarks.right_eye;
+        let width = axis.length_squared();
+        if width == 0.0 {
+            return 0.0;
+        }
+        let eyes = (landmarks.right_eye + landmarks.left_eye) / 2.0;
+        let mouth = (landmarks.mouth_right + landmarks.mouth_left) / 2.0;
+        // Fractions of the way from the right eye to the left, 0.5 when centered.
+        let across = |p: Vec2| (p - landmarks.right_eye).dot(axis) / width;
+        let yaw = ((across(landmarks.nose) - 0.5).abs() + (across(mouth) - 0.5).abs()) / 2.0;
+
+        let down = axis.perp().normalize();
+        let height = (mouth - eyes).dot(down);
+        if height <= 0.0 {
+            return 0.0;
+        }
+        let depth = (landmarks.nose - eyes).dot(down) / height;
+        let pitch = (depth - FRONTAL_NOSE_DEPTH).abs();
+
+        (1.0 - 2.0 * yaw.max(pitch)).clamp(0.0, 1.0)
+    }
+
+    /// Whether [`facing`](Self::facing) reaches `threshold`; 0.6 admits heads turned by up to
+    /// about 40 degrees.
+    pub fn facing_camera(&self, threshold: f32) -> bool {
+        self.facing() >= threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(nose: Vec2, mouth_shift: f32, roll: f32) -> Face {
+        let points = [
+            Vec2::new(-10.0, -10.0),
+            Vec2::new(10.0, -10.0),
+            nose,
+            Vec2::new(-8.0 + mouth_shift, 10.0),
+            Vec2::new(8.0 + mouth_shift, 10.0),
+        ]
+        .map(|p| Vec2::new(50.0, 50.0) + Vec2::from_angle(roll).rotate(p));
+        test_face(0.9, [30.0, 30.0, 40.0, 40.0], (100, 100), Some(points))
+    }
+
+    #[test]
+    fn tells_faces_turned_away() {
+        let frontal = face(Vec2::ZERO, 0.0, 0.0);
+        assert_eq!(1.0, frontal.facing());
+        // Tilting the head to the side doesn't turn it away.
+        assert!((face(Vec2::ZERO, 0.0, 0.5).facing() - 1.0).abs() < 1e-5);
+        assert!(frontal.facing_camera(0.6));
+
+        let turned = face(Vec2::new(-8.0, 0.0), -6.0, 0.0);
+        assert!((turned.facing() - 0.3).abs() < 1e-5);
+        assert!(!turned.facing_camera(0.6));
+        let looking_down = face(Vec2::new(0.0, 6.0), 0.0, 0.0);
+        assert!((looking_down.facing() - 0.4).abs() < 1e-5);
+        assert!(!looking_down.facing_camera(0.6));
+    }
+}
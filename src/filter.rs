@@ -0,0 +1,183 @@
+//! Rejecting unusable detections, such as blurred, stretched or cut off faces, inside the
+//! detector, so that they never reach application code.
+//!
+//! ```no_run
+//! # use rusty_yunet::filter::{AspectRatioFilter, BlurFilter, BoundaryFilter, FaceFilter};
+//! # use rusty_yunet::FaceDetector;
+//! let filter = FaceFilter::new()
+//!     .with(BlurFilter::default())
+//!     .with(AspectRatioFilter::default())
+//!     .with(BoundaryFilter::default());
+//! let mut detector = FaceDetector::new().with_filter(filter);
+//! ```
+
+use crate::quality::sharpness;
+use crate::{Face, ImageView};
+
+/// A test detected faces must pass.
+pub trait FaceFilterStage: Send {
+    /// Whether to keep `face`, detected in `image`.
+    fn accepts(&mut self, image: &ImageView, face: &Face) -> bool;
+}
+
+impl<F: FnMut(&ImageView, &Face) -> bool + Send> FaceFilterStage for F {
+    fn accepts(&mut self, image: &ImageView, face: &Face) -> bool {
+        self(image, face)
+    }
+}
+
+/// Rejects blurred faces, by the variance of the Laplacian of their luma, as
+/// [`FaceQuality::sharpness`](crate::FaceQuality::sharpness) measures it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlurFilter {
+    pub min_sharpness: f32,
+}
+
+impl Default for BlurFilter {
+    fn default() -> Self {
+        Self {
+            min_sharpness: 40.0,
+        }
+    }
+}
+
+impl FaceFilterStage for BlurFilter {
+    fn accepts(&mut self, image: &ImageView, face: &Face) -> bool {
+        sharpness(image, face) >= self.min_sharpness
+    }
+}
+
+/// Rejects face rectangles too narrow or too wide to be faces, by width over height.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AspectRatioFilter {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for AspectRatioFilter {
+    fn default() -> Self {
+        Self { min: 0.5, max: 1.5 }
+    }
+}
+
+impl FaceFilterStage for AspectRatioFilter {
+    fn accepts(&mut self, _: &ImageView, face: &Face) -> bool {
+        let rect = face.rectangle();
+        rect.h > 0.0 && (self.min..=self.max).contains(&(rect.w / rect.h))
+    }
+}
+
+/// Rejects faces cut off by the edges of the frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundaryFilter {
+    /// The fraction of the face rectangle allowed outside the frame.
+    pub max_outside: f32,
+}
+
+impl Default for BoundaryFilter {
+    fn default() -> Self {
+        Self { max_outside: 0.1 }
+    }
+}
+
+impl FaceFilterStage for BoundaryFilter {
+    fn accepts(&mut self, _: &ImageView, face: &Face) -> bool {
+        let rect = face.rectangle();
+        let (width, height) = face.detection_dimensions();
+        let inside_w = (rect.x + rect.w).min(width as f32) - rect.x.max(0.0);
+        let inside_h = (rect.y + rect.h).min(height as f32) - rect.y.max(0.0);
+        let area = rect.w * rect.h;
+        let inside = inside_w.max(0.0) * inside_h.max(0.0);
+        area > 0.0 && 1.0 - inside / area <= self.max_outside
+    }
+}
+
+/// Stages faces must all pass, in the order they were added, counting what each rejects.
+#[derive(Default)]
+pub struct FaceFilter {
+    stages: Vec<(Box<dyn FaceFilterStage>, u64)>,
+}
+
+impl FaceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, stage: impl FaceFilterStage + 'static) -> Self {
+        self.stages.push((Box::new(stage), 0));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Drops the faces of `image` that fail a stage.
+    pub fn apply(&mut self, image: &ImageView, faces: &mut Vec<Face>) {
+        faces.retain(|face| {
+            self.stages.iter_mut().all(|(stage, rejected)| {
+                let accepted = stage.accepts(image, face);
+                *rejected += u64::from(!accepted);
+                accepted
+            })
+        });
+    }
+
+    /// How many faces each stage rejected so far, in the order of the stages.
+    pub fn rejections(&self) -> Vec<u64> {
+        self.stages.iter().map(|&(_, rejected)| rejected).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+    use crate::FaceDetector;
+
+    #[test]
+    fn rejects_unusable_faces() {
+        // A checkerboard on the left, flat gray on the right.
+        let (width, height) = (80, 40);
+        let gray: Vec<u8> = (0..width * height)
+            .map(
+                |i| match (i % width < 40, (i % width / 2 + i / width / 2) % 2) {
+                    (true, 0) => 40,
+                    (true, _) => 220,
+                    (false, _) => 128,
+                },
+            )
+            .collect();
+        let image = ImageView::gray(&gray, width, height).unwrap();
+        let face = |x, w| {
+            let raw = RawFace {
+                score: 0.9,
+                x: x as f32,
+                y: 4.0,
+                w: w as f32,
+                h: 30.0,
+                lm: [x + 8; 10].map(|v| v as f32),
+            };
+            Face::from_raw_face(&raw, (width, height))
+        };
+        // Sharp, flat, stretched and cut off.
+        let mut faces = vec![face(4, 30), face(44, 30), face(0, 50), face(-10, 30)];
+        let mut filter = FaceFilter::new()
+            .with(BlurFilter::default())
+            .with(AspectRatioFilter::default())
+            .with(BoundaryFilter::default())
+            .with(|_: &ImageView, face: &Face| face.confidence() > 0.5);
+        filter.apply(&image, &mut faces);
+        assert_eq!(1, faces.len());
+        assert_eq!(4.0, faces[0].rectangle().x);
+        assert_eq!(vec![1, 1, 1, 0], filter.rejections());
+
+        // Attached to a detector.
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let rejecting = FaceFilter::new().with(|_: &ImageView, _: &Face| false);
+        let mut detector = FaceDetector::new().with_filter(rejecting);
+        let faces = detector.detect(image.as_raw(), 806, 605).unwrap();
+        assert!(faces.is_empty());
+        assert_eq!(vec![2], detector.filter().rejections());
+    }
+}
@@ -0,0 +1,167 @@
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use rusty_yunet_types::geometry::{
+    center_distance_matrix, iou_matrix, Bounded, CoordinateConvention, Origin, Rect, Units,
+};
+
+use crate::{Face, FaceLandmarks};
+
+/// How an image is fitted into an area of other proportions, such as a video frame into a
+/// window or canvas.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Fit {
+    /// Scaled to fill the area exactly, distorting it if the proportions differ.
+    #[default]
+    Stretch,
+    /// Scaled to fit within the area and centered, leaving bars along two edges, as a
+    /// letterboxed video.
+    Contain,
+    /// Scaled to cover the area and centered, cutting off two edges.
+    Cover,
+}
+
+/// Converts coordinates from one resolution to another, such as faces from the
+/// resolution they were detected at to that of the capture, or to a display area the frame
+/// is drawn into with a [`Fit`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateMapper {
+    from: (usize, usize),
+    to: (usize, usize),
+    scale: Vec2,
+    offset: Vec2,
+}
+
+impl CoordinateMapper {
+    /// Maps an image of dimensions `from` (width, height) into an area of dimensions `to`.
+    pub fn new(from: (usize, usize), to: (usize, usize), fit: Fit) -> Self {
+        let size = |(width, height): (usize, usize)| Vec2::new(width as f32, height as f32);
+        let (source, target) = (size(from), size(to));
+        let ratio = target / source.max(Vec2::ONE);
+        let scale = match fit {
+            Fit::Stretch => ratio,
+            Fit::Contain => Vec2::splat(ratio.min_element()),
+            Fit::Cover => Vec2::splat(ratio.max_element()),
+        };
+        Self {
+            from,
+            to,
+            scale,
+            offset: (target - source * scale) / 2.0,
+        }
+    }
+
+    /// Maps the frame `face` was detected in into an area of dimensions `to`.
+    pub fn for_face(face: &Face, to: (usize, usize), fit: Fit) -> Self {
+        Self::new(face.detection_dimensions(), to, fit)
+    }
+
+    /// Maps back from the area to the image, such as a click on a display to the frame.
+    pub fn inverse(&self) -> Self {
+        let scale = Vec2::ONE / self.scale;
+        Self {
+            from: self.to,
+            to: self.from,
+            scale,
+            offset: -self.offset * scale,
+        }
+    }
+
+    /// Maps through this mapper and then `next`, such as from detection to capture
+    /// resolution and on to a display. `next` is expected to map from the dimensions this
+    /// one maps to.
+    pub fn then(&self, next: &CoordinateMapper) -> Self {
+        Self {
+            from: self.from,
+            to: next.to,
+            scale: self.scale * next.scale,
+            offset: self.offset * next.scale + next.offset,
+        }
+    }
+
+    pub fn point(&self, p: Vec2) -> Vec2 {
+        p * self.scale + self.offset
+    }
+
+    pub fn rect(&self, rect: Rect) -> Rect {
+        Rect::new(
+            self.point(Vec2::new(rect.x, rect.y)),
+            rect.w * self.scale.x,
+            rect.h * self.scale.y,
+        )
+    }
+
+    pub fn landmarks(&self, landmarks: &FaceLandmarks) -> FaceLandmarks {
+        landmarks.map(|p| self.point(p))
+    }
+
+    /// The face with its rectangle and landmarks mapped, as if detected in an image of the
+    /// dimensions mapped to.
+    pub fn face(&self, face: &Face) -> Face {
+        face.mapped(|p| self.point(p), self.to)
+    }
+
+    /// Where the whole image lands in the area: all of it with [`Fit::Stretch`], inside the
+    /// bars with [`Fit::Contain`], and beyond its edges with [`Fit::Cover`].
+    pub fn viewport(&self) -> Rect {
+        self.rect(Rect::with_size(
+            0.0,
+            0.0,
+            self.from.0 as f32,
+            self.from.1 as f32,
+        ))
+    }
+
+    /// The dimensions (width, height) mapped from.
+    pub fn source_dimensions(&self) -> (usize, usize) {
+        self.from
+    }
+
+    /// The dimensions (width, height) mapped to.
+    pub fn target_dimensions(&self) -> (usize, usize) {
+        self.to
+    }
+}
+
+impl Bounded for Face {
+    fn bounds(&self) -> Rect {
+        self.rectangle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_between_resolutions() {
+        // A 4:3 frame letterboxed into a 16:9 display, with pillars on either side.
+        let display = CoordinateMapper::new((640, 480), (1280, 720), Fit::Contain);
+        assert_eq!(
+            Rect::with_size(160.0, 0.0, 960.0, 720.0),
+            display.viewport()
+        );
+        let rect = Rect::with_size(320.0, 240.0, 64.0, 48.0);
+        assert_eq!(
+            Rect::with_size(640.0, 360.0, 96.0, 72.0),
+            display.rect(rect)
+        );
+        assert_eq!(rect, display.inverse().rect(display.rect(rect)));
+
+        // Detected at half the capture resolution, then shown cropped to a square.
+        let capture = CoordinateMapper::new((320, 240), (640, 480), Fit::Stretch);
+        let square = CoordinateMapper::new((640, 480), (480, 480), Fit::Cover);
+        let chained = capture.then(&square);
+        assert_eq!(
+            Rect::with_size(-80.0, 0.0, 640.0, 480.0),
+            chained.viewport()
+        );
+        assert_eq!(
+            square.point(capture.point(Vec2::new(10.0, 20.0))),
+            chained.point(Vec2::new(10.0, 20.0))
+        );
+        assert_eq!((480, 480), chained.target_dimensions());
+    }
+}
@@ -0,0 +1,193 @@
+//! A gRPC service streaming frames in and their faces out over one call, for live video
+//! clients in any language, defined in `proto/rusty_yunet.proto` and served with tonic.
+//!
+//! ```no_run
+//! # use rusty_yunet::grpc::DetectionService;
+//! # use rusty_yunet::FaceDetectorPool;
+//! # async fn serve() -> Result<(), tonic::transport::Error> {
+//! let service = DetectionService::new(FaceDetectorPool::new(4));
+//! tonic::transport::Server::builder()
+//!     .add_service(service.into_server())
+//!     .serve("0.0.0.0:50051".parse().unwrap())
+//!     .await
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::{FaceDetectorPool, ImageView, YuNetError};
+
+/// The messages and the generated client and server of `proto/rusty_yunet.proto`.
+#[allow(clippy::clone_on_ref_ptr)]
+pub mod proto {
+    tonic::include_proto!("rusty_yunet");
+}
+
+use proto::face_detection_server::{FaceDetection, FaceDetectionServer};
+use proto::{Frame, FrameFaces};
+
+/// Largest frame accepted, in bytes, enough for 4K BGR frames.
+pub const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+/// Frames of a stream detected ahead of the client receiving their faces.
+const PENDING_FRAMES: usize = 4;
+
+impl From<&crate::Face> for proto::Face {
+    fn from(face: &crate::Face) -> Self {
+        let rect = face.rectangle();
+        Self {
+            confidence: face.confidence(),
+            rectangle: Some(proto::Rect {
+                x: rect.x,
+                y: rect.y,
+                w: rect.w,
+                h: rect.h,
+            }),
+            landmarks: face
+                .landmarks()
+                .as_array()
+                .iter()
+                .map(|p| proto::Point { x: p.x, y: p.y })
+                .collect(),
+        }
+    }
+}
+
+/// Detects the faces of each stream's frames in order, streams running at once on the
+/// detectors of a pool.
+pub struct DetectionService {
+    pool: Arc<FaceDetectorPool>,
+}
+
+impl DetectionService {
+    pub fn new(pool: FaceDetectorPool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// The service, for a tonic `Server`, accepting frames up to [`MAX_FRAME_BYTES`].
+    pub fn into_server(self) -> FaceDetectionServer<Self> {
+        FaceDetectionServer::new(self).max_decoding_message_size(MAX_FRAME_BYTES)
+    }
+}
+
+#[tonic::async_trait]
+impl FaceDetection for DetectionService {
+    type DetectStreamStream = ReceiverStream<Result<FrameFaces, Status>>;
+
+    async fn detect_stream(
+        &self,
+        request: Request<Streaming<Frame>>,
+    ) -> Result<Response<Self::DetectStreamStream>, Status> {
+        let mut frames = request.into_inner();
+        let pool = Arc::clone(&self.pool);
+        let (sender, receiver) = mpsc::channel(PENDING_FRAMES);
+        tokio::spawn(async move {
+            loop {
+                let faces = match frames.message().await {
+                    Ok(Some(frame)) => {
+                        let pool = Arc::clone(&pool);
+                        tokio::task::spawn_blocking(move || detect(&pool, &frame))
+                            .await
+                            .map_err(|e| Status::internal(e.to_string()))
+                    }
+                    Ok(None) => break,
+                    Err(status) => Err(status),
+                };
+                let failed = faces.is_err();
+                if sender.send(faces).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Detects the faces of a frame, reporting invalid frames in the reply rather than ending
+/// the stream.
+fn detect(pool: &FaceDetectorPool, frame: &Frame) -> FrameFaces {
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let channels = match frame.channels {
+        0 => 3,
+        channels => channels as usize,
+    };
+    let stride = match frame.stride {
+        0 => channels.saturating_mul(width),
+        stride => stride as usize,
+    };
+    let faces = match channels {
+        3 => ImageView::with_stride(&frame.data, width, height, stride),
+        1 => ImageView::gray_with_stride(&frame.data, width, height, stride),
+        _ => Err(YuNetError::InvalidImage),
+    }
+    .and_then(|image| pool.detect_image(&image));
+    match faces {
+        Ok(faces) => FrameFaces {
+            index: frame.index,
+            faces: faces.iter().map(proto::Face::from).collect(),
+            error: String::new(),
+        },
+        Err(e) => FrameFaces {
+            index: frame.index,
+            faces: Vec::new(),
+            error: e.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tokio_stream::StreamExt;
+
+    use super::proto::face_detection_client::FaceDetectionClient;
+    use super::*;
+
+    #[tokio::test]
+    async fn streams_faces_per_frame() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(DetectionService::new(FaceDetectorPool::new(2)).into_server())
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let frame = |index, height| Frame {
+            index,
+            width: 806,
+            height,
+            stride: 0,
+            channels: 0,
+            data: image.as_raw().clone(),
+        };
+        let mut client = FaceDetectionClient::connect(format!("http://{address}"))
+            .await
+            .unwrap();
+        let frames = tokio_stream::iter([frame(7, 605), frame(8, 606), frame(9, 605)]);
+        let replies: Vec<FrameFaces> = client
+            .detect_stream(frames)
+            .await
+            .unwrap()
+            .into_inner()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(
+            vec![7, 8, 9],
+            replies.iter().map(|r| r.index).collect::<Vec<_>>()
+        );
+        assert_eq!(2, replies[0].faces.len());
+        assert_eq!(5, replies[0].faces[0].landmarks.len());
+        assert_eq!(YuNetError::InvalidImage.to_string(), replies[1].error);
+        assert_eq!(2, replies[2].faces.len());
+    }
+}
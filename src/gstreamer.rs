@@ -0,0 +1,336 @@
+//! Detecting faces in the samples of a GStreamer appsink, and drawing them back into raw
+//! buffers, such as from a pad probe upstream of a sink.
+//!
+//! This module doesn't depend on the `gstreamer` crate, so that it works with whichever
+//! version of it an application uses: it takes the caps of a sample as a string and its
+//! buffer as mapped bytes. Set [`APPSINK_CAPS`] on the appsink, or put a `videoconvert`
+//! before it, so that negotiation settles on a format read here.
+//!
+//! ```ignore
+//! let appsink = gst_app::AppSink::builder()
+//!     .caps(&gst::Caps::from_str(rusty_yunet::gstreamer::APPSINK_CAPS)?)
+//!     .build();
+//! let mut converter = SampleConverter::new();
+//! let sample = appsink.pull_sample()?;
+//! let caps = VideoCaps::parse(&sample.caps().unwrap().to_string())?;
+//! let buffer = sample.buffer().unwrap().map_readable()?;
+//! let faces = detector.detect_image(&converter.view(&caps, &buffer)?)?;
+//! ```
+
+use crate::{Face, ImageView, YuNetError};
+
+/// Caps restricting an appsink to the raw formats [`SampleConverter`] reads.
+pub const APPSINK_CAPS: &str =
+    "video/x-raw, format=(string){ BGR, RGB, BGRx, BGRA, RGBx, RGBA, GRAY8, NV12, NV21, YUY2, I420 }";
+
+/// The raw video formats read from samples, by their GStreamer names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RawFormat {
+    /// `BGR`, read without copying.
+    Bgr,
+    Rgb,
+    Bgrx,
+    Bgra,
+    Rgbx,
+    Rgba,
+    /// `GRAY8`, read without copying.
+    Gray8,
+    Nv12,
+    Nv21,
+    /// `YUY2`, packed 4:2:2.
+    Yuy2,
+    I420,
+}
+
+impl RawFormat {
+    /// The format called `name` in caps.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "BGR" => RawFormat::Bgr,
+            "RGB" => RawFormat::Rgb,
+            "BGRx" => RawFormat::Bgrx,
+            "BGRA" => RawFormat::Bgra,
+            "RGBx" => RawFormat::Rgbx,
+            "RGBA" => RawFormat::Rgba,
+            "GRAY8" => RawFormat::Gray8,
+            "NV12" => RawFormat::Nv12,
+            "NV21" => RawFormat::Nv21,
+            "YUY2" => RawFormat::Yuy2,
+            "I420" => RawFormat::I420,
+            _ => return None,
+        })
+    }
+
+    /// Bytes per pixel and the offsets of red, green and blue, for packed RGB formats.
+    fn rgb_layout(self) -> Option<(usize, [usize; 3])> {
+        match self {
+            RawFormat::Bgr => Some((3, [2, 1, 0])),
+            RawFormat::Rgb => Some((3, [0, 1, 2])),
+            RawFormat::Bgrx | RawFormat::Bgra => Some((4, [2, 1, 0])),
+            RawFormat::Rgbx | RawFormat::Rgba => Some((4, [0, 1, 2])),
+            _ => None,
+        }
+    }
+}
+
+/// The format and dimensions of raw video, as negotiated on a pad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoCaps {
+    pub format: RawFormat,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl VideoCaps {
+    /// Reads caps as GStreamer prints them, such as
+    /// `video/x-raw, format=(string)NV12, width=(int)1280, height=(int)720`. Fails with
+    /// [`YuNetError::Decode`] if they aren't fixed raw video of a format listed in
+    /// [`RawFormat`].
+    pub fn parse(caps: &str) -> Result<Self, YuNetError> {
+        let unsupported = |why: &str| YuNetError::Decode(format!("{why} in caps `{caps}`"));
+        let mut fields = caps.split(',').map(str::trim);
+        if fields.next() != Some("video/x-raw") {
+            return Err(unsupported("not raw video"));
+        }
+        let (mut format, mut width, mut height) = (None, None, None);
+        for field in fields {
+            let Some((name, value)) = field.split_once('=') else {
+                continue;
+            };
+            // Drop the type, as in `(int)640`.
+            let value = value.trim();
+            let value = value
+                .split_once(')')
+                .map_or(value, |(_, value)| value)
+                .trim();
+            match name.trim() {
+                "format" => {
+                    format = Some(
+                        RawFormat::from_name(value)
+                            .ok_or_else(|| unsupported("unsupported format"))?,
+                    )
+                }
+                "width" => width = value.parse().ok(),
+                "height" => height = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Ok(VideoCaps {
+            format: format.ok_or_else(|| unsupported("no fixed format"))?,
+            width: width.ok_or_else(|| unsupported("no fixed width"))?,
+            height: height.ok_or_else(|| unsupported("no fixed height"))?,
+        })
+    }
+
+    /// The planes of a buffer as GStreamer lays them out by default, each as its offset,
+    /// stride, bytes of pixels per row, and rows. Rows are padded to four bytes.
+    fn planes(&self) -> Vec<(usize, usize, usize, usize)> {
+        let (w, h) = (self.width, self.height);
+        let round4 = |n: usize| n.next_multiple_of(4);
+        let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+        match self.format {
+            RawFormat::Gray8 => vec![(0, round4(w), w, h)],
+            RawFormat::Yuy2 => vec![(0, round4(2 * w), 4 * cw, h)],
+            RawFormat::Nv12 | RawFormat::Nv21 => {
+                let stride = round4(w);
+                vec![(0, stride, w, h), (stride * 2 * ch, stride, 2 * cw, ch)]
+            }
+            RawFormat::I420 => {
+                let (stride, chroma_stride) = (round4(w), round4(cw));
+                let u = stride * 2 * ch;
+                let v = u + chroma_stride * ch;
+                vec![
+                    (0, stride, w, h),
+                    (u, chroma_stride, cw, ch),
+                    (v, chroma_stride, cw, ch),
+                ]
+            }
+            format => {
+                let (bytes, _) = format.rgb_layout().expect("the remaining formats are RGB");
+                vec![(0, round4(bytes * w), bytes * w, h)]
+            }
+        }
+    }
+
+    /// Fails with [`YuNetError::InvalidImage`] if the frame is empty or `buffer` too short
+    /// to hold it.
+    fn check(&self, buffer: &[u8]) -> Result<(), YuNetError> {
+        let end = self
+            .planes()
+            .iter()
+            .map(|&(offset, stride, bytes, rows)| offset + stride * rows.saturating_sub(1) + bytes)
+            .max()
+            .unwrap_or_default();
+        if self.width == 0 || self.height == 0 || buffer.len() < end {
+            return Err(YuNetError::InvalidImage);
+        }
+        Ok(())
+    }
+}
+
+/// Views the buffers of samples as images for the detector, converting those of formats it
+/// doesn't read into BGR, in a buffer reused from one sample to the next.
+#[derive(Debug, Default)]
+pub struct SampleConverter {
+    bgr: Vec<u8>,
+    packed: Vec<u8>,
+}
+
+impl SampleConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The frame in `buffer`, of the given caps. Fails with [`YuNetError::InvalidImage`] if
+    /// `buffer` is too short for them.
+    pub fn view<'a>(
+        &'a mut self,
+        caps: &VideoCaps,
+        buffer: &'a [u8],
+    ) -> Result<ImageView<'a>, YuNetError> {
+        caps.check(buffer)?;
+        let (width, height) = (caps.width, caps.height);
+        let planes = caps.planes();
+        let yuv = match caps.format {
+            RawFormat::Bgr => return ImageView::with_stride(buffer, width, height, planes[0].1),
+            RawFormat::Gray8 => {
+                return ImageView::gray_with_stride(buffer, width, height, planes[0].1)
+            }
+            RawFormat::Nv12 => crate::convert::YuvFormat::Nv12,
+            RawFormat::Nv21 => crate::convert::YuvFormat::Nv21,
+            RawFormat::Yuy2 => crate::convert::YuvFormat::Yuyv,
+            RawFormat::I420 => crate::convert::YuvFormat::I420,
+            format => {
+                let (bytes, [r, g, b]) =
+                    format.rgb_layout().expect("the remaining formats are RGB");
+                let stride = planes[0].1;
+                self.bgr.clear();
+                for row in buffer.chunks(stride).take(height) {
+                    self.bgr.extend(
+                        row[..bytes * width]
+                            .chunks_exact(bytes)
+                            .flat_map(|px| [px[b], px[g], px[r]]),
+                    );
+                }
+                return ImageView::new(&self.bgr, width, height);
+            }
+        };
+        // The converter takes planes without padding.
+        self.packed.clear();
+        for (offset, stride, bytes, rows) in planes {
+            for row in 0..rows {
+                let start = offset + row * stride;
+                self.packed.extend_from_slice(&buffer[start..start + bytes]);
+            }
+        }
+        ImageView::from_yuv(yuv, &self.packed, width, height, &mut self.bgr)
+    }
+}
+
+/// Draws the outline of each face into a writable buffer of the given caps, in `color` (red,
+/// green, blue), `thickness` pixels wide. YUV and gray frames are drawn into their luma only,
+/// in the brightness of `color`. Faces must have been detected in frames of the same
+/// dimensions.
+///
+/// Fails with [`YuNetError::InvalidImage`] if `buffer` is too short for the caps.
+pub fn draw_faces(
+    caps: &VideoCaps,
+    buffer: &mut [u8],
+    faces: &[Face],
+    color: [u8; 3],
+    thickness: usize,
+) -> Result<(), YuNetError> {
+    caps.check(buffer)?;
+    let (offset, stride, _, _) = caps.planes()[0];
+    let [r, g, b] = color.map(f32::from);
+    let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+    let mut set = |x: usize, y: usize| {
+        let row = offset + y * stride;
+        match caps.format.rgb_layout() {
+            Some((bytes, channels)) => {
+                for (channel, value) in channels.into_iter().zip(color) {
+                    buffer[row + bytes * x + channel] = value;
+                }
+            }
+            None => match caps.format {
+                RawFormat::Gray8 => buffer[row + x] = luma.round() as u8,
+                // Limited range, as the converters read it.
+                RawFormat::Yuy2 => buffer[row + 2 * x] = (16.0 + luma * 219.0 / 255.0) as u8,
+                _ => buffer[row + x] = (16.0 + luma * 219.0 / 255.0) as u8,
+            },
+        }
+    };
+
+    let (width, height) = (caps.width as f32, caps.height as f32);
+    for face in faces {
+        let rect = face.rectangle();
+        let clamp = |v: f32, max: f32| v.round().clamp(0.0, max - 1.0) as usize;
+        let (x0, y0) = (clamp(rect.x, width), clamp(rect.y, height));
+        let (x1, y1) = (
+            clamp(rect.x + rect.w, width),
+            clamp(rect.y + rect.h, height),
+        );
+        for t in 0..thickness {
+            for x in x0..=x1 {
+                set(x, (y0 + t).min(y1));
+                set(x, y1.saturating_sub(t).max(y0));
+            }
+            for y in y0..=y1 {
+                set((x0 + t).min(x1), y);
+                set(x1.saturating_sub(t).max(x0), y);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::face_in;
+    use crate::Rect;
+
+    #[test]
+    fn reads_padded_samples() {
+        let caps = VideoCaps::parse(
+            "video/x-raw, format=(string)RGBx, width=(int)3, height=(int)2, framerate=(fraction)30/1",
+        )
+        .unwrap();
+        assert_eq!(
+            (RawFormat::Rgbx, 3, 2),
+            (caps.format, caps.width, caps.height)
+        );
+        let rgbx: Vec<u8> = (0..24).collect();
+        let mut converter = SampleConverter::new();
+        let view = converter.view(&caps, &rgbx).unwrap();
+        assert_eq!(&[2, 1, 0, 6, 5, 4], &view.data()[..6]);
+
+        // Rows of 3 gray pixels are padded to 4 bytes; the view keeps the stride.
+        let caps = VideoCaps::parse("video/x-raw, format=GRAY8, width=3, height=2").unwrap();
+        let gray = [10, 20, 30, 0, 40, 50, 60, 0];
+        let view = converter.view(&caps, &gray).unwrap();
+        assert_eq!((4, 1), (view.stride(), view.channels()));
+
+        // Each plane of an odd-sized I420 frame is padded separately.
+        let caps = VideoCaps::parse("video/x-raw, format=I420, width=3, height=3").unwrap();
+        let i420 = vec![128; 4 * 4 + 2 * 4 * 2];
+        let view = converter.view(&caps, &i420).unwrap();
+        assert_eq!((3, 3), view.dimensions());
+        assert!(matches!(
+            converter.view(&caps, &i420[..20]),
+            Err(YuNetError::InvalidImage)
+        ));
+
+        assert!(
+            VideoCaps::parse("video/x-raw, format=(string)P010_10LE, width=4, height=4").is_err()
+        );
+        assert!(VideoCaps::parse("audio/x-raw, format=S16LE").is_err());
+
+        let caps = VideoCaps::parse("video/x-raw, format=BGRx, width=8, height=8").unwrap();
+        let mut bgrx = vec![0; 8 * 8 * 4];
+        let face = face_in(Rect::with_size(2.0, 2.0, 4.0, 4.0), 0.9, (8, 8));
+        draw_faces(&caps, &mut bgrx, &[face], [255, 0, 0], 1).unwrap();
+        assert_eq!(&[0, 0, 255, 0], &bgrx[(2 * 8 + 2) * 4..(2 * 8 + 3) * 4]);
+        assert_eq!(&[0, 0, 0, 0], &bgrx[(4 * 8 + 4) * 4..(4 * 8 + 5) * 4]);
+    }
+}
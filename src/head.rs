@@ -0,0 +1,163 @@
+//! Estimating where the viewer's head is in front of the camera, for head-tracked parallax
+//! on ordinary displays, from the eyes of the main face alone.
+//!
+//! The distance follows from how far apart the eyes appear, assuming an average
+//! interocular distance, so it is only as good as that assumption: children and people
+//! turning their heads appear further away than they are.
+
+use glam::{Vec2, Vec3};
+
+use crate::primary::{PrimaryPolicy, PrimarySubject};
+use crate::Face;
+
+/// Tuning knobs for a [`HeadTracker`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct HeadTrackerConfig {
+    /// Horizontal field of view of the camera, in degrees.
+    pub horizontal_fov: f32,
+    /// Distance between the pupils, in meters. 63 mm is the adult average.
+    pub interocular_distance: f32,
+    /// Weight (0..1) of each new estimate in the reported position. Lower values steady the
+    /// position at the cost of lag; 1 disables smoothing.
+    pub smoothing: f32,
+    /// Faces less confident than this are ignored.
+    pub min_confidence: f32,
+    /// Whose head is tracked when several people are in view.
+    pub primary: PrimaryPolicy,
+}
+
+impl Default for HeadTrackerConfig {
+    fn default() -> Self {
+        Self {
+            horizontal_fov: 70.0,
+            interocular_distance: 0.063,
+            smoothing: 0.5,
+            min_confidence: 0.7,
+            primary: PrimaryPolicy::default(),
+        }
+    }
+}
+
+/// Where the head is relative to the camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadPosition {
+    /// The point between the eyes, in meters: x to the right of the image, y up and z away
+    /// from the camera, towards the viewer.
+    pub position: Vec3,
+    /// Apparent distance between the eyes, in pixels of the frame.
+    pub interocular_pixels: f32,
+}
+
+impl HeadPosition {
+    /// Estimates the head position of `face` as seen by a camera with a horizontal field of
+    /// view of `horizontal_fov` degrees and square pixels. `None` if the eyes coincide.
+    pub fn estimate(face: &Face, horizontal_fov: f32, interocular_distance: f32) -> Option<Self> {
+        let (width, height) = face.detection_dimensions();
+        let landmarks = face.landmarks();
+        let interocular_pixels = landmarks.right_eye.distance(landmarks.left_eye);
+        if interocular_pixels <= f32::EPSILON {
+            return None;
+        }
+        let focal = width as f32 / 2.0 / (horizontal_fov.to_radians() / 2.0).tan();
+        let z = focal * interocular_distance / interocular_pixels;
+        let midpoint = (landmarks.right_eye + landmarks.left_eye) / 2.0;
+        let offset = midpoint - Vec2::new(width as f32, height as f32) / 2.0;
+        Some(Self {
+            position: Vec3::new(offset.x * z / focal, -offset.y * z / focal, z),
+            interocular_pixels,
+        })
+    }
+}
+
+/// Turns the faces of consecutive frames into a smoothed stream of head positions of the
+/// main viewer, chosen by [`HeadTrackerConfig::primary`].
+#[derive(Debug, Clone)]
+pub struct HeadTracker {
+    config: HeadTrackerConfig,
+    subject: PrimarySubject,
+    position: Option<HeadPosition>,
+}
+
+impl HeadTracker {
+    pub fn new(config: HeadTrackerConfig) -> Self {
+        Self {
+            subject: PrimarySubject::new(config.primary),
+            config,
+            position: None,
+        }
+    }
+
+    /// Feeds the faces of the next frame, returning the updated head position, or `None`
+    /// once nobody is in view. Smoothing restarts when someone comes back into view.
+    pub fn update(&mut self, faces: &[Face]) -> Option<HeadPosition> {
+        let confident: Vec<Face> = faces
+            .iter()
+            .filter(|face| face.confidence() >= self.config.min_confidence)
+            .cloned()
+            .collect();
+        let estimate = self.subject.update(&confident).and_then(|primary| {
+            HeadPosition::estimate(
+                &confident[primary],
+                self.config.horizontal_fov,
+                self.config.interocular_distance,
+            )
+        });
+        let weight = self.config.smoothing.clamp(0.0, 1.0);
+        self.position = match (self.position, estimate) {
+            (Some(previous), Some(estimate)) => Some(HeadPosition {
+                position: previous.position.lerp(estimate.position, weight),
+                interocular_pixels: estimate.interocular_pixels,
+            }),
+            (_, estimate) => estimate,
+        };
+        self.position
+    }
+
+    /// The head position as of the last update.
+    pub fn position(&self) -> Option<HeadPosition> {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(size: i32, eyes: [i32; 4]) -> Face {
+        let [x, y] = [eyes[0] - size / 4, eyes[1] - size / 4].map(|v| v as f32);
+        let size = size as f32;
+        let eye = |i: usize| Vec2::new(eyes[i] as f32, eyes[i + 1] as f32);
+        let landmarks = [eye(0), eye(2), Vec2::ZERO, Vec2::ZERO, Vec2::ZERO];
+        test_face(0.9, [x, y, size, size], (640, 480), Some(landmarks))
+    }
+
+    #[test]
+    fn estimates_head_position() {
+        // At 90 degrees, the focal length is half the width: 320 pixels. Eyes 64 pixels
+        // apart are then 315 mm away.
+        let centered = face(120, [288, 240, 352, 240]);
+        let head = HeadPosition::estimate(&centered, 90.0, 0.063).unwrap();
+        assert!(head.position.abs_diff_eq(Vec3::new(0.0, 0.0, 0.315), 1e-4));
+
+        // Up and to the left of the image center, and twice as far.
+        let far = face(60, [128, 120, 160, 120]);
+        let head = HeadPosition::estimate(&far, 90.0, 0.063).unwrap();
+        let z = 320.0 * 0.063 / 32.0;
+        let expected = Vec3::new(-176.0 * z / 320.0, 120.0 * z / 320.0, z);
+        assert!(head.position.abs_diff_eq(expected, 1e-4));
+
+        let mut tracker = HeadTracker::new(HeadTrackerConfig {
+            horizontal_fov: 90.0,
+            ..HeadTrackerConfig::default()
+        });
+        let first = tracker.update(&[far.clone(), centered.clone()]).unwrap();
+        assert!(first.position.abs_diff_eq(Vec3::new(0.0, 0.0, 0.315), 1e-4));
+        let second = tracker.update(&[far]).unwrap();
+        assert!(second
+            .position
+            .abs_diff_eq(first.position.lerp(expected, 0.5), 1e-4));
+        assert!(tracker.update(&[]).is_none());
+    }
+}
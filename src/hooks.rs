@@ -0,0 +1,163 @@
+//! Extending detection with per-face processing of your own, such as quality checks, calls
+//! to other models or enrichment, whose outputs travel with each face as annotations.
+//!
+//! ```no_run
+//! # use rusty_yunet::hooks::{Annotation, HookChain};
+//! # use rusty_yunet::{FaceDetector, Pipeline, TrackerConfig};
+//! let hooks = HookChain::new().with_fn("frontal", |_frame, face| {
+//!     let eyes = face.landmarks().left_eye - face.landmarks().right_eye;
+//!     Some(Annotation::Flag(eyes.y.abs() < eyes.x.abs() * 0.2))
+//! });
+//! let mut pipeline =
+//!     Pipeline::new(FaceDetector::new(), TrackerConfig::default()).with_hooks(hooks);
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Face, ImageView};
+
+/// What a hook attaches to a face.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Annotation {
+    Flag(bool),
+    Number(f64),
+    Text(String),
+    /// Such as an embedding computed by another model.
+    Vector(Vec<f32>),
+}
+
+/// Processing run on every detected face, with access to the frame it was detected in.
+pub trait FaceHook: Send {
+    /// The name the output is attached under; see [`Face::annotation`].
+    fn name(&self) -> &str;
+
+    /// Looks at one face of `frame`, which carries the annotations of the hooks before this
+    /// one, returning what to attach to it, if anything.
+    fn annotate(&mut self, frame: &ImageView, face: &Face) -> Option<Annotation>;
+}
+
+struct FnHook<F> {
+    name: String,
+    f: F,
+}
+
+impl<F> FaceHook for FnHook<F>
+where
+    F: FnMut(&ImageView, &Face) -> Option<Annotation> + Send,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn annotate(&mut self, frame: &ImageView, face: &Face) -> Option<Annotation> {
+        (self.f)(frame, face)
+    }
+}
+
+/// Hooks run in the order they were added, each on every face of a frame.
+#[derive(Default)]
+pub struct HookChain {
+    hooks: Vec<Box<dyn FaceHook>>,
+}
+
+impl HookChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, hook: impl FaceHook + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a closure as a hook called `name`.
+    pub fn with_fn(
+        self,
+        name: impl Into<String>,
+        f: impl FnMut(&ImageView, &Face) -> Option<Annotation> + Send + 'static,
+    ) -> Self {
+        self.with(FnHook {
+            name: name.into(),
+            f,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Runs every hook on every face of `frame`, attaching their outputs. A hook's output
+    /// replaces any earlier one of the same name.
+    pub fn run(&mut self, frame: &ImageView, faces: &mut [Face]) {
+        for hook in &mut self.hooks {
+            for face in faces.iter_mut() {
+                if let Some(annotation) = hook.annotate(frame, face) {
+                    face.annotate(hook.name(), annotation);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    struct Brightness;
+
+    impl FaceHook for Brightness {
+        fn name(&self) -> &str {
+            "brightness"
+        }
+
+        fn annotate(&mut self, frame: &ImageView, face: &Face) -> Option<Annotation> {
+            let rect = face.rectangle();
+            let region = frame.crop(rect.x as usize, rect.y as usize, rect.w as usize, 1)?;
+            let row = &region.data()[..3 * region.width()];
+            Some(Annotation::Number(
+                row.iter().map(|&v| v as f64).sum::<f64>() / row.len() as f64,
+            ))
+        }
+    }
+
+    #[test]
+    fn annotates_faces_in_order() {
+        let mut bytes = vec![0; 3 * 8 * 4];
+        bytes[3 * 8 * 2..].fill(200);
+        let frame = ImageView::new(&bytes, 8, 4).unwrap();
+        let face = |y| {
+            let raw = RawFace {
+                score: 0.9,
+                x: 2.0,
+                y: y as f32,
+                w: 4.0,
+                h: 2.0,
+                lm: [0.0; 10],
+            };
+            Face::from_raw_face(&raw, (8, 4))
+        };
+        let mut faces = vec![face(0), face(2)];
+        let mut hooks = HookChain::new()
+            .with(Brightness)
+            .with_fn("bright", |_, face| match face.annotation("brightness")? {
+                Annotation::Number(brightness) => Some(Annotation::Flag(*brightness > 100.0)),
+                _ => None,
+            })
+            .with_fn("skipped", |_, _| None);
+        hooks.run(&frame, &mut faces);
+
+        assert_eq!(
+            Some(&Annotation::Number(0.0)),
+            faces[0].annotation("brightness")
+        );
+        assert_eq!(
+            Some(&Annotation::Flag(false)),
+            faces[0].annotation("bright")
+        );
+        assert_eq!(Some(&Annotation::Flag(true)), faces[1].annotation("bright"));
+        assert_eq!(2, faces[1].annotations().len());
+    }
+}
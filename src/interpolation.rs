@@ -0,0 +1,113 @@
+//! Smoothing overlays when detection runs at a lower rate than rendering, such as detecting
+//! at 10 Hz and rendering at 60 Hz, by blending each face between its last two detections.
+//!
+//! Blending between known positions rather than extrapolating keeps faces from overshooting,
+//! at the cost of showing them one detection interval late.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use rusty_yunet::{FaceInterpolator, TrackerConfig};
+//! # let (detected, render_times) = (vec![], vec![Duration::ZERO]);
+//! let mut interpolator = FaceInterpolator::new(TrackerConfig::default());
+//! interpolator.push(&detected, Duration::from_millis(100));
+//! for time in render_times {
+//!     for (id, face) in interpolator.at(time) {
+//!         // Draw `face`.
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{Face, Tracker, TrackerConfig};
+
+/// Blends the faces of the last two detections for any render time in between.
+#[derive(Debug, Clone, Default)]
+pub struct FaceInterpolator {
+    tracker: Tracker,
+    previous: HashMap<u64, Face>,
+    previous_time: Option<Duration>,
+    latest: Vec<(u64, Face)>,
+    latest_time: Option<Duration>,
+}
+
+impl FaceInterpolator {
+    /// Associates faces across detections with a [`Tracker`] of this configuration.
+    pub fn new(config: TrackerConfig) -> Self {
+        Self {
+            tracker: Tracker::new(config),
+            ..Self::default()
+        }
+    }
+
+    /// Feeds the faces of a detection of the frame captured at `timestamp`, which must not
+    /// decrease from one call to the next.
+    pub fn push(&mut self, faces: &[Face], timestamp: Duration) {
+        let ids = self.tracker.update_at(faces, Some(timestamp));
+        self.previous = std::mem::take(&mut self.latest).into_iter().collect();
+        self.previous_time = self.latest_time;
+        self.latest = ids.into_iter().zip(faces.iter().cloned()).collect();
+        self.latest_time = Some(timestamp);
+    }
+
+    /// The faces of the latest detection with their track IDs, each blended from its
+    /// previous detection towards the latest as `time` advances from the latest timestamp
+    /// by one detection interval. Faces new in the latest detection are returned as they
+    /// are; faces gone from it are not returned.
+    pub fn at(&self, time: Duration) -> Vec<(u64, Face)> {
+        let t = match (self.previous_time, self.latest_time) {
+            (Some(previous), Some(latest)) if latest > previous => {
+                let interval = (latest - previous).as_secs_f32();
+                (time.saturating_sub(latest).as_secs_f32() / interval).min(1.0)
+            }
+            _ => 1.0,
+        };
+        self.latest
+            .iter()
+            .map(|(id, face)| match self.previous.get(id) {
+                Some(previous) => (*id, previous.lerp(face, t)),
+                None => (*id, face.clone()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+    use glam::Vec2;
+
+    fn face(x: i32) -> Face {
+        let x = x as f32;
+        test_face(
+            0.9,
+            [x, 10.0, 20.0, 20.0],
+            (200, 100),
+            Some([Vec2::splat(x); 5]),
+        )
+    }
+
+    #[test]
+    fn blends_between_detections() {
+        let ms = Duration::from_millis;
+        let mut interpolator = FaceInterpolator::default();
+        interpolator.push(&[face(0)], ms(0));
+        assert_eq!(0.0, interpolator.at(ms(50))[0].1.rectangle().x);
+        // A second person appears as the first moves.
+        interpolator.push(&[face(10), face(100)], ms(100));
+        let x = |time| -> Vec<f32> {
+            interpolator
+                .at(ms(time))
+                .iter()
+                .map(|(_, face)| face.rectangle().x)
+                .collect()
+        };
+        assert_eq!(vec![0.0, 100.0], x(100));
+        assert_eq!(vec![5.0, 100.0], x(150));
+        assert_eq!(vec![10.0, 100.0], x(250));
+        let blended = &interpolator.at(ms(175))[0].1;
+        assert_eq!(7.5, blended.landmarks().nose.x);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::YuNetError;
+
+/// A borrowed BGR or 8-bit grayscale image, optionally with padding at the end of each row.
+///
+/// Grayscale images, such as those of IR presence cameras, are read as they are; the network
+/// sees each gray value in all three of its color channels.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a> {
+    data: &'a [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    channels: usize,
+}
+
+impl<'a> ImageView<'a> {
+    /// A tightly packed BGR image of the given dimensions.
+    pub fn new(data: &'a [u8], width: usize, height: usize) -> Result<Self, YuNetError> {
+        Self::with_stride(data, width, height, 3 * width)
+    }
+
+    /// A BGR image whose rows start `stride` bytes apart.
+    pub fn with_stride(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+    ) -> Result<Self, YuNetError> {
+        Self::with_layout(data, width, height, stride, 3)
+    }
+
+    /// A tightly packed 8-bit grayscale image of the given dimensions.
+    pub fn gray(data: &'a [u8], width: usize, height: usize) -> Result<Self, YuNetError> {
+        Self::gray_with_stride(data, width, height, width)
+    }
+
+    /// An 8-bit grayscale image whose rows start `stride` bytes apart.
+    pub fn gray_with_stride(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+    ) -> Result<Self, YuNetError> {
+        Self::with_layout(data, width, height, stride, 1)
+    }
+
+    /// A tightly packed image of `channels` bytes per pixel, 3 or 1.
+    pub(crate) fn packed(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        channels: usize,
+    ) -> Result<Self, YuNetError> {
+        Self::with_layout(data, width, height, channels * width, channels)
+    }
+
+    fn with_layout(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+        channels: usize,
+    ) -> Result<Self, YuNetError> {
+        let row = channels
+            .checked_mul(width)
+            .ok_or(YuNetError::InvalidImage)?;
+        if width == 0 || height == 0 || stride < row {
+            return Err(YuNetError::InvalidImage);
+        }
+        let required = stride
+            .checked_mul(height - 1)
+            .and_then(|rows| rows.checked_add(row))
+            .ok_or(YuNetError::InvalidImage)?;
+        if data.len() < required {
+            return Err(YuNetError::InvalidImage);
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+            stride,
+            channels,
+        })
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Distance in bytes between the starts of consecutive rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Bytes per pixel: 3 for BGR, 1 for grayscale.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// A view of a region of this image, sharing its pixel data. The region is clamped to
+    /// the image bounds; `None` if nothing of it remains.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Option<Self> {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        if width == 0 || height == 0 {
+            return None;
+        }
+        Some(Self {
+            data: &self.data[y * self.stride + self.channels * x..],
+            width,
+            height,
+            stride: self.stride,
+            channels: self.channels,
+        })
+    }
+}
+
+/// Memory layout of the BGR frames held by a [`FrameBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLayout {
+    pub width: usize,
+    pub height: usize,
+    /// Distance in bytes between the starts of consecutive rows.
+    pub stride: usize,
+}
+
+impl FrameLayout {
+    /// Tightly packed rows of `width` BGR pixels.
+    pub fn packed(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            stride: 3 * width,
+        }
+    }
+}
+
+/// A long-lived frame buffer owned by the caller, such as a memory-mapped camera capture
+/// buffer, which the detector reads in place without copying.
+#[derive(Debug)]
+pub struct FrameBuffer {
+    ptr: *const u8,
+    len: usize,
+    layout: FrameLayout,
+}
+
+impl FrameBuffer {
+    /// Registers `len` bytes at `ptr` as a frame of the given layout.
+    ///
+    /// The buffer is only read during calls that take it, such as
+    /// [`FaceDetector::detect_frame`](crate::FaceDetector::detect_frame); the caller is free
+    /// to refill it between them.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads of `len` bytes for as long as the `FrameBuffer` exists,
+    /// and the memory must not be written to, by this process or by a device such as a DMA
+    /// engine, for the whole duration of any call that reads the frame.
+    ///
+    /// Fails if `ptr` is null, `len` exceeds `isize::MAX` or the layout doesn't fit in `len`
+    /// bytes.
+    pub unsafe fn new(ptr: *const u8, len: usize, layout: FrameLayout) -> Result<Self, YuNetError> {
+        if ptr.is_null() || len > isize::MAX as usize {
+            return Err(YuNetError::InvalidImage);
+        }
+        let frame = Self { ptr, len, layout };
+        // Validates that the layout fits within the buffer.
+        ImageView::with_stride(frame.bytes(), layout.width, layout.height, layout.stride)?;
+        Ok(frame)
+    }
+
+    pub fn layout(&self) -> FrameLayout {
+        self.layout
+    }
+
+    /// The current contents of the buffer. The view must not outlive the call reading the
+    /// frame, which is why it isn't public: callers may write to the buffer between calls.
+    pub(crate) fn view(&self) -> ImageView<'_> {
+        let FrameLayout {
+            width,
+            height,
+            stride,
+        } = self.layout;
+        ImageView::with_stride(self.bytes(), width, height, stride)
+            .expect("layout was validated on construction")
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: guaranteed by the contract of `FrameBuffer::new`.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
@@ -0,0 +1,434 @@
+//! Frames handed over through shared memory from a separate capture process, so that the
+//! process owning a camera doesn't have to be the one detecting faces, nor serialize every
+//! frame through a pipe to it.
+//!
+//! The capture process writes frames into a ring of slots in a memory-mapped file, ideally
+//! under `/dev/shm` on Linux, with [`FrameRingWriter`]; the detecting process reads them
+//! with [`FrameRingReader`]. A reader that falls behind skips the frames overwritten in the
+//! meantime rather than slowing down the writer.
+//!
+//! Writers in other languages need to follow the layout, in native byte order:
+//!
+//! - A 64 byte header: the magic `YUNETRNG`, the version 1 as a `u32`, the number of slots
+//!   as a `u32`, the size of a slot's pixel data as a `u64` and the number of frames written
+//!   so far as an atomic `u64`, stored after the frame's slot is complete.
+//! - Then each slot: a 64 byte header of a sequence lock as an atomic `u64`, odd while the
+//!   slot is being written, the index of the frame, its timestamp in microseconds, its
+//!   width, height, stride and channels as `u32`s, then the pixel data, padded to a multiple
+//!   of 64 bytes. Frame `n` goes in slot `n % slots`.
+//!
+//! ```no_run
+//! # use rusty_yunet::ipc::FrameRingReader;
+//! # use rusty_yunet::FaceDetector;
+//! let mut ring = FrameRingReader::open("/dev/shm/camera0")?;
+//! let mut detector = FaceDetector::new();
+//! loop {
+//!     if let Some(frame) = ring.wait_next(std::time::Duration::from_secs(1))? {
+//!         let faces = detector.detect_image(&frame.image).unwrap();
+//!         println!("frame {}: {} faces", frame.index, faces.len());
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::ImageView;
+
+const MAGIC: &[u8; 8] = b"YUNETRNG";
+const VERSION: u32 = 1;
+const HEADER: usize = 64;
+const SLOT_HEADER: usize = 64;
+/// How often [`FrameRingReader::wait_next`] looks for a new frame.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// Offsets in the header.
+const SLOTS: usize = 12;
+const SLOT_SIZE: usize = 16;
+const WRITTEN: usize = 24;
+
+// Offsets in a slot header.
+const LOCK: usize = 0;
+const INDEX: usize = 8;
+const TIMESTAMP: usize = 16;
+const WIDTH: usize = 24;
+const HEIGHT: usize = 28;
+const STRIDE: usize = 32;
+const CHANNELS: usize = 36;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// The atomic `u64` at `offset` of a mapping, which must be a multiple of 8.
+///
+/// # Safety
+///
+/// `offset + 8` must lie within the mapping starting at `base`, which is page aligned.
+unsafe fn atomic<'a>(base: *const u8, offset: usize) -> &'a AtomicU64 {
+    &*(base.add(offset) as *const AtomicU64)
+}
+
+/// Copies `bytes` to `offset` of a mapping.
+///
+/// # Safety
+///
+/// `offset + bytes.len()` must lie within the mapping starting at `base`.
+unsafe fn put(base: *mut u8, offset: usize, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), base.add(offset), bytes.len());
+}
+
+/// The capture side of a ring, writing frames for a [`FrameRingReader`] in another
+/// process.
+#[derive(Debug)]
+pub struct FrameRingWriter {
+    map: MmapMut,
+    slots: usize,
+    slot_size: usize,
+    written: u64,
+}
+
+impl FrameRingWriter {
+    /// Creates, or replaces, the ring file at `path` with `slots` slots for frames of up to
+    /// `max_frame_bytes` of pixels each.
+    pub fn create(
+        path: impl AsRef<Path>,
+        slots: usize,
+        max_frame_bytes: usize,
+    ) -> io::Result<Self> {
+        if slots == 0 || slots > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a frame ring needs 1 to 2^32 - 1 slots",
+            ));
+        }
+        let slot_size = max_frame_bytes.next_multiple_of(64);
+        let len = slots
+            .checked_mul(SLOT_HEADER + slot_size)
+            .and_then(|slots| slots.checked_add(HEADER))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame ring too large"))?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(len as u64)?;
+        // SAFETY: the file was just truncated for this ring; other processes are expected
+        // to only map it through `FrameRingReader`, which reads it as racily written.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        map[SLOTS..SLOTS + 4].copy_from_slice(&(slots as u32).to_ne_bytes());
+        map[SLOT_SIZE..SLOT_SIZE + 8].copy_from_slice(&(slot_size as u64).to_ne_bytes());
+        map[8..12].copy_from_slice(&VERSION.to_ne_bytes());
+        map[..8].copy_from_slice(MAGIC);
+        map.flush_async()?;
+        Ok(Self {
+            map,
+            slots,
+            slot_size,
+            written: 0,
+        })
+    }
+
+    /// The largest frame, in bytes of packed rows, the ring holds.
+    pub fn max_frame_bytes(&self) -> usize {
+        self.slot_size
+    }
+
+    /// Copies `image` into the next slot as frame `written()`, overwriting the oldest
+    /// frame. Fails if its packed rows exceed [`max_frame_bytes`](Self::max_frame_bytes).
+    pub fn write(&mut self, image: &ImageView, timestamp: Duration) -> io::Result<()> {
+        let row = image.width() * image.channels();
+        let len = row * image.height();
+        if len > self.slot_size || row.max(image.height()) > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "a {}x{} frame doesn't fit in the ring's slots of {} bytes",
+                    image.width(),
+                    image.height(),
+                    self.slot_size
+                ),
+            ));
+        }
+        let index = self.written;
+        let slot = HEADER + (index % self.slots as u64) as usize * (SLOT_HEADER + self.slot_size);
+        let base = self.map.as_mut_ptr();
+        // SAFETY: the slot lies within the mapping, its header at a multiple of 8, and
+        // `len` was checked to fit its pixel data. Readers may be copying the slot
+        // concurrently; the lock tells them to discard what they copied.
+        unsafe {
+            let lock = atomic(base, slot + LOCK);
+            lock.fetch_add(1, Ordering::Relaxed);
+            fence(Ordering::Release);
+            put(base, slot + INDEX, &index.to_ne_bytes());
+            put(
+                base,
+                slot + TIMESTAMP,
+                &(timestamp.as_micros() as u64).to_ne_bytes(),
+            );
+            for (offset, value) in [
+                (WIDTH, image.width()),
+                (HEIGHT, image.height()),
+                (STRIDE, row),
+                (CHANNELS, image.channels()),
+            ] {
+                put(base, slot + offset, &(value as u32).to_ne_bytes());
+            }
+            for y in 0..image.height() {
+                let start = y * image.stride();
+                let source = &image.data()[start..start + row];
+                put(base, slot + SLOT_HEADER + y * row, source);
+            }
+            lock.fetch_add(1, Ordering::Release);
+        }
+        self.written += 1;
+        // SAFETY: the header lies within the mapping.
+        unsafe { atomic(base, WRITTEN) }.store(self.written, Ordering::Release);
+        Ok(())
+    }
+
+    /// How many frames have been written.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+/// A frame read from a ring.
+#[derive(Debug, Clone, Copy)]
+pub struct RingFrame<'a> {
+    /// The position of the frame among those written, from 0.
+    pub index: u64,
+    /// The timestamp the writer gave the frame, to microsecond precision.
+    pub timestamp: Duration,
+    pub image: ImageView<'a>,
+}
+
+/// The detecting side of a ring, reading the frames a [`FrameRingWriter`] writes in another
+/// process into a buffer of its own.
+#[derive(Debug)]
+pub struct FrameRingReader {
+    map: Mmap,
+    slots: u64,
+    slot_size: usize,
+    next: u64,
+    dropped: u64,
+    buffer: Vec<u8>,
+}
+
+impl FrameRingReader {
+    /// Opens the ring file at `path`, starting from the frames written after this call.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the ring is written to concurrently, which readers guard against with the
+        // sequence locks of its slots and by copying frames out before using them.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER || &map[..8] != MAGIC {
+            return Err(invalid("not a frame ring"));
+        }
+        let u32_at =
+            |offset: usize| u32::from_ne_bytes(map[offset..offset + 4].try_into().unwrap());
+        if u32_at(8) != VERSION {
+            return Err(invalid("unsupported frame ring version"));
+        }
+        let slots = u32_at(SLOTS) as usize;
+        let slot_size = u64::from_ne_bytes(map[SLOT_SIZE..SLOT_SIZE + 8].try_into().unwrap());
+        let fits = usize::try_from(slot_size)
+            .ok()
+            .filter(|size| size % 64 == 0)
+            .and_then(|size| size.checked_add(SLOT_HEADER)?.checked_mul(slots))
+            .and_then(|len| len.checked_add(HEADER))
+            .is_some_and(|len| slots > 0 && len <= map.len());
+        if !fits {
+            return Err(invalid("frame ring truncated"));
+        }
+        let mut reader = Self {
+            map,
+            slots: slots as u64,
+            slot_size: slot_size as usize,
+            next: 0,
+            dropped: 0,
+            buffer: Vec::new(),
+        };
+        reader.next = reader.written();
+        Ok(reader)
+    }
+
+    /// How many frames the writer has written.
+    pub fn written(&self) -> u64 {
+        // SAFETY: the header lies within the mapping, which was validated on opening.
+        unsafe { atomic(self.map.as_ptr(), WRITTEN) }.load(Ordering::Acquire)
+    }
+
+    /// How many frames were overwritten before they could be read.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// The oldest unread frame still in the ring, if any, skipping those overwritten,
+    /// including one whose overwriting never completed because the writer died meanwhile.
+    pub fn read_next(&mut self) -> io::Result<Option<RingFrame<'_>>> {
+        loop {
+            let written = self.written();
+            if written <= self.next {
+                return Ok(None);
+            }
+            let oldest = written.saturating_sub(self.slots);
+            if self.next < oldest {
+                self.dropped += oldest - self.next;
+                self.next = oldest;
+            }
+            // A frame that was written in full but no longer reads back was, or is being,
+            // overwritten: waiting for it is pointless, and endless if the writer died mid-write.
+            let layout = self.copy(self.next)?;
+            self.next += 1;
+            match layout {
+                Some(layout) => return self.frame(layout).map(Some),
+                None => self.dropped += 1,
+            }
+        }
+    }
+
+    /// The newest frame if it hasn't been read, skipping any older unread ones.
+    pub fn latest(&mut self) -> io::Result<Option<RingFrame<'_>>> {
+        let written = self.written();
+        if written > self.next + 1 {
+            self.dropped += written - 1 - self.next;
+            self.next = written - 1;
+        }
+        self.read_next()
+    }
+
+    /// [`read_next`](Self::read_next), waiting up to `timeout` for a frame to be written.
+    pub fn wait_next(&mut self, timeout: Duration) -> io::Result<Option<RingFrame<'_>>> {
+        let deadline = Instant::now() + timeout;
+        while self.written() <= self.next && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        self.read_next()
+    }
+
+    /// Copies frame `index` into the buffer, returning its timestamp and layout, or `None`
+    /// if the writer overwrote it.
+    fn copy(&mut self, index: u64) -> io::Result<Option<(Duration, [usize; 4])>> {
+        let slot = HEADER + (index % self.slots) as usize * (SLOT_HEADER + self.slot_size);
+        let base = self.map.as_ptr();
+        // SAFETY: the slot lies within the mapping, which was validated on opening.
+        let lock = unsafe { atomic(base, slot + LOCK) };
+        let before = lock.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            return Ok(None);
+        }
+        // Plain reads racing the writer, only trusted if the lock didn't move meanwhile.
+        let read_u64 = |offset: usize| {
+            // SAFETY: the slot header lies within the mapping.
+            unsafe { std::ptr::read_volatile(base.add(slot + offset) as *const u64) }
+        };
+        let read_u32 = |offset: usize| {
+            // SAFETY: as above.
+            unsafe { std::ptr::read_volatile(base.add(slot + offset) as *const u32) as usize }
+        };
+        let (frame, timestamp) = (read_u64(INDEX), read_u64(TIMESTAMP));
+        let layout = [WIDTH, HEIGHT, STRIDE, CHANNELS].map(read_u32);
+        let len = layout[2].saturating_mul(layout[1]).min(self.slot_size);
+        self.buffer.resize(len, 0);
+        // SAFETY: `len` is capped to the slot's pixel data, which lies within the mapping.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                base.add(slot + SLOT_HEADER),
+                self.buffer.as_mut_ptr(),
+                len,
+            )
+        };
+        fence(Ordering::Acquire);
+        if lock.load(Ordering::Relaxed) != before || frame != index {
+            return Ok(None);
+        }
+        Ok(Some((Duration::from_micros(timestamp), layout)))
+    }
+
+    fn frame(&self, (timestamp, layout): (Duration, [usize; 4])) -> io::Result<RingFrame<'_>> {
+        let [width, height, stride, channels] = layout;
+        let image = match channels {
+            3 => ImageView::with_stride(&self.buffer, width, height, stride),
+            1 => ImageView::gray_with_stride(&self.buffer, width, height, stride),
+            _ => return Err(invalid("frame of unsupported channels in ring")),
+        }
+        .map_err(|_| invalid("frame of invalid layout in ring"))?;
+        Ok(RingFrame {
+            index: self.next - 1,
+            timestamp,
+            image,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FaceDetector;
+
+    #[test]
+    fn hands_frames_over() {
+        let path = std::env::temp_dir().join(format!("rusty-yunet-ring-{}", std::process::id()));
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let sample = ImageView::new(image.as_raw(), 806, 605).unwrap();
+        let mut writer = FrameRingWriter::create(&path, 2, 806 * 605 * 3).unwrap();
+        writer.write(&sample, Duration::ZERO).unwrap();
+        let mut reader = FrameRingReader::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(reader.read_next().unwrap().is_none());
+
+        // Strided frames are packed, and faces detected in place.
+        let padded: Vec<u8> = image
+            .as_raw()
+            .chunks(806 * 3)
+            .flat_map(|row| row.iter().copied().chain([0; 6]))
+            .collect();
+        let padded = ImageView::with_stride(&padded, 806, 605, 806 * 3 + 6).unwrap();
+        writer.write(&padded, Duration::from_millis(40)).unwrap();
+        let frame = reader.read_next().unwrap().unwrap();
+        assert_eq!(
+            (1, Duration::from_millis(40)),
+            (frame.index, frame.timestamp)
+        );
+        assert_eq!(806 * 3, frame.image.stride());
+        assert_eq!(
+            2,
+            FaceDetector::new()
+                .detect_image(&frame.image)
+                .unwrap()
+                .len()
+        );
+
+        // A reader behind by more than the ring skips the overwritten frames.
+        let gray = ImageView::gray(&[7; 64 * 48], 64, 48).unwrap();
+        for i in 2..6 {
+            writer.write(&gray, Duration::from_millis(40 * i)).unwrap();
+        }
+        assert_eq!(4, reader.read_next().unwrap().unwrap().index);
+        assert_eq!(2, reader.dropped());
+        let frame = reader.read_next().unwrap().unwrap();
+        assert_eq!((5, 1), (frame.index, frame.image.channels()));
+        assert!(reader.read_next().unwrap().is_none());
+
+        // A writer dying while overwriting a slot leaves its lock odd; the frame it held is
+        // skipped rather than waited for.
+        for i in 6..8 {
+            writer.write(&gray, Duration::from_millis(40 * i)).unwrap();
+        }
+        // SAFETY: slot 0, holding frame 6, lies within the mapping.
+        unsafe { atomic(writer.map.as_mut_ptr(), HEADER + LOCK) }.fetch_add(1, Ordering::Release);
+        assert_eq!(7, reader.read_next().unwrap().unwrap().index);
+        assert_eq!(3, reader.dropped());
+        assert!(reader.read_next().unwrap().is_none());
+        let huge = ImageView::gray(&[0; 2000 * 2000], 2000, 2000).unwrap();
+        assert!(writer.write(&huge, Duration::ZERO).is_err());
+    }
+}
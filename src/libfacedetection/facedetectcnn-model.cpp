@@ -54,17 +54,20 @@ cv::TickMeter cvtm;
 #endif
 
 
-#define NUM_CONV_LAYER 53
-
 extern ConvInfoStruct param_pConvInfo[NUM_CONV_LAYER];
 Filters<float> g_pFilters[NUM_CONV_LAYER];
 
 bool param_initialized = false;
 
-void init_parameters()
+void init_parameters(Filters<float>* filters)
 {
     for(int i = 0; i < NUM_CONV_LAYER; i++)
-        g_pFilters[i] = param_pConvInfo[i];
+        filters[i] = param_pConvInfo[i];
+}
+
+void init_parameters()
+{
+    init_parameters(g_pFilters);
 }
 
 std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int width, int height, int step)
@@ -78,18 +81,23 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
     }
     TIME_END("init");
 
+    return objectdetect_cnn(g_pFilters, rgbImageData, width, height, step);
+}
+
+std::vector<FaceRect> objectdetect_cnn(const Filters<float>* filters, const unsigned char * rgbImageData, int width, int height, int step, bool suppress, int channels, HeadOutputs* heads)
+{
 
     TIME_START;
-    auto fx = setDataFrom3x3S2P1to1x1S1P0FromImage(rgbImageData, width, height, 3, step);
+    auto fx = setDataFrom3x3S2P1to1x1S1P0FromImage(rgbImageData, width, height, channels, step);
     TIME_END("convert data");
 
     /***************CONV0*********************/
     TIME_START;
-    fx = convolution(fx, g_pFilters[0]);
+    fx = convolution(fx, filters[0]);
     TIME_END("conv_head");
 
     TIME_START;
-    fx = convolutionDP(fx, g_pFilters[1], g_pFilters[2]);
+    fx = convolutionDP(fx, filters[1], filters[2]);
     TIME_END("conv0");
 
     TIME_START;
@@ -98,12 +106,12 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
 
     /***************CONV1*********************/
     TIME_START;
-    fx = convolution4layerUnit(fx, g_pFilters[3], g_pFilters[4], g_pFilters[5], g_pFilters[6]);
+    fx = convolution4layerUnit(fx, filters[3], filters[4], filters[5], filters[6]);
     TIME_END("conv1");
 
     /***************CONV2*********************/
     TIME_START;
-    fx = convolution4layerUnit(fx, g_pFilters[7], g_pFilters[8], g_pFilters[9], g_pFilters[10]);
+    fx = convolution4layerUnit(fx, filters[7], filters[8], filters[9], filters[10]);
     TIME_END("conv2");
 
     /***************CONV3*********************/
@@ -112,7 +120,7 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
     TIME_END("pool3");
 
     TIME_START;
-    auto fb1 = convolution4layerUnit(fx, g_pFilters[11], g_pFilters[12], g_pFilters[13], g_pFilters[14]);
+    auto fb1 = convolution4layerUnit(fx, filters[11], filters[12], filters[13], filters[14]);
     TIME_END("conv3");
 
     /***************CONV4*********************/
@@ -121,7 +129,7 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
     TIME_END("pool4");
 
     TIME_START;
-    auto fb2 = convolution4layerUnit(fx, g_pFilters[15], g_pFilters[16], g_pFilters[17], g_pFilters[18]);
+    auto fb2 = convolution4layerUnit(fx, filters[15], filters[16], filters[17], filters[18]);
     TIME_END("conv4");
 
     /***************CONV5*********************/
@@ -130,17 +138,17 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
     TIME_END("pool5");
 
     TIME_START;
-    auto fb3 = convolution4layerUnit(fx, g_pFilters[19], g_pFilters[20], g_pFilters[21], g_pFilters[22]);
+    auto fb3 = convolution4layerUnit(fx, filters[19], filters[20], filters[21], filters[22]);
     TIME_END("conv5");
 
     CDataBlob<float> pred_reg[3], pred_cls[3], pred_kps[3], pred_obj[3];
     /***************branch5*********************/
     TIME_START;
-    fb3 = convolutionDP(fb3, g_pFilters[27], g_pFilters[28]);
-    pred_cls[2] = convolutionDP(fb3, g_pFilters[33], g_pFilters[34], false);
-    pred_reg[2] = convolutionDP(fb3, g_pFilters[39], g_pFilters[40], false);
-    pred_kps[2] = convolutionDP(fb3, g_pFilters[51], g_pFilters[52], false);
-    pred_obj[2] = convolutionDP(fb3, g_pFilters[45], g_pFilters[46], false);
+    fb3 = convolutionDP(fb3, filters[27], filters[28]);
+    pred_cls[2] = convolutionDP(fb3, filters[33], filters[34], false);
+    pred_reg[2] = convolutionDP(fb3, filters[39], filters[40], false);
+    pred_kps[2] = convolutionDP(fb3, filters[51], filters[52], false);
+    pred_obj[2] = convolutionDP(fb3, filters[45], filters[46], false);
     TIME_END("branch5");
 
     /*****************add5*********************/    
@@ -150,11 +158,11 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
 
     /*****************add6*********************/    
     TIME_START;
-    fb2 = convolutionDP(fb2, g_pFilters[25], g_pFilters[26]);
-    pred_cls[1] = convolutionDP(fb2, g_pFilters[31], g_pFilters[32], false);
-    pred_reg[1] = convolutionDP(fb2, g_pFilters[37], g_pFilters[38], false);
-    pred_kps[1] = convolutionDP(fb2, g_pFilters[49], g_pFilters[50], false);
-    pred_obj[1] = convolutionDP(fb2, g_pFilters[43], g_pFilters[44], false);
+    fb2 = convolutionDP(fb2, filters[25], filters[26]);
+    pred_cls[1] = convolutionDP(fb2, filters[31], filters[32], false);
+    pred_reg[1] = convolutionDP(fb2, filters[37], filters[38], false);
+    pred_kps[1] = convolutionDP(fb2, filters[49], filters[50], false);
+    pred_obj[1] = convolutionDP(fb2, filters[43], filters[44], false);
     TIME_END("branch4");
 
     /*****************add4*********************/
@@ -164,11 +172,11 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
 
     /***************branch3*********************/
     TIME_START;
-    fb1 = convolutionDP(fb1, g_pFilters[23], g_pFilters[24]);
-    pred_cls[0] = convolutionDP(fb1, g_pFilters[29], g_pFilters[30], false);
-    pred_reg[0] = convolutionDP(fb1, g_pFilters[35], g_pFilters[36], false);
-    pred_kps[0] = convolutionDP(fb1, g_pFilters[47], g_pFilters[48], false);
-    pred_obj[0] = convolutionDP(fb1, g_pFilters[41], g_pFilters[42], false);
+    fb1 = convolutionDP(fb1, filters[23], filters[24]);
+    pred_cls[0] = convolutionDP(fb1, filters[29], filters[30], false);
+    pred_reg[0] = convolutionDP(fb1, filters[35], filters[36], false);
+    pred_kps[0] = convolutionDP(fb1, filters[47], filters[48], false);
+    pred_obj[0] = convolutionDP(fb1, filters[41], filters[42], false);
     TIME_END("branch3");
     
     /***************PRIORBOX*********************/
@@ -179,6 +187,21 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
     TIME_END("prior");
     /***************PRIORBOX*********************/
 
+    if (heads != nullptr)
+    {
+        const int strides[3] = {8, 16, 32};
+        for (int i = 0; i < 3; i++)
+        {
+            heads[i].stride = strides[i];
+            heads[i].rows = pred_cls[i].rows;
+            heads[i].cols = pred_cls[i].cols;
+            heads[i].cls = blob2vector(pred_cls[i]);
+            heads[i].obj = blob2vector(pred_obj[i]);
+            heads[i].reg = blob2vector(pred_reg[i]);
+            heads[i].kps = blob2vector(pred_kps[i]);
+        }
+    }
+
     TIME_START;
     bbox_decode(pred_reg[0], prior3, 8);
     bbox_decode(pred_reg[1], prior4, 16);
@@ -198,7 +221,10 @@ std::vector<FaceRect> objectdetect_cnn(const unsigned char * rgbImageData, int w
     TIME_END("decode")
 
     TIME_START;
-    std::vector<FaceRect> facesInfo = detection_output(cls, reg, kps, obj, 0.3f, 0.5f, 1000, 100);
+    // Without suppression, an overlap threshold of 1 keeps every candidate.
+    std::vector<FaceRect> facesInfo = suppress
+        ? detection_output(cls, reg, kps, obj, 0.3f, 0.5f, 1000, 100)
+        : detection_output(cls, reg, kps, obj, 1.0f, 0.5f, -1, -1);
     TIME_END("detection output")
     return facesInfo;
 }
@@ -80,8 +80,8 @@ void myFree_(void* ptr)
 
 
 CDataBlob<float> setDataFrom3x3S2P1to1x1S1P0FromImage(const unsigned char* inputData, int imgWidth, int imgHeight, int imgChannels, int imgWidthStep, int padDivisor) {
-    if (imgChannels != 3) {
-        std::cerr << __FUNCTION__ << ": The input image must be a 3-channel RGB image." << std::endl;
+    if (imgChannels != 3 && imgChannels != 1) {
+        std::cerr << __FUNCTION__ << ": The input image must be a 3-channel RGB or a 1-channel gray image." << std::endl;
         exit(1);
     }
     if (padDivisor != 32) {
@@ -114,9 +114,10 @@ CDataBlob<float> setDataFrom3x3S2P1to1x1S1P0FromImage(const unsigned char* input
                     const unsigned char * pImgData = inputData + size_t(imgWidthStep) * srcy + imgChannels * srcx;
 
                     int output_channel_offset = ((fy + 1) * 3 + fx + 1) ; //3x3 filters, 3-channel image
-                    pData[output_channel_offset * imgChannels] = pImgData[0];
-                    pData[output_channel_offset * imgChannels + 1] = pImgData[1];
-                    pData[output_channel_offset * imgChannels + 2] = pImgData[2];
+                    int next = imgChannels == 1 ? 0 : 1; //a gray value fills all three channels
+                    pData[output_channel_offset * 3] = pImgData[0];
+                    pData[output_channel_offset * 3 + 1] = pImgData[next];
+                    pData[output_channel_offset * 3 + 2] = pImgData[2 * next];
                 }
             }                    
         }
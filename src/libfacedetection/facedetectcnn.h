@@ -99,11 +99,11 @@ void myFree_(void* ptr);
 typedef struct FaceRect_
 {
     float score;
-    int x;
-    int y;
-    int w;
-    int h;
-    int lm[10];
+    float x;
+    float y;
+    float w;
+    float h;
+    float lm[10];
 }FaceRect;
 
 typedef struct ConvInfoStruct_ {
@@ -394,7 +394,25 @@ class Filters{
 
 };
 
+#define NUM_CONV_LAYER 53
+
+void init_parameters(Filters<float>* filters);
+
+// The undecoded outputs of the heads on one level of the feature pyramid, each flattened
+// by blob2vector to rows * cols anchors.
+struct HeadOutputs {
+    int stride;
+    int rows;
+    int cols;
+    CDataBlob<float> cls;
+    CDataBlob<float> obj;
+    CDataBlob<float> reg;
+    CDataBlob<float> kps;
+};
+
 std::vector<FaceRect> objectdetect_cnn(const unsigned char* rgbImageData, int width, int height, int step);
+// When `heads` isn't null, stores the outputs of the heads of the three levels there.
+std::vector<FaceRect> objectdetect_cnn(const Filters<float>* filters, const unsigned char* rgbImageData, int width, int height, int step, bool suppress = true, int channels = 3, HeadOutputs* heads = nullptr);
 
 CDataBlob<float> setDataFrom3x3S2P1to1x1S1P0FromImage(const unsigned char* inputData, int imgWidth, int imgHeight, int imgChannels, int imgWidthStep, int padDivisor=32);
 CDataBlob<float> convolution(const CDataBlob<float>& inputData, const Filters<float>& filters, bool do_relu = true);
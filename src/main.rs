@@ -1,11 +1,398 @@
-fn main() {
-    // Loads a sample with three faces clearly staggered in distance. Detecting the biggest
-    // face with high confidence should be completely expected. Detecting the mid-sized face
-    // is good, as it probably stretches what we consider "presence" in front of a normal
-    // installation. Detecting the smallest face is very unrealistic and unnecessary.
-    //
-    // Detecting two faces with this test at this resolution can be considered a good result.
-    //
-    // This code is replicated as a unit test in `lib.rs`. It's kept here as well as an informal
-    // benchmark.
+//! The `rusty-yunet` command, built with the `cli` feature: the faces in image files,
+//! directories and camera streams as JSON lines or CSV, and the HTTP detection service of
+//! [`rusty_yunet::server`].
+//!
+//! ```sh
+//! rusty-yunet detect photos/ --format csv --progress > faces.csv
+//! rusty-yunet detect group.jpg --save-crops crops/
+//! rusty-yunet detect rtsp://door/live --size 1280x720
+//! rusty-yunet detect group.jpg --origin bottom-left --units normalized
+//! rusty-yunet detect --config site.toml
+//! rusty-yunet serve --port 8080 --workers 4
+//! rusty-yunet serve --source rtsp://door/live
+//! ```
+
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use image::RgbImage;
+use rusty_yunet::config::{AppConfig, InputSource};
+use rusty_yunet::csv::CsvWriter;
+use rusty_yunet::drawing::{save_crops, Crop};
+use rusty_yunet::progress::{NoProgress, ProgressBar};
+use rusty_yunet::rtsp::{RtspConfig, RtspSource};
+use rusty_yunet::server::{DetectionServer, ServerConfig};
+use rusty_yunet::{CoordinateConvention, Face, FaceDetector, ImageView, Origin, Units};
+
+fn cli() -> Command {
+    let config = Arg::new("config")
+        .long("config")
+        .value_name("FILE")
+        .value_parser(value_parser!(PathBuf))
+        .help("Settings in TOML, or JSON by extension; the source and detector sections apply");
+    let origin = Arg::new("origin")
+        .long("origin")
+        .value_parser(["top-left", "bottom-left", "center"])
+        .default_value("top-left")
+        .help("Where coordinates are measured from; y points up unless from the top left");
+    let units = Arg::new("units")
+        .long("units")
+        .value_parser(["pixels", "normalized"])
+        .default_value("pixels")
+        .help("Pixels, or fractions of the frame: 0..1 from a corner, -1..1 from the center");
+    let size = Arg::new("size")
+        .long("size")
+        .value_name("WIDTHxHEIGHT")
+        .value_parser(parse_size)
+        .default_value("1280x720")
+        .help("The resolution RTSP frames are scaled to");
+    Command::new("rusty-yunet")
+        .about("Detects faces with YuNet")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("detect")
+                .about("Writes the faces of each image or frame to standard output, a line each")
+                .arg(
+                    Arg::new("input")
+                        .value_name("INPUT")
+                        .help("An image file, a directory of images or an rtsp:// URL; defaults to the source of --config"),
+                )
+                .arg(config.clone())
+                .arg(origin.clone())
+                .arg(units.clone())
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["json", "csv"])
+                        .default_value("json")
+                        .help("JSON lines, or CSV with a header row"),
+                )
+                .arg(
+                    Arg::new("save-crops")
+                        .long("save-crops")
+                        .value_name("DIR")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Also write every face as a PNG named with its index and confidence, in a directory per image or frame"),
+                )
+                .arg(
+                    Arg::new("progress")
+                        .long("progress")
+                        .action(ArgAction::SetTrue)
+                        .help("Show a progress bar on standard error while detecting in a directory"),
+                )
+                .arg(size.clone()),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Answers POST /detect with the faces of uploaded images, as JSON")
+                .arg(
+                    Arg::new("port")
+                        .long("port")
+                        .value_parser(value_parser!(u16))
+                        .default_value("8080"),
+                )
+                .arg(
+                    Arg::new("bind")
+                        .long("bind")
+                        .value_name("ADDRESS")
+                        .default_value("0.0.0.0")
+                        .help("The address to listen on"),
+                )
+                .arg(
+                    Arg::new("workers")
+                        .long("workers")
+                        .value_parser(value_parser!(usize))
+                        .help("Detections running at once; defaults to the number of cores"),
+                )
+                .arg(
+                    Arg::new("source")
+                        .long("source")
+                        .value_name("URL")
+                        .help("An rtsp:// camera stream to detect in as well, its latest faces answering GET /faces; defaults to an RTSP source of --config"),
+                )
+                .arg(size)
+                .arg(config)
+                .arg(origin)
+                .arg(units),
+        )
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    let result = match matches.subcommand() {
+        Some(("detect", args)) => detect(args),
+        Some(("serve", args)) => serve(args),
+        _ => unreachable!("a subcommand is required"),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("rusty-yunet: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn settings(args: &ArgMatches) -> Result<AppConfig, Box<dyn Error>> {
+    match args.get_one::<PathBuf>("config") {
+        Some(path) => Ok(AppConfig::load(path)?),
+        None => Ok(AppConfig::default()),
+    }
+}
+
+fn convention(args: &ArgMatches) -> CoordinateConvention {
+    let origin = match args.get_one::<String>("origin").map(String::as_str) {
+        Some("bottom-left") => Origin::BottomLeft,
+        Some("center") => Origin::Center,
+        _ => Origin::TopLeft,
+    };
+    let units = match args.get_one::<String>("units").map(String::as_str) {
+        Some("normalized") => Units::Normalized,
+        _ => Units::Pixels,
+    };
+    CoordinateConvention::new(origin, units)
+}
+
+fn serve(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let settings = settings(args)?;
+    let mut config = ServerConfig::default();
+    config.detector = settings.detector.clone();
+    config.convention = convention(args);
+    if let Some(&workers) = args.get_one::<usize>("workers") {
+        config.workers = workers;
+        config.max_connections = 4 * workers;
+    }
+    let bind = args.get_one::<String>("bind").expect("has a default");
+    let port = *args.get_one::<u16>("port").expect("has a default");
+    let server = DetectionServer::bind((bind.as_str(), port), config)?;
+    let source = match (args.get_one::<String>("source"), &settings.source) {
+        (Some(url), _) => Some(url.clone()),
+        (None, Some(InputSource::Rtsp(url))) => Some(url.clone()),
+        _ => None,
+    };
+    if let Some(url) = source {
+        let &(width, height) = args.get_one("size").expect("has a default");
+        let mut detector = settings.build_detector()?;
+        let mut stream = RtspSource::new(RtspConfig::new(url.clone(), width, height));
+        let publisher = server.publisher();
+        // The source reconnects on its own, backing off, and fails only once it gives up.
+        std::thread::spawn(move || loop {
+            let frame = match stream.next_frame() {
+                Ok(frame) => frame,
+                Err(error) => {
+                    eprintln!("rusty-yunet: {url}: {error}");
+                    std::process::exit(1);
+                }
+            };
+            match detector.detect_image(&frame.image) {
+                Ok(faces) => publisher.publish(frame.index, &faces),
+                Err(error) => eprintln!("{url}: frame {}: {error}", frame.index),
+            }
+        });
+    }
+    eprintln!("listening on {}", server.local_addr()?);
+    Ok(server.serve()?)
+}
+
+fn detect(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let settings = settings(args)?;
+    let source = match args.get_one::<String>("input") {
+        Some(url) if url.starts_with("rtsp://") || url.starts_with("rtsps://") => {
+            InputSource::Rtsp(url.clone())
+        }
+        Some(path) if Path::new(path).is_dir() => InputSource::Directory(path.into()),
+        Some(path) => InputSource::File(path.into()),
+        None => settings
+            .source
+            .clone()
+            .ok_or("no input, neither given nor in the source section of --config")?,
+    };
+    let mut detector = settings.build_detector()?;
+    let convention = convention(args);
+    let mut output = if args.get_one::<String>("format").expect("has a default") == "csv" {
+        Output::Csv(CsvWriter::new(io::stdout())?.with_convention(convention))
+    } else {
+        Output::Json(io::stdout(), convention)
+    };
+    let crops = args.get_one::<PathBuf>("save-crops");
+
+    match source {
+        InputSource::File(path) => {
+            let faces = detector.detect_file(&path)?;
+            output.write(&path.display().to_string(), 0, &faces)?;
+            if let Some(crops) = crops {
+                let stem = path.file_stem().unwrap_or(path.as_os_str());
+                save_crops(
+                    &image::open(&path)?.to_rgb8(),
+                    &faces,
+                    &Crop::default(),
+                    crops.join(stem),
+                )?;
+            }
+        }
+        InputSource::Directory(dir) => {
+            let files = if args.get_flag("progress") {
+                detector.detect_dir(&dir, &mut ProgressBar::new())?
+            } else {
+                detector.detect_dir(&dir, &mut NoProgress)?
+            };
+            for (path, faces) in files {
+                let faces = match faces {
+                    Ok(faces) => faces,
+                    Err(error) => {
+                        eprintln!("{}: {error}", path.display());
+                        continue;
+                    }
+                };
+                output.write(&path.display().to_string(), 0, &faces)?;
+                if let Some(crops) = crops {
+                    let relative = path.strip_prefix(&dir).unwrap_or(&path).with_extension("");
+                    let image = image::open(&path)?.to_rgb8();
+                    save_crops(&image, &faces, &Crop::default(), crops.join(relative))?;
+                }
+            }
+        }
+        InputSource::Rtsp(url) => {
+            let &(width, height) = args.get_one("size").expect("has a default");
+            let mut stream = RtspSource::new(RtspConfig::new(url.clone(), width, height));
+            loop {
+                let frame = stream.next_frame().map_err(|e| format!("{url}: {e}"))?;
+                detect_frame(
+                    &mut detector,
+                    &mut output,
+                    &url,
+                    frame.index,
+                    &frame.image,
+                    crops,
+                )?;
+            }
+        }
+        #[cfg(feature = "ipc")]
+        InputSource::Ipc(path) => {
+            let mut ring = rusty_yunet::ipc::FrameRingReader::open(&path)?;
+            let source = path.display().to_string();
+            loop {
+                let timeout = std::time::Duration::from_secs(1);
+                if let Some(frame) = ring.wait_next(timeout)? {
+                    detect_frame(
+                        &mut detector,
+                        &mut output,
+                        &source,
+                        frame.index,
+                        &frame.image,
+                        crops,
+                    )?;
+                }
+            }
+        }
+        #[cfg(not(feature = "ipc"))]
+        InputSource::Ipc(_) => return Err("shared-memory rings need the ipc feature".into()),
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// Detects in a frame of a stream and writes its faces out, and their crops to a directory
+/// of the frame's own in `crops`.
+fn detect_frame(
+    detector: &mut FaceDetector,
+    output: &mut Output,
+    source: &str,
+    index: u64,
+    image: &ImageView,
+    crops: Option<&PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let faces = detector.detect_image(image)?;
+    output.write(source, index, &faces)?;
+    output.flush()?;
+    if let Some(crops) = crops.filter(|_| !faces.is_empty()) {
+        let dir = crops.join(format!("frame-{index}"));
+        save_crops(&to_rgb(image), &faces, &Crop::default(), dir)?;
+    }
+    Ok(())
+}
+
+/// Where the faces go, a line per image or frame.
+enum Output {
+    /// `{"source":"photo.jpg","index":0,"faces":[...]}`, the faces as the server answers them.
+    Json(io::Stdout, CoordinateConvention),
+    Csv(CsvWriter<io::Stdout>),
+}
+
+impl Output {
+    fn write(&mut self, source: &str, index: u64, faces: &[Face]) -> io::Result<()> {
+        match self {
+            Output::Json(out, convention) => {
+                let digits = match convention.units {
+                    Units::Pixels => 2,
+                    Units::Normalized => 4,
+                };
+                let mut line = format!(
+                    r#"{{"source":{},"index":{index},"faces":["#,
+                    json_string(source)
+                );
+                for (i, face) in faces.iter().enumerate() {
+                    let rect = face.rectangle_in(*convention);
+                    let _ = write!(
+                        line,
+                        r#"{}{{"confidence":{:.3},"box":[{:.digits$},{:.digits$},{:.digits$},{:.digits$}],"landmarks":["#,
+                        if i == 0 { "" } else { "," },
+                        face.confidence(),
+                        rect.x,
+                        rect.y,
+                        rect.w,
+                        rect.h,
+                    );
+                    for (j, p) in face.landmarks_in(*convention).as_array().iter().enumerate() {
+                        let separator = if j == 0 { "" } else { "," };
+                        let _ = write!(line, "{separator}{:.digits$},{:.digits$}", p.x, p.y);
+                    }
+                    line.push_str("]}");
+                }
+                line.push_str("]}");
+                writeln!(out, "{line}")
+            }
+            Output::Csv(csv) => csv.write_faces(source, index, faces),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Json(out, _) => out.flush(),
+            Output::Csv(csv) => csv.flush(),
+        }
+    }
+}
+
+/// A BGR or grayscale frame as RGB, for cropping.
+fn to_rgb(image: &ImageView) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let channels = image.channels();
+    RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let pixel = &image.data()[y as usize * image.stride() + x as usize * channels..];
+        match channels {
+            1 => image::Rgb([pixel[0]; 3]),
+            _ => image::Rgb([pixel[2], pixel[1], pixel[0]]),
+        }
+    })
+}
+
+fn parse_size(size: &str) -> Result<(usize, usize), String> {
+    let parsed = size
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+    match parsed {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err("expected WIDTHxHEIGHT, such as 1280x720".to_owned()),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    serde_json::Value::from(text).to_string()
 }
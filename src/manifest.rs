@@ -0,0 +1,118 @@
+//! A record of a batch, video or daemon run: what ran, on what, and how it went, so that
+//! results can be reproduced and audited later. Serialize it with the `serde_support`
+//! feature.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::provenance::{backend_name, MODEL_HASH, MODEL_NAME, MODEL_VERSION};
+use crate::{DetectorConfig, DetectorStats, Face, YuNetError};
+
+/// How processing one input went.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemStatus {
+    Ok { faces: usize },
+    Failed { error: String },
+}
+
+/// One input of a run, such as an image path or a stream URL.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestItem {
+    pub input: String,
+    pub status: ItemStatus,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunManifest {
+    pub model: String,
+    pub model_version: String,
+    /// See [`MODEL_HASH`]. Models passed as bytes to
+    /// [`FaceDetector::with_native_model`](crate::FaceDetector::with_native_model) aren't
+    /// covered.
+    pub model_hash: String,
+    pub backend: String,
+    /// Version of this crate.
+    pub crate_version: String,
+    pub config: DetectorConfig,
+    pub items: Vec<ManifestItem>,
+    /// Inputs that failed.
+    pub failed: usize,
+    /// Faces found over all inputs.
+    pub faces_found: usize,
+    /// Timing statistics of the detector, as of the end of the run.
+    pub stats: Option<DetectorStats>,
+}
+
+impl RunManifest {
+    /// Starts the manifest of a run with a detector of the given configuration.
+    pub fn new(config: &DetectorConfig) -> Self {
+        Self {
+            model: MODEL_NAME.to_owned(),
+            model_version: MODEL_VERSION.to_owned(),
+            model_hash: MODEL_HASH.to_owned(),
+            backend: backend_name(config.backend).to_owned(),
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            config: config.clone(),
+            items: Vec::new(),
+            failed: 0,
+            faces_found: 0,
+            stats: None,
+        }
+    }
+
+    /// Records how processing `input` went.
+    pub fn record(&mut self, input: impl Into<String>, result: &Result<Vec<Face>, YuNetError>) {
+        let status = match result {
+            Ok(faces) => {
+                self.faces_found += faces.len();
+                ItemStatus::Ok { faces: faces.len() }
+            }
+            Err(error) => {
+                self.failed += 1;
+                ItemStatus::Failed {
+                    error: error.to_string(),
+                }
+            }
+        };
+        self.items.push(ManifestItem {
+            input: input.into(),
+            status,
+        });
+    }
+
+    /// Completes the manifest with the detector's statistics, such as from
+    /// [`FaceDetector::stats`](crate::FaceDetector::stats).
+    pub fn finish(&mut self, stats: DetectorStats) {
+        self.stats = Some(stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FaceDetector;
+
+    #[test]
+    fn records_a_run() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut detector = FaceDetector::new();
+        let mut manifest = RunManifest::new(detector.config());
+        for (input, bytes) in [("sample.jpg", image.as_raw().clone()), ("empty", vec![])] {
+            manifest.record(input, &detector.detect(&bytes, width, height));
+        }
+        manifest.finish(detector.stats());
+
+        assert_eq!(16, manifest.model_hash.len());
+        assert_eq!((1, 2), (manifest.failed, manifest.faces_found));
+        assert_eq!(ItemStatus::Ok { faces: 2 }, manifest.items[0].status);
+        assert!(matches!(
+            manifest.items[1].status,
+            ItemStatus::Failed { .. }
+        ));
+        assert_eq!(1, manifest.stats.unwrap().detections);
+    }
+}
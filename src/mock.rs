@@ -0,0 +1,145 @@
+//! Stand-ins for the detector, for unit testing applications built on this crate without
+//! sample images, a model or the C++ toolchain.
+//!
+//! A [`ScriptedBackend`] returns canned faces frame after frame. Run it through a
+//! [`Pipeline`](crate::Pipeline) to test tracking, or feed its faces to a
+//! [`PresenceDetector`](crate::PresenceDetector):
+//!
+//! ```
+//! use rusty_yunet::mock::{face, ScriptedBackend};
+//! use rusty_yunet::tracking::TrackerConfig;
+//! use rusty_yunet::{ImageView, Pipeline, Rect};
+//!
+//! let backend = ScriptedBackend::new([
+//!     vec![face(Rect::with_size(100.0, 100.0, 50.0, 60.0), 0.9)],
+//!     vec![face(Rect::with_size(104.0, 100.0, 50.0, 60.0), 0.9)],
+//! ]);
+//! let mut pipeline = Pipeline::new(backend, TrackerConfig::default());
+//! let blank = vec![0; 640 * 480 * 3];
+//! let frame = ImageView::new(&blank, 640, 480)?;
+//! let results = pipeline.run([frame; 2])?;
+//! assert_eq!(results[0].track_ids, results[1].track_ids);
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+
+use std::collections::VecDeque;
+
+use glam::Vec2;
+
+use crate::recognition::ALIGNMENT_TEMPLATE;
+use crate::{DetectionBackend, Face, FaceLandmarks, ImageView, Rect, YuNetError};
+
+/// A [`DetectionBackend`] returning the faces of a script, one entry per frame, whatever
+/// the frames hold. Once the script runs out, frames have no faces.
+#[derive(Debug, Default)]
+pub struct ScriptedBackend {
+    script: VecDeque<Result<Vec<Face>, YuNetError>>,
+    /// The dimensions of each frame detected in so far.
+    frames: Vec<(usize, usize)>,
+}
+
+impl ScriptedBackend {
+    /// Returns each of `frames` in turn.
+    pub fn new(frames: impl IntoIterator<Item = Vec<Face>>) -> Self {
+        Self {
+            script: frames.into_iter().map(Ok).collect(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Adds a frame with `faces` to the end of the script.
+    pub fn then(mut self, faces: Vec<Face>) -> Self {
+        self.script.push_back(Ok(faces));
+        self
+    }
+
+    /// Adds a frame failing with `error` to the end of the script.
+    pub fn then_fail(mut self, error: YuNetError) -> Self {
+        self.script.push_back(Err(error));
+        self
+    }
+
+    /// The dimensions of each frame detected in so far, in order.
+    pub fn frames(&self) -> &[(usize, usize)] {
+        &self.frames
+    }
+
+    /// The frames left in the script.
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+}
+
+impl DetectionBackend for ScriptedBackend {
+    fn detect_image(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        self.frames.push(image.dimensions());
+        self.script.pop_front().unwrap_or(Ok(Vec::new()))
+    }
+}
+
+/// A face at `rectangle`, with landmarks where they typically sit within it, in a frame
+/// of 640x480 pixels.
+pub fn face(rectangle: Rect, confidence: f32) -> Face {
+    face_in(rectangle, confidence, (640, 480))
+}
+
+/// Like [`face`], in a frame of the given dimensions (width, height).
+pub fn face_in(rectangle: Rect, confidence: f32, dimensions: (usize, usize)) -> Face {
+    let origin = Vec2::new(rectangle.x, rectangle.y);
+    let scale = Vec2::new(rectangle.w, rectangle.h) / 112.0;
+    let landmarks = FaceLandmarks::from_array(
+        ALIGNMENT_TEMPLATE.map(|[x, y]| origin + Vec2::new(x, y) * scale),
+    );
+    Face::new(confidence, rectangle, landmarks, dimensions)
+}
+
+/// A face for the crate's unit tests: `[x, y, width, height]` in a frame of the given
+/// dimensions, with `landmarks` if given and where they typically sit otherwise.
+#[cfg(test)]
+pub(crate) fn test_face(
+    confidence: f32,
+    [x, y, w, h]: [f32; 4],
+    dimensions: (usize, usize),
+    landmarks: Option<[Vec2; 5]>,
+) -> Face {
+    let rectangle = Rect::with_size(x, y, w, h);
+    match landmarks {
+        Some(points) => Face::new(
+            confidence,
+            rectangle,
+            FaceLandmarks::from_array(points),
+            dimensions,
+        ),
+        None => face_in(rectangle, confidence, dimensions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracking::TrackerConfig;
+    use crate::Pipeline;
+
+    #[test]
+    fn drives_a_pipeline_with_scripted_faces() {
+        let at = |x: f32| face(Rect::with_size(x, 100.0, 50.0, 60.0), 0.9);
+        let backend = ScriptedBackend::new([vec![at(100.0)], vec![at(104.0), at(400.0)]])
+            .then_fail(YuNetError::FaceDetectionFailed);
+        let mut pipeline = Pipeline::new(backend, TrackerConfig::default());
+        let blank = vec![0; 64 * 48 * 3];
+        let frame = ImageView::new(&blank, 64, 48).unwrap();
+
+        let first = pipeline.process(&frame).unwrap();
+        let second = pipeline.process(&frame).unwrap();
+        assert_eq!(first.track_ids[0], second.track_ids[0]);
+        assert_ne!(second.track_ids[0], second.track_ids[1]);
+        assert!(first.faces[0].landmarks().visibility((640, 480)) == [true; 5]);
+        assert!(matches!(
+            pipeline.process(&frame),
+            Err(YuNetError::FaceDetectionFailed)
+        ));
+        assert!(pipeline.process(&frame).unwrap().faces.is_empty());
+        assert_eq!(&[(64, 48); 4], pipeline.detector().frames());
+        assert_eq!(0, pipeline.detector().remaining());
+    }
+}
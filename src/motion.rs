@@ -0,0 +1,185 @@
+//! Skipping detection on frames of a static scene, such as the empty room an always-on
+//! presence installation watches most of the time, by comparing a small grayscale copy of
+//! each frame with that of the last frame detected.
+//!
+//! [`MotionGate`] wraps a detector, or any [`DetectionBackend`], and hands back the faces of
+//! the last detection, marked stale, until enough of the scene changes. As a
+//! [`DetectionBackend`] itself, it slots into a [`Pipeline`](crate::Pipeline), whose tracks
+//! then simply persist through static frames.
+
+use crate::detector::DetectionBackend;
+use crate::{resample, Face, FaceDetector, ImageView, YuNetError};
+
+/// Tuning knobs for a [`MotionGate`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MotionGateConfig {
+    /// The longer side of the grayscale copies compared, small enough that camera noise
+    /// averages out.
+    pub side: usize,
+    /// The least difference (0..255) in a pixel of the copies that counts as a change.
+    pub pixel_threshold: u8,
+    /// The fraction (0..1) of pixels that must change for detection to run.
+    pub min_changed: f32,
+    /// Detection runs after at most this many stale frames in a row, whatever the scene, so
+    /// that faces holding perfectly still, or changes too small to notice, are caught up
+    /// with.
+    pub max_stale: u32,
+}
+
+impl Default for MotionGateConfig {
+    fn default() -> Self {
+        Self {
+            side: 64,
+            pixel_threshold: 16,
+            min_changed: 0.005,
+            max_stale: 30,
+        }
+    }
+}
+
+/// The faces of a frame passed through a [`MotionGate`].
+#[derive(Debug, Clone)]
+pub struct GatedFaces {
+    pub faces: Vec<Face>,
+    /// Whether the faces are those of an earlier frame, detection having been skipped as
+    /// the scene hadn't changed.
+    pub stale: bool,
+}
+
+/// Runs a detector only on frames that differ from the last one it ran on; see the
+/// [module](self) documentation.
+pub struct MotionGate<D = FaceDetector> {
+    detector: D,
+    config: MotionGateConfig,
+    /// The grayscale copy of the last frame detected, with its dimensions.
+    reference: Option<((usize, usize), Vec<u8>)>,
+    faces: Vec<Face>,
+    stale: u32,
+}
+
+impl<D: DetectionBackend> MotionGate<D> {
+    pub fn new(detector: D, config: MotionGateConfig) -> Self {
+        Self {
+            detector,
+            config,
+            reference: None,
+            faces: Vec::new(),
+            stale: 0,
+        }
+    }
+
+    /// Detects faces in `frame`, or returns those of the last frame detected if the scene
+    /// hasn't changed since.
+    pub fn detect(&mut self, frame: &ImageView) -> Result<GatedFaces, YuNetError> {
+        let thumbnail = self.thumbnail(frame);
+        let dimensions = frame.dimensions();
+        let unchanged = self.reference.as_ref().is_some_and(|(seen, reference)| {
+            *seen == dimensions && !self.changed(reference, &thumbnail)
+        });
+        if unchanged && self.stale < self.config.max_stale {
+            self.stale += 1;
+            return Ok(GatedFaces {
+                faces: self.faces.clone(),
+                stale: true,
+            });
+        }
+        let faces = self.detector.detect_image(frame)?;
+        self.reference = Some((dimensions, thumbnail));
+        self.faces.clone_from(&faces);
+        self.stale = 0;
+        Ok(GatedFaces {
+            faces,
+            stale: false,
+        })
+    }
+
+    /// Stale frames returned since detection last ran.
+    pub fn stale_frames(&self) -> u32 {
+        self.stale
+    }
+
+    /// Forgets the last frame detected, so that detection runs on the next, such as after
+    /// the detector's configuration changed.
+    pub fn reset(&mut self) {
+        self.reference = None;
+    }
+
+    pub fn detector(&mut self) -> &mut D {
+        &mut self.detector
+    }
+
+    pub fn config(&self) -> &MotionGateConfig {
+        &self.config
+    }
+
+    /// A grayscale copy of `frame` downscaled to fit within [`MotionGateConfig::side`].
+    fn thumbnail(&self, frame: &ImageView) -> Vec<u8> {
+        let (width, height) = frame.dimensions();
+        let (w, h) = resample::fit_within(width, height, self.config.side.max(1));
+        let small = resample::resize(frame, w, h);
+        let channels = frame.channels();
+        small
+            .chunks_exact(channels)
+            .map(|pixel| (pixel.iter().map(|&v| v as u32).sum::<u32>() / channels as u32) as u8)
+            .collect()
+    }
+
+    fn changed(&self, reference: &[u8], thumbnail: &[u8]) -> bool {
+        let threshold = self.config.pixel_threshold;
+        let changed = reference
+            .iter()
+            .zip(thumbnail)
+            .filter(|&(a, b)| a.abs_diff(*b) >= threshold)
+            .count();
+        changed as f32 > self.config.min_changed * thumbnail.len() as f32
+    }
+}
+
+impl<D: DetectionBackend> DetectionBackend for MotionGate<D> {
+    fn detect_image(&mut self, image: &ImageView) -> Result<Vec<Face>, YuNetError> {
+        self.detect(image).map(|gated| gated.faces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, ScriptedBackend};
+    use crate::Rect;
+
+    #[test]
+    fn skips_static_frames() {
+        let face = |x| vec![mock::face(Rect::with_size(x, 100.0, 60.0, 70.0), 0.9)];
+        let backend = ScriptedBackend::new([face(100.0), face(300.0), face(300.0)]);
+        let mut gate = MotionGate::new(
+            backend,
+            MotionGateConfig {
+                max_stale: 3,
+                ..Default::default()
+            },
+        );
+        let empty = vec![40; 640 * 480 * 3];
+        // A little sensor noise doesn't count as a change; a person walking in does.
+        let mut noisy = empty.clone();
+        noisy.iter_mut().step_by(7).for_each(|v| *v += 3);
+        let mut person = empty.clone();
+        for row in person.chunks_exact_mut(640 * 3).skip(100).take(200) {
+            row[300 * 3..420 * 3].fill(200);
+        }
+        let view = |pixels| ImageView::new(pixels, 640, 480).unwrap();
+
+        let mut run = |pixels| {
+            let gated = gate.detect(&view(pixels)).unwrap();
+            (gated.stale, gated.faces[0].rectangle().x)
+        };
+        assert_eq!((false, 100.0), run(&empty));
+        assert_eq!((true, 100.0), run(&noisy));
+        assert_eq!((true, 100.0), run(&empty));
+        assert_eq!((false, 300.0), run(&person));
+        // Static for longer than `max_stale`, detection runs anyway.
+        let stale: Vec<bool> = (0..4).map(|_| run(&person).0).collect();
+        assert_eq!(vec![true, true, true, false], stale);
+        assert_eq!(0, gate.detector().remaining());
+    }
+}
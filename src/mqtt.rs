@@ -0,0 +1,412 @@
+//! Publishing presence to an MQTT broker, such as the Mosquitto add-on of Home Assistant, so
+//! that home automations can react to people in front of a camera.
+//!
+//! Under `<base_topic>/<node_id>`, by default `rusty-yunet/rusty_yunet`, go:
+//!
+//! - `availability`: `online` once connected and `offline` once the publisher is dropped or,
+//!   as its last will, its connection is lost.
+//! - `presence`: `ON` when a person appears and `OFF` when they leave, and `OFF` on connecting.
+//! - `count`: the number of faces counting towards presence whenever it changes.
+//! - `event`: `{"event":"appeared"}`, `{"event":"left"}` or
+//!   `{"event":"still_present","seconds":12.0}` for every [`PresenceEvent`].
+//!
+//! All but events are retained. On connecting, Home Assistant discovery configs for an
+//! occupancy binary sensor and a face count sensor are published, retained, under
+//! `<discovery_prefix>/binary_sensor/<node_id>/presence/config` and
+//! `<discovery_prefix>/sensor/<node_id>/count/config`, so that both show up as entities of
+//! one device without any YAML.
+//!
+//! Messages are sent with MQTT 3.1.1 at QoS 0 over plain TCP.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::{Face, PresenceDetector, PresenceEvent};
+
+/// How long to wait for the broker to accept a connection or take a message.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where and as whom an [`MqttPresencePublisher`] publishes.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MqttConfig {
+    /// Identifies the publisher to the broker and the device to Home Assistant, unique per
+    /// camera.
+    pub node_id: String,
+    /// The device name shown in Home Assistant.
+    pub name: String,
+    pub base_topic: String,
+    /// The prefix Home Assistant discovers devices under, or `None` not to announce any.
+    pub discovery_prefix: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// How long the broker waits for a message or ping before dropping the connection.
+    pub keep_alive: Duration,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            node_id: "rusty_yunet".to_owned(),
+            name: "Face detector".to_owned(),
+            base_topic: "rusty-yunet".to_owned(),
+            discovery_prefix: Some("homeassistant".to_owned()),
+            username: None,
+            password: None,
+            keep_alive: Duration::from_secs(60),
+        }
+    }
+}
+
+/// A connection to an MQTT broker publishing the events of a [`PresenceDetector`].
+#[derive(Debug)]
+pub struct MqttPresencePublisher {
+    stream: TcpStream,
+    config: MqttConfig,
+    last_sent: Instant,
+    count: usize,
+}
+
+impl MqttPresencePublisher {
+    /// Connects to the broker at `broker`, such as `"homeassistant.local:1883"`, and
+    /// announces the sensors.
+    pub fn connect(broker: impl ToSocketAddrs, config: MqttConfig) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(broker)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        stream.write_all(&connect_packet(&config))?;
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        match connack {
+            [0x20, 2, _, 0] => {}
+            [0x20, 2, _, code] => {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("MQTT broker refused the connection with code {code}"),
+                ))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "MQTT broker answered without a CONNACK",
+                ))
+            }
+        }
+
+        let mut publisher = Self {
+            stream,
+            config,
+            last_sent: Instant::now(),
+            count: 0,
+        };
+        if let Some(prefix) = &publisher.config.discovery_prefix {
+            let node = &publisher.config.node_id;
+            let binary_sensor = format!("{prefix}/binary_sensor/{node}/presence/config");
+            let sensor = format!("{prefix}/sensor/{node}/count/config");
+            let (presence, count) = discovery(&publisher.config);
+            publisher.publish(&binary_sensor, presence.as_bytes(), true)?;
+            publisher.publish(&sensor, count.as_bytes(), true)?;
+        }
+        publisher.publish(&publisher.topic("availability"), b"online", true)?;
+        publisher.publish(&publisher.topic("presence"), b"OFF", true)?;
+        publisher.publish(&publisher.topic("count"), b"0", true)?;
+        Ok(publisher)
+    }
+
+    pub fn config(&self) -> &MqttConfig {
+        &self.config
+    }
+
+    /// Feeds `presence` the faces of a frame and publishes what changed.
+    pub fn update(
+        &mut self,
+        presence: &mut PresenceDetector,
+        faces: &[Face],
+        timestamp: Duration,
+    ) -> io::Result<Option<PresenceEvent>> {
+        let event = presence.update(faces, timestamp);
+        self.send(presence, event)?;
+        Ok(event)
+    }
+
+    /// Publishes `event`, if any, and the count of `presence` if it changed, or pings the
+    /// broker if nothing was sent in a while. Call it for every frame after updating
+    /// `presence`, to keep the connection alive.
+    pub fn send(
+        &mut self,
+        presence: &PresenceDetector,
+        event: Option<PresenceEvent>,
+    ) -> io::Result<()> {
+        if let Some(event) = event {
+            let (state, json) = match event {
+                PresenceEvent::PersonAppeared => (Some("ON"), r#"{"event":"appeared"}"#.to_owned()),
+                PresenceEvent::PersonLeft => (Some("OFF"), r#"{"event":"left"}"#.to_owned()),
+                PresenceEvent::StillPresent(since) => (
+                    None,
+                    format!(
+                        r#"{{"event":"still_present","seconds":{:.1}}}"#,
+                        since.as_secs_f32()
+                    ),
+                ),
+            };
+            if let Some(state) = state {
+                self.publish(&self.topic("presence"), state.as_bytes(), true)?;
+            }
+            self.publish(&self.topic("event"), json.as_bytes(), false)?;
+        }
+        if presence.count() != self.count {
+            self.count = presence.count();
+            let count = self.count.to_string();
+            self.publish(&self.topic("count"), count.as_bytes(), true)?;
+        }
+        if self.last_sent.elapsed() >= self.config.keep_alive / 2 {
+            self.write(&[0xc0, 0])?;
+        }
+        self.discard_replies()
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}/{name}", self.config.base_topic, self.config.node_id)
+    }
+
+    fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+        let mut body = Vec::with_capacity(2 + topic.len() + payload.len());
+        push_str(&mut body, topic);
+        body.extend_from_slice(payload);
+        self.write(&packet(0x30 | retain as u8, &body))
+    }
+
+    fn write(&mut self, packet: &[u8]) -> io::Result<()> {
+        self.stream.write_all(packet)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Reads away the ping responses, the only packets the broker sends a publisher at QoS
+    /// 0, noticing if it closed the connection.
+    fn discard_replies(&mut self) -> io::Result<()> {
+        self.stream.set_nonblocking(true)?;
+        let mut buffer = [0; 64];
+        let result = loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => break Err(io::ErrorKind::ConnectionAborted.into()),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+}
+
+impl Drop for MqttPresencePublisher {
+    fn drop(&mut self) {
+        // A clean disconnect discards the last will, so announce it here.
+        let _ = self.publish(&self.topic("availability"), b"offline", true);
+        let _ = self.write(&[0xe0, 0]);
+    }
+}
+
+/// Appends an MQTT string: its length as two bytes, then its bytes.
+fn push_str(buffer: &mut Vec<u8>, s: &str) {
+    buffer.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(s.as_bytes());
+}
+
+/// A packet of the given type and flags, with the length of `body` as a variable-length
+/// integer.
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+        packet.push(if length > 0 { byte | 0x80 } else { byte });
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn connect_packet(config: &MqttConfig) -> Vec<u8> {
+    // A clean session with a retained last will of going offline.
+    let mut flags = 0x02 | 0x04 | 0x20;
+    if config.username.is_some() {
+        flags |= 0x80;
+    }
+    if config.password.is_some() {
+        flags |= 0x40;
+    }
+    let mut body = Vec::new();
+    push_str(&mut body, "MQTT");
+    body.push(4);
+    body.push(flags);
+    let keep_alive = config.keep_alive.as_secs().min(u16::MAX as u64) as u16;
+    body.extend_from_slice(&keep_alive.to_be_bytes());
+    push_str(&mut body, &config.node_id);
+    push_str(
+        &mut body,
+        &format!("{}/{}/availability", config.base_topic, config.node_id),
+    );
+    push_str(&mut body, "offline");
+    for credential in [&config.username, &config.password].into_iter().flatten() {
+        push_str(&mut body, credential);
+    }
+    packet(0x10, &body)
+}
+
+/// The Home Assistant discovery configs of the presence and count sensors.
+fn discovery(config: &MqttConfig) -> (String, String) {
+    let base = format!("{}/{}", config.base_topic, config.node_id);
+    let node = json_string(&config.node_id);
+    let common = format!(
+        r#""availability_topic":{},"device":{{"identifiers":[{node}],"name":{},"model":"rusty-yunet","sw_version":"{}"}}"#,
+        json_string(&format!("{base}/availability")),
+        json_string(&config.name),
+        env!("CARGO_PKG_VERSION"),
+    );
+    let presence = format!(
+        r#"{{"name":"Presence","unique_id":{},"device_class":"occupancy","state_topic":{},{common}}}"#,
+        json_string(&format!("{}_presence", config.node_id)),
+        json_string(&format!("{base}/presence")),
+    );
+    let count = format!(
+        r#"{{"name":"Faces","unique_id":{},"state_class":"measurement","state_topic":{},{common}}}"#,
+        json_string(&format!("{}_count", config.node_id)),
+        json_string(&format!("{base}/count")),
+    );
+    (presence, count)
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::detector::RawFace;
+    use crate::PresenceConfig;
+
+    /// Reads a packet off a client: its first byte and its body.
+    fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+        let mut byte = [0];
+        stream.read_exact(&mut byte).ok()?;
+        let header = byte[0];
+        let (mut length, mut shift) = (0, 0);
+        loop {
+            stream.read_exact(&mut byte).ok()?;
+            length |= ((byte[0] & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).ok()?;
+        Some((header, body))
+    }
+
+    #[test]
+    fn publishes_presence_to_broker() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let broker = std::thread::spawn(move || {
+            let (mut client, _) = listener.accept().unwrap();
+            let (header, connect) = read_packet(&mut client).unwrap();
+            assert_eq!(0x10, header);
+            assert_eq!(b"\0\x04MQTT\x04\xe6", &connect[..8]);
+            client.write_all(&[0x20, 2, 0, 0]).unwrap();
+            let mut published = Vec::new();
+            while let Some((header, body)) = read_packet(&mut client) {
+                if header & 0xf0 != 0x30 {
+                    continue;
+                }
+                let length = u16::from_be_bytes([body[0], body[1]]) as usize;
+                let topic = String::from_utf8(body[2..2 + length].to_vec()).unwrap();
+                let payload = String::from_utf8(body[2 + length..].to_vec()).unwrap();
+                published.push((topic, payload, header & 1 == 1));
+            }
+            published
+        });
+
+        let config = MqttConfig {
+            node_id: "door".to_owned(),
+            username: Some("user".to_owned()),
+            password: Some("secret".to_owned()),
+            ..Default::default()
+        };
+        let mut publisher = MqttPresencePublisher::connect(address, config).unwrap();
+        let mut presence = PresenceDetector::new(PresenceConfig {
+            appear_after: Duration::from_secs(1),
+            leave_after: Duration::from_secs(1),
+            ..Default::default()
+        });
+        let raw = RawFace {
+            score: 0.9,
+            x: 10.0,
+            y: 10.0,
+            w: 40.0,
+            h: 40.0,
+            lm: [0.0; 10],
+        };
+        let face = [Face::from_raw_face(&raw, (100, 100))];
+        for (second, faces) in [(0, &face[..]), (1, &face), (2, &[]), (3, &[])] {
+            publisher
+                .update(&mut presence, faces, Duration::from_secs(second))
+                .unwrap();
+        }
+        drop(publisher);
+
+        let published = broker.join().unwrap();
+        let (topic, config, retained) = &published[0];
+        assert_eq!("homeassistant/binary_sensor/door/presence/config", topic);
+        assert!(retained);
+        assert!(config.contains(r#""state_topic":"rusty-yunet/door/presence""#));
+        assert!(config.contains(r#""device_class":"occupancy""#));
+        assert_eq!("homeassistant/sensor/door/count/config", published[1].0);
+        let state = |topic: &str, payload: &str| {
+            (
+                format!("rusty-yunet/door/{topic}"),
+                payload.to_owned(),
+                topic != "event",
+            )
+        };
+        assert_eq!(
+            vec![
+                state("availability", "online"),
+                state("presence", "OFF"),
+                state("count", "0"),
+                state("count", "1"),
+                state("presence", "ON"),
+                state("event", r#"{"event":"appeared"}"#),
+                state("presence", "OFF"),
+                state("event", r#"{"event":"left"}"#),
+                state("count", "0"),
+                state("availability", "offline"),
+            ],
+            published[2..]
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Detecting faces in several cameras at once, such as the two to four of a shop floor or an
+//! installation, sharing a few detectors between them rather than running a whole
+//! [`Pipeline`](crate::Pipeline) per camera.
+//!
+//! Each call to [`MultiSourceDetector::process`] is a round taking the latest frame of any
+//! number of sources. When the round holds more frames than
+//! [`MultiSourceConfig::max_per_round`], the sources take turns, so that none starves.
+//! Faces carry the name of their source in their [`context`](crate::Face::context), and
+//! each source is tracked on its own; presence can be fused across all of them.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::pipeline::FrameResult;
+use crate::tracking::{Tracker, TrackerConfig};
+use crate::{
+    DetectionContext, DetectorConfig, Face, FaceDetectorPool, ImageView, PresenceConfig,
+    PresenceDetector, PresenceEvent, YuNetError,
+};
+
+/// Tuning knobs for a [`MultiSourceDetector`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MultiSourceConfig {
+    /// Detectors shared by the sources, each on its own thread during a round.
+    pub detectors: usize,
+    pub detector: DetectorConfig,
+    /// Configuration of the tracker of each source.
+    pub tracker: TrackerConfig,
+    /// The most frames detected in one round, if bounded, such as to the number of
+    /// detectors to keep rounds as short as one detection.
+    pub max_per_round: Option<usize>,
+    /// Fuse presence over the latest faces of every source with this configuration, so that
+    /// a person counts as present while any camera sees them.
+    pub presence: Option<PresenceConfig>,
+}
+
+impl Default for MultiSourceConfig {
+    fn default() -> Self {
+        Self {
+            detectors: 1,
+            detector: DetectorConfig::default(),
+            tracker: TrackerConfig::default(),
+            max_per_round: None,
+            presence: None,
+        }
+    }
+}
+
+/// Identifies a source added to a [`MultiSourceDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceId(usize);
+
+/// The faces a round found in the frame of one source.
+#[derive(Debug)]
+pub struct SourceResult {
+    pub source: SourceId,
+    /// The frame's result, with the index and track IDs of its source, or why it failed.
+    /// One source failing doesn't fail the others.
+    pub result: Result<FrameResult, YuNetError>,
+}
+
+/// What a [`MultiSourceDetector::process`] round did.
+#[derive(Debug, Default)]
+pub struct MultiSourceRound {
+    /// A result per frame detected, in the order of the sources.
+    pub results: Vec<SourceResult>,
+    /// The sources whose frames waited for a later round.
+    pub skipped: Vec<SourceId>,
+    /// The change in fused presence, if [`MultiSourceConfig::presence`] is set.
+    pub presence: Option<PresenceEvent>,
+}
+
+struct Source {
+    name: String,
+    tracker: Tracker,
+    /// The faces of the last frame detected, for fused presence.
+    faces: Vec<Face>,
+}
+
+/// Detects and tracks faces in the frames of several sources over a shared pool of
+/// detectors.
+pub struct MultiSourceDetector {
+    pool: FaceDetectorPool,
+    config: MultiSourceConfig,
+    sources: Vec<Source>,
+    /// The source first in line in the next round.
+    next: usize,
+    presence: Option<PresenceDetector>,
+    /// The latest timestamp fed to `presence`, which must not decrease.
+    presence_time: Duration,
+}
+
+impl MultiSourceDetector {
+    /// Fails if the configured backend isn't available in this build.
+    pub fn new(config: MultiSourceConfig) -> Result<Self, YuNetError> {
+        Ok(Self {
+            pool: FaceDetectorPool::with_config(config.detectors, config.detector.clone())?,
+            presence: config.presence.clone().map(PresenceDetector::new),
+            config,
+            sources: Vec::new(),
+            next: 0,
+            presence_time: Duration::ZERO,
+        })
+    }
+
+    /// Adds a source, whose name is recorded in the context of its faces.
+    pub fn add_source(&mut self, name: impl Into<String>) -> SourceId {
+        self.sources.push(Source {
+            name: name.into(),
+            tracker: Tracker::new(self.config.tracker.clone()),
+            faces: Vec::new(),
+        });
+        SourceId(self.sources.len() - 1)
+    }
+
+    /// Detects faces in the latest frame of any number of sources, each presented at its
+    /// timestamp, which must not decrease from one round to the next for the same source.
+    /// Of several frames of one source, only the last is detected.
+    ///
+    /// Panics if a source wasn't added to this detector.
+    pub fn process(&mut self, frames: &[(SourceId, ImageView, Duration)]) -> MultiSourceRound {
+        let mut latest: Vec<Option<(&ImageView, Duration)>> = vec![None; self.sources.len()];
+        for (source, image, timestamp) in frames {
+            latest[source.0] = Some((image, *timestamp));
+        }
+        // Take the sources in turn from the first in line, wrapping around.
+        let count = self.sources.len();
+        let mut queued: Vec<usize> = (0..count)
+            .map(|i| (self.next + i) % count)
+            .filter(|&i| latest[i].is_some())
+            .collect();
+        let limit = self.config.max_per_round.unwrap_or(usize::MAX).max(1);
+        let mut skipped: Vec<SourceId> = queued
+            .split_off(limit.min(queued.len()))
+            .into_iter()
+            .map(SourceId)
+            .collect();
+        skipped.sort();
+        if let Some(&last) = queued.last() {
+            self.next = (last + 1) % count;
+        }
+
+        let jobs: Vec<(usize, &ImageView, DetectionContext)> = queued
+            .iter()
+            .map(|&i| {
+                let (image, timestamp) = latest[i].expect("queued sources have a frame");
+                let context = DetectionContext {
+                    frame_index: Some(self.sources[i].tracker.frame()),
+                    timestamp: Some(timestamp),
+                    source: Some(self.sources[i].name.clone()),
+                };
+                (i, image, context)
+            })
+            .collect();
+        let detected = self.detect_all(&jobs);
+
+        let mut results: Vec<SourceResult> = detected
+            .into_iter()
+            .zip(&jobs)
+            .map(|(faces, &(i, _, ref context))| {
+                let source = &mut self.sources[i];
+                let result = faces.map(|faces| {
+                    let index = source.tracker.frame();
+                    let track_ids = source.tracker.update_at(&faces, context.timestamp);
+                    source.faces.clone_from(&faces);
+                    FrameResult {
+                        index,
+                        timestamp: source.tracker.timestamp(),
+                        faces,
+                        track_ids,
+                    }
+                });
+                SourceResult {
+                    source: SourceId(i),
+                    result,
+                }
+            })
+            .collect();
+        results.sort_by_key(|result| result.source);
+
+        let presence = self.presence.as_mut().and_then(|presence| {
+            let newest = jobs.iter().filter_map(|(_, _, context)| context.timestamp);
+            self.presence_time = newest.fold(self.presence_time, Duration::max);
+            let faces: Vec<Face> = self
+                .sources
+                .iter()
+                .flat_map(|source| source.faces.iter().cloned())
+                .collect();
+            presence.update(&faces, self.presence_time)
+        });
+        MultiSourceRound {
+            results,
+            skipped,
+            presence,
+        }
+    }
+
+    /// Detects in every image, spread over the detectors of the pool, each on its own
+    /// thread.
+    fn detect_all(
+        &self,
+        jobs: &[(usize, &ImageView, DetectionContext)],
+    ) -> Vec<Result<Vec<Face>, YuNetError>> {
+        let results = Mutex::new(vec![None; jobs.len()]);
+        let next = AtomicUsize::new(0);
+        let work = || {
+            let mut detector = self.pool.get();
+            loop {
+                let job = next.fetch_add(1, Ordering::Relaxed);
+                let Some((_, image, context)) = jobs.get(job) else {
+                    break;
+                };
+                let faces = detector.request(image).context(context.clone()).run();
+                results.lock().unwrap_or_else(|e| e.into_inner())[job] = Some(faces);
+            }
+        };
+        let threads = self.pool.size().min(jobs.len());
+        if threads > 1 {
+            std::thread::scope(|scope| {
+                for _ in 0..threads {
+                    scope.spawn(work);
+                }
+            });
+        } else if threads == 1 {
+            work();
+        }
+        results
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .into_iter()
+            .map(|faces| faces.expect("every job is run"))
+            .collect()
+    }
+
+    pub fn source_name(&self, source: SourceId) -> &str {
+        &self.sources[source.0].name
+    }
+
+    pub fn tracker(&self, source: SourceId) -> &Tracker {
+        &self.sources[source.0].tracker
+    }
+
+    /// The fused presence, if [`MultiSourceConfig::presence`] is set.
+    pub fn presence(&self) -> Option<&PresenceDetector> {
+        self.presence.as_ref()
+    }
+
+    pub fn config(&self) -> &MultiSourceConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_turns_between_sources() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let frame = ImageView::new(image.as_raw(), 806, 605).unwrap();
+        let blank = vec![0; 320 * 240 * 3];
+        let empty = ImageView::new(&blank, 320, 240).unwrap();
+        let mut detector = MultiSourceDetector::new(MultiSourceConfig {
+            detectors: 2,
+            max_per_round: Some(2),
+            presence: Some(PresenceConfig {
+                appear_after: Duration::ZERO,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        let door = detector.add_source("door");
+        let till = detector.add_source("till");
+        let aisle = detector.add_source("aisle");
+
+        let at = Duration::from_millis;
+        let round = detector.process(&[
+            (door, empty, at(0)),
+            (till, frame, at(0)),
+            (aisle, empty, at(0)),
+        ]);
+        assert_eq!(vec![aisle], round.skipped);
+        assert_eq!(
+            vec![door, till],
+            round.results.iter().map(|r| r.source).collect::<Vec<_>>()
+        );
+        let faces = &round.results[1].result.as_ref().unwrap().faces;
+        assert_eq!(2, faces.len());
+        let context = faces[0].context().unwrap();
+        assert_eq!(Some("till"), context.source.as_deref());
+        assert_eq!(Some(PresenceEvent::PersonAppeared), round.presence);
+
+        // The skipped source goes first next time; the faces of the till still count
+        // towards presence while it waits.
+        let round = detector.process(&[
+            (door, empty, at(100)),
+            (till, frame, at(100)),
+            (aisle, empty, at(100)),
+        ]);
+        assert_eq!(vec![till], round.skipped);
+        assert_eq!(
+            vec![door, aisle],
+            round.results.iter().map(|r| r.source).collect::<Vec<_>>()
+        );
+        assert!(detector.presence().unwrap().is_present());
+        assert_eq!(2, detector.tracker(door).frame());
+        assert_eq!(1, detector.tracker(till).frame());
+        assert_eq!(1, detector.tracker(aisle).frame());
+        assert_eq!("aisle", detector.source_name(aisle));
+    }
+}
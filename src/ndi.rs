@@ -0,0 +1,217 @@
+//! Publishing frames, such as annotated ones, as an NDI source on the local network, for
+//! media servers like Resolume, TouchDesigner or vMix to ingest directly.
+//!
+//! The NDI runtime is proprietary and installed separately, from <https://ndi.video>. It is
+//! loaded when the first sender is created, from the directory named by
+//! `NDI_RUNTIME_DIR_V6` or `NDI_RUNTIME_DIR_V5` if set, or else from the library search
+//! path.
+
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::io;
+use std::path::PathBuf;
+use std::ptr;
+
+use image::RgbImage;
+use libloading::Library;
+
+use crate::drawing::{annotate_faces, Theme};
+use crate::Face;
+
+#[cfg(target_os = "windows")]
+const LIBRARY_NAMES: &[&str] = &["Processing.NDI.Lib.x64.dll"];
+#[cfg(target_os = "macos")]
+const LIBRARY_NAMES: &[&str] = &["libndi.dylib", "/usr/local/lib/libndi.dylib"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_NAMES: &[&str] = &["libndi.so.6", "libndi.so.5", "libndi.so"];
+
+/// `NDIlib_FourCC_video_type_BGRX`.
+const FOURCC_BGRX: c_int = i32::from_le_bytes(*b"BGRX");
+/// `NDIlib_frame_format_type_progressive`.
+const FRAME_FORMAT_PROGRESSIVE: c_int = 1;
+/// `NDIlib_send_timecode_synthesize`.
+const TIMECODE_SYNTHESIZE: i64 = i64::MAX;
+
+/// `NDIlib_send_create_t`.
+#[repr(C)]
+struct SendCreate {
+    name: *const c_char,
+    groups: *const c_char,
+    clock_video: bool,
+    clock_audio: bool,
+}
+
+/// `NDIlib_video_frame_v2_t`.
+#[repr(C)]
+struct VideoFrame {
+    xres: c_int,
+    yres: c_int,
+    fourcc: c_int,
+    frame_rate_n: c_int,
+    frame_rate_d: c_int,
+    picture_aspect_ratio: f32,
+    frame_format_type: c_int,
+    timecode: i64,
+    data: *mut u8,
+    line_stride_in_bytes: c_int,
+    metadata: *const c_char,
+    timestamp: i64,
+}
+
+type Initialize = unsafe extern "C" fn() -> bool;
+type SendCreateFn = unsafe extern "C" fn(*const SendCreate) -> *mut c_void;
+type SendVideo = unsafe extern "C" fn(*mut c_void, *const VideoFrame);
+type SendDestroy = unsafe extern "C" fn(*mut c_void);
+
+/// An NDI source that frames are sent to as they come, unclocked.
+pub struct NdiSender {
+    instance: *mut c_void,
+    send_video: SendVideo,
+    destroy: SendDestroy,
+    frame_rate: (i32, i32),
+    bgrx: Vec<u8>,
+    /// Keeps the functions above loaded.
+    _library: Library,
+}
+
+// SAFETY: NDI send instances may be used from any thread, one call at a time, which
+// `&mut self` guarantees.
+unsafe impl Send for NdiSender {}
+
+impl NdiSender {
+    /// Announces a source called `name` on the network. Fails with
+    /// [`io::ErrorKind::NotFound`] if the NDI runtime isn't installed.
+    pub fn new(name: &str) -> io::Result<Self> {
+        let name =
+            CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let library = load_library()?;
+        let other = |message: &str| io::Error::other(message.to_owned());
+        // SAFETY: the signatures match those of the NDI SDK headers, versions 5 and 6.
+        unsafe {
+            let symbol_error = |e: libloading::Error| other(&e.to_string());
+            let initialize = *library
+                .get::<Initialize>(b"NDIlib_initialize\0")
+                .map_err(symbol_error)?;
+            let create = *library
+                .get::<SendCreateFn>(b"NDIlib_send_create\0")
+                .map_err(symbol_error)?;
+            let send_video = *library
+                .get::<SendVideo>(b"NDIlib_send_send_video_v2\0")
+                .map_err(symbol_error)?;
+            let destroy = *library
+                .get::<SendDestroy>(b"NDIlib_send_destroy\0")
+                .map_err(symbol_error)?;
+            if !initialize() {
+                return Err(other("NDI isn't supported on this CPU"));
+            }
+            let instance = create(&SendCreate {
+                name: name.as_ptr(),
+                groups: ptr::null(),
+                clock_video: false,
+                clock_audio: false,
+            });
+            if instance.is_null() {
+                return Err(other("failed to create the NDI sender"));
+            }
+            Ok(Self {
+                instance,
+                send_video,
+                destroy,
+                frame_rate: (30, 1),
+                bgrx: Vec::new(),
+                _library: library,
+            })
+        }
+    }
+
+    /// The frame rate receivers are told to expect, as a fraction such as 30000/1001.
+    /// Defaults to 30.
+    pub fn with_frame_rate(mut self, numerator: i32, denominator: i32) -> Self {
+        self.frame_rate = (numerator, denominator.max(1));
+        self
+    }
+
+    pub fn send(&mut self, image: &RgbImage) {
+        to_bgrx(image, &mut self.bgrx);
+        let frame = VideoFrame {
+            xres: image.width() as c_int,
+            yres: image.height() as c_int,
+            fourcc: FOURCC_BGRX,
+            frame_rate_n: self.frame_rate.0,
+            frame_rate_d: self.frame_rate.1,
+            picture_aspect_ratio: 0.0,
+            frame_format_type: FRAME_FORMAT_PROGRESSIVE,
+            timecode: TIMECODE_SYNTHESIZE,
+            data: self.bgrx.as_mut_ptr(),
+            line_stride_in_bytes: 4 * image.width() as c_int,
+            metadata: ptr::null(),
+            timestamp: 0,
+        };
+        // SAFETY: the synchronous send copies the frame before returning.
+        unsafe { (self.send_video)(self.instance, &frame) }
+    }
+
+    /// Sends a copy of `image` with `faces` drawn on it.
+    pub fn send_annotated(&mut self, image: &RgbImage, faces: &[Face], theme: &Theme) {
+        let mut annotated = image.clone();
+        annotate_faces(&mut annotated, faces, theme);
+        self.send(&annotated);
+    }
+}
+
+impl Drop for NdiSender {
+    fn drop(&mut self) {
+        // SAFETY: the instance came from `NDIlib_send_create` and is destroyed only here.
+        unsafe { (self.destroy)(self.instance) }
+    }
+}
+
+/// Whether the NDI runtime can be loaded, for [`capabilities`](crate::capabilities).
+pub(crate) fn runtime_available() -> io::Result<()> {
+    load_library().map(drop)
+}
+
+fn load_library() -> io::Result<Library> {
+    let runtime_dirs = ["NDI_RUNTIME_DIR_V6", "NDI_RUNTIME_DIR_V5"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .flat_map(|dir| {
+            LIBRARY_NAMES
+                .iter()
+                .map(move |name| PathBuf::from(&dir).join(name))
+        });
+    let candidates = runtime_dirs.chain(LIBRARY_NAMES.iter().map(PathBuf::from));
+    for candidate in candidates {
+        // SAFETY: loading the NDI runtime runs no initializers with preconditions.
+        if let Ok(library) = unsafe { Library::new(&candidate) } {
+            return Ok(library);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "the NDI runtime isn't installed",
+    ))
+}
+
+fn to_bgrx(image: &RgbImage, bgrx: &mut Vec<u8>) {
+    bgrx.clear();
+    bgrx.extend(image.pixels().flat_map(|p| [p[2], p[1], p[0], 255]));
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+
+    #[test]
+    fn converts_frames_to_bgrx() {
+        let image = RgbImage::from_pixel(2, 1, Rgb([1, 2, 3]));
+        let mut bgrx = vec![9; 100];
+        to_bgrx(&image, &mut bgrx);
+        assert_eq!(vec![3, 2, 1, 255, 3, 2, 1, 255], bgrx);
+        assert_eq!(b"BGRX", &FOURCC_BGRX.to_le_bytes());
+
+        let error = NdiSender::new("nul\0name").err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+    }
+}
@@ -0,0 +1,118 @@
+//! Turning frames upright before detection, for rotated camera mounts, phone photos and
+//! mirrored selfie previews.
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::io::ImageView;
+
+/// A clockwise rotation by a multiple of 90 degrees.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+/// How a frame is transformed before detection: mirrored horizontally, then rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Orientation {
+    pub(crate) rotation: Rotation,
+    pub(crate) mirror: bool,
+    /// Dimensions (width, height) of the original frame.
+    pub(crate) dimensions: (usize, usize),
+}
+
+impl Orientation {
+    pub(crate) fn is_identity(&self) -> bool {
+        self.rotation == Rotation::None && !self.mirror
+    }
+
+    /// Dimensions (width, height) of the transformed frame.
+    pub(crate) fn oriented_dimensions(&self) -> (usize, usize) {
+        let (width, height) = self.dimensions;
+        match self.rotation {
+            Rotation::Cw90 | Rotation::Cw270 => (height, width),
+            Rotation::None | Rotation::Cw180 => (width, height),
+        }
+    }
+
+    /// A tightly packed copy of `image` transformed as described, written into `dst`,
+    /// reusing its allocation.
+    pub(crate) fn apply_into(&self, image: &ImageView, dst: &mut Vec<u8>) {
+        let (width, height) = self.dimensions;
+        let (out_width, out_height) = self.oriented_dimensions();
+        let (src, stride, channels) = (image.data(), image.stride(), image.channels());
+        dst.clear();
+        dst.reserve(out_width * out_height * channels);
+        for v in 0..out_height {
+            for u in 0..out_width {
+                let (x, y) = match self.rotation {
+                    Rotation::None => (u, v),
+                    Rotation::Cw90 => (v, height - 1 - u),
+                    Rotation::Cw180 => (width - 1 - u, height - 1 - v),
+                    Rotation::Cw270 => (width - 1 - v, u),
+                };
+                let x = if self.mirror { width - 1 - x } else { x };
+                dst.extend_from_slice(&src[y * stride + channels * x..][..channels]);
+            }
+        }
+    }
+
+    /// Maps a point of the transformed frame back to the original frame.
+    pub(crate) fn restore(&self, p: Vec2) -> Vec2 {
+        let (width, height) = (self.dimensions.0 as f32, self.dimensions.1 as f32);
+        let p = match self.rotation {
+            Rotation::None => p,
+            Rotation::Cw90 => Vec2::new(p.y, height - p.x),
+            Rotation::Cw180 => Vec2::new(width - p.x, height - p.y),
+            Rotation::Cw270 => Vec2::new(width - p.y, p.x),
+        };
+        if self.mirror {
+            Vec2::new(width - p.x, p.y)
+        } else {
+            p
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_transformed_pixels() {
+        // 3x2 pixels, each holding its own coordinates.
+        let bytes: Vec<u8> = (0..2u8)
+            .flat_map(|y| (0..3u8).flat_map(move |x| [x, y, 0]))
+            .collect();
+        let image = ImageView::new(&bytes, 3, 2).unwrap();
+        for rotation in [
+            Rotation::None,
+            Rotation::Cw90,
+            Rotation::Cw180,
+            Rotation::Cw270,
+        ] {
+            for mirror in [false, true] {
+                let orientation = Orientation {
+                    rotation,
+                    mirror,
+                    dimensions: (3, 2),
+                };
+                let (width, _) = orientation.oriented_dimensions();
+                let mut oriented = Vec::new();
+                orientation.apply_into(&image, &mut oriented);
+                for (i, pixel) in oriented.chunks(3).enumerate() {
+                    // The center of each transformed pixel maps to the center of its source.
+                    let center = Vec2::new((i % width) as f32, (i / width) as f32) + 0.5;
+                    let source = orientation.restore(center) - 0.5;
+                    assert_eq!(Vec2::new(pixel[0] as f32, pixel[1] as f32), source);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Sending faces as Open Sound Control messages over UDP, the lingua franca of Max/MSP,
+//! TouchDesigner, Pure Data and most other tools interactive installations are built in.
+//!
+//! Each frame goes out as one bundle, so that receivers never see half a frame:
+//!
+//! - `<prefix>/frame ii`: the frame index and the number of faces, then per face
+//! - `<prefix>/face if ffff ffffffffff`: the track ID, the confidence, the rectangle as x, y,
+//!   width and height, and the landmarks as x, y pairs in the order of [`FaceLandmarks`].
+//!
+//! Coordinates are normalized to 0..1 of the frame, y down, unless the sink is given another
+//! convention with [`with_convention`](OscSink::with_convention). Indices and IDs wrap to 32
+//! bits.
+//!
+//! [`FaceLandmarks`]: crate::FaceLandmarks
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::pipeline::FrameResult;
+use crate::{CoordinateConvention, Face, Origin, Units};
+
+/// A UDP socket sending faces to a set of OSC receivers.
+#[derive(Debug)]
+pub struct OscSink {
+    socket: UdpSocket,
+    targets: Vec<SocketAddr>,
+    prefix: String,
+    convention: CoordinateConvention,
+}
+
+impl OscSink {
+    /// Sends to every address `targets` resolves to, such as `"127.0.0.1:7000"` or a slice
+    /// of addresses, under the address prefix `/yunet`.
+    pub fn new(targets: impl ToSocketAddrs) -> io::Result<Self> {
+        let targets: Vec<SocketAddr> = targets.to_socket_addrs()?.collect();
+        let any = match targets.first() {
+            Some(SocketAddr::V6(_)) => "[::]:0",
+            _ => "0.0.0.0:0",
+        };
+        Ok(Self {
+            socket: UdpSocket::bind(any)?,
+            targets,
+            prefix: "/yunet".to_owned(),
+            convention: CoordinateConvention::new(Origin::TopLeft, Units::Normalized),
+        })
+    }
+
+    /// Replaces the `/yunet` prefix of message addresses, such as to tell several cameras
+    /// apart. It should start with a slash.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sends rectangles and landmarks in `convention`, such as -1..1 from the center of the
+    /// frame for shaders, rather than normalized from the top-left corner.
+    pub fn with_convention(mut self, convention: CoordinateConvention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    pub fn targets(&self) -> &[SocketAddr] {
+        &self.targets
+    }
+
+    /// Sends the faces of a pipeline frame, with their track IDs.
+    pub fn send(&self, result: &FrameResult) -> io::Result<()> {
+        self.send_bundle(
+            result.index,
+            &result.faces,
+            result.track_ids.iter().copied(),
+        )
+    }
+
+    /// Sends the faces of a frame without tracking, with their positions in `faces` as IDs.
+    pub fn send_faces(&self, frame: u64, faces: &[Face]) -> io::Result<()> {
+        self.send_bundle(frame, faces, 0..)
+    }
+
+    fn send_bundle(
+        &self,
+        frame: u64,
+        faces: &[Face],
+        ids: impl Iterator<Item = u64>,
+    ) -> io::Result<()> {
+        let mut messages = vec![message(
+            &format!("{}/frame", self.prefix),
+            &[Arg::Int(frame as i32), Arg::Int(faces.len() as i32)],
+        )];
+        let face_address = format!("{}/face", self.prefix);
+        for (face, id) in faces.iter().zip(ids) {
+            let rect = face.rectangle_in(self.convention);
+            let mut args = vec![
+                Arg::Int(id as i32),
+                Arg::Float(face.confidence()),
+                Arg::Float(rect.x),
+                Arg::Float(rect.y),
+                Arg::Float(rect.w),
+                Arg::Float(rect.h),
+            ];
+            for point in face.landmarks_in(self.convention).as_array() {
+                args.extend([Arg::Float(point.x), Arg::Float(point.y)]);
+            }
+            messages.push(message(&face_address, &args));
+        }
+        let packet = bundle(&messages);
+        for target in &self.targets {
+            self.socket.send_to(&packet, target)?;
+        }
+        Ok(())
+    }
+}
+
+enum Arg {
+    Int(i32),
+    Float(f32),
+}
+
+/// Appends an OSC string: its bytes, a terminating nul and padding to a multiple of four.
+fn push_str(buffer: &mut Vec<u8>, s: &str) {
+    buffer.extend_from_slice(s.as_bytes());
+    buffer.resize((buffer.len() + 4) & !3, 0);
+}
+
+fn message(address: &str, args: &[Arg]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    push_str(&mut buffer, address);
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            Arg::Int(_) => 'i',
+            Arg::Float(_) => 'f',
+        }))
+        .collect();
+    push_str(&mut buffer, &tags);
+    for arg in args {
+        match arg {
+            Arg::Int(v) => buffer.extend_from_slice(&v.to_be_bytes()),
+            Arg::Float(v) => buffer.extend_from_slice(&v.to_be_bytes()),
+        }
+    }
+    buffer
+}
+
+/// A bundle of messages to be handled immediately.
+fn bundle(messages: &[Vec<u8>]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    push_str(&mut buffer, "#bundle");
+    buffer.extend_from_slice(&1u64.to_be_bytes());
+    for message in messages {
+        buffer.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        buffer.extend_from_slice(message);
+    }
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    #[test]
+    fn sends_bundles() {
+        assert_eq!(
+            b"/a\0\0,if\0\0\0\0\x07\x3f\x80\0\0".to_vec(),
+            message("/a", &[Arg::Int(7), Arg::Float(1.0)])
+        );
+
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sink = OscSink::new(receiver.local_addr().unwrap())
+            .unwrap()
+            .with_prefix("/cam1");
+        let raw = RawFace {
+            score: 0.5,
+            x: 10.0,
+            y: 20.0,
+            w: 30.0,
+            h: 40.0,
+            lm: [50.0; 10],
+        };
+        let face = Face::from_raw_face(&raw, (100, 200));
+        sink.send_faces(3, &[face]).unwrap();
+
+        let mut packet = [0; 1024];
+        let len = receiver.recv(&mut packet).unwrap();
+        let frame = message("/cam1/frame", &[Arg::Int(3), Arg::Int(1)]);
+        let face_address = b"/cam1/face\0\0,ifffffffffffffff\0\0\0";
+        let face_len = face_address.len() + 4 * 16;
+        assert_eq!(16 + 4 + frame.len() + 4 + face_len, len);
+        assert!(packet.starts_with(b"#bundle\0\0\0\0\0\0\0\0\x01"));
+        assert_eq!(frame, packet[20..20 + frame.len()]);
+        let face = &packet[len - face_len..len];
+        assert!(face.starts_with(face_address));
+        // The normalized x of the rectangle, 10 of 100 pixels.
+        let x = &face[face_address.len() + 8..][..4];
+        assert_eq!(0.1f32.to_be_bytes(), x);
+    }
+}
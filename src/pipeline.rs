@@ -0,0 +1,469 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hooks::HookChain;
+use crate::tracking::{Tracker, TrackerConfig};
+use crate::{
+    CancellationToken, DetectionBackend, DetectionContext, DetectorConfig, Face, FaceDetector,
+    ImageView, YuNetError,
+};
+
+/// The faces found in one frame of a stream.
+#[derive(Debug, Clone)]
+pub struct FrameResult {
+    /// Position of the frame in the stream, starting at zero.
+    pub index: u64,
+    /// When the frame is presented, if given or interpolated from earlier frames.
+    pub timestamp: Option<Duration>,
+    pub faces: Vec<Face>,
+    /// The track ID of each face, in the same order.
+    pub track_ids: Vec<u64>,
+}
+
+/// The state a pipeline carries from one frame to the next, so that an interrupted run can
+/// resume mid-stream and report exactly what an uninterrupted run would have.
+///
+/// Where the run's outputs stood go in the checkpoint too, as a byte offset per named
+/// sink, so that what the interrupted run wrote after it can be cut off on resuming:
+///
+/// ```no_run
+/// # use rusty_yunet::csv::CsvWriter;
+/// # use rusty_yunet::{FaceDetector, Pipeline};
+/// # fn run(pipeline: Pipeline, mut csv: CsvWriter<std::fs::File>) -> std::io::Result<()> {
+/// let checkpoint = pipeline.checkpoint().with_sink("csv", csv.position()?);
+/// // After an interruption:
+/// let csv = CsvWriter::resume(checkpoint.reopen_sink("csv", "faces.csv")?);
+/// let pipeline = Pipeline::resume(FaceDetector::new(), checkpoint);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// With the `serde_support` feature, checkpoints can be serialized, such as to a file
+/// rewritten every few hundred frames.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    tracker: Tracker,
+    /// Byte offsets of the sinks, by name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    sinks: BTreeMap<String, u64>,
+}
+
+impl Checkpoint {
+    /// Index of the first frame to process after resuming. Results the interrupted run
+    /// already wrote for this frame or later ones must be discarded, which
+    /// [`reopen_sink`](Self::reopen_sink) does for sinks writing to files.
+    pub fn next_frame(&self) -> u64 {
+        self.tracker.frame()
+    }
+
+    /// Records that the sink called `name` had written `offset` bytes when the checkpoint
+    /// was taken, replacing any offset recorded for it before. Flush the sink first.
+    pub fn with_sink(mut self, name: impl Into<String>, offset: u64) -> Self {
+        self.sinks.insert(name.into(), offset);
+        self
+    }
+
+    /// The offset recorded for the sink called `name`.
+    pub fn sink_offset(&self, name: &str) -> Option<u64> {
+        self.sinks.get(name).copied()
+    }
+
+    /// Opens the file at `path` the sink called `name` writes to, truncated to the offset
+    /// recorded for it and positioned at its end, so that writing on produces the bytes an
+    /// uninterrupted run would have.
+    ///
+    /// Fails with [`io::ErrorKind::NotFound`] if no offset was recorded for `name`, and with
+    /// [`io::ErrorKind::UnexpectedEof`] if the file is shorter than it.
+    pub fn reopen_sink(&self, name: &str, path: impl AsRef<Path>) -> io::Result<File> {
+        let offset = self.sink_offset(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no offset for sink {name}"),
+            )
+        })?;
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        if file.metadata()?.len() < offset {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("sink {name} is shorter than its offset of {offset} bytes"),
+            ));
+        }
+        file.set_len(offset)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(file)
+    }
+}
+
+/// Detects and tracks faces over consecutive frames of a video stream, with a
+/// [`FaceDetector`] or, in tests, another [`DetectionBackend`].
+pub struct Pipeline<D = FaceDetector> {
+    detector: D,
+    tracker: Tracker,
+    hooks: HookChain,
+    source: Option<String>,
+}
+
+impl<D: DetectionBackend> Pipeline<D> {
+    pub fn new(detector: D, tracker: TrackerConfig) -> Self {
+        Self {
+            detector,
+            tracker: Tracker::new(tracker),
+            hooks: HookChain::new(),
+            source: None,
+        }
+    }
+
+    /// Continues a run from a checkpoint, starting with frame
+    /// [`next_frame`](Checkpoint::next_frame). The tracker configuration comes with the
+    /// checkpoint.
+    pub fn resume(detector: D, checkpoint: Checkpoint) -> Self {
+        Self {
+            detector,
+            tracker: checkpoint.tracker,
+            hooks: HookChain::new(),
+            source: None,
+        }
+    }
+
+    /// Runs `hooks` on the faces of every frame, before they are tracked.
+    pub fn with_hooks(mut self, hooks: HookChain) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Records the source, with the index and timestamp of the frame, in every face; see
+    /// [`Face::context`]. For merging the faces of several cameras into one stream.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// The state after the frames processed so far, without sink offsets; add them with
+    /// [`Checkpoint::with_sink`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            tracker: self.tracker.clone(),
+            sinks: BTreeMap::new(),
+        }
+    }
+
+    /// Processes the next frame of the stream.
+    pub fn process(&mut self, frame: &ImageView) -> Result<FrameResult, YuNetError> {
+        self.process_at(frame, None)
+    }
+
+    /// Processes the next frame of the stream, presented at `timestamp`. Pass the decoded
+    /// presentation timestamps of variable frame rate video, so that track dwell times and
+    /// velocities follow real time; see [`Tracker::update_at`].
+    pub fn process_at(
+        &mut self,
+        frame: &ImageView,
+        timestamp: Option<Duration>,
+    ) -> Result<FrameResult, YuNetError> {
+        let mut faces = self.detector.detect_image(frame)?;
+        if let Some(source) = &self.source {
+            let context = Arc::new(DetectionContext {
+                frame_index: Some(self.tracker.frame()),
+                timestamp,
+                source: Some(source.clone()),
+            });
+            for face in &mut faces {
+                face.set_context(Arc::clone(&context));
+            }
+        }
+        self.hooks.run(frame, &mut faces);
+        Ok(track(&mut self.tracker, faces, timestamp))
+    }
+
+    /// Processes every frame in turn, stopping at the first error.
+    pub fn run<'a>(
+        &mut self,
+        frames: impl IntoIterator<Item = ImageView<'a>>,
+    ) -> Result<Vec<FrameResult>, YuNetError> {
+        self.run_with_cancel(frames, &CancellationToken::new())
+    }
+
+    /// Like [`run`](Self::run), stopping with [`YuNetError::Cancelled`] before the next
+    /// frame once `cancel` is cancelled.
+    pub fn run_with_cancel<'a>(
+        &mut self,
+        frames: impl IntoIterator<Item = ImageView<'a>>,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<FrameResult>, YuNetError> {
+        frames
+            .into_iter()
+            .map(|frame| cancel.check().and_then(|()| self.process(&frame)))
+            .collect()
+    }
+
+    pub fn detector(&mut self) -> &mut D {
+        &mut self.detector
+    }
+
+    pub fn tracker(&self) -> &Tracker {
+        &self.tracker
+    }
+}
+
+/// Tuning knobs for a [`ThroughputPipeline`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ThroughputConfig {
+    /// Frames queued before a batch is processed.
+    pub batch_size: usize,
+    /// Detectors working on a batch in parallel, each on its own thread.
+    pub workers: usize,
+    pub detector: DetectorConfig,
+    pub tracker: TrackerConfig,
+}
+
+impl Default for ThroughputConfig {
+    fn default() -> Self {
+        let workers = std::thread::available_parallelism().map_or(1, usize::from);
+        Self {
+            batch_size: 8 * workers,
+            workers,
+            detector: DetectorConfig::default(),
+            tracker: TrackerConfig::default(),
+        }
+    }
+}
+
+/// Like [`Pipeline`], but for archive processing rather than live streams: frames are
+/// queued and detected in batches spread over several detectors, trading latency for
+/// frames per second. Tracking still sees the frames in order, so the results are the same.
+pub struct ThroughputPipeline {
+    detectors: Vec<FaceDetector>,
+    tracker: Tracker,
+    batch_size: usize,
+    queue: Vec<QueuedFrame>,
+}
+
+struct QueuedFrame {
+    bgr: Vec<u8>,
+    width: usize,
+    height: usize,
+    timestamp: Option<Duration>,
+}
+
+impl ThroughputPipeline {
+    /// Both the batch size and the worker count are raised to at least one.
+    pub fn new(config: ThroughputConfig) -> Result<Self, YuNetError> {
+        let detectors = (0..config.workers.max(1))
+            .map(|_| FaceDetector::with_config(config.detector.clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            detectors,
+            tracker: Tracker::new(config.tracker),
+            batch_size: config.batch_size.max(1),
+            queue: Vec::new(),
+        })
+    }
+
+    /// Continues a run from a checkpoint, starting with frame
+    /// [`next_frame`](Checkpoint::next_frame). `config.tracker` is ignored in favour of the
+    /// configuration saved with the checkpoint.
+    pub fn resume(config: ThroughputConfig, checkpoint: Checkpoint) -> Result<Self, YuNetError> {
+        let mut pipeline = Self::new(config)?;
+        pipeline.tracker = checkpoint.tracker;
+        Ok(pipeline)
+    }
+
+    /// The state after the last processed batch, without sink offsets; add them with
+    /// [`Checkpoint::with_sink`]. Queued frames aren't part of it, and must be pushed again
+    /// after resuming.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            tracker: self.tracker.clone(),
+            sinks: BTreeMap::new(),
+        }
+    }
+
+    /// Queues a tightly packed BGR frame. Returns the results of the whole batch when this
+    /// frame completes it, and nothing otherwise.
+    pub fn push(
+        &mut self,
+        bgr: Vec<u8>,
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<FrameResult>, YuNetError> {
+        self.push_at(bgr, width, height, None)
+    }
+
+    /// Like [`push`](Self::push), for a frame presented at `timestamp`.
+    pub fn push_at(
+        &mut self,
+        bgr: Vec<u8>,
+        width: usize,
+        height: usize,
+        timestamp: Option<Duration>,
+    ) -> Result<Vec<FrameResult>, YuNetError> {
+        ImageView::new(&bgr, width, height)?;
+        self.queue.push(QueuedFrame {
+            bgr,
+            width,
+            height,
+            timestamp,
+        });
+        if self.queue.len() < self.batch_size {
+            return Ok(Vec::new());
+        }
+        self.flush()
+    }
+
+    /// Processes the queued frames, such as the last, incomplete batch at the end of a
+    /// stream. If any of them fails, the whole batch is dropped without advancing the
+    /// tracker.
+    pub fn flush(&mut self) -> Result<Vec<FrameResult>, YuNetError> {
+        let queue = std::mem::take(&mut self.queue);
+        if queue.is_empty() {
+            return Ok(Vec::new());
+        }
+        let chunk = queue.len().div_ceil(self.detectors.len());
+        let detected: Vec<Vec<Face>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = queue
+                .chunks(chunk)
+                .zip(&mut self.detectors)
+                .map(|(frames, detector)| {
+                    scope.spawn(move || {
+                        frames
+                            .iter()
+                            .map(|frame| detector.detect(&frame.bgr, frame.width, frame.height))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("detection panicked"))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|chunks| chunks.into_iter().flatten().collect())
+        })?;
+
+        Ok(detected
+            .into_iter()
+            .zip(&queue)
+            .map(|(faces, frame)| track(&mut self.tracker, faces, frame.timestamp))
+            .collect())
+    }
+
+    pub fn tracker(&self) -> &Tracker {
+        &self.tracker
+    }
+}
+
+/// Advances the tracker over the faces of the next frame.
+fn track(tracker: &mut Tracker, faces: Vec<Face>, timestamp: Option<Duration>) -> FrameResult {
+    let index = tracker.frame();
+    let track_ids = tracker.update_at(&faces, timestamp);
+    FrameResult {
+        index,
+        timestamp: tracker.timestamp(),
+        faces,
+        track_ids,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv::CsvWriter;
+
+    #[test]
+    fn tracks_faces_of_a_still_stream() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = image.dimensions();
+        let frame = ImageView::new(image.as_raw(), width as usize, height as usize).unwrap();
+
+        let mut pipeline = Pipeline::new(FaceDetector::new(), TrackerConfig::default());
+        let results = pipeline.run([frame; 3]).unwrap();
+        assert_eq!(2, results[2].index);
+        assert_eq!(results[0].track_ids, results[2].track_ids);
+        assert_eq!(2, pipeline.tracker().tracks().len());
+    }
+
+    #[test]
+    fn resumes_from_checkpoint() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = image.dimensions();
+        let frame = ImageView::new(image.as_raw(), width as usize, height as usize).unwrap();
+        let config = TrackerConfig::default();
+
+        let path =
+            std::env::temp_dir().join(format!("rusty-yunet-resume-{}.csv", std::process::id()));
+        let write = |csv: &mut CsvWriter<File>, results: &[FrameResult]| {
+            for result in results {
+                csv.write_faces("clip", result.index, &result.faces)
+                    .unwrap();
+            }
+        };
+
+        let mut pipeline = Pipeline::new(FaceDetector::new(), config.clone());
+        let uninterrupted = pipeline.run([frame; 3]).unwrap();
+        let mut csv = CsvWriter::new(File::create(&path).unwrap()).unwrap();
+        write(&mut csv, &uninterrupted);
+        csv.flush().unwrap();
+        let expected = std::fs::read(&path).unwrap();
+
+        let mut pipeline = Pipeline::new(FaceDetector::new(), config);
+        let mut resumed = pipeline.run([frame]).unwrap();
+        let mut csv = CsvWriter::new(File::create(&path).unwrap()).unwrap();
+        write(&mut csv, &resumed);
+        let checkpoint = pipeline
+            .checkpoint()
+            .with_sink("csv", csv.position().unwrap());
+        // Rows written after the checkpoint, lost with the rest of the run.
+        write(&mut csv, &pipeline.run([frame]).unwrap());
+        drop((pipeline, csv));
+        assert_eq!(1, checkpoint.next_frame());
+        assert!(checkpoint.reopen_sink("json", &path).is_err());
+
+        let mut pipeline = Pipeline::resume(FaceDetector::new(), checkpoint.clone());
+        let mut csv = CsvWriter::resume(checkpoint.reopen_sink("csv", &path).unwrap());
+        let rest = pipeline.run([frame; 2]).unwrap();
+        write(&mut csv, &rest);
+        csv.flush().unwrap();
+        resumed.extend(rest);
+        assert_eq!(format!("{uninterrupted:?}"), format!("{resumed:?}"));
+        assert_eq!(expected, std::fs::read(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn batches_frames_in_order() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut pipeline = ThroughputPipeline::new(ThroughputConfig {
+            batch_size: 2,
+            workers: 2,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(pipeline
+            .push(image.to_vec(), width, height)
+            .unwrap()
+            .is_empty());
+        let batch = pipeline.push(image.to_vec(), width, height).unwrap();
+        assert_eq!(
+            vec![0, 1],
+            batch.iter().map(|r| r.index).collect::<Vec<_>>()
+        );
+        assert_eq!(batch[0].track_ids, batch[1].track_ids);
+        assert!(pipeline.push(vec![0; 3], width, height).is_err());
+        assert!(pipeline
+            .push(image.to_vec(), width, height)
+            .unwrap()
+            .is_empty());
+        assert_eq!(2, pipeline.flush().unwrap()[0].index);
+        assert!(pipeline.flush().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+//! Glob-importable re-exports of the commonly used types.
+//!
+//! ```
+//! use rusty_yunet::prelude::*;
+//! ```
+
+#[cfg(feature = "rayon")]
+pub use crate::detector::detect_faces_parallel;
+pub use crate::detector::{detect_faces, DetectorConfig, FaceDetector, FaceDetectorPool};
+pub use crate::{
+    Face, FaceLandmarks, FaceSelection, ImageView, Pipeline, Rect, Tracker, TrackerConfig,
+    YuNetError,
+};
@@ -0,0 +1,217 @@
+//! Debounced presence of a person in front of an installation, such as a kiosk or an
+//! interactive display, from the faces of consecutive frames.
+
+use std::time::Duration;
+
+use crate::primary::{PrimaryPolicy, PrimarySubject};
+use crate::{Face, FaceSize};
+
+/// Tuning knobs for a [`PresenceDetector`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct PresenceConfig {
+    /// Faces less confident than this are ignored.
+    pub min_confidence: f32,
+    /// Faces smaller than this are ignored, such as passers-by in the background.
+    pub min_face_size: Option<FaceSize>,
+    /// How long a face must be seen without interruption before a person counts as present.
+    pub appear_after: Duration,
+    /// How long no face may be seen before a present person counts as gone, bridging missed
+    /// detections and people briefly turning away.
+    pub leave_after: Duration,
+    /// Interval between [`PresenceEvent::StillPresent`] reports.
+    pub report_interval: Duration,
+    /// Who [`PresenceDetector::primary`] reports when several people are in view.
+    pub primary: PrimaryPolicy,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.8,
+            min_face_size: None,
+            appear_after: Duration::from_millis(500),
+            leave_after: Duration::from_secs(2),
+            report_interval: Duration::from_secs(10),
+            primary: PrimaryPolicy::default(),
+        }
+    }
+}
+
+/// A change in presence reported by [`PresenceDetector::update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceEvent {
+    PersonAppeared,
+    PersonLeft,
+    /// Reported every [`PresenceConfig::report_interval`] with the time since the person
+    /// appeared.
+    StillPresent(Duration),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Faces have been seen continuously since the given time, if at all.
+    Absent { seen_since: Option<Duration> },
+    Present {
+        since: Duration,
+        last_seen: Duration,
+        last_report: Duration,
+    },
+}
+
+/// Turns per-frame detections into debounced appearance and departure events.
+#[derive(Debug, Clone)]
+pub struct PresenceDetector {
+    config: PresenceConfig,
+    state: State,
+    subject: PrimarySubject,
+    primary: Option<Face>,
+    count: usize,
+}
+
+impl PresenceDetector {
+    pub fn new(config: PresenceConfig) -> Self {
+        Self {
+            subject: PrimarySubject::new(config.primary),
+            config,
+            state: State::Absent { seen_since: None },
+            primary: None,
+            count: 0,
+        }
+    }
+
+    /// Feeds the faces of the frame presented at `timestamp`, which must not decrease from
+    /// one call to the next.
+    pub fn update(&mut self, faces: &[Face], timestamp: Duration) -> Option<PresenceEvent> {
+        let admitted: Vec<Face> = faces
+            .iter()
+            .filter(|face| {
+                face.confidence() >= self.config.min_confidence
+                    && self
+                        .config
+                        .min_face_size
+                        .is_none_or(|size| size.admits(face))
+            })
+            .cloned()
+            .collect();
+        let seen = !admitted.is_empty();
+        self.count = admitted.len();
+        self.primary = self
+            .subject
+            .update(&admitted)
+            .map(|primary| admitted[primary].clone());
+
+        match &mut self.state {
+            State::Absent { seen_since } => {
+                if !seen {
+                    *seen_since = None;
+                    return None;
+                }
+                let since = *seen_since.get_or_insert(timestamp);
+                if timestamp.saturating_sub(since) < self.config.appear_after {
+                    return None;
+                }
+                self.state = State::Present {
+                    since,
+                    last_seen: timestamp,
+                    last_report: timestamp,
+                };
+                Some(PresenceEvent::PersonAppeared)
+            }
+            State::Present {
+                since,
+                last_seen,
+                last_report,
+            } => {
+                if seen {
+                    *last_seen = timestamp;
+                } else if timestamp.saturating_sub(*last_seen) >= self.config.leave_after {
+                    self.state = State::Absent { seen_since: None };
+                    return Some(PresenceEvent::PersonLeft);
+                }
+                if timestamp.saturating_sub(*last_report) < self.config.report_interval {
+                    return None;
+                }
+                *last_report = timestamp;
+                Some(PresenceEvent::StillPresent(
+                    timestamp.saturating_sub(*since),
+                ))
+            }
+        }
+    }
+
+    /// Whether a person currently counts as present.
+    pub fn is_present(&self) -> bool {
+        matches!(self.state, State::Present { .. })
+    }
+
+    /// The face of the person the installation should respond to in the last frame, chosen
+    /// by [`PresenceConfig::primary`] among the faces that count towards presence.
+    pub fn primary(&self) -> Option<&Face> {
+        self.primary.as_ref()
+    }
+
+    /// How many faces counted towards presence in the last frame.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn config(&self) -> &PresenceConfig {
+        &self.config
+    }
+}
+
+impl Default for PresenceDetector {
+    fn default() -> Self {
+        Self::new(PresenceConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(score: f32) -> Face {
+        test_face(score, [10.0, 10.0, 40.0, 40.0], (100, 100), None)
+    }
+
+    #[test]
+    fn debounces_presence() {
+        let mut presence = PresenceDetector::new(PresenceConfig {
+            appear_after: Duration::from_secs(1),
+            leave_after: Duration::from_secs(2),
+            report_interval: Duration::from_secs(3),
+            ..Default::default()
+        });
+        let face = [face(0.9)];
+        let mut events = Vec::new();
+        for (second, faces) in [
+            (0, &face[..]),
+            // A blip too short to count, then an unconfident face.
+            (1, &[]),
+            (2, &[self::face(0.5)]),
+            (3, &face),
+            (4, &face),
+            // A missed detection doesn't end the presence.
+            (5, &[]),
+            (6, &face),
+            (7, &face),
+            (8, &[]),
+            (9, &[]),
+            (10, &[]),
+        ] {
+            let event = presence.update(faces, Duration::from_secs(second));
+            events.extend(event.map(|event| (second, event)));
+        }
+        assert_eq!(
+            vec![
+                (4, PresenceEvent::PersonAppeared),
+                (7, PresenceEvent::StillPresent(Duration::from_secs(4))),
+                (9, PresenceEvent::PersonLeft),
+            ],
+            events
+        );
+        assert!(!presence.is_present());
+    }
+}
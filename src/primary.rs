@@ -0,0 +1,163 @@
+//! Arbitrating which of several people in front of the camera an interactive experience
+//! responds to, so that it doesn't jump between them from one frame to the next.
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{Face, Tracker};
+
+/// How the primary subject is chosen among the faces of a frame.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PrimaryPolicy {
+    /// The largest face, usually the nearest person.
+    #[default]
+    Largest,
+    /// The face nearest to the center of the frame.
+    ClosestToCenter,
+    /// The face that has been in view the longest, the largest among those that arrived
+    /// together.
+    LongestDwelling,
+    /// The largest face once chosen stays primary until its track is lost, however large
+    /// other faces get. While it goes undetected for a few frames, there is no primary face.
+    Sticky,
+}
+
+/// Picks the primary subject of consecutive frames by a [`PrimaryPolicy`], following faces
+/// across frames with a [`Tracker`] of its own.
+#[derive(Debug, Clone)]
+pub struct PrimarySubject {
+    policy: PrimaryPolicy,
+    tracker: Tracker,
+    current: Option<u64>,
+}
+
+impl PrimarySubject {
+    pub fn new(policy: PrimaryPolicy) -> Self {
+        Self {
+            policy,
+            tracker: Tracker::default(),
+            current: None,
+        }
+    }
+
+    /// Feeds the faces of the next frame, returning the index in `faces` of the primary
+    /// subject, if any.
+    pub fn update(&mut self, faces: &[Face]) -> Option<usize> {
+        let ids = self.tracker.update(faces);
+        let largest =
+            || (0..faces.len()).max_by(|&a, &b| area(&faces[a]).total_cmp(&area(&faces[b])));
+        let index = match self.policy {
+            PrimaryPolicy::Largest => largest(),
+            PrimaryPolicy::ClosestToCenter => (0..faces.len())
+                .min_by(|&a, &b| center_offset(&faces[a]).total_cmp(&center_offset(&faces[b]))),
+            PrimaryPolicy::LongestDwelling => {
+                let first_frame = |id: u64| {
+                    self.tracker
+                        .tracks()
+                        .iter()
+                        .find(|track| track.id() == id)
+                        .map_or(u64::MAX, |track| track.first_frame())
+                };
+                (0..faces.len()).min_by(|&a, &b| {
+                    first_frame(ids[a])
+                        .cmp(&first_frame(ids[b]))
+                        .then(area(&faces[b]).total_cmp(&area(&faces[a])))
+                })
+            }
+            PrimaryPolicy::Sticky => match self.current {
+                Some(id) if self.tracker.tracks().iter().any(|track| track.id() == id) => {
+                    return ids.iter().position(|&i| i == id);
+                }
+                _ => largest(),
+            },
+        };
+        self.current = index.map(|index| ids[index]);
+        index
+    }
+
+    /// The track ID of the current primary subject, as assigned by the internal tracker.
+    pub fn track_id(&self) -> Option<u64> {
+        self.current
+    }
+
+    pub fn policy(&self) -> PrimaryPolicy {
+        self.policy
+    }
+}
+
+impl Default for PrimarySubject {
+    fn default() -> Self {
+        Self::new(PrimaryPolicy::default())
+    }
+}
+
+fn area(face: &Face) -> f32 {
+    face.rectangle().area()
+}
+
+fn center_offset(face: &Face) -> f32 {
+    let (width, height) = face.detection_dimensions();
+    let center = Vec2::new(width as f32, height as f32) / 2.0;
+    face.rectangle().center().distance_squared(center)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+    use crate::TrackerConfig;
+
+    fn face(x: i32, size: i32) -> Face {
+        let [x, size] = [x, size].map(|v| v as f32);
+        test_face(0.9, [x, 40.0, size, size], (300, 100), None)
+    }
+
+    #[test]
+    fn arbitrates_between_people() {
+        // Someone at the left edge, then a larger person stepping in at the center, then the
+        // first person leaving.
+        let frames = [
+            vec![face(0, 20)],
+            vec![face(0, 20), face(130, 40)],
+            vec![face(130, 40), face(1, 20)],
+            vec![face(131, 40)],
+        ];
+        let primary = |policy| {
+            let mut subject = PrimarySubject::new(policy);
+            frames
+                .iter()
+                .map(|faces| subject.update(faces).map(|i| faces[i].rectangle().x))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![Some(0.0), Some(130.0), Some(130.0), Some(131.0)],
+            primary(PrimaryPolicy::Largest)
+        );
+        assert_eq!(
+            primary(PrimaryPolicy::Largest),
+            primary(PrimaryPolicy::ClosestToCenter)
+        );
+        assert_eq!(
+            vec![Some(0.0), Some(0.0), Some(1.0), Some(131.0)],
+            primary(PrimaryPolicy::LongestDwelling)
+        );
+
+        // The first person is only replaced once the tracker gives up on them.
+        assert_eq!(
+            vec![Some(0.0), Some(0.0), Some(1.0), None],
+            primary(PrimaryPolicy::Sticky)
+        );
+        let mut subject = PrimarySubject::new(PrimaryPolicy::Sticky);
+        for faces in &frames {
+            subject.update(faces);
+        }
+        let last = &frames[3];
+        let later: Vec<_> = (0..TrackerConfig::default().max_missed)
+            .map(|_| subject.update(last).map(|i| last[i].rectangle().x))
+            .collect();
+        assert_eq!(None, later[0]);
+        assert_eq!(Some(&Some(131.0)), later.last());
+    }
+}
@@ -0,0 +1,95 @@
+//! Feedback while batches run, such as when indexing thousands of photos with
+//! [`FaceDetector::detect_dir`](crate::FaceDetector::detect_dir).
+
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Told about the progress of a batch after each of its inputs.
+pub trait ProgressReporter {
+    /// `done` of `total` inputs are processed, the last being `current`, if it's a file.
+    fn report(&mut self, done: usize, total: usize, current: Option<&Path>);
+}
+
+impl<F: FnMut(usize, usize, Option<&Path>)> ProgressReporter for F {
+    fn report(&mut self, done: usize, total: usize, current: Option<&Path>) {
+        self(done, total, current)
+    }
+}
+
+/// Reports nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn report(&mut self, _: usize, _: usize, _: Option<&Path>) {}
+}
+
+/// A text progress bar redrawn in place on a terminal, standard error by default, ending
+/// its line once the batch is done. Write errors are ignored.
+#[derive(Debug)]
+pub struct ProgressBar<W: Write = io::Stderr> {
+    writer: W,
+    width: usize,
+}
+
+impl ProgressBar {
+    pub fn new() -> Self {
+        Self::to(io::stderr())
+    }
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> ProgressBar<W> {
+    pub fn to(writer: W) -> Self {
+        Self { writer, width: 30 }
+    }
+
+    /// Replaces the default of 30 characters.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> ProgressReporter for ProgressBar<W> {
+    fn report(&mut self, done: usize, total: usize, current: Option<&Path>) {
+        let filled = (done * self.width).checked_div(total).unwrap_or(self.width);
+        let current = current.map(|path| path.display().to_string());
+        let _ = write!(
+            self.writer,
+            "\r[{}{}] {done}/{total} {}\x1b[K",
+            "#".repeat(filled),
+            " ".repeat(self.width - filled),
+            current.as_deref().unwrap_or(""),
+        );
+        if done >= total {
+            let _ = writeln!(self.writer);
+        }
+        let _ = self.writer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_a_bar() {
+        let mut bar = ProgressBar::to(Vec::new()).with_width(4);
+        bar.report(1, 2, Some(Path::new("a.jpg")));
+        bar.report(2, 2, None);
+        assert_eq!(
+            "\r[##  ] 1/2 a.jpg\x1b[K\r[####] 2/2 \x1b[K\n",
+            String::from_utf8(bar.into_inner()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Faces and frames as Protocol Buffers, defined in `proto/detections.proto`, for sending
+//! detections to other processes and languages at frame rate, where JSON is too verbose.
+//! Each face takes about 80 bytes.
+//!
+//! ```
+//! # use rusty_yunet::{mock, proto, FrameResult, Rect};
+//! let frame = FrameResult {
+//!     index: 7,
+//!     timestamp: None,
+//!     faces: vec![mock::face(Rect::with_size(100.0, 80.0, 60.0, 70.0), 0.9)],
+//!     track_ids: vec![3],
+//! };
+//! let bytes = proto::encode_frame(&frame);
+//! let decoded = proto::decode_frame(&bytes).unwrap();
+//! assert_eq!(frame.faces[0].rectangle(), decoded.faces[0].rectangle());
+//! ```
+
+use std::time::Duration;
+
+use glam::Vec2;
+use prost::Message;
+
+use crate::{Face, FaceLandmarks, FrameResult, YuNetError};
+
+/// The messages of `proto/detections.proto`.
+pub mod messages {
+    include!(concat!(env!("OUT_DIR"), "/rusty_yunet.detections.rs"));
+}
+
+impl Face {
+    /// The face as a message, without its provenance, context and annotations.
+    pub fn to_proto(&self) -> messages::Face {
+        let rect = self.rectangle();
+        let (width, height) = self.detection_dimensions();
+        messages::Face {
+            confidence: self.confidence(),
+            rectangle: Some(messages::Rect {
+                x: rect.x,
+                y: rect.y,
+                w: rect.w,
+                h: rect.h,
+            }),
+            landmarks: self
+                .landmarks()
+                .as_array()
+                .iter()
+                .flat_map(|p| [p.x, p.y])
+                .collect(),
+            width: width as u32,
+            height: height as u32,
+        }
+    }
+
+    /// Fails if the message lacks its rectangle or doesn't hold five landmarks.
+    pub fn from_proto(message: &messages::Face) -> Result<Self, YuNetError> {
+        let rect = message
+            .rectangle
+            .as_ref()
+            .ok_or_else(|| YuNetError::Decode("face without a rectangle".to_owned()))?;
+        let landmarks: [f32; 10] = message.landmarks.as_slice().try_into().map_err(|_| {
+            YuNetError::Decode(format!(
+                "expected 10 landmark coordinates, got {}",
+                message.landmarks.len()
+            ))
+        })?;
+        Ok(Face::new(
+            message.confidence,
+            crate::Rect::with_size(rect.x, rect.y, rect.w, rect.h),
+            FaceLandmarks::from_array(
+                [0, 1, 2, 3, 4].map(|i| Vec2::new(landmarks[2 * i], landmarks[2 * i + 1])),
+            ),
+            (message.width as usize, message.height as usize),
+        ))
+    }
+}
+
+impl From<&FrameResult> for messages::Frame {
+    fn from(frame: &FrameResult) -> Self {
+        messages::Frame {
+            index: frame.index,
+            timestamp_us: frame.timestamp.map(|t| t.as_micros() as u64),
+            faces: frame.faces.iter().map(Face::to_proto).collect(),
+            track_ids: frame.track_ids.clone(),
+        }
+    }
+}
+
+/// A frame's faces, track IDs and timing, encoded.
+pub fn encode_frame(frame: &FrameResult) -> Vec<u8> {
+    messages::Frame::from(frame).encode_to_vec()
+}
+
+/// Decodes a frame encoded by [`encode_frame`], or by any other implementation of the
+/// schema.
+pub fn decode_frame(bytes: &[u8]) -> Result<FrameResult, YuNetError> {
+    let message = messages::Frame::decode(bytes).map_err(|e| YuNetError::Decode(e.to_string()))?;
+    Ok(FrameResult {
+        index: message.index,
+        timestamp: message.timestamp_us.map(Duration::from_micros),
+        faces: message
+            .faces
+            .iter()
+            .map(Face::from_proto)
+            .collect::<Result<_, _>>()?,
+        track_ids: message.track_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, Rect};
+
+    #[test]
+    fn round_trips_frames() {
+        let faces: Vec<Face> = (0..2)
+            .map(|i| mock::face(Rect::with_size(100.0 * i as f32, 80.0, 60.0, 70.5), 0.9))
+            .collect();
+        let frame = FrameResult {
+            index: 42,
+            timestamp: Some(Duration::from_millis(1400)),
+            faces,
+            track_ids: vec![3, 5],
+        };
+        let bytes = encode_frame(&frame);
+        assert!(bytes.len() < 2 * 80, "{} bytes", bytes.len());
+
+        let decoded = decode_frame(&bytes).unwrap();
+        assert_eq!((42, frame.timestamp), (decoded.index, decoded.timestamp));
+        assert_eq!(frame.track_ids, decoded.track_ids);
+        for (face, decoded) in frame.faces.iter().zip(&decoded.faces) {
+            assert_eq!(face.rectangle(), decoded.rectangle());
+            assert_eq!(face.landmarks().as_array(), decoded.landmarks().as_array());
+            assert_eq!(face.detection_dimensions(), decoded.detection_dimensions());
+        }
+
+        let mut broken = frame.faces[0].to_proto();
+        broken.landmarks.pop();
+        assert!(Face::from_proto(&broken).is_err());
+        assert!(decode_frame(&[0xff]).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Backend;
+
+/// Identifies the model bundled with this crate.
+pub const MODEL_NAME: &str = "libfacedetection-yunet";
+/// The upstream libfacedetection commit the bundled model and kernels are frozen to.
+pub const MODEL_VERSION: &str = "40926655865c233b33d3de94302174efb6b5ac55";
+/// FNV-1a hash of the bundled weights, as 16 hex digits.
+pub const MODEL_HASH: &str = env!("YUNET_MODEL_HASH");
+
+/// Which model, configuration and compute backend produced a detection, so that archives
+/// mixing results from different setups remain interpretable.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    pub model: String,
+    pub model_version: String,
+    /// The resolution (width, height) of the frame the network actually ran on, after any
+    /// downscaling.
+    pub input_size: (usize, usize),
+    pub backend: String,
+    /// Version of this crate.
+    pub crate_version: String,
+    /// See [`ModelInfo::hash`]. Empty in detections recorded before it was.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub model_hash: String,
+}
+
+/// The YuNet models a detector can run, which differ in their heads or the precision of
+/// their weights.
+///
+/// This crate reads no ONNX graphs, so variants are told apart by the magic of parameters
+/// in the format of [`NATIVE_MODEL`](crate::NATIVE_MODEL), with
+/// [`of_native_model`](Self::of_native_model). The 2022 release, whose anchor-based heads
+/// the backends don't implement, has no variant here.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ModelVariant {
+    /// The 2023 release (`face_detection_yunet_2023mar` in OpenCV's model zoo):
+    /// anchor-free classification, objectness, box and landmark heads at strides 8, 16 and
+    /// 32, decoded as [`RawLevel`](crate::RawLevel) describes. Float weights.
+    #[default]
+    Yunet2023Mar,
+    /// The 2023 release with int8 weights, as
+    /// [`quantize_native_model`](crate::quantize_native_model) makes them, a quarter of the
+    /// size of the float ones for builds that fetch their model, such as in browsers. Its
+    /// pointwise convolutions, most of the network, multiply integers; on the portable
+    /// kernels of the native backend that runs at about the speed of the float weights.
+    /// Boxes move by under a pixel and confidences by under 0.01. Decoded as the float
+    /// model.
+    Yunet2023MarInt8,
+}
+
+impl ModelVariant {
+    /// The variant of parameters passed as bytes to
+    /// [`with_native_model`](crate::FaceDetector::with_native_model), or `None` if they
+    /// aren't YuNet parameters at all.
+    #[cfg(feature = "native")]
+    pub fn of_native_model(model: &[u8]) -> Option<Self> {
+        crate::detector::variant_of(model)
+    }
+}
+
+/// The model a [`FaceDetector`](crate::FaceDetector) runs, as returned by
+/// [`model_info`](crate::FaceDetector::model_info), for telling months later which model
+/// produced which results.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// [`MODEL_NAME`], or `custom` for parameters passed to
+    /// [`FaceDetector::with_native_model`](crate::FaceDetector::with_native_model).
+    pub name: String,
+    /// [`MODEL_VERSION`], or `custom`.
+    pub version: String,
+    /// The release the model is of, which parameters passed as bytes share as they must
+    /// have the shapes of the bundled ones.
+    pub variant: ModelVariant,
+    /// [`MODEL_HASH`], or the FNV-1a hash of the parameters passed as bytes, as 16 hex
+    /// digits.
+    pub hash: String,
+    /// The size (width, height) every frame is fitted to, if
+    /// [`DetectorConfig::input_size`](crate::DetectorConfig::input_size) is set. YuNet
+    /// otherwise runs on frames of any size up to
+    /// [`MAX_INPUT_PIXELS`](crate::MAX_INPUT_PIXELS).
+    pub input_size: Option<(usize, usize)>,
+    /// The backend, and for libfacedetection the CPU kernels it was compiled with.
+    pub backend: String,
+}
+
+/// Which frame of which source a detection was made in, as given by the caller, so that
+/// faces from several cameras can be merged into one stream without wrapping each.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DetectionContext {
+    /// Position of the frame in its source, starting at zero.
+    pub frame_index: Option<u64>,
+    /// When the frame was presented or captured.
+    pub timestamp: Option<Duration>,
+    /// Identifies the source, such as a camera name or a file path.
+    pub source: Option<String>,
+}
+
+impl Provenance {
+    pub(crate) fn current(input_size: (usize, usize), model: &ModelInfo) -> Self {
+        Self {
+            model: model.name.clone(),
+            model_version: model.version.clone(),
+            input_size,
+            backend: model.backend.clone(),
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            model_hash: model.hash.clone(),
+        }
+    }
+}
+
+impl ModelInfo {
+    /// The bundled model, or the parameters of the given hash.
+    pub(crate) fn new(
+        custom: Option<u64>,
+        variant: ModelVariant,
+        input_size: Option<(usize, usize)>,
+        backend: Backend,
+    ) -> Self {
+        let (name, version, hash) = match custom {
+            Some(hash) => ("custom", "custom", format!("{hash:016x}")),
+            None => (MODEL_NAME, MODEL_VERSION, MODEL_HASH.to_owned()),
+        };
+        Self {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            variant,
+            hash,
+            input_size,
+            backend: backend_name(backend).to_owned(),
+        }
+    }
+}
+
+/// 64-bit FNV-1a, as `build.rs` hashes the bundled weights with. Unlike the standard
+/// library's hashers, its output never changes.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The backend, and for libfacedetection the CPU kernels it was compiled with.
+pub(crate) fn backend_name(backend: Backend) -> &'static str {
+    if backend == Backend::Native {
+        "native-cpu"
+    } else if cfg!(target_feature = "avx2") {
+        "cpu-avx2"
+    } else if cfg!(target_feature = "neon") {
+        "cpu-neon"
+    } else {
+        "cpu"
+    }
+}
@@ -0,0 +1,116 @@
+//! Replacing track IDs with salted hashes before analytics leave the device, so that
+//! reports can be shared without enabling long-term tracking of individuals.
+//!
+//! The salt changes every rotation period: within a period a visitor keeps one pseudonym,
+//! across periods the pseudonyms of the same visitor can't be linked without the secret.
+
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::pipeline::FrameResult;
+
+/// A keyed hash turning IDs into pseudonyms.
+pub trait IdHasher {
+    fn hash_id(&self, key: u64, id: u64) -> u64;
+}
+
+/// SipHash from the standard library. Its output may change between Rust releases, so plug
+/// in a hasher of your own, such as HMAC-SHA256, if pseudonyms must match across builds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SipIdHasher;
+
+impl IdHasher for SipIdHasher {
+    fn hash_id(&self, key: u64, id: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        id.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Hashes IDs with a salt derived from a secret and the current rotation period.
+#[derive(Debug, Clone)]
+pub struct Pseudonymizer<H = SipIdHasher> {
+    hasher: H,
+    secret: u64,
+    rotation: Duration,
+}
+
+impl Pseudonymizer {
+    /// Uses a random secret, so that pseudonyms can't be linked across restarts either.
+    /// A zero `rotation` never rotates the salt.
+    pub fn new(rotation: Duration) -> Self {
+        Self::with_secret(RandomState::new().build_hasher().finish(), rotation)
+    }
+
+    /// Uses a persisted secret, keeping pseudonyms stable across restarts within a period.
+    pub fn with_secret(secret: u64, rotation: Duration) -> Self {
+        Self::with_hasher(SipIdHasher, secret, rotation)
+    }
+}
+
+impl<H: IdHasher> Pseudonymizer<H> {
+    pub fn with_hasher(hasher: H, secret: u64, rotation: Duration) -> Self {
+        Self {
+            hasher,
+            secret,
+            rotation,
+        }
+    }
+
+    /// The pseudonym of `id` in the rotation period containing `at`.
+    pub fn pseudonym(&self, id: u64, at: SystemTime) -> u64 {
+        self.pseudonym_since_epoch(id, at.duration_since(UNIX_EPOCH).unwrap_or_default())
+    }
+
+    /// Like [`pseudonym`](Self::pseudonym), at a time since the Unix epoch.
+    pub fn pseudonym_since_epoch(&self, id: u64, at: Duration) -> u64 {
+        let period = match self.rotation.as_secs() {
+            0 => 0,
+            secs => at.as_secs() / secs,
+        };
+        let salt = self.hasher.hash_id(self.secret, period);
+        self.hasher.hash_id(salt, id)
+    }
+
+    /// Replaces the track IDs of a pipeline frame captured at `at` with their pseudonyms.
+    pub fn pseudonymize(&self, result: &mut FrameResult, at: SystemTime) {
+        for id in &mut result.track_ids {
+            *id = self.pseudonym(*id, at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_pseudonyms() {
+        let day = Duration::from_secs(86_400);
+        let pseudonymizer = Pseudonymizer::with_secret(42, day);
+        let morning = Duration::from_secs(19_000 * 86_400 + 8 * 3600);
+        let evening = morning + Duration::from_secs(12 * 3600);
+        let next_day = morning + day;
+
+        let id = pseudonymizer.pseudonym_since_epoch(7, morning);
+        assert_ne!(7, id);
+        assert_eq!(id, pseudonymizer.pseudonym_since_epoch(7, evening));
+        assert_ne!(id, pseudonymizer.pseudonym_since_epoch(7, next_day));
+        assert_ne!(id, pseudonymizer.pseudonym_since_epoch(8, morning));
+        assert_ne!(
+            id,
+            Pseudonymizer::with_secret(43, day).pseudonym_since_epoch(7, morning)
+        );
+
+        let mut result = FrameResult {
+            index: 0,
+            timestamp: None,
+            faces: Vec::new(),
+            track_ids: vec![7, 8],
+        };
+        pseudonymizer.pseudonymize(&mut result, UNIX_EPOCH + morning);
+        assert_eq!(id, result.track_ids[0]);
+    }
+}
@@ -0,0 +1,117 @@
+//! Python bindings, built as an extension module named `rusty_yunet`. Images are NumPy
+//! arrays of shape (height, width, 3) in BGR order, as returned by OpenCV's `imread`.
+
+use std::sync::Mutex;
+
+use numpy::{PyReadonlyArray3, PyUntypedArrayMethods};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::{DetectorConfig, Face, FaceDetector, ImageView, YuNetError};
+
+fn to_py_err(error: YuNetError) -> PyErr {
+    match error {
+        YuNetError::InvalidImage => PyValueError::new_err(error.to_string()),
+        _ => PyRuntimeError::new_err(error.to_string()),
+    }
+}
+
+/// A detected face, in pixel coordinates of the input image.
+#[pyclass(name = "Face", module = "rusty_yunet", frozen)]
+pub struct PyFace(Face);
+
+#[pymethods]
+impl PyFace {
+    #[getter]
+    fn confidence(&self) -> f32 {
+        self.0.confidence()
+    }
+
+    /// `(x, y, width, height)`.
+    #[getter]
+    fn rectangle(&self) -> (f32, f32, f32, f32) {
+        let rect = self.0.rectangle();
+        (rect.x, rect.y, rect.w, rect.h)
+    }
+
+    /// `(x, y, width, height)` in 0..1 coordinates.
+    #[getter]
+    fn normalized_rectangle(&self) -> (f32, f32, f32, f32) {
+        let rect = self.0.normalized_rectangle();
+        (rect.x, rect.y, rect.w, rect.h)
+    }
+
+    /// `(x, y)` of the right eye, left eye, nose, right and left mouth corners.
+    #[getter]
+    fn landmarks(&self) -> [(f32, f32); 5] {
+        self.0
+            .landmarks()
+            .as_array()
+            .map(|point| (point.x, point.y))
+    }
+
+    fn __repr__(&self) -> String {
+        let (x, y, w, h) = self.rectangle();
+        format!(
+            "Face(confidence={:.3}, rectangle=({x}, {y}, {w}, {h}))",
+            self.0.confidence()
+        )
+    }
+}
+
+/// A [`FaceDetector`] with the default backend. Python objects may be shared between
+/// threads, so calls on the same detector take turns.
+#[pyclass(name = "FaceDetector", module = "rusty_yunet", frozen)]
+pub struct PyFaceDetector(Mutex<FaceDetector>);
+
+#[pymethods]
+impl PyFaceDetector {
+    #[new]
+    #[pyo3(signature = (max_side=None, refine_landmarks=false))]
+    fn new(max_side: Option<usize>, refine_landmarks: bool) -> PyResult<Self> {
+        let config = DetectorConfig {
+            max_side,
+            refine_landmarks,
+            ..DetectorConfig::default()
+        };
+        FaceDetector::with_config(config)
+            .map(|detector| Self(Mutex::new(detector)))
+            .map_err(to_py_err)
+    }
+
+    /// Detects faces in a `uint8` array of shape (height, width, 3) holding BGR pixels.
+    /// Arrays that aren't C-contiguous are copied first.
+    fn detect(&self, py: Python<'_>, image: PyReadonlyArray3<'_, u8>) -> PyResult<Vec<PyFace>> {
+        let &[height, width, channels] = image.shape() else {
+            unreachable!("the array type is three-dimensional");
+        };
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("python_detect", width, height, channels).entered();
+        if channels != 3 {
+            return Err(to_py_err(YuNetError::InvalidImage));
+        }
+        let copy;
+        let bytes = match image.as_slice() {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                copy = image.as_array().iter().copied().collect::<Vec<u8>>();
+                &copy
+            }
+        };
+        let image = ImageView::new(bytes, width, height).map_err(to_py_err)?;
+        let faces = py
+            .allow_threads(|| {
+                let mut detector = self.0.lock().unwrap_or_else(|e| e.into_inner());
+                detector.detect_image(&image)
+            })
+            .map_err(to_py_err)?;
+        Ok(faces.into_iter().map(PyFace).collect())
+    }
+}
+
+#[pymodule]
+fn rusty_yunet(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyFace>()?;
+    module.add_class::<PyFaceDetector>()?;
+    Ok(())
+}
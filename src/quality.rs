@@ -0,0 +1,262 @@
+//! Rating how usable a detected face is, such as for enrollment or recognition, and keeping
+//! the best shot of each tracked person.
+
+use std::collections::HashMap;
+
+use crate::pipeline::FrameResult;
+use crate::tracking::Track;
+use crate::{resample, Face, ImageView};
+
+/// Longest side, in pixels, faces are downscaled to before measuring sharpness, so that it
+/// compares across face sizes.
+const SHARPNESS_SIDE: usize = 96;
+
+/// How [`Face::quality`] weighs and normalizes its components.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct QualityConfig {
+    pub sharpness_weight: f32,
+    pub size_weight: f32,
+    pub frontality_weight: f32,
+    pub confidence_weight: f32,
+    /// Variance of the Laplacian of the face's luma at which it counts as fully sharp.
+    pub full_sharpness: f32,
+    /// Shorter side of the face rectangle, in pixels, at which it counts as fully sized,
+    /// such as the input size of a recognition model.
+    pub full_size: f32,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            sharpness_weight: 0.35,
+            size_weight: 0.2,
+            frontality_weight: 0.3,
+            confidence_weight: 0.15,
+            full_sharpness: 400.0,
+            full_size: 112.0,
+        }
+    }
+}
+
+/// The components of a face's quality, each from 0 (worst) to 1 (best), and their
+/// weighted mean.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceQuality {
+    pub sharpness: f32,
+    pub size: f32,
+    /// How directly the face looks at the camera, by how centered its nose is between its
+    /// eyes.
+    pub frontality: f32,
+    pub confidence: f32,
+    pub score: f32,
+}
+
+impl Face {
+    /// Rates this face, detected in `image`, with the default [`QualityConfig`].
+    pub fn quality_score(&self, image: &ImageView) -> f32 {
+        self.quality(image, &QualityConfig::default()).score
+    }
+
+    /// Rates this face, detected in `image`, by sharpness, size, frontality and confidence.
+    pub fn quality(&self, image: &ImageView, config: &QualityConfig) -> FaceQuality {
+        let rect = self.rectangle();
+        let sharpness = (sharpness(image, self) / config.full_sharpness).min(1.0);
+        let size = (rect.w.min(rect.h) / config.full_size).clamp(0.0, 1.0);
+        let frontality = frontality(self);
+        let confidence = self.confidence().clamp(0.0, 1.0);
+        let weights = config.sharpness_weight
+            + config.size_weight
+            + config.frontality_weight
+            + config.confidence_weight;
+        let score = if weights > 0.0 {
+            (sharpness * config.sharpness_weight
+                + size * config.size_weight
+                + frontality * config.frontality_weight
+                + confidence * config.confidence_weight)
+                / weights
+        } else {
+            0.0
+        };
+        FaceQuality {
+            sharpness,
+            size,
+            frontality,
+            confidence,
+            score,
+        }
+    }
+}
+
+/// Variance of the Laplacian of the face's luma, 0 if it lies outside `image`.
+pub(crate) fn sharpness(image: &ImageView, face: &Face) -> f32 {
+    let rect = face.rectangle();
+    let (width, height) = image.dimensions();
+    let x0 = rect.x.max(0.0) as usize;
+    let y0 = rect.y.max(0.0) as usize;
+    let x1 = ((rect.x + rect.w).max(0.0) as usize).min(width);
+    let y1 = ((rect.y + rect.h).max(0.0) as usize).min(height);
+    let Some(crop) = image.crop(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0)) else {
+        return 0.0;
+    };
+    let (w, h) = resample::fit_within(crop.width(), crop.height(), SHARPNESS_SIDE);
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+    let pixels = resample::resize(&crop, w, h);
+    let channels = crop.channels();
+    let luma: Vec<f32> = pixels
+        .chunks_exact(channels)
+        .map(|pixel| match pixel {
+            [b, g, r] => 0.114 * *b as f32 + 0.587 * *g as f32 + 0.299 * *r as f32,
+            _ => pixel[0] as f32,
+        })
+        .collect();
+    let laplacian: Vec<f32> = (1..h - 1)
+        .flat_map(|y| (1..w - 1).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let at = |x: usize, y: usize| luma[y * w + x];
+            at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y)
+        })
+        .collect();
+    let mean = laplacian.iter().sum::<f32>() / laplacian.len() as f32;
+    laplacian
+        .iter()
+        .map(|v| (v - mean) * (v - mean))
+        .sum::<f32>()
+        / laplacian.len() as f32
+}
+
+/// 1 when the nose projects midway between the eyes, falling to 0 as it reaches either.
+fn frontality(face: &Face) -> f32 {
+    let landmarks = face.landmarks();
+    let axis = landmarks.left_eye - landmarks.right_eye;
+    let distance = axis.length_squared();
+    if distance == 0.0 {
+        return 0.0;
+    }
+    let along = (landmarks.nose - landmarks.right_eye).dot(axis) / distance;
+    (1.0 - (along - 0.5).abs() * 2.0).clamp(0.0, 1.0)
+}
+
+/// The best-rated instance of a tracked face.
+#[derive(Debug, Clone)]
+pub struct BestFace {
+    pub face: Face,
+    pub quality: FaceQuality,
+    /// Index of the frame it was found in.
+    pub frame: u64,
+}
+
+/// Keeps the best-rated face of each track across the frames of a pipeline, such as to
+/// enroll each person from their best shot.
+#[derive(Debug, Clone, Default)]
+pub struct BestFrameSelector {
+    config: QualityConfig,
+    best: HashMap<u64, BestFace>,
+}
+
+impl BestFrameSelector {
+    pub fn new(config: QualityConfig) -> Self {
+        Self {
+            config,
+            best: HashMap::new(),
+        }
+    }
+
+    /// Rates the faces of a pipeline frame, `image`, returning the indices of those now the
+    /// best of their tracks, such as to keep a copy of the frame for them.
+    pub fn update(&mut self, result: &FrameResult, image: &ImageView) -> Vec<usize> {
+        let mut improved = Vec::new();
+        for (i, (face, &id)) in result.faces.iter().zip(&result.track_ids).enumerate() {
+            let quality = face.quality(image, &self.config);
+            if self
+                .best
+                .get(&id)
+                .is_some_and(|best| best.quality.score >= quality.score)
+            {
+                continue;
+            }
+            self.best.insert(
+                id,
+                BestFace {
+                    face: face.clone(),
+                    quality,
+                    frame: result.index,
+                },
+            );
+            improved.push(i);
+        }
+        improved
+    }
+
+    /// The best face of track `track_id` so far.
+    pub fn best(&self, track_id: u64) -> Option<&BestFace> {
+        self.best.get(&track_id)
+    }
+
+    /// Removes and returns the best face of track `track_id`, such as once it ended.
+    pub fn take(&mut self, track_id: u64) -> Option<BestFace> {
+        self.best.remove(&track_id)
+    }
+
+    /// Forgets the tracks that are no longer alive, as
+    /// [`StageScheduler::retain_tracks`](crate::StageScheduler::retain_tracks) does.
+    pub fn retain_tracks(&mut self, tracks: &[Track]) {
+        self.best
+            .retain(|id, _| tracks.iter().any(|track| track.id() == *id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+    use glam::Vec2;
+
+    fn face(x: i32, nose: i32) -> Face {
+        let landmarks = [(10, 15), (30, 15), (nose, 25), (12, 32), (28, 32)]
+            .map(|(dx, y)| Vec2::new((x + dx) as f32, y as f32));
+        test_face(0.9, [x as f32, 0.0, 40.0, 40.0], (80, 40), Some(landmarks))
+    }
+
+    #[test]
+    fn prefers_sharp_frontal_faces() {
+        // A checkerboard on the left, flat gray on the right.
+        let (width, height) = (80, 40);
+        let gray: Vec<u8> = (0..width * height)
+            .map(
+                |i| match (i % width < 40, (i % width / 2 + i / width / 2) % 2) {
+                    (true, 0) => 40,
+                    (true, _) => 220,
+                    (false, _) => 128,
+                },
+            )
+            .collect();
+        let image = ImageView::gray(&gray, width, height).unwrap();
+        let (sharp, flat, turned) = (face(0, 20), face(40, 20), face(0, 12));
+        let quality = sharp.quality(&image, &QualityConfig::default());
+        assert_eq!((1.0, 1.0), (quality.sharpness, quality.frontality));
+        assert_eq!(
+            0.0,
+            flat.quality(&image, &QualityConfig::default()).sharpness
+        );
+        assert!((turned.quality(&image, &QualityConfig::default()).frontality - 0.2).abs() < 1e-6);
+        assert!(sharp.quality_score(&image) > turned.quality_score(&image));
+        assert!(turned.quality_score(&image) > flat.quality_score(&image));
+
+        let mut selector = BestFrameSelector::default();
+        let frame = |index, faces: Vec<Face>| FrameResult {
+            index,
+            timestamp: None,
+            track_ids: vec![7; faces.len()],
+            faces,
+        };
+        assert_eq!(vec![0], selector.update(&frame(0, vec![turned]), &image));
+        assert!(selector.update(&frame(1, vec![flat]), &image).is_empty());
+        assert_eq!(vec![0], selector.update(&frame(2, vec![sharp]), &image));
+        assert_eq!(2, selector.best(7).unwrap().frame);
+        selector.retain_tracks(&[]);
+        assert!(selector.take(7).is_none());
+    }
+}
@@ -0,0 +1,247 @@
+//! Identifying people from detected faces with a recognition model of your choice, such as
+//! SFace, which pairs with YuNet in OpenCV, run on a runtime of your choice: faces are
+//! aligned by their landmarks into the crops such models expect, embedded by an
+//! [`EmbeddingModel`], and matched by the cosine similarity of their embeddings.
+//!
+//! ```no_run
+//! # use rusty_yunet::recognition::{embed_faces, match_faces, EmbeddingModel};
+//! # use rusty_yunet::recognition::SFACE_MATCH_THRESHOLD;
+//! # use rusty_yunet::{FaceDetector, ImageView, YuNetError};
+//! struct SFace; // Wrapping an ONNX runtime session, say.
+//!
+//! impl EmbeddingModel for SFace {
+//!     fn input_size(&self) -> usize {
+//!         112
+//!     }
+//!
+//!     fn embed(&mut self, aligned: &ImageView) -> Result<Vec<f32>, YuNetError> {
+//!         // Run the model on the 112x112 crop.
+//!         # Ok(vec![])
+//!     }
+//! }
+//!
+//! # let (bgr, width, height, known) = (vec![], 0, 0, vec![]);
+//! let image = ImageView::new(&bgr, width, height)?;
+//! let faces = FaceDetector::new().detect_image(&image)?;
+//! let embeddings = embed_faces(&mut SFace, &image, &faces)?;
+//! for found in match_faces(&embeddings, &known, SFACE_MATCH_THRESHOLD).into_iter().flatten() {
+//!     println!("face {} is person {}", found.probe, found.gallery);
+//! }
+//! # Ok::<(), YuNetError>(())
+//! ```
+
+use glam::Vec2;
+
+use crate::{Face, ImageView, YuNetError};
+
+mod gallery;
+pub use gallery::FaceGallery;
+
+/// Where the landmarks of a face, in the order of [`FaceLandmarks::points`], land in the
+/// 112x112 crops of SFace and ArcFace models.
+///
+/// [`FaceLandmarks::points`]: crate::FaceLandmarks::points
+pub const ALIGNMENT_TEMPLATE: [[f32; 2]; 5] = [
+    [38.2946, 51.6963],
+    [73.5318, 51.5014],
+    [56.0252, 71.7366],
+    [41.5493, 92.3655],
+    [70.7299, 92.2041],
+];
+
+/// The cosine similarity above which OpenCV considers two SFace embeddings the same person.
+pub const SFACE_MATCH_THRESHOLD: f32 = 0.363;
+
+/// A recognition model turning aligned face crops into embeddings.
+pub trait EmbeddingModel {
+    /// The side of the square crops the model takes, such as 112.
+    fn input_size(&self) -> usize;
+
+    /// Embeds a crop from [`align_face`], with the channels of the image the face was
+    /// detected in, BGR or grayscale.
+    fn embed(&mut self, aligned: &ImageView) -> Result<Vec<f32>, YuNetError>;
+}
+
+/// Warps the face into a square crop of `size` pixels, rotated and scaled so that its
+/// landmarks best fit [`ALIGNMENT_TEMPLATE`], scaled to the size. `image` must be the frame
+/// the face was detected in. The crop is tightly packed, with the channels of `image`, and
+/// black where it extends beyond it.
+pub fn align_face(image: &ImageView, face: &Face, size: usize) -> Vec<u8> {
+    let scale = size as f32 / 112.0;
+    let template = ALIGNMENT_TEMPLATE.map(|[x, y]| Vec2::new(x, y) * scale);
+    let (rotation, translation) = similarity(&face.landmarks().as_array(), &template);
+    // The inverse transform, mapping crop pixels back onto the image.
+    let inverse = Vec2::new(rotation.x, -rotation.y) / rotation.length_squared();
+
+    let (src, stride, channels) = (image.data(), image.stride(), image.channels());
+    let (width, height) = image.dimensions();
+    let pixel = |x: isize, y: isize, c: usize| -> f32 {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            return 0.0;
+        }
+        src[y as usize * stride + x as usize * channels + c] as f32
+    };
+    let mut crop = vec![0u8; size * size * channels];
+    for y in 0..size {
+        for x in 0..size {
+            let p = inverse.rotate(Vec2::new(x as f32, y as f32) - translation);
+            let (x0, y0) = (p.x.floor(), p.y.floor());
+            let (fx, fy) = (p.x - x0, p.y - y0);
+            let (x0, y0) = (x0 as isize, y0 as isize);
+            for c in 0..channels {
+                let top = pixel(x0, y0, c) * (1.0 - fx) + pixel(x0 + 1, y0, c) * fx;
+                let bottom = pixel(x0, y0 + 1, c) * (1.0 - fx) + pixel(x0 + 1, y0 + 1, c) * fx;
+                crop[(y * size + x) * channels + c] =
+                    (top * (1.0 - fy) + bottom * fy).round() as u8;
+            }
+        }
+    }
+    crop
+}
+
+/// The least-squares similarity transform from `from` to `to`, as the rotation and scale,
+/// as a complex number for [`Vec2::rotate`], and the translation after them.
+fn similarity(from: &[Vec2; 5], to: &[Vec2; 5]) -> (Vec2, Vec2) {
+    let mean = |points: &[Vec2; 5]| points.iter().sum::<Vec2>() / 5.0;
+    let (from_mean, to_mean) = (mean(from), mean(to));
+    let (mut dot, mut cross, mut norm) = (0.0, 0.0, 0.0);
+    for (a, b) in from.iter().zip(to) {
+        let (a, b) = (*a - from_mean, *b - to_mean);
+        dot += a.dot(b);
+        cross += a.perp_dot(b);
+        norm += a.length_squared();
+    }
+    let rotation = if norm > 0.0 {
+        Vec2::new(dot, cross) / norm
+    } else {
+        Vec2::X
+    };
+    (rotation, to_mean - rotation.rotate(from_mean))
+}
+
+/// Aligns every face of `image` for `model` and embeds it.
+pub fn embed_faces(
+    model: &mut impl EmbeddingModel,
+    image: &ImageView,
+    faces: &[Face],
+) -> Result<Vec<Vec<f32>>, YuNetError> {
+    let size = model.input_size();
+    faces
+        .iter()
+        .map(|face| {
+            let crop = align_face(image, face, size);
+            model.embed(&ImageView::packed(&crop, size, size, image.channels())?)
+        })
+        .collect()
+}
+
+/// The cosine of the angle between two embeddings, from -1 to 1 for the same person. 0 if
+/// either is zero.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let norms =
+        a.iter().map(|a| a * a).sum::<f32>().sqrt() * b.iter().map(|b| b * b).sum::<f32>().sqrt();
+    if norms > 0.0 {
+        dot / norms
+    } else {
+        0.0
+    }
+}
+
+/// A probe embedding matched to one of a gallery by [`match_faces`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceMatch {
+    pub probe: usize,
+    pub gallery: usize,
+    pub similarity: f32,
+}
+
+/// Matches each probe embedding, such as of the faces of a new photo, to the most similar
+/// embedding of `gallery`, such as of known people, if more similar than `threshold`.
+/// Several probes may match the same gallery embedding.
+pub fn match_faces(
+    probes: &[Vec<f32>],
+    gallery: &[Vec<f32>],
+    threshold: f32,
+) -> Vec<Option<FaceMatch>> {
+    probes
+        .iter()
+        .enumerate()
+        .map(|(probe, embedding)| {
+            gallery
+                .iter()
+                .enumerate()
+                .map(|(gallery, known)| FaceMatch {
+                    probe,
+                    gallery,
+                    similarity: cosine_similarity(embedding, known),
+                })
+                .filter(|found| found.similarity > threshold)
+                .max_by(|a, b| a.similarity.total_cmp(&b.similarity))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    /// Embeds a crop as its total intensity per quadrant.
+    struct Quadrants;
+
+    impl EmbeddingModel for Quadrants {
+        fn input_size(&self) -> usize {
+            56
+        }
+
+        fn embed(&mut self, aligned: &ImageView) -> Result<Vec<f32>, YuNetError> {
+            let mut sums = vec![0.0; 4];
+            for (i, &v) in aligned.data().iter().enumerate() {
+                let (x, y) = ((i / 3) % 56, i / 3 / 56);
+                sums[(y / 28) * 2 + x / 28] += v as f32;
+            }
+            Ok(sums)
+        }
+    }
+
+    #[test]
+    fn aligns_embeds_and_matches() {
+        // A gray frame with a face whose landmarks sit on the template at half scale,
+        // offset by (10, 20), and whose top left quadrant is white.
+        let (width, height) = (80, 80);
+        let mut bgr = vec![100; 3 * width * height];
+        for y in 0..34 {
+            bgr[3 * y * width..][..3 * 24].fill(250);
+        }
+        let image = ImageView::new(&bgr, width, height).unwrap();
+        let mut lm = [0; 10];
+        for (i, [x, y]) in ALIGNMENT_TEMPLATE.iter().enumerate() {
+            lm[2 * i] = (x / 2.0).round() as i32 + 10;
+            lm[2 * i + 1] = (y / 2.0).round() as i32 + 20;
+        }
+        let raw = RawFace {
+            score: 0.9,
+            x: 10.0,
+            y: 20.0,
+            w: 56.0,
+            h: 56.0,
+            lm: lm.map(|v| v as f32),
+        };
+        let face = Face::from_raw_face(&raw, (width, height));
+
+        let crop = align_face(&image, &face, 56);
+        assert_eq!(250, crop[3 * (5 * 56 + 5)]);
+        assert_eq!(100, crop[3 * (50 * 56 + 50)]);
+        let embeddings = embed_faces(&mut Quadrants, &image, &[face]).unwrap();
+        assert!(embeddings[0][0] > embeddings[0][3]);
+
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(0.0, cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]));
+        assert_eq!(0.0, cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]));
+        let gallery = vec![vec![0.0, 1.0], vec![1.0, 0.1]];
+        let matches = match_faces(&[vec![1.0, 0.0], vec![-1.0, 0.0]], &gallery, 0.5);
+        assert_eq!(Some(1), matches[0].map(|found| found.gallery));
+        assert_eq!(None, matches[1]);
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use super::{align_face, cosine_similarity, EmbeddingModel, SFACE_MATCH_THRESHOLD};
+use crate::{Face, ImageView, YuNetError};
+
+/// Identifies files written by [`FaceGallery::save`], followed by a format version.
+const MAGIC: &[u8; 4] = b"YNFG";
+const VERSION: u32 = 1;
+
+/// Known people and embeddings of their faces, answering who a face is, such as whether
+/// someone at the door is a member of the household.
+///
+/// ```no_run
+/// # use rusty_yunet::recognition::{EmbeddingModel, FaceGallery};
+/// # use rusty_yunet::{Face, ImageView};
+/// # fn example(model: impl EmbeddingModel, image: &ImageView, faces: &[Face]) -> Result<(), rusty_yunet::YuNetError> {
+/// let mut gallery = FaceGallery::new(model);
+/// gallery.enroll("Alex", &faces[0], image)?;
+/// gallery.save("household.gallery")?;
+/// if let Some((name, similarity)) = gallery.identify(&faces[1], image)? {
+///     println!("{name} ({similarity:.2})");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct FaceGallery<M> {
+    model: M,
+    entries: Vec<(String, Vec<f32>)>,
+    threshold: f32,
+}
+
+impl<M: EmbeddingModel> FaceGallery<M> {
+    /// An empty gallery embedding faces with `model`, matching them above
+    /// [`SFACE_MATCH_THRESHOLD`].
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            entries: Vec::new(),
+            threshold: SFACE_MATCH_THRESHOLD,
+        }
+    }
+
+    /// Replaces the cosine similarity above which faces are identified, for models other
+    /// than SFace.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Adds `face`, detected in `image`, as one of `name`. Enrolling several faces of a
+    /// person, such as in other lighting, makes identifying them more reliable.
+    pub fn enroll(
+        &mut self,
+        name: impl Into<String>,
+        face: &Face,
+        image: &ImageView,
+    ) -> Result<(), YuNetError> {
+        let embedding = self.embed(face, image)?;
+        self.entries.push((name.into(), embedding));
+        Ok(())
+    }
+
+    /// The person `face`, detected in `image`, is most similar to and the cosine
+    /// similarity, if above the threshold.
+    pub fn identify(
+        &mut self,
+        face: &Face,
+        image: &ImageView,
+    ) -> Result<Option<(&str, f32)>, YuNetError> {
+        let embedding = self.embed(face, image)?;
+        Ok(self
+            .entries
+            .iter()
+            .map(|(name, known)| (name.as_str(), cosine_similarity(&embedding, known)))
+            .filter(|&(_, similarity)| similarity > self.threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1)))
+    }
+
+    fn embed(&mut self, face: &Face, image: &ImageView) -> Result<Vec<f32>, YuNetError> {
+        let size = self.model.input_size();
+        let crop = align_face(image, face, size);
+        self.model
+            .embed(&ImageView::packed(&crop, size, size, image.channels())?)
+    }
+
+    /// Forgets every face of `name`, returning how many there were.
+    pub fn remove(&mut self, name: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|(enrolled, _)| enrolled != name);
+        before - self.entries.len()
+    }
+
+    /// The enrolled people, in order of enrollment, each once.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in &self.entries {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The number of enrolled faces.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Writes the enrolled names and embeddings to a file, which only galleries of the
+    /// same model should [`load`](Self::load).
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), YuNetError> {
+        let mut file = std::fs::File::create(path).map_err(io_error)?;
+        self.write_to(&mut file).map_err(io_error)
+    }
+
+    /// Reads a gallery written by [`save`](Self::save), embedding further faces with
+    /// `model`.
+    pub fn load(path: impl AsRef<Path>, model: M) -> Result<Self, YuNetError> {
+        let mut file = std::fs::File::open(path).map_err(io_error)?;
+        Self::read_from(&mut file, model)
+    }
+
+    /// Writes the gallery in the format of [`save`](Self::save): the magic bytes and
+    /// version, then each face's name and embedding, lengths first, little-endian.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (name, embedding) in &self.entries {
+            writer.write_all(&(name.len() as u32).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&(embedding.len() as u32).to_le_bytes())?;
+            for value in embedding {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a gallery in the format of [`save`](Self::save), failing with
+    /// [`YuNetError::InvalidFile`] if it isn't one.
+    pub fn read_from(reader: &mut impl Read, model: M) -> Result<Self, YuNetError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).map_err(io_error)?;
+        if &magic != MAGIC || read_u32(reader)? != VERSION {
+            return Err(YuNetError::InvalidFile);
+        }
+        let mut gallery = Self::new(model);
+        for _ in 0..read_u32(reader)? {
+            // Read through `take` so that corrupt lengths don't allocate.
+            let length = read_u32(reader)? as usize;
+            let mut name = Vec::new();
+            reader
+                .by_ref()
+                .take(length as u64)
+                .read_to_end(&mut name)
+                .map_err(io_error)?;
+            if name.len() != length {
+                return Err(YuNetError::InvalidFile);
+            }
+            let name = String::from_utf8(name).map_err(|_| YuNetError::InvalidFile)?;
+            let embedding = (0..read_u32(reader)?)
+                .map(|_| read_u32(reader).map(f32::from_bits))
+                .collect::<Result<_, _>>()?;
+            gallery.entries.push((name, embedding));
+        }
+        Ok(gallery)
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, YuNetError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes).map_err(io_error)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn io_error(e: std::io::Error) -> YuNetError {
+    if e.kind() == std::io::ErrorKind::UnexpectedEof {
+        YuNetError::InvalidFile
+    } else {
+        YuNetError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::RawFace;
+
+    /// Embeds a crop as its total color.
+    struct MeanColor;
+
+    impl EmbeddingModel for MeanColor {
+        fn input_size(&self) -> usize {
+            8
+        }
+
+        fn embed(&mut self, aligned: &ImageView) -> Result<Vec<f32>, YuNetError> {
+            let mut sums = vec![0.0; 3];
+            for (i, &v) in aligned.data().iter().enumerate() {
+                sums[i % 3] += v as f32;
+            }
+            Ok(sums)
+        }
+    }
+
+    #[test]
+    fn enrolls_identifies_and_persists() {
+        // Faces over a blue and a red half of the frame.
+        let (width, height) = (40, 20);
+        let bgr: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                if i % width < 20 {
+                    [200, 0, 0]
+                } else {
+                    [0, 0, 200]
+                }
+            })
+            .collect();
+        let image = ImageView::new(&bgr, width, height).unwrap();
+        let face = |x| {
+            let raw = RawFace {
+                score: 0.9,
+                x: x as f32,
+                y: 2.0,
+                w: 16.0,
+                h: 16.0,
+                lm: [x + 5, 7, x + 11, 7, x + 8, 11, x + 6, 15, x + 10, 15].map(|v| v as f32),
+            };
+            Face::from_raw_face(&raw, (width, height))
+        };
+        let (blue, red) = (face(2), face(22));
+
+        let mut gallery = FaceGallery::new(MeanColor).with_threshold(0.9);
+        gallery.enroll("blue", &blue, &image).unwrap();
+        assert_eq!(
+            Some("blue"),
+            gallery.identify(&blue, &image).unwrap().map(|m| m.0)
+        );
+        assert_eq!(None, gallery.identify(&red, &image).unwrap());
+
+        gallery.enroll("red", &red, &image).unwrap();
+        let mut bytes = Vec::new();
+        gallery.write_to(&mut bytes).unwrap();
+        let mut loaded = FaceGallery::read_from(&mut bytes.as_slice(), MeanColor).unwrap();
+        assert_eq!(vec!["blue", "red"], loaded.names());
+        let (name, similarity) = loaded.identify(&red, &image).unwrap().unwrap();
+        assert_eq!("red", name);
+        assert!(similarity > 0.99);
+        assert_eq!(1, loaded.remove("red"));
+        assert_eq!(1, loaded.len());
+
+        assert!(matches!(
+            FaceGallery::read_from(&mut &bytes[..bytes.len() - 1], MeanColor),
+            Err(YuNetError::InvalidFile)
+        ));
+    }
+}
@@ -0,0 +1,312 @@
+//! Evidence capture for doorbell-style applications: saving the frame a face first appears
+//! in, with a few frames before and after it, as a [`Pipeline`](crate::Pipeline) runs.
+//!
+//! ```no_run
+//! # use rusty_yunet::recorder::{DirectoryStore, Recorder, RecorderConfig};
+//! # use rusty_yunet::{FaceDetector, ImageView, Pipeline, TrackerConfig};
+//! # let frames: Vec<image::RgbImage> = Vec::new();
+//! let mut pipeline = Pipeline::new(FaceDetector::new(), TrackerConfig::default());
+//! let mut recorder = Recorder::new(RecorderConfig::default(), DirectoryStore::new("events"));
+//! for frame in &frames {
+//!     let bgr: Vec<u8> = frame.pixels().flat_map(|p| [p[2], p[1], p[0]]).collect();
+//!     let (width, height) = (frame.width() as usize, frame.height() as usize);
+//!     let result = pipeline.process(&ImageView::new(&bgr, width, height)?)?;
+//!     recorder.observe(frame, &result)?;
+//! }
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use image::RgbImage;
+
+use crate::pipeline::FrameResult;
+use crate::YuNetError;
+
+/// Tuning knobs for a [`Recorder`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RecorderConfig {
+    /// Frames saved from before the one a face appears in.
+    pub pre_frames: usize,
+    /// Frames saved from after it.
+    pub post_frames: usize,
+    /// The shortest time between the starts of two events; faces appearing sooner are not
+    /// recorded, so that a crowd at the door doesn't fill the disk.
+    pub min_interval: Duration,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            pre_frames: 0,
+            post_frames: 0,
+            min_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Where a snapshot stands in its event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnapshotRole {
+    Before,
+    /// The frame the faces first appeared in.
+    Trigger,
+    After,
+}
+
+/// A frame saved by a [`Recorder`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Counts the events recorded, starting at zero.
+    pub event: u64,
+    pub role: SnapshotRole,
+    /// The index of the frame in the stream.
+    pub frame: u64,
+    pub timestamp: Option<Duration>,
+    /// The tracks whose appearance started the event.
+    pub tracks: Vec<u64>,
+    pub image: RgbImage,
+}
+
+/// Where a [`Recorder`] saves its snapshots.
+pub trait SnapshotStore: Send {
+    fn save(&mut self, snapshot: &Snapshot) -> Result<(), YuNetError>;
+}
+
+/// Saves snapshots as image files in a directory, created if needed, named
+/// `event-<event>-frame-<frame>.<extension>`, such as `event-0003-frame-000120.jpg`.
+#[derive(Debug, Clone)]
+pub struct DirectoryStore {
+    dir: PathBuf,
+    extension: String,
+}
+
+impl DirectoryStore {
+    /// Saves JPEG files in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            extension: "jpg".to_owned(),
+        }
+    }
+
+    /// Saves in the format of `extension`, such as `png`.
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file `snapshot` is saved to.
+    pub fn path(&self, snapshot: &Snapshot) -> PathBuf {
+        self.dir.join(format!(
+            "event-{:04}-frame-{:06}.{}",
+            snapshot.event, snapshot.frame, self.extension
+        ))
+    }
+}
+
+impl SnapshotStore for DirectoryStore {
+    fn save(&mut self, snapshot: &Snapshot) -> Result<(), YuNetError> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| YuNetError::Io(e.to_string()))?;
+        snapshot
+            .image
+            .save(self.path(snapshot))
+            .map_err(|e| YuNetError::Io(e.to_string()))
+    }
+}
+
+/// Saves frames whenever a new track starts, that is when a face first appears.
+pub struct Recorder<S = DirectoryStore> {
+    config: RecorderConfig,
+    store: S,
+    /// The latest frames, for the frames before an event.
+    history: VecDeque<(u64, Option<Duration>, RgbImage)>,
+    /// Track IDs below this were seen already; the tracker hands them out in order.
+    next_track: u64,
+    /// The current event, while frames after it remain to be saved.
+    recording: Option<(Snapshot, usize)>,
+    last_event: Option<Duration>,
+    events: u64,
+    started: Instant,
+}
+
+impl<S: SnapshotStore> Recorder<S> {
+    pub fn new(config: RecorderConfig, store: S) -> Self {
+        Self {
+            config,
+            store,
+            history: VecDeque::new(),
+            next_track: 0,
+            recording: None,
+            last_event: None,
+            events: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Takes in the results of a pipeline for the frame `image`, saving it and the frames
+    /// before it if it starts an event, or saving it if it follows one. Returns the number
+    /// of the event started, if any.
+    ///
+    /// Frames without a timestamp are rate limited by the time elapsed since the recorder
+    /// was created.
+    pub fn observe(
+        &mut self,
+        image: &RgbImage,
+        result: &FrameResult,
+    ) -> Result<Option<u64>, YuNetError> {
+        let appeared: Vec<u64> = result
+            .track_ids
+            .iter()
+            .copied()
+            .filter(|&id| id >= self.next_track)
+            .collect();
+        if let Some(&max) = appeared.iter().max() {
+            self.next_track = max + 1;
+        }
+        let now = result.timestamp.unwrap_or_else(|| self.started.elapsed());
+
+        let mut started = None;
+        if let Some((snapshot, remaining)) = &mut self.recording {
+            snapshot.role = SnapshotRole::After;
+            snapshot.frame = result.index;
+            snapshot.timestamp = result.timestamp;
+            snapshot.image = image.clone();
+            self.store.save(snapshot)?;
+            *remaining -= 1;
+            if *remaining == 0 {
+                self.recording = None;
+            }
+        } else if !appeared.is_empty()
+            && self
+                .last_event
+                .is_none_or(|last| now.saturating_sub(last) >= self.config.min_interval)
+        {
+            let event = self.events;
+            self.events += 1;
+            self.last_event = Some(now);
+            let mut snapshot = Snapshot {
+                event,
+                role: SnapshotRole::Before,
+                frame: 0,
+                timestamp: None,
+                tracks: appeared,
+                image: RgbImage::new(0, 0),
+            };
+            for (frame, timestamp, image) in self.history.drain(..) {
+                (snapshot.frame, snapshot.timestamp, snapshot.image) = (frame, timestamp, image);
+                self.store.save(&snapshot)?;
+            }
+            snapshot.role = SnapshotRole::Trigger;
+            (snapshot.frame, snapshot.timestamp) = (result.index, result.timestamp);
+            snapshot.image = image.clone();
+            self.store.save(&snapshot)?;
+            if self.config.post_frames > 0 {
+                self.recording = Some((snapshot, self.config.post_frames));
+            }
+            started = Some(event);
+        }
+
+        // Frames saved as part of an event aren't saved again before the next one.
+        if self.config.pre_frames > 0 && started.is_none() && self.recording.is_none() {
+            if self.history.len() == self.config.pre_frames {
+                self.history.pop_front();
+            }
+            self.history
+                .push_back((result.index, result.timestamp, image.clone()));
+        }
+        Ok(started)
+    }
+
+    /// How many events were recorded.
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn config(&self) -> &RecorderConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{face, ScriptedBackend};
+    use crate::{ImageView, Pipeline, Rect, TrackerConfig};
+
+    #[derive(Default)]
+    struct Saved(Vec<(u64, SnapshotRole, u64)>);
+
+    impl SnapshotStore for Saved {
+        fn save(&mut self, snapshot: &Snapshot) -> Result<(), YuNetError> {
+            self.0.push((snapshot.event, snapshot.role, snapshot.frame));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn saves_frames_around_new_faces() {
+        let door = face(Rect::with_size(100.0, 100.0, 80.0, 80.0), 0.9);
+        let hall = face(Rect::with_size(400.0, 100.0, 80.0, 80.0), 0.9);
+        let backend = ScriptedBackend::new([
+            vec![],
+            vec![],
+            vec![door.clone()],
+            vec![door.clone()],
+            vec![door.clone(), hall.clone()],
+            vec![door, hall],
+        ]);
+        let mut pipeline = Pipeline::new(backend, TrackerConfig::default());
+        let config = RecorderConfig {
+            pre_frames: 1,
+            post_frames: 1,
+            min_interval: Duration::from_secs(2),
+        };
+        let mut recorder = Recorder::new(config, Saved::default());
+        let frame = vec![0; 640 * 480 * 3];
+        let image = RgbImage::new(640, 480);
+        let mut events = Vec::new();
+        for i in 0..6 {
+            let view = ImageView::new(&frame, 640, 480).unwrap();
+            let result = pipeline
+                .process_at(&view, Some(Duration::from_millis(500 * i)))
+                .unwrap();
+            events.push(recorder.observe(&image, &result).unwrap());
+        }
+        // The face in the hall appears too soon after the one at the door to be recorded.
+        assert_eq!(vec![None, None, Some(0), None, None, None], events);
+        assert_eq!(
+            vec![
+                (0, SnapshotRole::Before, 1),
+                (0, SnapshotRole::Trigger, 2),
+                (0, SnapshotRole::After, 3),
+            ],
+            recorder.store().0
+        );
+
+        let store = DirectoryStore::new("events").with_extension("png");
+        let snapshot = Snapshot {
+            event: 3,
+            role: SnapshotRole::Trigger,
+            frame: 120,
+            timestamp: None,
+            tracks: vec![0],
+            image,
+        };
+        assert_eq!(
+            Path::new("events/event-0003-frame-000120.png"),
+            store.path(&snapshot)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Recording frames with their detections into one file on site, and replaying them at a
+//! desk, so that what trackers, presence and zones made of an installation's detections can
+//! be reproduced without its camera or detector.
+//!
+//! Replayed frames carry the faces as they were detected, in the order and at the
+//! timestamps they were, so feeding them to a fresh [`Tracker`](crate::Tracker) or
+//! [`PresenceDetector`](crate::PresenceDetector) with the installation's configuration gives
+//! the same track IDs and events as on site.
+//!
+//! ```no_run
+//! # use rusty_yunet::replay::DetectionLog;
+//! # use rusty_yunet::{Tracker, TrackerConfig};
+//! let mut tracker = Tracker::new(TrackerConfig::default());
+//! for frame in DetectionLog::replay("entrance.yunetlog")? {
+//!     let frame = frame?;
+//!     let ids = tracker.update_at(&frame.faces, frame.timestamp);
+//!     println!("{:?}: tracks {ids:?}", frame.timestamp);
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! The file is little-endian: the magic `YUNETLOG` and the version 1 as a `u32`, then each
+//! frame: its timestamp in microseconds as a `u64`, `u64::MAX` if unknown; the width and
+//! height it was detected at and the width, height and channels of the pixels stored, as
+//! `u32`s; the tightly packed pixels; the number of faces as a `u32`; and each face as its
+//! confidence, rectangle and the x, y pairs of its landmarks as 15 `f32`s, then the width and
+//! height of its detection as `u32`s.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use glam::Vec2;
+
+use crate::geometry::{CoordinateMapper, Fit};
+use crate::{resample, Face, FaceLandmarks, ImageView, Rect};
+
+const MAGIC: &[u8; 8] = b"YUNETLOG";
+const VERSION: u32 = 1;
+const NO_TIMESTAMP: u64 = u64::MAX;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// Entry points of the log format: [`record`](Self::record) and [`replay`](Self::replay).
+pub struct DetectionLog;
+
+impl DetectionLog {
+    /// Creates, or replaces, the log at `path`.
+    pub fn record(path: impl AsRef<Path>) -> io::Result<LogWriter> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        Ok(LogWriter {
+            file,
+            max_side: None,
+            frames: 0,
+        })
+    }
+
+    /// The frames of the log at `path`, in the order they were recorded.
+    pub fn replay(path: impl AsRef<Path>) -> io::Result<Replay> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut header = [0; 12];
+        file.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(invalid("not a detection log"));
+        }
+        if header[8..] != VERSION.to_le_bytes() {
+            return Err(invalid("unsupported detection log version"));
+        }
+        Ok(Replay { file })
+    }
+}
+
+/// Appends frames to a log; see [`DetectionLog::record`].
+pub struct LogWriter {
+    file: BufWriter<File>,
+    max_side: Option<usize>,
+    frames: u64,
+}
+
+impl LogWriter {
+    /// Downscales frames whose longer side exceeds `max_side` before storing them, to keep
+    /// logs of long sessions small. Faces are stored as detected.
+    pub fn with_max_side(mut self, max_side: usize) -> Self {
+        self.max_side = Some(max_side);
+        self
+    }
+
+    /// Appends a frame with the faces detected in it.
+    pub fn write(
+        &mut self,
+        image: &ImageView,
+        faces: &[Face],
+        timestamp: Option<Duration>,
+    ) -> io::Result<()> {
+        let (width, height) = image.dimensions();
+        let stored = self
+            .max_side
+            .filter(|&max_side| width.max(height) > max_side)
+            .map_or((width, height), |max_side| {
+                resample::fit_within(width, height, max_side)
+            });
+        let pixels = if stored == (width, height) {
+            let row = width * image.channels();
+            (0..height)
+                .flat_map(|y| &image.data()[y * image.stride()..][..row])
+                .copied()
+                .collect()
+        } else {
+            resample::resize(image, stored.0, stored.1)
+        };
+
+        let timestamp = timestamp.map_or(NO_TIMESTAMP, |t| t.as_micros() as u64);
+        let mut record = Vec::with_capacity(40 + pixels.len() + 68 * faces.len());
+        record.extend_from_slice(&timestamp.to_le_bytes());
+        for value in [width, height, stored.0, stored.1, image.channels()] {
+            record.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        record.extend_from_slice(&pixels);
+        record.extend_from_slice(&(faces.len() as u32).to_le_bytes());
+        for face in faces {
+            let rect = face.rectangle();
+            let points = face.landmarks().as_array();
+            let values = [face.confidence(), rect.x, rect.y, rect.w, rect.h]
+                .into_iter()
+                .chain(points.iter().flat_map(|p| [p.x, p.y]));
+            for value in values {
+                record.extend_from_slice(&value.to_le_bytes());
+            }
+            let (width, height) = face.detection_dimensions();
+            record.extend_from_slice(&(width as u32).to_le_bytes());
+            record.extend_from_slice(&(height as u32).to_le_bytes());
+        }
+        self.file.write_all(&record)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Frames written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Writes out what's buffered, such as before an installation shuts down. Dropping the
+    /// writer also does, ignoring errors.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A frame read back from a log.
+#[derive(Debug, Clone)]
+pub struct LoggedFrame {
+    pub timestamp: Option<Duration>,
+    /// The dimensions of the frame as detected, which the faces' coordinates are in.
+    pub dimensions: (usize, usize),
+    /// The faces as detected.
+    pub faces: Vec<Face>,
+    pixels: Vec<u8>,
+    stored: (usize, usize),
+    channels: usize,
+}
+
+impl LoggedFrame {
+    /// The frame's pixels as stored, downscaled if the log was recorded
+    /// [`with_max_side`](LogWriter::with_max_side).
+    pub fn image(&self) -> ImageView<'_> {
+        ImageView::packed(&self.pixels, self.stored.0, self.stored.1, self.channels)
+            .expect("logged frames are packed")
+    }
+
+    /// Maps the faces' coordinates to those of [`image`](Self::image), such as to draw them
+    /// on it.
+    pub fn mapper(&self) -> CoordinateMapper {
+        CoordinateMapper::new(self.dimensions, self.stored, Fit::Stretch)
+    }
+}
+
+/// The frames of a log, in order; see [`DetectionLog::replay`]. A log cut short, such as by
+/// a power loss, ends with an error for its last, partial frame.
+pub struct Replay {
+    file: BufReader<File>,
+}
+
+impl Replay {
+    fn read_frame(&mut self) -> io::Result<Option<LoggedFrame>> {
+        let mut timestamp = [0; 8];
+        match self.file.read_exact(&mut timestamp) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let timestamp = match u64::from_le_bytes(timestamp) {
+            NO_TIMESTAMP => None,
+            micros => Some(Duration::from_micros(micros)),
+        };
+        let [width, height, stored_width, stored_height, channels] = self.u32s()?;
+        if !matches!(channels, 1 | 3) {
+            return Err(invalid("logged frame is neither BGR nor grayscale"));
+        }
+        let mut pixels = vec![0; stored_width * stored_height * channels];
+        self.file.read_exact(&mut pixels)?;
+        let [count] = self.u32s()?;
+        let faces = (0..count)
+            .map(|_| {
+                let mut values = [0f32; 15];
+                for value in &mut values {
+                    let mut bytes = [0; 4];
+                    self.file.read_exact(&mut bytes)?;
+                    *value = f32::from_le_bytes(bytes);
+                }
+                let [confidence, x, y, w, h, ..] = values;
+                let points =
+                    [0, 1, 2, 3, 4].map(|i| Vec2::new(values[5 + 2 * i], values[6 + 2 * i]));
+                let [width, height] = self.u32s()?;
+                Ok(Face::new(
+                    confidence,
+                    Rect::with_size(x, y, w, h),
+                    FaceLandmarks::from_array(points),
+                    (width, height),
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Some(LoggedFrame {
+            timestamp,
+            dimensions: (width, height),
+            faces,
+            pixels,
+            stored: (stored_width, stored_height),
+            channels,
+        }))
+    }
+
+    fn u32s<const N: usize>(&mut self) -> io::Result<[usize; N]> {
+        let mut values = [0; N];
+        for value in &mut values {
+            let mut bytes = [0; 4];
+            self.file.read_exact(&mut bytes)?;
+            *value = u32::from_le_bytes(bytes) as usize;
+        }
+        Ok(values)
+    }
+}
+
+impl Iterator for Replay {
+    type Item = io::Result<LoggedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock, Tracker, TrackerConfig};
+
+    #[test]
+    fn replays_tracking_as_recorded() {
+        let path =
+            std::env::temp_dir().join(format!("rusty-yunet-{}.yunetlog", std::process::id()));
+        let pixels = vec![90; 640 * 480 * 3];
+        let frame = ImageView::new(&pixels, 640, 480).unwrap();
+        let mut live = Tracker::new(TrackerConfig::default());
+        let mut writer = DetectionLog::record(&path).unwrap().with_max_side(160);
+        let mut recorded = Vec::new();
+        for i in 0..6 {
+            // One face walking right, another appearing halfway.
+            let mut faces = vec![mock::face(
+                Rect::with_size(100.0 + 20.0 * i as f32, 100.0, 60.0, 70.0),
+                0.9,
+            )];
+            if i >= 3 {
+                faces.push(mock::face(Rect::with_size(400.0, 200.0, 50.0, 60.0), 0.8));
+            }
+            let timestamp = Some(Duration::from_millis(100 * i));
+            writer.write(&frame, &faces, timestamp).unwrap();
+            recorded.push(live.update_at(&faces, timestamp));
+        }
+        writer.flush().unwrap();
+        assert_eq!(6, writer.frames());
+
+        let mut replayed = Tracker::new(TrackerConfig::default());
+        let frames: Vec<LoggedFrame> = DetectionLog::replay(&path)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(6, frames.len());
+        for (frame, ids) in frames.iter().zip(&recorded) {
+            assert_eq!(*ids, replayed.update_at(&frame.faces, frame.timestamp));
+        }
+        let last = &frames[5];
+        assert_eq!((160, 120), last.image().dimensions());
+        assert_eq!(90, last.image().data()[0]);
+        assert_eq!(
+            Rect::with_size(100.0, 50.0, 12.5, 15.0),
+            last.mapper().rect(last.faces[1].rectangle())
+        );
+
+        // A log cut short mid-frame ends with an error.
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 10]).unwrap();
+        let replay = DetectionLog::replay(&path).unwrap();
+        let results: Vec<_> = replay.collect();
+        assert_eq!(6, results.len());
+        assert!(results[5].is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
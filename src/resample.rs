@@ -0,0 +1,104 @@
+use crate::geometry::{CoordinateMapper, Rect};
+use crate::io::ImageView;
+
+/// Downscales an image by averaging the block of source pixels covered by each destination
+/// pixel, or upscales it by repeating pixels. The output is tightly packed, with the
+/// channels of the input.
+pub(crate) fn resize(image: &ImageView, dst_width: usize, dst_height: usize) -> Vec<u8> {
+    let mut dst = vec![0u8; dst_width * dst_height * image.channels()];
+    resize_into(
+        image,
+        dst_width,
+        dst_height,
+        &mut dst,
+        dst_width * image.channels(),
+    );
+    dst
+}
+
+/// Like [`resize`], into rows of `dst` starting `dst_stride` bytes apart.
+fn resize_into(
+    image: &ImageView,
+    dst_width: usize,
+    dst_height: usize,
+    dst: &mut [u8],
+    dst_stride: usize,
+) {
+    let (src, stride, channels) = (image.data(), image.stride(), image.channels());
+    let (width, height) = image.dimensions();
+    for dy in 0..dst_height {
+        let y0 = dy * height / dst_height;
+        let y1 = ((dy + 1) * height / dst_height).max(y0 + 1).min(height);
+        for dx in 0..dst_width {
+            let x0 = dx * width / dst_width;
+            let x1 = ((dx + 1) * width / dst_width).max(x0 + 1).min(width);
+            let mut sum = [0u32; 3];
+            for y in y0..y1 {
+                let row = &src[y * stride..];
+                for x in x0..x1 {
+                    for (c, s) in sum[..channels].iter_mut().enumerate() {
+                        *s += row[x * channels + c] as u32;
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            let out = &mut dst[dy * dst_stride + dx * channels..][..channels];
+            for (o, s) in out.iter_mut().zip(sum) {
+                *o = ((s + count / 2) / count) as u8;
+            }
+        }
+    }
+}
+
+/// Resamples an image into a tightly packed one of the dimensions `mapper` maps to, placed
+/// as it maps the image, such as letterboxed or cropped. Uncovered areas are black. Writes
+/// into `dst`, reusing its allocation.
+pub(crate) fn fit_into(image: &ImageView, mapper: &CoordinateMapper, dst: &mut Vec<u8>) {
+    let (width, height) = mapper.target_dimensions();
+    let channels = image.channels();
+    dst.clear();
+    dst.resize(width * height * channels, 0);
+    let target = Rect::with_size(0.0, 0.0, width as f32, height as f32);
+    let visible = mapper.inverse().rect(target);
+    let x0 = visible.x.round().max(0.0) as usize;
+    let y0 = visible.y.round().max(0.0) as usize;
+    let x1 = ((visible.x + visible.w).round().max(0.0) as usize).min(image.width());
+    let y1 = ((visible.y + visible.h).round().max(0.0) as usize).min(image.height());
+    let Some(source) = image.crop(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0)) else {
+        return;
+    };
+    let placed = mapper.rect(Rect::with_size(
+        x0 as f32,
+        y0 as f32,
+        source.width() as f32,
+        source.height() as f32,
+    ));
+    let dx0 = (placed.x.round().max(0.0) as usize).min(width);
+    let dy0 = (placed.y.round().max(0.0) as usize).min(height);
+    let dx1 = ((placed.x + placed.w).round().max(0.0) as usize).clamp(dx0, width);
+    let dy1 = ((placed.y + placed.h).round().max(0.0) as usize).clamp(dy0, height);
+    if dx1 == dx0 || dy1 == dy0 {
+        return;
+    }
+    let start = (dy0 * width + dx0) * channels;
+    resize_into(
+        &source,
+        dx1 - dx0,
+        dy1 - dy0,
+        &mut dst[start..],
+        width * channels,
+    );
+}
+
+/// Dimensions that fit `width`x`height` within `max_side`, preserving the aspect ratio.
+pub(crate) fn fit_within(width: usize, height: usize, max_side: usize) -> (usize, usize) {
+    let longest = width.max(height);
+    if longest <= max_side {
+        return (width, height);
+    }
+    let scale = max_side as f32 / longest as f32;
+    (
+        ((width as f32 * scale).round() as usize).max(1),
+        ((height as f32 * scale).round() as usize).max(1),
+    )
+}
@@ -0,0 +1,252 @@
+//! Frames from IP cameras over RTSP, decoded by an `ffmpeg` process, with reconnection built
+//! in since streams drop constantly in practice.
+//!
+//! `ffmpeg` must be installed separately, and is looked up on the search path unless
+//! [`RtspConfig::ffmpeg`] says otherwise. It scales frames to the configured size, so that
+//! their size is known before the first one arrives and doesn't change when the camera's
+//! does.
+//!
+//! ```no_run
+//! # use rusty_yunet::rtsp::{RtspConfig, RtspSource};
+//! # use rusty_yunet::FaceDetector;
+//! let mut camera = RtspSource::new(RtspConfig::new("rtsp://192.168.1.20/stream1", 1280, 720));
+//! let mut detector = FaceDetector::new();
+//! loop {
+//!     let frame = camera.next_frame()?;
+//!     let faces = detector.detect_image(&frame.image).unwrap();
+//!     println!("frame {}: {} faces", frame.index, faces.len());
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::ImageView;
+
+/// How RTSP carries the stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RtspTransport {
+    /// Interleaved in the control connection, which gets through firewalls and NAT.
+    #[default]
+    Tcp,
+    Udp,
+}
+
+/// Where an [`RtspSource`] reads from, and how it reconnects.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RtspConfig {
+    pub url: String,
+    /// The size frames are scaled to, in pixels.
+    pub width: usize,
+    pub height: usize,
+    pub transport: RtspTransport,
+    /// The `ffmpeg` executable.
+    pub ffmpeg: PathBuf,
+    /// How long the camera may stay silent before the connection is dropped.
+    pub timeout: Duration,
+    /// The wait before the first reconnection, doubled after each failed one.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Connection attempts in a row without a frame before giving up, or `None` to keep
+    /// trying forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl RtspConfig {
+    pub fn new(url: impl Into<String>, width: usize, height: usize) -> Self {
+        Self {
+            url: url.into(),
+            width,
+            height,
+            transport: RtspTransport::default(),
+            ffmpeg: PathBuf::from("ffmpeg"),
+            timeout: Duration::from_secs(5),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.ffmpeg);
+        let transport = match self.transport {
+            RtspTransport::Tcp => "tcp",
+            RtspTransport::Udp => "udp",
+        };
+        command
+            .args([
+                "-nostdin",
+                "-loglevel",
+                "error",
+                "-rtsp_transport",
+                transport,
+            ])
+            .args(["-timeout", &self.timeout.as_micros().to_string()])
+            .args(["-i", &self.url, "-an"])
+            .args(["-vf", &format!("scale={}:{}", self.width, self.height)])
+            .args(["-f", "rawvideo", "-pix_fmt", "bgr24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        command
+    }
+}
+
+/// A frame read from an [`RtspSource`].
+#[derive(Debug, Clone, Copy)]
+pub struct RtspFrame<'a> {
+    /// The position of the frame among those read, across reconnections, from 0.
+    pub index: u64,
+    /// When the frame was read, since the source was created.
+    pub timestamp: Duration,
+    pub image: ImageView<'a>,
+}
+
+/// Reads the frames of an RTSP stream as BGR images, restarting `ffmpeg` whenever the stream
+/// ends or stalls.
+#[derive(Debug)]
+pub struct RtspSource {
+    config: RtspConfig,
+    process: Option<(Child, ChildStdout)>,
+    buffer: Vec<u8>,
+    next: u64,
+    reconnects: u64,
+    started: Instant,
+}
+
+impl RtspSource {
+    /// Connects on the first call to [`next_frame`](Self::next_frame).
+    pub fn new(config: RtspConfig) -> Self {
+        Self {
+            buffer: vec![0; 3 * config.width * config.height],
+            config,
+            process: None,
+            next: 0,
+            reconnects: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Waits for the next frame, reconnecting as many times as it takes, waiting longer after
+    /// each failed attempt. Fails if `ffmpeg` can't be started, or after
+    /// [`RtspConfig::max_attempts`] attempts in a row without a frame.
+    pub fn next_frame(&mut self) -> io::Result<RtspFrame<'_>> {
+        let mut backoff = self.config.initial_backoff;
+        let mut attempts = 0;
+        loop {
+            if self.process.is_none() {
+                if self.config.max_attempts.is_some_and(|max| attempts >= max) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotConnected,
+                        format!("no frames from {} in {attempts} attempts", self.config.url),
+                    ));
+                }
+                if attempts > 0 || self.next > 0 {
+                    self.reconnects += 1;
+                }
+                attempts += 1;
+                let mut child = self.config.command().spawn()?;
+                let stdout = child.stdout.take().expect("stdout is piped");
+                self.process = Some((child, stdout));
+            }
+            let (_, stdout) = self.process.as_mut().expect("connected above");
+            match stdout.read_exact(&mut self.buffer) {
+                Ok(()) => break,
+                Err(_error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(url = %self.config.url, error = %_error, ?backoff, "lost RTSP stream");
+                    self.disconnect();
+                    std::thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.config.max_backoff);
+                }
+            }
+        }
+        let index = self.next;
+        self.next += 1;
+        let image = ImageView::new(&self.buffer, self.config.width, self.config.height)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        Ok(RtspFrame {
+            index,
+            timestamp: self.started.elapsed(),
+            image,
+        })
+    }
+
+    /// How many times the stream was reconnected, successfully or not.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    pub fn config(&self) -> &RtspConfig {
+        &self.config
+    }
+
+    fn disconnect(&mut self) {
+        if let Some((mut child, _)) = self.process.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for RtspSource {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+/// Whether `ffmpeg` can be started from the search path, for
+/// [`capabilities`](crate::capabilities).
+pub(crate) fn ffmpeg_available() -> io::Result<()> {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(drop)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn reconnects_after_drops() {
+        // Stands in for ffmpeg: fails the first time, then streams two 2x2 frames and ends.
+        let dir = std::env::temp_dir().join(format!("rusty-yunet-rtsp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("ffmpeg");
+        let marker = dir.join("connected");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nif [ -e {0} ]; then head -c 24 /dev/zero; else touch {0}; exit 1; fi\n",
+                marker.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = RtspConfig::new("rtsp://camera/stream", 2, 2);
+        config.ffmpeg = script;
+        config.initial_backoff = Duration::from_millis(1);
+        config.max_attempts = Some(3);
+        let mut source = RtspSource::new(config);
+        for index in 0..3 {
+            let frame = source.next_frame().unwrap();
+            assert_eq!((index, (2, 2)), (frame.index, frame.image.dimensions()));
+        }
+        assert_eq!(2, source.reconnects());
+
+        std::fs::remove_file(&marker).unwrap();
+        let mut config = source.config().clone();
+        config.ffmpeg = dir.join("missing");
+        assert!(RtspSource::new(config).next_frame().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+//! Indexing photo collections: walking a directory tree and detecting faces in every image
+//! file found, optionally in parallel.
+//!
+//! ```no_run
+//! # use rusty_yunet::scan::{scan_directory, ScanOptions};
+//! let mut scan = scan_directory("photos", ScanOptions::default())?;
+//! for (path, faces) in &mut scan {
+//!     match faces {
+//!         Ok(faces) => println!("{}: {} faces", path.display(), faces.len()),
+//!         Err(e) => eprintln!("{}: {e}", path.display()),
+//!     }
+//! }
+//! println!("{:?}", scan.summary());
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "rayon")]
+use crate::FaceDetectorPool;
+use crate::{DetectorConfig, Face, FaceDetector, YuNetError};
+
+/// File extensions scanned by default, compared case-insensitively.
+pub const IMAGE_EXTENSIONS: [&str; 8] = ["jpg", "jpeg", "png", "bmp", "gif", "tif", "tiff", "webp"];
+
+/// What [`scan_directory`] scans, and how.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Extensions of the files to detect faces in, without the dot.
+    pub extensions: Vec<String>,
+    /// Whether subdirectories are scanned too.
+    pub recursive: bool,
+    pub config: DetectorConfig,
+    /// Images processed at once, on the current rayon thread pool, with a detector each.
+    /// Images are processed one by one without the `rayon` feature.
+    pub threads: usize,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            extensions: IMAGE_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            recursive: true,
+            config: DetectorConfig::default(),
+            threads: 1,
+        }
+    }
+}
+
+/// Totals over the files a [`Scan`] has yielded so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    pub files: usize,
+    /// Files that couldn't be decoded or detected in, and directories that couldn't be
+    /// listed.
+    pub failed: usize,
+    pub files_with_faces: usize,
+    pub faces: usize,
+}
+
+impl ScanSummary {
+    fn record(&mut self, result: &Result<Vec<Face>, YuNetError>) {
+        match result {
+            Ok(faces) => {
+                self.files += 1;
+                self.faces += faces.len();
+                self.files_with_faces += usize::from(!faces.is_empty());
+            }
+            Err(_) => self.failed += 1,
+        }
+    }
+}
+
+/// Detects faces in every image file under `path`, in path order, yielding each with its
+/// faces as it goes. Directories that can't be listed are yielded with
+/// [`YuNetError::Io`]. Fails if the configured backend isn't available.
+pub fn scan_directory(path: impl AsRef<Path>, options: ScanOptions) -> Result<Scan, YuNetError> {
+    #[cfg(feature = "rayon")]
+    let detector = if options.threads > 1 {
+        Detector::Pool(FaceDetectorPool::with_config(
+            options.threads,
+            options.config,
+        )?)
+    } else {
+        Detector::Single(Box::new(FaceDetector::with_config(options.config)?))
+    };
+    #[cfg(not(feature = "rayon"))]
+    let detector = Detector::Single(Box::new(FaceDetector::with_config(options.config)?));
+    Ok(Scan {
+        files: Walker::new(path.as_ref(), options.extensions, options.recursive),
+        detector,
+        threads: options.threads.max(1),
+        ready: VecDeque::new(),
+        summary: ScanSummary::default(),
+    })
+}
+
+enum Detector {
+    Single(Box<FaceDetector>),
+    #[cfg(feature = "rayon")]
+    Pool(FaceDetectorPool),
+}
+
+/// The files of a [`scan_directory`] and the faces detected in them.
+pub struct Scan {
+    files: Walker,
+    detector: Detector,
+    threads: usize,
+    ready: VecDeque<(PathBuf, Result<Vec<Face>, YuNetError>)>,
+    summary: ScanSummary,
+}
+
+impl Scan {
+    pub fn summary(&self) -> &ScanSummary {
+        &self.summary
+    }
+
+    /// Detects faces in the next batch of files, listing errors passing through.
+    fn fill(&mut self) {
+        let batch: Vec<_> = self.files.by_ref().take(self.threads).collect();
+        match &mut self.detector {
+            Detector::Single(detector) => {
+                self.ready.extend(batch.into_iter().map(|file| match file {
+                    Ok(path) => {
+                        let faces = detector.detect_file(&path);
+                        (path, faces)
+                    }
+                    Err(unlisted) => failed_listing(unlisted),
+                }))
+            }
+            #[cfg(feature = "rayon")]
+            Detector::Pool(pool) => {
+                use rayon::prelude::*;
+
+                let detected: Vec<_> = batch
+                    .into_par_iter()
+                    .map(|file| match file {
+                        Ok(path) => {
+                            let faces = pool.get().detect_file(&path);
+                            (path, faces)
+                        }
+                        Err(unlisted) => failed_listing(unlisted),
+                    })
+                    .collect();
+                self.ready.extend(detected);
+            }
+        }
+    }
+}
+
+fn failed_listing((dir, e): (PathBuf, io::Error)) -> (PathBuf, Result<Vec<Face>, YuNetError>) {
+    (dir, Err(YuNetError::Io(e.to_string())))
+}
+
+impl Iterator for Scan {
+    type Item = (PathBuf, Result<Vec<Face>, YuNetError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ready.is_empty() {
+            self.fill();
+        }
+        let item = self.ready.pop_front()?;
+        self.summary.record(&item.1);
+        Some(item)
+    }
+}
+
+/// The image files under a directory in path order, listed lazily, with the directories
+/// that can't be listed.
+pub(crate) struct Walker {
+    /// Paths left to visit, the next last.
+    pending: Vec<PathBuf>,
+    root: Option<PathBuf>,
+    extensions: Vec<String>,
+    recursive: bool,
+}
+
+impl Walker {
+    pub(crate) fn new(root: &Path, extensions: Vec<String>, recursive: bool) -> Self {
+        Self {
+            pending: Vec::new(),
+            root: Some(root.to_path_buf()),
+            extensions,
+            recursive,
+        }
+    }
+
+    fn list(&mut self, dir: PathBuf) -> Result<(), (PathBuf, io::Error)> {
+        let entries = std::fs::read_dir(&dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(|e| (dir.clone(), e))?;
+        let start = self.pending.len();
+        self.pending.extend(entries);
+        self.pending[start..].sort_by(|a, b| b.cmp(a));
+        Ok(())
+    }
+
+    fn is_image(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|image| extension.eq_ignore_ascii_case(image))
+            })
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<PathBuf, (PathBuf, io::Error)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            if let Err(failed) = self.list(root) {
+                return Some(Err(failed));
+            }
+        }
+        while let Some(path) = self.pending.pop() {
+            if path.is_dir() {
+                if self.recursive {
+                    if let Err(failed) = self.list(path) {
+                        return Some(Err(failed));
+                    }
+                }
+            } else if self.is_image(&path) {
+                return Some(Ok(path));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_trees() {
+        let dir = std::env::temp_dir().join(format!("rusty-yunet-tree-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("b/c")).unwrap();
+        std::fs::copy("sample.jpg", dir.join("b/c/sample.JPG")).unwrap();
+        std::fs::copy("sample.jpg", dir.join("a.jpg")).unwrap();
+        std::fs::write(dir.join("b/broken.png"), b"not an image").unwrap();
+        std::fs::write(dir.join("b/notes.txt"), b"skipped").unwrap();
+        let options = ScanOptions {
+            config: DetectorConfig {
+                max_side: Some(320),
+                ..DetectorConfig::default()
+            },
+            threads: 2,
+            ..ScanOptions::default()
+        };
+
+        let mut scan = scan_directory(&dir, options.clone()).unwrap();
+        let paths: Vec<_> = scan.by_ref().map(|(path, _)| path).collect();
+        assert_eq!(
+            vec![
+                dir.join("a.jpg"),
+                dir.join("b/broken.png"),
+                dir.join("b/c/sample.JPG")
+            ],
+            paths
+        );
+        let summary = *scan.summary();
+        assert_eq!(
+            (2, 1, 2),
+            (summary.files, summary.failed, summary.files_with_faces)
+        );
+
+        let flat = ScanOptions {
+            recursive: false,
+            ..options
+        };
+        assert_eq!(1, scan_directory(&dir, flat).unwrap().count());
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (path, missing) = scan_directory(&dir, ScanOptions::default())
+            .unwrap()
+            .next()
+            .unwrap();
+        assert_eq!(dir, path);
+        assert!(matches!(missing, Err(YuNetError::Io(_))));
+    }
+}
@@ -0,0 +1,162 @@
+//! Deciding when expensive second stages, such as alignment, embeddings or attribute
+//! models, run on tracked faces, so that pipelines scale to many faces without recomputing
+//! the same results every frame.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::pipeline::FrameResult;
+use crate::tracking::Track;
+use crate::{Face, FaceSize};
+
+/// When a second stage runs again on a track it already ran on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rerun {
+    /// On every frame the track is seen in.
+    Always,
+    /// Only once per track.
+    Never,
+    /// Once this many frames have passed since the last run.
+    EveryFrames(u64),
+    /// Once this much time has passed since the last run, by the frame timestamps. Tracks
+    /// of untimestamped frames run only once.
+    Every(Duration),
+}
+
+/// Which faces a [`StageScheduler`] lets a second stage run on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagePolicy {
+    /// Faces less confident than this are skipped, and tried again on later frames.
+    pub min_confidence: f32,
+    /// Faces smaller than this are skipped, and tried again on later frames.
+    pub min_face_size: Option<FaceSize>,
+    pub rerun: Rerun,
+}
+
+impl Default for StagePolicy {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.0,
+            min_face_size: None,
+            rerun: Rerun::Never,
+        }
+    }
+}
+
+/// Applies a [`StagePolicy`] to the tracked faces of consecutive frames, remembering when
+/// the stage last ran on each track.
+#[derive(Debug, Clone, Default)]
+pub struct StageScheduler {
+    policy: StagePolicy,
+    /// Frame index and timestamp of the last run, per track ID.
+    last_run: HashMap<u64, (u64, Option<Duration>)>,
+}
+
+impl StageScheduler {
+    pub fn new(policy: StagePolicy) -> Self {
+        Self {
+            policy,
+            last_run: HashMap::new(),
+        }
+    }
+
+    /// Whether the stage should run on `face`, of track `track_id`, in the frame of the
+    /// given index and timestamp. Answering yes records a run.
+    pub fn should_run(
+        &mut self,
+        track_id: u64,
+        face: &Face,
+        frame: u64,
+        timestamp: Option<Duration>,
+    ) -> bool {
+        let policy = &self.policy;
+        if face.confidence() < policy.min_confidence
+            || !policy.min_face_size.is_none_or(|size| size.admits(face))
+        {
+            return false;
+        }
+        let due = match (self.last_run.get(&track_id), policy.rerun) {
+            (None, _) | (Some(_), Rerun::Always) => true,
+            (Some(_), Rerun::Never) => false,
+            (Some(&(last, _)), Rerun::EveryFrames(frames)) => frame - last >= frames,
+            (Some(&(_, last)), Rerun::Every(interval)) => match (timestamp, last) {
+                (Some(now), Some(last)) => now.saturating_sub(last) >= interval,
+                _ => false,
+            },
+        };
+        if due {
+            self.last_run.insert(track_id, (frame, timestamp));
+        }
+        due
+    }
+
+    /// Indices of the faces of a pipeline frame the stage should run on, recording those
+    /// runs.
+    pub fn select(&mut self, result: &FrameResult) -> Vec<usize> {
+        result
+            .faces
+            .iter()
+            .zip(&result.track_ids)
+            .enumerate()
+            .filter(|(_, (face, &id))| self.should_run(id, face, result.index, result.timestamp))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Forgets the tracks that are no longer alive, such as after
+    /// [`Tracker::tracks`](crate::Tracker::tracks) dropped them, so that memory doesn't grow
+    /// over long runs.
+    pub fn retain_tracks(&mut self, tracks: &[Track]) {
+        self.last_run
+            .retain(|id, _| tracks.iter().any(|track| track.id() == *id));
+    }
+
+    pub fn policy(&self) -> &StagePolicy {
+        &self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(score: f32) -> Face {
+        test_face(score, [10.0, 10.0, 20.0, 20.0], (100, 100), None)
+    }
+
+    #[test]
+    fn runs_according_to_policy() {
+        let mut once = StageScheduler::new(StagePolicy {
+            min_confidence: 0.8,
+            ..Default::default()
+        });
+        // Skipped while unconfident, then run once.
+        assert!(!once.should_run(0, &face(0.5), 0, None));
+        assert!(once.should_run(0, &face(0.9), 1, None));
+        assert!(!once.should_run(0, &face(0.9), 2, None));
+        assert!(once.should_run(1, &face(0.9), 2, None));
+
+        let mut periodic = StageScheduler::new(StagePolicy {
+            rerun: Rerun::Every(Duration::from_secs(1)),
+            ..Default::default()
+        });
+        let at = |ms| Some(Duration::from_millis(ms));
+        let runs: Vec<bool> = [0, 400, 900, 1000, 1500, 2100]
+            .into_iter()
+            .enumerate()
+            .map(|(frame, ms)| periodic.should_run(0, &face(0.9), frame as u64, at(ms)))
+            .collect();
+        assert_eq!(vec![true, false, false, true, false, true], runs);
+
+        let result = FrameResult {
+            index: 6,
+            timestamp: None,
+            faces: vec![face(0.9), face(0.9)],
+            track_ids: vec![0, 7],
+        };
+        assert_eq!(vec![1], periodic.select(&result));
+        periodic.retain_tracks(&[]);
+        assert_eq!(vec![0, 1], periodic.select(&result));
+    }
+}
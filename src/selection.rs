@@ -0,0 +1,176 @@
+//! Picking and ordering detections, such as reducing a frame to its one main face.
+//!
+//! ```no_run
+//! use rusty_yunet::prelude::*;
+//!
+//! # let (bytes, width, height) = (vec![0u8; 300], 10, 10);
+//! let faces = detect_faces(&bytes, width, height)?.filter_confidence(0.9);
+//! if let Some(face) = faces.largest() {
+//!     println!("main face at {:?}", face.rectangle());
+//! }
+//! # Ok::<(), YuNetError>(())
+//! ```
+
+use std::cmp::Ordering;
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::Face;
+
+/// The order detectors return faces in; see
+/// [`DetectorConfig::result_order`](crate::DetectorConfig::result_order). Faces that tie are
+/// ordered by position, top to bottom then left to right, so the order only depends on the
+/// faces themselves.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ResultOrder {
+    /// Most confident first.
+    #[default]
+    Confidence,
+    /// Largest first.
+    Area,
+    /// Rows from top to bottom, each from left to right, as text is read.
+    ReadingOrder,
+}
+
+impl ResultOrder {
+    pub fn sort(&self, faces: &mut Vec<Face>) {
+        match self {
+            ResultOrder::Confidence => faces.sort_by_confidence(),
+            ResultOrder::Area => faces.sort_by_area(),
+            ResultOrder::ReadingOrder => faces.sort_reading_order(),
+        }
+    }
+}
+
+/// Selection and sorting combinators for the faces of a frame.
+pub trait FaceSelection {
+    /// The face with the largest rectangle.
+    fn largest(&self) -> Option<&Face>;
+    /// The face YuNet is most confident about.
+    fn most_confident(&self) -> Option<&Face>;
+    /// The face whose center is nearest to the center of the frame it was detected in.
+    fn closest_to_center(&self) -> Option<&Face>;
+    /// Keeps the faces with at least `min` confidence.
+    fn filter_confidence(self, min: f32) -> Self;
+    /// Sorts the faces, largest first.
+    fn sort_by_area(&mut self);
+    /// Sorts the faces, most confident first.
+    fn sort_by_confidence(&mut self);
+    /// Sorts the faces in rows from top to bottom, each from left to right. A face starts a
+    /// new row when its center lies below the topmost face of the row.
+    fn sort_reading_order(&mut self);
+}
+
+impl FaceSelection for Vec<Face> {
+    fn largest(&self) -> Option<&Face> {
+        self.iter().max_by(|a, b| area(a).total_cmp(&area(b)))
+    }
+
+    fn most_confident(&self) -> Option<&Face> {
+        self.iter()
+            .max_by(|a, b| a.confidence().total_cmp(&b.confidence()))
+    }
+
+    fn closest_to_center(&self) -> Option<&Face> {
+        self.iter()
+            .min_by(|a, b| center_offset(a).total_cmp(&center_offset(b)))
+    }
+
+    fn filter_confidence(mut self, min: f32) -> Self {
+        self.retain(|face| face.confidence() >= min);
+        self
+    }
+
+    fn sort_by_area(&mut self) {
+        self.sort_by(|a, b| area(b).total_cmp(&area(a)).then_with(|| position(a, b)));
+    }
+
+    fn sort_by_confidence(&mut self) {
+        self.sort_by(|a, b| {
+            b.confidence()
+                .total_cmp(&a.confidence())
+                .then_with(|| position(a, b))
+        });
+    }
+
+    fn sort_reading_order(&mut self) {
+        self.sort_by(position);
+        let mut row_start = 0;
+        for i in 1..=self.len() {
+            let new_row = self.get(i).is_none_or(|face| {
+                let first = self[row_start].rectangle();
+                face.rectangle().center().y > first.y + first.h
+            });
+            if new_row {
+                self[row_start..i].sort_by(|a, b| {
+                    let (a, b) = (a.rectangle(), b.rectangle());
+                    a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+                });
+                row_start = i;
+            }
+        }
+    }
+}
+
+/// Top to bottom, then left to right.
+fn position(a: &Face, b: &Face) -> Ordering {
+    let (a, b) = (a.rectangle(), b.rectangle());
+    a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+}
+
+fn area(face: &Face) -> f32 {
+    face.rectangle().area()
+}
+
+/// Distance from the face center to the frame center, in normalized coordinates.
+fn center_offset(face: &Face) -> f32 {
+    face.normalized_rectangle()
+        .center()
+        .distance(Vec2::splat(0.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(score: f32, x: i32, size: i32) -> Face {
+        face_at(score, x, 40, size)
+    }
+
+    fn face_at(score: f32, x: i32, y: i32, size: i32) -> Face {
+        let [x, y, size] = [x, y, size].map(|v| v as f32);
+        test_face(score, [x, y, size, size], (100, 100), None)
+    }
+
+    #[test]
+    fn selects_and_sorts() {
+        let mut faces = vec![face(0.8, 0, 30), face(0.95, 45, 10), face(0.6, 70, 20)];
+        assert_eq!(0.8, faces.largest().unwrap().confidence());
+        assert_eq!(0.95, faces.most_confident().unwrap().confidence());
+        assert_eq!(0.95, faces.closest_to_center().unwrap().confidence());
+
+        faces.sort_by_area();
+        let scores: Vec<f32> = faces.iter().map(Face::confidence).collect();
+        assert_eq!(vec![0.8, 0.6, 0.95], scores);
+        faces.sort_by_confidence();
+        assert_eq!(0.95, faces[0].confidence());
+
+        let faces = faces.filter_confidence(0.7);
+        assert_eq!(2, faces.len());
+
+        // Two faces side by side, slightly staggered, above a third.
+        let mut faces = vec![
+            face_at(0.7, 30, 50, 20),
+            face_at(0.9, 60, 10, 20),
+            face_at(0.8, 0, 15, 20),
+        ];
+        ResultOrder::ReadingOrder.sort(&mut faces);
+        let scores: Vec<f32> = faces.iter().map(Face::confidence).collect();
+        assert_eq!(vec![0.8, 0.9, 0.7], scores);
+        assert!(Vec::new().filter_confidence(0.5).largest().is_none());
+    }
+}
@@ -0,0 +1,537 @@
+//! A small HTTP service detecting faces in uploaded images, so that services in other
+//! languages can run the detector as a sidecar.
+//!
+//! `POST /detect` takes one of:
+//!
+//! - an image file, such as a JPEG or PNG, as the body;
+//! - a `multipart/form-data` form, whose first part is the image file;
+//! - raw pixels, with `X-Width` and `X-Height` headers, and optionally `X-Channels`, 3 for
+//!   BGR (the default) or 1 for grayscale, and `X-Stride`, the bytes between row starts.
+//!
+//! and answers with the faces in pixel coordinates of the image, to a hundredth of a pixel,
+//! or in the [`convention`](ServerConfig::convention) configured, boxes as x, y, width and
+//! height and landmarks as x, y pairs in the order of [`FaceLandmarks`]:
+//!
+//! ```json
+//! {"faces":[{"confidence":0.924,"box":[186.12,333.13,49.01,61.92],"landmarks":[206.69,358.49,225.96,357.85,221.32,371.15,209.87,378.43,225.51,379.46]}]}
+//! ```
+//!
+//! or with a 4xx or 5xx status and `{"error":"..."}`. `GET /health` answers
+//! `{"status":"ok","workers":4}`.
+//!
+//! The faces of a stream the server doesn't receive itself, such as an IP camera read by
+//! another thread, are handed over with a [`FacePublisher`]. `GET /faces` answers those of
+//! the latest frame, with its index, as `{"index":812,"faces":[...]}`, or 503 before the
+//! first.
+//!
+//! ```no_run
+//! # use rusty_yunet::server::{DetectionServer, ServerConfig};
+//! let server = DetectionServer::bind("0.0.0.0:8080", ServerConfig::default())?;
+//! server.serve()?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`FaceLandmarks`]: crate::FaceLandmarks
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{
+    CoordinateConvention, DetectorConfig, Face, FaceDetectorPool, ImageView, Units, YuNetError,
+};
+
+/// Longest request line and headers accepted, in bytes.
+const MAX_HEAD: u64 = 16 * 1024;
+
+/// How a [`DetectionServer`] detects and how much load it takes.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ServerConfig {
+    pub detector: DetectorConfig,
+    /// Detections running at once, each with a detector of its own.
+    pub workers: usize,
+    /// Connections handled at once, including those waiting for a worker. Further ones are
+    /// answered with 503 Service Unavailable.
+    pub max_connections: usize,
+    /// Largest request body accepted, in bytes.
+    pub max_body: usize,
+    /// How long a connection may take to send its request.
+    pub read_timeout: Duration,
+    /// Where coordinates are measured from and in what units; normalized ones are given to
+    /// four decimals.
+    pub convention: CoordinateConvention,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            detector: DetectorConfig::default(),
+            workers,
+            max_connections: 4 * workers,
+            max_body: 32 * 1024 * 1024,
+            read_timeout: Duration::from_secs(30),
+            convention: CoordinateConvention::default(),
+        }
+    }
+}
+
+/// A listening socket and the detectors serving it.
+pub struct DetectionServer {
+    listener: TcpListener,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    pool: FaceDetectorPool,
+    config: ServerConfig,
+    connections: AtomicUsize,
+    /// The answer to `GET /faces`, once a frame was published.
+    latest: Mutex<Option<String>>,
+}
+
+/// Hands the faces of a stream's frames to a [`DetectionServer`], for `GET /faces`. Clones
+/// publish to the same server.
+#[derive(Clone)]
+pub struct FacePublisher {
+    shared: Arc<Shared>,
+}
+
+impl FacePublisher {
+    /// Replaces the faces `GET /faces` answers with those of frame `index`.
+    pub fn publish(&self, index: u64, faces: &[Face]) {
+        let json = faces_json(faces, self.shared.config.convention);
+        let json = format!(r#"{{"index":{index},{}"#, &json[1..]);
+        *self.shared.latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(json);
+    }
+}
+
+impl DetectionServer {
+    /// Listens on `address`, such as `"0.0.0.0:8080"`. Fails if the address can't be bound
+    /// or the configured backend isn't available.
+    pub fn bind(address: impl ToSocketAddrs, config: ServerConfig) -> io::Result<Self> {
+        let pool = FaceDetectorPool::with_config(config.workers, config.detector.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::Unsupported, e))?;
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            shared: Arc::new(Shared {
+                pool,
+                config,
+                connections: AtomicUsize::new(0),
+                latest: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// A handle for publishing the faces of a stream's frames, such as from a thread
+    /// reading a camera.
+    pub fn publisher(&self) -> FacePublisher {
+        FacePublisher {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// The address listened on, such as to learn the port when binding port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answers requests, each connection on a thread of its own. Connections that fail to be
+    /// accepted, such as while out of file descriptors, are skipped; fails only if the
+    /// listening socket itself does.
+    pub fn serve(&self) -> io::Result<()> {
+        loop {
+            let mut stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(error) => {
+                    let Some(pause) = accept_pause(&error) else {
+                        return Err(error);
+                    };
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%error, "failed to accept a connection");
+                    std::thread::sleep(pause);
+                    continue;
+                }
+            };
+            let shared = Arc::clone(&self.shared);
+            if shared.connections.fetch_add(1, Ordering::AcqRel) >= shared.config.max_connections {
+                shared.connections.fetch_sub(1, Ordering::AcqRel);
+                let _ = respond(&mut stream, 503, &error_json("too many connections"));
+                continue;
+            }
+            std::thread::spawn(move || {
+                let _ = shared.handle(stream);
+                shared.connections.fetch_sub(1, Ordering::AcqRel);
+            });
+        }
+    }
+}
+
+/// A request refused, as its status and message.
+type Refusal = (u16, String);
+
+impl Shared {
+    /// Answers the one request of a connection.
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(self.config.read_timeout))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (status, body) = match self.answer(&mut reader) {
+            Ok(json) => (200, json),
+            Err((status, message)) => (status, error_json(&message)),
+        };
+        respond(&mut stream, status, &body)
+    }
+
+    fn answer(&self, reader: &mut impl BufRead) -> Result<String, Refusal> {
+        let request = Request::read(reader)?;
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/health") => Ok(format!(
+                r#"{{"status":"ok","workers":{}}}"#,
+                self.pool.size()
+            )),
+            ("POST", "/detect") => {
+                let body = request.body(reader, self.config.max_body)?;
+                let faces = self.detect(&request, &body).map_err(|e| match e {
+                    YuNetError::ImageTooLarge { .. } => (413, e.to_string()),
+                    YuNetError::FaceDetectionFailed
+                    | YuNetError::Inference { .. }
+                    | YuNetError::Backend { .. } => (500, e.to_string()),
+                    _ => (400, e.to_string()),
+                })?;
+                Ok(faces_json(&faces, self.config.convention))
+            }
+            ("GET", "/faces") => self
+                .latest
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+                .ok_or_else(|| (503, "no frame published yet".to_owned())),
+            (_, "/health" | "/detect" | "/faces") => Err((405, "method not allowed".to_owned())),
+            _ => Err((404, "not found".to_owned())),
+        }
+    }
+
+    fn detect(&self, request: &Request, body: &[u8]) -> Result<Vec<Face>, YuNetError> {
+        let mut detector = self.pool.get();
+        let content_type = request.header("content-type").unwrap_or_default();
+        if let Some(boundary) = content_type
+            .strip_prefix("multipart/form-data")
+            .and_then(|parameters| parameters.split("boundary=").nth(1))
+        {
+            let boundary = boundary
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim_matches('"');
+            let file = first_part(body, boundary).ok_or(YuNetError::InvalidFile)?;
+            return detector.detect_encoded(file);
+        }
+        let Some(width) = request.number("x-width")? else {
+            return detector.detect_encoded(body);
+        };
+        let height = request
+            .number("x-height")?
+            .ok_or(YuNetError::InvalidImage)?;
+        let channels = request.number("x-channels")?.unwrap_or(3);
+        let stride = request
+            .number("x-stride")?
+            .unwrap_or(channels.saturating_mul(width));
+        let image = match channels {
+            3 => ImageView::with_stride(body, width, height, stride)?,
+            1 => ImageView::gray_with_stride(body, width, height, stride)?,
+            _ => return Err(YuNetError::InvalidImage),
+        };
+        detector.detect_image(&image)
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// Names lowercased.
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// Reads the request line and headers.
+    fn read(reader: &mut impl BufRead) -> Result<Self, Refusal> {
+        let bad = |message: &str| (400, message.to_owned());
+        let mut head = reader.take(MAX_HEAD);
+        let mut line = String::new();
+        let mut next_line = |line: &mut String| -> Result<(), Refusal> {
+            line.clear();
+            match head.read_line(line) {
+                Ok(_) if line.ends_with('\n') => Ok(()),
+                Ok(_) => Err((431, "request head too large or truncated".to_owned())),
+                Err(e) => Err((400, e.to_string())),
+            }
+        };
+        next_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(bad("malformed request line"));
+        };
+        let (method, path) = (
+            method.to_owned(),
+            target.split('?').next().unwrap_or_default().to_owned(),
+        );
+        let mut headers = Vec::new();
+        loop {
+            next_line(&mut line)?;
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| bad("malformed header"))?;
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+        }
+        Ok(Self {
+            method,
+            path,
+            headers,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// A header holding a number, failing if it holds anything else.
+    fn number(&self, name: &str) -> Result<Option<usize>, YuNetError> {
+        self.header(name)
+            .map(|value| value.parse().map_err(|_| YuNetError::InvalidImage))
+            .transpose()
+    }
+
+    /// Reads the body, as long as its `Content-Length`.
+    fn body(&self, reader: &mut impl Read, max: usize) -> Result<Vec<u8>, Refusal> {
+        if self.header("transfer-encoding").is_some() {
+            return Err((411, "send a Content-Length instead of chunks".to_owned()));
+        }
+        let length: usize = self
+            .header("content-length")
+            .ok_or((411, "Content-Length required".to_owned()))?
+            .parse()
+            .map_err(|_| (400, "malformed Content-Length".to_owned()))?;
+        if length > max {
+            return Err((413, format!("body larger than {max} bytes")));
+        }
+        let mut body = vec![0; length];
+        reader
+            .read_exact(&mut body)
+            .map_err(|e| (400, e.to_string()))?;
+        Ok(body)
+    }
+}
+
+/// The content of the first part of a multipart body.
+fn first_part<'a>(body: &'a [u8], boundary: &str) -> Option<&'a [u8]> {
+    let delimiter = format!("--{boundary}");
+    let start = find(body, delimiter.as_bytes())? + delimiter.len();
+    let part = &body[start..];
+    let content = find(part, b"\r\n\r\n")? + 4;
+    let end = find(&part[content..], format!("\r\n{delimiter}").as_bytes())?;
+    Some(&part[content..content + end])
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn respond(stream: &mut TcpStream, status: u16, json: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{json}",
+        json.len()
+    )?;
+    stream.flush()
+}
+
+/// How long to wait before accepting again after `error`, or `None` if the listening socket
+/// itself failed. Running out of descriptors or buffers lasts until other connections
+/// close, so accepting is held off a little rather than failing again at once.
+fn accept_pause(error: &io::Error) -> Option<Duration> {
+    #[cfg(unix)]
+    const EXHAUSTED: [i32; 3] = [libc::EMFILE, libc::ENFILE, libc::ENOBUFS];
+    // WSAEMFILE and WSAENOBUFS.
+    #[cfg(windows)]
+    const EXHAUSTED: [i32; 2] = [10024, 10055];
+    #[cfg(not(any(unix, windows)))]
+    const EXHAUSTED: [i32; 0] = [];
+
+    match error.kind() {
+        io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::Interrupted
+        | io::ErrorKind::WouldBlock
+        | io::ErrorKind::TimedOut => Some(Duration::ZERO),
+        io::ErrorKind::OutOfMemory => Some(Duration::from_millis(100)),
+        _ if error
+            .raw_os_error()
+            .is_some_and(|code| EXHAUSTED.contains(&code)) =>
+        {
+            Some(Duration::from_millis(100))
+        }
+        _ => None,
+    }
+}
+
+fn faces_json(faces: &[Face], convention: CoordinateConvention) -> String {
+    let digits = match convention.units {
+        Units::Pixels => 2,
+        Units::Normalized => 4,
+    };
+    let mut json = String::from(r#"{"faces":["#);
+    for (i, face) in faces.iter().enumerate() {
+        let rect = face.rectangle_in(convention);
+        let _ = write!(
+            json,
+            r#"{}{{"confidence":{:.3},"box":[{:.digits$},{:.digits$},{:.digits$},{:.digits$}],"landmarks":["#,
+            if i == 0 { "" } else { "," },
+            face.confidence(),
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+        );
+        for (j, p) in face.landmarks_in(convention).as_array().iter().enumerate() {
+            let separator = if j == 0 { "" } else { "," };
+            let _ = write!(json, "{separator}{:.digits$},{:.digits$}", p.x, p.y);
+        }
+        json.push_str("]}");
+    }
+    json.push_str("]}");
+    json
+}
+
+fn error_json(message: &str) -> String {
+    let escaped: String = message
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '\\' => vec!['\\', c],
+            c if c.is_control() => vec![' '],
+            c => vec![c],
+        })
+        .collect();
+    format!(r#"{{"error":"{escaped}"}}"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(address: SocketAddr, head: &str, body: &[u8]) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_detections() {
+        let config = ServerConfig {
+            workers: 2,
+            ..ServerConfig::default()
+        };
+        let server = DetectionServer::bind("127.0.0.1:0", config).unwrap();
+        let address = server.local_addr().unwrap();
+        let publisher = server.publisher();
+        std::thread::spawn(move || server.serve());
+
+        let jpeg = std::fs::read("sample.jpg").unwrap();
+        let post = |headers: &str, body: &[u8]| {
+            let head = format!(
+                "POST /detect HTTP/1.1\r\nContent-Length: {}\r\n{headers}\r\n",
+                body.len()
+            );
+            request(address, &head, body)
+        };
+        let response = post("Content-Type: image/jpeg\r\n", &jpeg);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(
+            response.contains(r#"{"faces":[{"confidence":0.924,"box":[186.12,333.13,49.01,61.92]"#),
+            "{response}"
+        );
+
+        let mut form =
+            b"--xyz\r\nContent-Disposition: form-data; name=\"image\"; filename=\"a.jpg\"\r\n\r\n"
+                .to_vec();
+        form.extend_from_slice(&jpeg);
+        form.extend_from_slice(b"\r\n--xyz--\r\n");
+        let response = post("Content-Type: multipart/form-data; boundary=xyz\r\n", &form);
+        assert_eq!(2, response.matches("confidence").count(), "{response}");
+
+        let pixels = image::load_from_memory(&jpeg).unwrap().to_bgr8().into_raw();
+        let response = post("X-Width: 806\r\nX-Height: 605\r\n", &pixels);
+        assert_eq!(2, response.matches("confidence").count(), "{response}");
+        let response = post("X-Width: 806\r\nX-Height: 606\r\n", &pixels);
+        assert!(response.starts_with("HTTP/1.1 400 "), "{response}");
+        assert!(response.contains(r#"{"error":"Image buffer doesn't match its dimensions"}"#));
+
+        let response = request(address, "GET /health HTTP/1.1\r\n\r\n", &[]);
+        assert!(
+            response.ends_with(r#"{"status":"ok","workers":2}"#),
+            "{response}"
+        );
+        let response = request(address, "GET /detect HTTP/1.1\r\n\r\n", &[]);
+        assert!(response.starts_with("HTTP/1.1 405 "), "{response}");
+
+        let response = request(address, "GET /faces HTTP/1.1\r\n\r\n", &[]);
+        assert!(response.starts_with("HTTP/1.1 503 "), "{response}");
+        publisher.publish(7, &[]);
+        let response = request(address, "GET /faces HTTP/1.1\r\n\r\n", &[]);
+        assert!(
+            response.ends_with(r#"{"index":7,"faces":[]}"#),
+            "{response}"
+        );
+
+        // Running out of descriptors doesn't stop the server, a broken socket does.
+        let failed = |kind: io::ErrorKind| accept_pause(&io::Error::from(kind));
+        assert_eq!(
+            Some(Duration::ZERO),
+            failed(io::ErrorKind::ConnectionAborted)
+        );
+        assert_eq!(None, failed(io::ErrorKind::InvalidInput));
+        #[cfg(unix)]
+        assert!(accept_pause(&io::Error::from_raw_os_error(libc::EMFILE)).is_some());
+
+        let face = Face::new(
+            0.5,
+            crate::Rect::with_size(10.0, 20.0, 30.0, 40.0),
+            crate::FaceLandmarks::from_array([glam::Vec2::new(50.0, 50.0); 5]),
+            (100, 200),
+        );
+        let uv = CoordinateConvention::new(crate::Origin::BottomLeft, Units::Normalized);
+        let landmarks = ["0.5000,0.7500"; 5].join(",");
+        assert_eq!(
+            format!(
+                r#"{{"faces":[{{"confidence":0.500,"box":[0.1000,0.7000,0.3000,0.2000],"landmarks":[{landmarks}]}}]}}"#
+            ),
+            faces_json(&[face], uv)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{geometry, Face, Rect};
+
+/// Detections stored as a struct of arrays, for crunching large numbers of stored faces
+/// with tight, vectorizable loops.
+///
+/// Index `i` of every array describes the same face. The arrays are only exposed as
+/// slices, so that they always stay the same length.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FacesSoA {
+    scores: Vec<f32>,
+    x: Vec<f32>,
+    y: Vec<f32>,
+    w: Vec<f32>,
+    h: Vec<f32>,
+    landmarks: Vec<[Vec2; 5]>,
+    detection_dimensions: Vec<(usize, usize)>,
+    normalized: bool,
+}
+
+impl FacesSoA {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            scores: Vec::with_capacity(capacity),
+            x: Vec::with_capacity(capacity),
+            y: Vec::with_capacity(capacity),
+            w: Vec::with_capacity(capacity),
+            h: Vec::with_capacity(capacity),
+            landmarks: Vec::with_capacity(capacity),
+            detection_dimensions: Vec::with_capacity(capacity),
+            normalized: false,
+        }
+    }
+
+    /// An empty set whose faces are stored in normalized 0..1 coordinates.
+    pub fn normalized() -> Self {
+        Self {
+            normalized: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Appends a face, converting it to normalized coordinates if this set is normalized.
+    pub fn push(&mut self, face: &Face) {
+        let dimensions = face.detection_dimensions();
+        let (rect, scale) = if self.normalized {
+            let scale = Vec2::new(dimensions.0 as f32, dimensions.1 as f32);
+            (face.normalized_rectangle(), scale)
+        } else {
+            (face.rectangle(), Vec2::ONE)
+        };
+        let lm = face.landmarks();
+        self.scores.push(face.confidence());
+        self.x.push(rect.x);
+        self.y.push(rect.y);
+        self.w.push(rect.w);
+        self.h.push(rect.h);
+        self.landmarks.push(lm.as_array().map(|p| p / scale));
+        self.detection_dimensions.push(dimensions);
+    }
+
+    /// Converts all coordinates to normalized 0..1 space. Does nothing if already normalized.
+    pub fn normalize(&mut self) {
+        if self.normalized {
+            return;
+        }
+        for i in 0..self.len() {
+            let (width, height) = self.detection_dimensions[i];
+            let scale = Vec2::new(width as f32, height as f32);
+            self.x[i] /= scale.x;
+            self.w[i] /= scale.x;
+            self.y[i] /= scale.y;
+            self.h[i] /= scale.y;
+            for p in &mut self.landmarks[i] {
+                *p /= scale;
+            }
+        }
+        self.normalized = true;
+    }
+
+    pub fn scores(&self) -> &[f32] {
+        &self.scores
+    }
+
+    pub fn x(&self) -> &[f32] {
+        &self.x
+    }
+
+    pub fn y(&self) -> &[f32] {
+        &self.y
+    }
+
+    pub fn w(&self) -> &[f32] {
+        &self.w
+    }
+
+    pub fn h(&self) -> &[f32] {
+        &self.h
+    }
+
+    /// Right eye, left eye, nose, right and left mouth corners of each face.
+    pub fn landmarks(&self) -> &[[Vec2; 5]] {
+        &self.landmarks
+    }
+
+    pub fn detection_dimensions(&self) -> &[(usize, usize)] {
+        &self.detection_dimensions
+    }
+
+    /// Whether coordinates are in normalized 0..1 space rather than pixels.
+    pub fn is_normalized(&self) -> bool {
+        self.normalized
+    }
+
+    /// The rectangle of face `i`.
+    pub fn rect(&self, i: usize) -> Rect {
+        Rect::with_size(self.x[i], self.y[i], self.w[i], self.h[i])
+    }
+
+    pub fn rects(&self) -> Vec<Rect> {
+        (0..self.len()).map(|i| self.rect(i)).collect()
+    }
+
+    pub fn areas(&self) -> Vec<f32> {
+        self.w
+            .iter()
+            .zip(&self.h)
+            .map(|(w, h)| w.max(0.0) * h.max(0.0))
+            .collect()
+    }
+
+    /// Intersection over union between every face of `self` (rows) and `other` (columns),
+    /// as returned by [`geometry::iou_matrix`].
+    ///
+    /// Both sets should use the same coordinate space.
+    pub fn iou_matrix(&self, other: &FacesSoA) -> Vec<Vec<f32>> {
+        geometry::iou_matrix(&self.rects(), &other.rects())
+    }
+
+    /// Keeps only the faces with a score of at least `min_score`.
+    pub fn retain_min_score(&mut self, min_score: f32) {
+        let keep: Vec<bool> = self.scores.iter().map(|&s| s >= min_score).collect();
+        fn retain<T>(values: &mut Vec<T>, keep: &[bool]) {
+            let mut keep = keep.iter();
+            values.retain(|_| *keep.next().unwrap());
+        }
+        retain(&mut self.scores, &keep);
+        retain(&mut self.x, &keep);
+        retain(&mut self.y, &keep);
+        retain(&mut self.w, &keep);
+        retain(&mut self.h, &keep);
+        retain(&mut self.landmarks, &keep);
+        retain(&mut self.detection_dimensions, &keep);
+    }
+}
+
+impl From<&[Face]> for FacesSoA {
+    fn from(faces: &[Face]) -> Self {
+        let mut soa = Self::with_capacity(faces.len());
+        for face in faces {
+            soa.push(face);
+        }
+        soa
+    }
+}
+
+impl From<Vec<Face>> for FacesSoA {
+    fn from(faces: Vec<Face>) -> Self {
+        Self::from(faces.as_slice())
+    }
+}
+
+impl FromIterator<Face> for FacesSoA {
+    fn from_iter<I: IntoIterator<Item = Face>>(faces: I) -> Self {
+        let mut soa = Self::default();
+        for face in faces {
+            soa.push(&face);
+        }
+        soa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(score: f32, x: i32, y: i32, w: i32, h: i32) -> Face {
+        let [x, y, w, h] = [x, y, w, h].map(|v| v as f32);
+        test_face(score, [x, y, w, h], (200, 100), Some([Vec2::splat(x); 5]))
+    }
+
+    #[test]
+    fn batch_operations() {
+        let faces = vec![
+            face(0.9, 0, 0, 10, 10),
+            face(0.5, 5, 0, 10, 10),
+            face(0.8, 100, 50, 20, 20),
+        ];
+        let soa = FacesSoA::from(faces.clone());
+
+        let matrix = soa.iou_matrix(&soa);
+        assert_eq!(3, matrix.len());
+        assert_eq!(1.0, matrix[0][0]);
+        assert!((matrix[0][1] - 50.0 / 150.0).abs() < 1e-6);
+        assert_eq!(0.0, matrix[0][2]);
+
+        let mut normalized = soa.clone();
+        normalized.normalize();
+        normalized.normalize();
+        assert_eq!(0.5, normalized.x()[2]);
+        assert_eq!(0.5, normalized.y()[2]);
+        assert_eq!(0.1, normalized.w()[2]);
+        assert_eq!(normalized.landmarks()[2][0], Vec2::new(0.5, 1.0));
+
+        let mut pushed = FacesSoA::normalized();
+        pushed.push(&faces[2]);
+        assert_eq!(normalized.rect(2), pushed.rect(0));
+
+        let mut confident = soa;
+        confident.retain_min_score(0.8);
+        assert_eq!([0.9, 0.8], confident.scores());
+        assert_eq!([0.0, 100.0], confident.x());
+        assert_eq!(2, confident.landmarks().len());
+    }
+}
@@ -0,0 +1,385 @@
+//! A SQLite index of the faces in image files, for photo managers to persist detections and
+//! rescan collections incrementally, detecting only in files added or changed since.
+//!
+//! ```no_run
+//! # use rusty_yunet::store::DetectionStore;
+//! # use rusty_yunet::{FaceDetector, NoProgress};
+//! let mut store = DetectionStore::open("faces.sqlite")?;
+//! let summary = store.rescan(&mut FaceDetector::new(), "photos", &mut NoProgress)?;
+//! println!("{} files detected in, {} unchanged", summary.scanned, summary.skipped);
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+//!
+//! Each file is recorded under its path as given, with its size, modification time and an
+//! FNV-1a hash of its contents, and the [`MODEL_VERSION`] its faces were detected with.
+//! Faces are stored in the coordinates of the decoded pixels, as
+//! [`FaceDetector::detect_file`] reports them.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use glam::Vec2;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::progress::ProgressReporter;
+use crate::provenance::{fnv1a, MODEL_VERSION};
+use crate::scan::{Walker, IMAGE_EXTENSIONS};
+use crate::{Face, FaceDetector, FaceLandmarks, Rect, YuNetError};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        path TEXT PRIMARY KEY,
+        hash TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        modified INTEGER,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        model_version TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS faces (
+        path TEXT NOT NULL REFERENCES files (path) ON DELETE CASCADE,
+        x REAL NOT NULL, y REAL NOT NULL, w REAL NOT NULL, h REAL NOT NULL,
+        confidence REAL NOT NULL,
+        right_eye_x REAL NOT NULL, right_eye_y REAL NOT NULL,
+        left_eye_x REAL NOT NULL, left_eye_y REAL NOT NULL,
+        nose_x REAL NOT NULL, nose_y REAL NOT NULL,
+        mouth_right_x REAL NOT NULL, mouth_right_y REAL NOT NULL,
+        mouth_left_x REAL NOT NULL, mouth_left_y REAL NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS faces_path ON faces (path);
+    PRAGMA foreign_keys = ON;
+";
+
+/// What [`DetectionStore::rescan`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RescanSummary {
+    /// Files detected in, being new or changed.
+    pub scanned: usize,
+    /// Files unchanged since they were stored.
+    pub skipped: usize,
+    /// Files that couldn't be decoded, whose records were removed.
+    pub failed: usize,
+    /// Records of files no longer found, removed.
+    pub removed: usize,
+}
+
+/// Faces per image file, persisted in a SQLite database.
+pub struct DetectionStore {
+    connection: Connection,
+}
+
+impl DetectionStore {
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, YuNetError> {
+        Self::with_connection(Connection::open(path).map_err(store_error)?)
+    }
+
+    /// A database that lives as long as the store, such as for tests.
+    pub fn open_in_memory() -> Result<Self, YuNetError> {
+        Self::with_connection(Connection::open_in_memory().map_err(store_error)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, YuNetError> {
+        connection.execute_batch(SCHEMA).map_err(store_error)?;
+        Ok(Self { connection })
+    }
+
+    /// Records `faces` as those of the file at `path`, replacing any earlier record.
+    pub fn save(&mut self, path: impl AsRef<Path>, faces: &[Face]) -> Result<(), YuNetError> {
+        let path = path.as_ref();
+        let file = FileState::read(path)?;
+        let (width, height) = match faces.first() {
+            Some(face) => face.detection_dimensions(),
+            None => image::image_dimensions(path)
+                .map(|(w, h)| (w as usize, h as usize))
+                .map_err(|e| YuNetError::Decode(e.to_string()))?,
+        };
+        let key = key(path);
+        let transaction = self.connection.transaction().map_err(store_error)?;
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO files VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    key,
+                    file.hash,
+                    file.size,
+                    file.modified,
+                    width as i64,
+                    height as i64,
+                    MODEL_VERSION
+                ],
+            )
+            .map_err(store_error)?;
+        transaction
+            .execute("DELETE FROM faces WHERE path = ?1", [&key])
+            .map_err(store_error)?;
+        for face in faces {
+            let (rect, lm) = (face.rectangle(), face.landmarks());
+            transaction
+                .execute(
+                    "INSERT INTO faces VALUES
+                        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                    params![
+                        key,
+                        rect.x,
+                        rect.y,
+                        rect.w,
+                        rect.h,
+                        face.confidence(),
+                        lm.right_eye.x,
+                        lm.right_eye.y,
+                        lm.left_eye.x,
+                        lm.left_eye.y,
+                        lm.nose.x,
+                        lm.nose.y,
+                        lm.mouth_right.x,
+                        lm.mouth_right.y,
+                        lm.mouth_left.x,
+                        lm.mouth_left.y
+                    ],
+                )
+                .map_err(store_error)?;
+        }
+        transaction.commit().map_err(store_error)
+    }
+
+    /// The faces recorded for the file at `path`, none if it isn't recorded.
+    pub fn faces(&self, path: impl AsRef<Path>) -> Result<Option<Vec<Face>>, YuNetError> {
+        let key = key(path.as_ref());
+        let dimensions = self
+            .connection
+            .query_row(
+                "SELECT width, height FROM files WHERE path = ?1",
+                [&key],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)? as usize,
+                        row.get::<_, i64>(1)? as usize,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(store_error)?;
+        let Some(dimensions) = dimensions else {
+            return Ok(None);
+        };
+        let mut statement = self
+            .connection
+            .prepare("SELECT * FROM faces WHERE path = ?1 ORDER BY rowid")
+            .map_err(store_error)?;
+        let faces = statement
+            .query_map([&key], |row| {
+                let value = |i: usize| row.get::<_, f32>(i);
+                let point =
+                    |i| -> rusqlite::Result<Vec2> { Ok(Vec2::new(value(i)?, value(i + 1)?)) };
+                Ok(Face::new(
+                    value(5)?,
+                    Rect::with_size(value(1)?, value(2)?, value(3)?, value(4)?),
+                    FaceLandmarks::from_array([
+                        point(6)?,
+                        point(8)?,
+                        point(10)?,
+                        point(12)?,
+                        point(14)?,
+                    ]),
+                    dimensions,
+                ))
+            })
+            .and_then(|faces| faces.collect::<Result<Vec<_>, _>>())
+            .map_err(store_error)?;
+        Ok(Some(faces))
+    }
+
+    /// Every recorded file, in path order.
+    pub fn files(&self) -> Result<Vec<PathBuf>, YuNetError> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT path FROM files ORDER BY path")
+            .map_err(store_error)?;
+        statement
+            .query_map([], |row| row.get::<_, String>(0).map(PathBuf::from))
+            .and_then(|paths| paths.collect())
+            .map_err(store_error)
+    }
+
+    /// Removes the record of the file at `path`, returning whether there was one.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Result<bool, YuNetError> {
+        self.connection
+            .execute("DELETE FROM files WHERE path = ?1", [key(path.as_ref())])
+            .map(|removed| removed > 0)
+            .map_err(store_error)
+    }
+
+    /// Whether the record of the file at `path` is up to date: its contents are unchanged
+    /// and its faces were detected with the current model. Files whose size and
+    /// modification time are unchanged aren't read.
+    pub fn is_current(&mut self, path: impl AsRef<Path>) -> Result<bool, YuNetError> {
+        let path = path.as_ref();
+        let key = key(path);
+        let stored = self
+            .connection
+            .query_row(
+                "SELECT hash, size, modified FROM files WHERE path = ?1 AND model_version = ?2",
+                params![key, MODEL_VERSION],
+                |row| {
+                    Ok(FileState {
+                        hash: row.get(0)?,
+                        size: row.get(1)?,
+                        modified: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(store_error)?;
+        let Some(stored) = stored else {
+            return Ok(false);
+        };
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return Ok(false);
+        };
+        let (size, modified) = FileState::times(&metadata);
+        if (size, modified) == (stored.size, stored.modified) {
+            return Ok(true);
+        }
+        // Touched, such as by a copy or a sync tool, but possibly unchanged.
+        let current = FileState::read(path)?;
+        if current.hash != stored.hash {
+            return Ok(false);
+        }
+        self.connection
+            .execute(
+                "UPDATE files SET size = ?2, modified = ?3 WHERE path = ?1",
+                params![key, current.size, current.modified],
+            )
+            .map_err(store_error)?;
+        Ok(true)
+    }
+
+    /// Brings the records of the image files under `dir` up to date, detecting faces in
+    /// the files added or changed since they were recorded, and removing records of files
+    /// gone. Fails if a directory can't be listed or a query fails.
+    pub fn rescan(
+        &mut self,
+        detector: &mut FaceDetector,
+        dir: impl AsRef<Path>,
+        progress: &mut impl ProgressReporter,
+    ) -> Result<RescanSummary, YuNetError> {
+        let dir = dir.as_ref();
+        let extensions = IMAGE_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+        let paths = Walker::new(dir, extensions, true)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|(dir, e)| YuNetError::Io(format!("{}: {e}", dir.display())))?;
+
+        let mut summary = RescanSummary::default();
+        for stored in self.files()? {
+            if stored.starts_with(dir) && !stored.exists() {
+                self.remove(&stored)?;
+                summary.removed += 1;
+            }
+        }
+        for (i, path) in paths.iter().enumerate() {
+            if self.is_current(path)? {
+                summary.skipped += 1;
+            } else {
+                match detector.detect_file(path) {
+                    Ok(faces) => {
+                        self.save(path, &faces)?;
+                        summary.scanned += 1;
+                    }
+                    Err(_) => {
+                        self.remove(path)?;
+                        summary.failed += 1;
+                    }
+                }
+            }
+            progress.report(i + 1, paths.len(), Some(path));
+        }
+        Ok(summary)
+    }
+}
+
+struct FileState {
+    hash: String,
+    size: i64,
+    modified: Option<i64>,
+}
+
+impl FileState {
+    fn read(path: &Path) -> Result<Self, YuNetError> {
+        let io_error = |e: std::io::Error| YuNetError::Io(format!("{}: {e}", path.display()));
+        let bytes = std::fs::read(path).map_err(io_error)?;
+        let (size, modified) = Self::times(&std::fs::metadata(path).map_err(io_error)?);
+        Ok(Self {
+            hash: format!("{:016x}", fnv1a(&bytes)),
+            size,
+            modified,
+        })
+    }
+
+    /// The size and the modification time in nanoseconds since the Unix epoch, if known.
+    fn times(metadata: &std::fs::Metadata) -> (i64, Option<i64>) {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos() as i64);
+        (metadata.len() as i64, modified)
+    }
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn store_error(e: rusqlite::Error) -> YuNetError {
+    YuNetError::Store(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DetectorConfig, NoProgress};
+
+    #[test]
+    fn rescans_incrementally() {
+        let dir = std::env::temp_dir().join(format!("rusty-yunet-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy("sample.jpg", dir.join("a.jpg")).unwrap();
+        std::fs::copy("sample.jpg", dir.join("b.jpg")).unwrap();
+        let config = DetectorConfig {
+            max_side: Some(320),
+            ..DetectorConfig::default()
+        };
+        let mut detector = FaceDetector::with_config(config).unwrap();
+        let mut store = DetectionStore::open_in_memory().unwrap();
+
+        let summary = store.rescan(&mut detector, &dir, &mut NoProgress).unwrap();
+        assert_eq!(2, summary.scanned);
+        let detected = detector.detect_file(dir.join("a.jpg")).unwrap();
+        let stored = store.faces(dir.join("a.jpg")).unwrap().unwrap();
+        assert_eq!(detected.len(), stored.len());
+        assert_eq!(detected[0].rectangle(), stored[0].rectangle());
+        assert_eq!(detected[0].landmarks().nose, stored[0].landmarks().nose);
+        assert_eq!((806, 605), stored[0].detection_dimensions());
+
+        std::fs::remove_file(dir.join("b.jpg")).unwrap();
+        std::fs::write(dir.join("a.jpg"), b"no longer an image").unwrap();
+        let summary = store.rescan(&mut detector, &dir, &mut NoProgress).unwrap();
+        assert_eq!(
+            (0, 0, 1, 1),
+            (
+                summary.scanned,
+                summary.skipped,
+                summary.failed,
+                summary.removed
+            )
+        );
+        assert!(store.files().unwrap().is_empty());
+
+        std::fs::copy("sample.jpg", dir.join("a.jpg")).unwrap();
+        store.rescan(&mut detector, &dir, &mut NoProgress).unwrap();
+        let summary = store.rescan(&mut detector, &dir, &mut NoProgress).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((0, 1), (summary.scanned, summary.skipped));
+    }
+}
@@ -0,0 +1,130 @@
+//! Continuous detection driven by the detector rather than the caller, for daemons that
+//! hand over a frame source and react to events.
+
+use std::ops::ControlFlow;
+
+use crate::{Face, FaceDetector, ImageView, YuNetError};
+
+/// A frame yielded by the source of [`FaceDetector::run_stream`].
+pub trait StreamFrame {
+    /// The frame as a BGR image, or why it can't be read as one.
+    fn image(&self) -> Result<ImageView<'_>, YuNetError>;
+}
+
+impl StreamFrame for ImageView<'_> {
+    fn image(&self) -> Result<ImageView<'_>, YuNetError> {
+        Ok(*self)
+    }
+}
+
+/// A tightly packed BGR buffer with its width and height, as in
+/// [`ThroughputPipeline::push`](crate::ThroughputPipeline::push).
+impl StreamFrame for (Vec<u8>, usize, usize) {
+    fn image(&self) -> Result<ImageView<'_>, YuNetError> {
+        ImageView::new(&self.0, self.1, self.2)
+    }
+}
+
+impl<T: StreamFrame> StreamFrame for Result<T, YuNetError> {
+    fn image(&self) -> Result<ImageView<'_>, YuNetError> {
+        match self {
+            Ok(frame) => frame.image(),
+            Err(error) => Err(error.clone()),
+        }
+    }
+}
+
+/// What [`FaceDetector::run_stream`] reports for each frame. `frame` counts frames from
+/// zero.
+#[derive(Debug)]
+pub enum StreamEvent<'a> {
+    FacesDetected {
+        frame: u64,
+        faces: &'a [Face],
+    },
+    NoFaces {
+        frame: u64,
+    },
+    /// The frame couldn't be read or processed. The stream carries on with the next one
+    /// unless the subscriber breaks.
+    Error {
+        frame: u64,
+        error: YuNetError,
+    },
+}
+
+impl FaceDetector {
+    /// Detects faces in every frame of `source` until it ends or `on_event` breaks,
+    /// returning how many frames were processed.
+    ///
+    /// Sources whose capture can fail may yield `Result`s, with failures reported as
+    /// [`StreamEvent::Error`].
+    pub fn run_stream<F: StreamFrame>(
+        &mut self,
+        source: impl IntoIterator<Item = F>,
+        mut on_event: impl FnMut(StreamEvent) -> ControlFlow<()>,
+    ) -> u64 {
+        let mut frames = 0;
+        for source_frame in source {
+            let frame = frames;
+            frames += 1;
+            let event = match source_frame
+                .image()
+                .and_then(|image| self.detect_image(&image))
+            {
+                Ok(faces) if faces.is_empty() => on_event(StreamEvent::NoFaces { frame }),
+                Ok(faces) => on_event(StreamEvent::FacesDetected {
+                    frame,
+                    faces: &faces,
+                }),
+                Err(error) => on_event(StreamEvent::Error { frame, error }),
+            };
+            if event.is_break() {
+                break;
+            }
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_events_until_stopped() {
+        let image = image::open("sample.jpg").unwrap().to_bgr8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let source = vec![
+            Ok((image.to_vec(), width, height)),
+            Ok((vec![0; 3 * 64 * 64], 64, 64)),
+            Err(YuNetError::Decode("truncated".to_owned())),
+            Ok((vec![0; 3], width, height)),
+            Ok((image.to_vec(), width, height)),
+        ];
+
+        let mut events = Vec::new();
+        let processed = FaceDetector::new().run_stream(source, |event| {
+            events.push(match event {
+                StreamEvent::FacesDetected { frame, faces } => format!("{frame}: {}", faces.len()),
+                StreamEvent::NoFaces { frame } => format!("{frame}: none"),
+                StreamEvent::Error { frame, error } => format!("{frame}: {error}"),
+            });
+            if events.len() == 4 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(4, processed);
+        assert_eq!(
+            vec![
+                "0: 2",
+                "1: none",
+                "2: Failed to decode image: truncated",
+                "3: Image buffer doesn't match its dimensions",
+            ],
+            events
+        );
+    }
+}
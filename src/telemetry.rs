@@ -0,0 +1,112 @@
+//! Metrics of a detector's health, such as for monitoring an unattended installation
+//! remotely, recorded through the [`metrics`] facade. Install an exporter, such as
+//! `metrics-exporter-prometheus`, to publish them:
+//!
+//! ```ignore
+//! metrics_exporter_prometheus::PrometheusBuilder::new()
+//!     .with_http_listener(([0, 0, 0, 0], 9000))
+//!     .install()?;
+//! rusty_yunet::telemetry::describe_metrics();
+//! ```
+//!
+//! Every frame a [`FaceDetector`](crate::FaceDetector) processes is recorded, labeled with
+//! its backend, save for warm-up and self-test runs.
+
+use metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+
+use crate::detector::{Backend, DetectionStats};
+use crate::provenance::backend_name;
+use crate::YuNetError;
+
+/// Frames processed, a counter.
+pub const FRAMES: &str = "yunet_frames_total";
+/// Time from receiving a frame to returning its faces, a histogram.
+pub const DETECTION_SECONDS: &str = "yunet_detection_seconds";
+/// Faces found in each frame, a histogram.
+pub const FACES_PER_FRAME: &str = "yunet_faces_per_frame";
+/// Frames that failed, a counter labeled with the `error`.
+pub const ERRORS: &str = "yunet_errors_total";
+
+/// Describes the metrics to the installed recorder, for exporters that publish help texts
+/// and units.
+pub fn describe_metrics() {
+    describe_counter!(FRAMES, Unit::Count, "Frames processed");
+    describe_histogram!(
+        DETECTION_SECONDS,
+        Unit::Seconds,
+        "Time from receiving a frame to returning its faces"
+    );
+    describe_histogram!(FACES_PER_FRAME, Unit::Count, "Faces found in each frame");
+    describe_counter!(ERRORS, Unit::Count, "Frames that failed");
+}
+
+pub(crate) fn record_frame(backend: Backend, stats: &DetectionStats) {
+    let backend = backend_name(backend);
+    counter!(FRAMES, "backend" => backend).increment(1);
+    histogram!(DETECTION_SECONDS, "backend" => backend).record(stats.total_ms() / 1000.0);
+    histogram!(FACES_PER_FRAME, "backend" => backend).record(stats.faces_found as f64);
+}
+
+pub(crate) fn record_error(backend: Backend, error: &YuNetError) {
+    let error = match error {
+        YuNetError::InvalidImage => "invalid_image",
+        YuNetError::ImageTooLarge { .. } => "image_too_large",
+        YuNetError::Cancelled => "cancelled",
+        _ => "other",
+    };
+    counter!(ERRORS, "backend" => backend_name(backend), "error" => error).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString};
+
+    use super::*;
+    use crate::FaceDetector;
+
+    /// Records the names of the metrics registered.
+    #[derive(Default)]
+    struct Names(Mutex<Vec<String>>);
+
+    impl Recorder for Names {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata) -> Counter {
+            self.0.lock().unwrap().push(key.name().to_owned());
+            Counter::noop()
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata) -> Gauge {
+            self.0.lock().unwrap().push(key.name().to_owned());
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata) -> Histogram {
+            self.0.lock().unwrap().push(key.name().to_owned());
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn records_frames_and_errors() {
+        let names = Names::default();
+        metrics::with_local_recorder(&names, || {
+            let mut detector = FaceDetector::new();
+            detector.warm_up(64, 64).unwrap();
+            detector
+                .detect_image(&crate::ImageView::new(&[0; 3 * 64 * 64], 64, 64).unwrap())
+                .unwrap();
+            let huge = vec![0; 6000 * 6000];
+            let huge = crate::ImageView::gray(&huge, 6000, 6000).unwrap();
+            assert!(detector.detect_image(&huge).is_err());
+        });
+        assert_eq!(
+            vec![FRAMES, DETECTION_SECONDS, FACES_PER_FRAME, ERRORS],
+            *names.0.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Regression testing of detections against recorded fixtures, such as to catch changes to
+//! the model, a backend or the build of a deployment that alter what is detected.
+//!
+//! A fixture is a JSON file naming an image, relative to the fixture, and the faces expected
+//! in it:
+//!
+//! ```json
+//! {
+//!   "image": "sample.jpg",
+//!   "faces": [
+//!     {
+//!       "rect": { "x": 186.0, "y": 333.0, "w": 49.0, "h": 61.0 },
+//!       "landmarks": {
+//!         "right_eye": [206.0, 358.0],
+//!         "left_eye": [225.0, 357.0],
+//!         "nose": [221.0, 371.0],
+//!         "mouth_right": [209.0, 378.0],
+//!         "mouth_left": [225.0, 379.0]
+//!       },
+//!       "confidence": 0.924
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Record fixtures with a detector known to be good, then check every later build against
+//! them:
+//!
+//! ```no_run
+//! # use rusty_yunet::testing::{check_dir, Fixture, Tolerance};
+//! # use rusty_yunet::FaceDetector;
+//! let mut detector = FaceDetector::new();
+//! Fixture::record(&mut detector, "fixtures/door.jpg")?.save("fixtures/door.json")?;
+//!
+//! for report in check_dir(&mut detector, "fixtures", &Tolerance::default())? {
+//!     assert!(report.passed(), "{report}");
+//! }
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::scan::Walker;
+use crate::{Face, FaceDetector, FaceLandmarks, Rect, YuNetError};
+
+/// A face a fixture expects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedFace {
+    pub rect: Rect,
+    pub landmarks: FaceLandmarks,
+    pub confidence: f32,
+}
+
+impl From<&Face> for ExpectedFace {
+    fn from(face: &Face) -> Self {
+        Self {
+            rect: face.rectangle(),
+            landmarks: face.landmarks().clone(),
+            confidence: face.confidence(),
+        }
+    }
+}
+
+/// An image and the faces expected in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Relative to the fixture file in JSON, and as given or resolved against it otherwise.
+    pub image: PathBuf,
+    pub faces: Vec<ExpectedFace>,
+}
+
+impl Fixture {
+    /// Detects the faces of `image` with `detector` as the expected ones.
+    pub fn record(
+        detector: &mut FaceDetector,
+        image: impl AsRef<Path>,
+    ) -> Result<Self, YuNetError> {
+        let image = image.as_ref().to_path_buf();
+        let faces = detector.detect_file(&image)?;
+        Ok(Self {
+            image,
+            faces: faces.iter().map(ExpectedFace::from).collect(),
+        })
+    }
+
+    /// Reads a fixture, resolving its image against the directory of the fixture.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, YuNetError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| YuNetError::Io(e.to_string()))?;
+        let mut fixture: Self =
+            serde_json::from_str(&json).map_err(|e| YuNetError::Conversion {
+                what: format!("fixture {}", path.display()),
+                source: Arc::new(e),
+            })?;
+        if let Some(dir) = path.parent() {
+            fixture.image = dir.join(&fixture.image);
+        }
+        Ok(fixture)
+    }
+
+    /// Writes the fixture as JSON, with the path of its image relative to the fixture if
+    /// it lies under the directory of the fixture, and as given otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), YuNetError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let image = self.image.strip_prefix(dir).unwrap_or(&self.image);
+        let fixture = Self {
+            image: image.to_path_buf(),
+            faces: self.faces.clone(),
+        };
+        let json = serde_json::to_string_pretty(&fixture).expect("fixtures serialize");
+        std::fs::write(path, json + "\n").map_err(|e| YuNetError::Io(e.to_string()))
+    }
+}
+
+/// How far detections may stray from those of a fixture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tolerance {
+    /// How well a detection must overlap an expected face to match it.
+    pub min_iou: f32,
+    /// The mean distance of the landmarks of a match from the expected ones, relative to
+    /// the width of the expected face.
+    pub landmark_error: f32,
+    /// How far the confidence of a match may stray from the expected one.
+    pub confidence: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            min_iou: 0.7,
+            landmark_error: 0.1,
+            confidence: 0.05,
+        }
+    }
+}
+
+/// A difference between the detections and a fixture beyond the tolerance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// No detection matched an expected face.
+    Missing {
+        expected: Rect,
+    },
+    Landmarks {
+        expected: Rect,
+        error: f32,
+    },
+    Confidence {
+        expected: Rect,
+        expected_confidence: f32,
+        confidence: f32,
+    },
+    /// A detection matched no expected face.
+    Unexpected {
+        found: Rect,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let at = |rect: &Rect| format!("({}, {}, {}x{})", rect.x, rect.y, rect.w, rect.h);
+        match self {
+            Self::Missing { expected } => write!(f, "face at {} not detected", at(expected)),
+            Self::Landmarks { expected, error } => write!(
+                f,
+                "landmarks of face at {} off by {:.1}% of its width",
+                at(expected),
+                error * 100.0
+            ),
+            Self::Confidence {
+                expected,
+                expected_confidence,
+                confidence,
+            } => write!(
+                f,
+                "face at {} detected with confidence {confidence:.3}, not {expected_confidence:.3}",
+                at(expected)
+            ),
+            Self::Unexpected { found } => write!(f, "unexpected face at {}", at(found)),
+        }
+    }
+}
+
+/// The outcome of checking a detector against a fixture.
+#[derive(Debug, Clone)]
+pub struct FixtureReport {
+    pub image: PathBuf,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl FixtureReport {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for FixtureReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.image.display())?;
+        if self.passed() {
+            return write!(f, "passed");
+        }
+        write!(f, "{} mismatches", self.mismatches.len())?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Detects the faces of the image of `fixture` with `detector` and compares them with the
+/// expected ones. Fails only if the image can't be read.
+pub fn check(
+    detector: &mut FaceDetector,
+    fixture: &Fixture,
+    tolerance: &Tolerance,
+) -> Result<FixtureReport, YuNetError> {
+    let faces = detector.detect_file(&fixture.image)?;
+    Ok(FixtureReport {
+        image: fixture.image.clone(),
+        mismatches: compare(&faces, &fixture.faces, tolerance),
+    })
+}
+
+/// Checks `detector` against every fixture, every `.json` file, under `dir` and its
+/// subdirectories, in path order. Fails if a fixture or its image can't be read.
+pub fn check_dir(
+    detector: &mut FaceDetector,
+    dir: impl AsRef<Path>,
+    tolerance: &Tolerance,
+) -> Result<Vec<FixtureReport>, YuNetError> {
+    Walker::new(dir.as_ref(), vec!["json".to_owned()], true)
+        .map(|path| {
+            let path = path.map_err(|(_, e)| YuNetError::Io(e.to_string()))?;
+            check(detector, &Fixture::load(path)?, tolerance)
+        })
+        .collect()
+}
+
+fn compare(faces: &[Face], expected: &[ExpectedFace], tolerance: &Tolerance) -> Vec<Mismatch> {
+    let mut matched = vec![false; faces.len()];
+    let mut mismatches = Vec::new();
+    for expected in expected {
+        let best = faces
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !matched[*i])
+            .map(|(i, face)| (i, face.rectangle().iou(&expected.rect)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|&(_, iou)| iou >= tolerance.min_iou);
+        let Some((i, _)) = best else {
+            mismatches.push(Mismatch::Missing {
+                expected: expected.rect,
+            });
+            continue;
+        };
+        matched[i] = true;
+        let face = &faces[i];
+        let error = face
+            .landmarks()
+            .as_array()
+            .iter()
+            .zip(expected.landmarks.as_array())
+            .map(|(found, expected)| found.distance(expected))
+            .sum::<f32>()
+            / 5.0
+            / expected.rect.w.max(1.0);
+        if error > tolerance.landmark_error {
+            mismatches.push(Mismatch::Landmarks {
+                expected: expected.rect,
+                error,
+            });
+        }
+        if (face.confidence() - expected.confidence).abs() > tolerance.confidence {
+            mismatches.push(Mismatch::Confidence {
+                expected: expected.rect,
+                expected_confidence: expected.confidence,
+                confidence: face.confidence(),
+            });
+        }
+    }
+    mismatches.extend(
+        faces
+            .iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(face, _)| Mismatch::Unexpected {
+                found: face.rectangle(),
+            }),
+    );
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_recorded_fixtures() {
+        let mut detector = FaceDetector::new();
+        let reports = check_dir(&mut detector, "testdata", &Tolerance::default()).unwrap();
+        assert!(!reports.is_empty());
+        for report in &reports {
+            assert!(report.passed(), "{report}");
+        }
+
+        // Round trips, and flags a regressed detector.
+        let path =
+            std::env::temp_dir().join(format!("rusty-yunet-fixture-{}.json", std::process::id()));
+        let mut fixture = Fixture::record(&mut detector, "sample.jpg").unwrap();
+        fixture.image = std::fs::canonicalize(&fixture.image).unwrap();
+        fixture.save(&path).unwrap();
+        let mut fixture = Fixture::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(2, fixture.faces.len());
+        fixture.faces[0].rect.x += 30.0;
+        fixture.faces[1].confidence -= 0.2;
+        let report = check(&mut detector, &fixture, &Tolerance::default()).unwrap();
+        assert!(matches!(
+            report.mismatches[..],
+            [
+                Mismatch::Missing { .. },
+                Mismatch::Confidence { .. },
+                Mismatch::Unexpected { .. }
+            ]
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::assoc::greedy_matches;
+use crate::geometry::iou_matrix;
+use crate::Face;
+
+/// Tuning knobs for a [`Tracker`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TrackerConfig {
+    /// How well a face must overlap a track's last position to continue it.
+    pub min_iou: f32,
+    /// Frames a track survives without a matching face before it is dropped.
+    pub max_missed: u64,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            min_iou: 0.3,
+            max_missed: 5,
+        }
+    }
+}
+
+/// A face followed across frames.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Track {
+    id: u64,
+    face: Face,
+    first_frame: u64,
+    last_frame: u64,
+    first_seen: Option<Duration>,
+    last_seen: Option<Duration>,
+    velocity: Option<Vec2>,
+}
+
+impl Track {
+    /// Unique among the tracks of its [`Tracker`].
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The most recent detection of this face.
+    pub fn face(&self) -> &Face {
+        &self.face
+    }
+
+    /// Index of the frame this track started in.
+    pub fn first_frame(&self) -> u64 {
+        self.first_frame
+    }
+
+    /// Index of the last frame this face was detected in.
+    pub fn last_frame(&self) -> u64 {
+        self.last_frame
+    }
+
+    /// Number of frames from the first to the last detection of this face, both included.
+    pub fn age(&self) -> u64 {
+        self.last_frame - self.first_frame + 1
+    }
+
+    /// Time from the first to the last detection of this face, when frames are timestamped.
+    /// Unlike [`age`](Self::age) divided by a frame rate, this holds for variable frame rate
+    /// video too. Zero if the timestamps went backwards, such as with reordered frames.
+    pub fn dwell(&self) -> Option<Duration> {
+        Some(self.last_seen?.saturating_sub(self.first_seen?))
+    }
+
+    /// Movement of the face center, in pixels per second, between its last two detections.
+    /// Only known when frames are timestamped.
+    pub fn velocity(&self) -> Option<Vec2> {
+        self.velocity
+    }
+}
+
+/// Assigns stable IDs to faces across consecutive frames by matching them to the tracks of
+/// the previous frames, most overlapping pairs first.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct Tracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    next_id: u64,
+    frame: u64,
+    /// The first and latest frames given a timestamp, with their indices.
+    first_timestamp: Option<(u64, Duration)>,
+    last_timestamp: Option<(u64, Duration)>,
+    timestamp: Option<Duration>,
+}
+
+impl Tracker {
+    pub fn new(config: TrackerConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Advances to the next frame, returning the track ID of each of its faces in order.
+    /// Faces that match no track start a new one.
+    pub fn update(&mut self, faces: &[Face]) -> Vec<u64> {
+        self.update_at(faces, None)
+    }
+
+    /// Like [`update`](Self::update), for a frame presented at `timestamp`, such as the
+    /// presentation timestamp decoded from a video container.
+    ///
+    /// Frames without a timestamp following timestamped ones are placed at the average
+    /// interval between the timestamps seen so far.
+    pub fn update_at(&mut self, faces: &[Face], timestamp: Option<Duration>) -> Vec<u64> {
+        let frame = self.frame;
+        self.frame += 1;
+        let timestamp = self.frame_timestamp(frame, timestamp);
+        self.timestamp = timestamp;
+
+        let last_seen: Vec<&Face> = self.tracks.iter().map(|t| &t.face).collect();
+        let iou = iou_matrix(&last_seen, faces);
+        let mut ids: Vec<Option<u64>> = vec![None; faces.len()];
+        for (t, f, _) in greedy_matches(&iou, self.config.min_iou) {
+            let track = &mut self.tracks[t];
+            if let (Some(now), Some(then)) = (timestamp, track.last_seen) {
+                let elapsed = now.saturating_sub(then).as_secs_f32();
+                if elapsed > 0.0 {
+                    let moved = faces[f].rectangle().center() - track.face.rectangle().center();
+                    track.velocity = Some(moved / elapsed);
+                }
+            }
+            track.face = faces[f].clone();
+            track.last_frame = frame;
+            track.last_seen = timestamp;
+            ids[f] = Some(track.id);
+        }
+
+        let ids = ids
+            .into_iter()
+            .zip(faces)
+            .map(|(id, face)| {
+                id.unwrap_or_else(|| {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    self.tracks.push(Track {
+                        id,
+                        face: face.clone(),
+                        first_frame: frame,
+                        last_frame: frame,
+                        first_seen: timestamp,
+                        last_seen: timestamp,
+                        velocity: None,
+                    });
+                    id
+                })
+            })
+            .collect();
+        let max_missed = self.config.max_missed;
+        self.tracks
+            .retain(|track| frame - track.last_frame <= max_missed);
+        ids
+    }
+
+    /// Tracks that are still alive, including those whose face went undetected for a few
+    /// frames.
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Index of the next frame passed to [`update`](Self::update).
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The timestamp of the last frame passed to [`update_at`](Self::update_at), as given or
+    /// interpolated.
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    /// The given timestamp of a frame, or one interpolated from those of earlier frames.
+    fn frame_timestamp(&mut self, frame: u64, timestamp: Option<Duration>) -> Option<Duration> {
+        match timestamp {
+            Some(timestamp) => {
+                self.first_timestamp.get_or_insert((frame, timestamp));
+                self.last_timestamp = Some((frame, timestamp));
+                Some(timestamp)
+            }
+            None => {
+                let (first_frame, first) = self.first_timestamp?;
+                let (last_frame, last) = self.last_timestamp?;
+                let interval = match last_frame - first_frame {
+                    0 => 0,
+                    frames => last.saturating_sub(first).as_nanos() / u128::from(frames),
+                };
+                let elapsed = interval.saturating_mul(u128::from(frame - last_frame));
+                last.checked_add(Duration::from_nanos(u64::try_from(elapsed).ok()?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::test_face;
+
+    fn face(x: i32) -> Face {
+        let x = x as f32;
+        test_face(
+            0.9,
+            [x, 10.0, 20.0, 20.0],
+            (200, 100),
+            Some([Vec2::splat(x); 5]),
+        )
+    }
+
+    #[test]
+    fn keeps_ids_across_frames() {
+        let mut tracker = Tracker::new(TrackerConfig {
+            max_missed: 1,
+            ..Default::default()
+        });
+        assert_eq!(vec![0, 1], tracker.update(&[face(0), face(100)]));
+        // Both moved a little, and listed in a different order.
+        assert_eq!(vec![1, 0], tracker.update(&[face(104), face(3)]));
+        // The second face disappears for longer than allowed, then comes back as new.
+        assert_eq!(vec![0], tracker.update(&[face(5)]));
+        assert_eq!(vec![0], tracker.update(&[face(6)]));
+        assert_eq!(vec![0, 2], tracker.update(&[face(7), face(104)]));
+        assert_eq!(0, tracker.tracks()[0].first_frame());
+        assert_eq!(4, tracker.tracks()[0].last_frame());
+        assert_eq!(5, tracker.tracks()[0].age());
+        assert_eq!(None, tracker.tracks()[0].dwell());
+    }
+
+    #[test]
+    fn uses_frame_timestamps() {
+        let mut tracker = Tracker::default();
+        let ms = Duration::from_millis;
+        tracker.update_at(&[face(0)], Some(ms(0)));
+        // A long gap, as in variable frame rate footage.
+        tracker.update_at(&[face(2)], Some(ms(100)));
+        tracker.update_at(&[face(3)], Some(ms(110)));
+        let track = &tracker.tracks()[0];
+        assert_eq!(Some(ms(110)), track.dwell());
+        assert_eq!(Some(Vec2::new(100.0, 0.0)), track.velocity());
+
+        // Missing timestamps continue at the average interval so far.
+        tracker.update_at(&[face(4)], None);
+        assert_eq!(Some(ms(165)), tracker.tracks()[0].dwell());
+
+        // Timestamps going backwards, as with reordered frames, don't panic.
+        let mut tracker = Tracker::default();
+        tracker.update_at(&[face(0)], Some(ms(100)));
+        tracker.update_at(&[face(1)], Some(ms(40)));
+        assert_eq!(Some(Duration::ZERO), tracker.tracks()[0].dwell());
+        tracker.update_at(&[face(2)], None);
+        assert_eq!(Some(ms(40)), tracker.timestamp());
+    }
+}
@@ -0,0 +1,265 @@
+//! An overlay for interactive tuning: the live frame with every candidate face, those the
+//! current thresholds reject greyed out, sliders for the thresholds and a frame rate
+//! readout, rendered into an image for whatever window an application opens.
+//!
+//! The detector runs with thresholds lowered to the floor of the sliders, so that moving
+//! them shows at once which faces they would keep. Once tuned,
+//! [`Viewer::config`] is the configuration to deploy.
+//!
+//! ```no_run
+//! # use rusty_yunet::viewer::Viewer;
+//! # use rusty_yunet::{DetectorConfig, FaceDetector};
+//! # let frames: Vec<(image::RgbImage, Vec<u8>)> = Vec::new();
+//! let mut viewer = Viewer::new(DetectorConfig::default());
+//! let mut detector = FaceDetector::with_config(viewer.detector_config())?;
+//! for (rgb, bgr) in &frames {
+//!     let faces = detector.detect(bgr, rgb.width() as usize, rgb.height() as usize)?;
+//!     let overlay = viewer.render(rgb, &faces, std::time::Instant::now());
+//!     // Show `overlay` in a window titled `viewer.title()`, and pass clicks on it to
+//!     // `viewer.click(x, y)`.
+//! }
+//! # Ok::<(), rusty_yunet::YuNetError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use image::{Rgb, RgbImage};
+
+use crate::drawing::{annotate, fill_rect, Annotation, Palette, Theme};
+use crate::{DetectorConfig, Face, FaceSize};
+
+/// Height of each slider, in pixels.
+const SLIDER_HEIGHT: u32 = 12;
+/// Frames the frame rate is averaged over.
+const FPS_WINDOW: usize = 30;
+
+/// A threshold adjusted from the viewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slider {
+    pub name: &'static str,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Slider {
+    /// Where the value lies between the ends, 0..1.
+    pub fn position(&self) -> f32 {
+        ((self.value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+
+    /// Moves the value to `position` (0..1) between the ends.
+    pub fn set_position(&mut self, position: f32) {
+        self.value = self.min + position.clamp(0.0, 1.0) * (self.max - self.min);
+    }
+}
+
+/// Renders detections with tuning controls; see the [module](self) documentation.
+#[derive(Debug, Clone)]
+pub struct Viewer {
+    config: DetectorConfig,
+    /// The minimum confidence, then the minimum face size as a fraction of the frame.
+    sliders: [Slider; 2],
+    /// When the latest frames were rendered, for the frame rate.
+    frames: VecDeque<Instant>,
+    /// The faces of the latest frame, and how many the thresholds keep.
+    counts: (usize, usize),
+    /// The size of the latest frame rendered, for clicks.
+    dimensions: (u32, u32),
+}
+
+impl Viewer {
+    /// Starts the sliders at the thresholds of `config`.
+    pub fn new(config: DetectorConfig) -> Self {
+        let min_face = match config.min_face_size {
+            Some(FaceSize::Fraction(fraction)) => fraction,
+            _ => 0.0,
+        };
+        Self {
+            sliders: [
+                Slider {
+                    name: "min confidence",
+                    value: config.min_confidence.unwrap_or(0.5),
+                    min: 0.3,
+                    max: 1.0,
+                },
+                Slider {
+                    name: "min face",
+                    value: min_face,
+                    min: 0.0,
+                    max: 0.5,
+                },
+            ],
+            config,
+            frames: VecDeque::new(),
+            counts: (0, 0),
+            dimensions: (0, 0),
+        }
+    }
+
+    /// The configuration to detect with while tuning: the one given, with thresholds at the
+    /// floor of the sliders.
+    pub fn detector_config(&self) -> DetectorConfig {
+        DetectorConfig {
+            min_confidence: Some(self.sliders[0].min),
+            min_face_size: None,
+            ..self.config.clone()
+        }
+    }
+
+    /// The configuration given, with the thresholds of the sliders.
+    pub fn config(&self) -> DetectorConfig {
+        let min_face = self.sliders[1].value;
+        DetectorConfig {
+            min_confidence: Some(self.sliders[0].value),
+            min_face_size: (min_face > 0.0).then_some(FaceSize::Fraction(min_face)),
+            ..self.config.clone()
+        }
+    }
+
+    pub fn sliders(&self) -> &[Slider] {
+        &self.sliders
+    }
+
+    pub fn sliders_mut(&mut self) -> &mut [Slider] {
+        &mut self.sliders
+    }
+
+    /// Whether the thresholds of the sliders keep `face`.
+    pub fn accepts(&self, face: &Face) -> bool {
+        face.confidence() >= self.sliders[0].value
+            && FaceSize::Fraction(self.sliders[1].value).admits(face)
+    }
+
+    /// Draws `faces` onto a copy of `image` with the sliders along its bottom edge, counting
+    /// it as shown at `now` for the frame rate.
+    pub fn render(&mut self, image: &RgbImage, faces: &[Face], now: Instant) -> RgbImage {
+        if self.frames.len() == FPS_WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(now);
+
+        let mut overlay = image.clone();
+        self.dimensions = overlay.dimensions();
+        let annotations: Vec<Annotation> = faces
+            .iter()
+            .map(|face| {
+                let accepted = self.accepts(face);
+                Annotation::from_face(face, overlay.dimensions()).with_id(u64::from(!accepted))
+            })
+            .collect();
+        self.counts = (
+            faces.len(),
+            faces.iter().filter(|f| self.accepts(f)).count(),
+        );
+        let theme = Theme {
+            palette: Palette::ById(vec![Rgb([0, 220, 0]), Rgb([128, 128, 128])]),
+            ..Theme::default()
+        };
+        annotate(&mut overlay, &annotations, &theme);
+
+        let width = overlay.width();
+        for (i, slider) in self.sliders.iter().enumerate() {
+            let y = self.slider_top(i) as i32;
+            let filled = (slider.position() * width as f32).round() as u32;
+            fill_rect(&mut overlay, 0, y, width, SLIDER_HEIGHT, Rgb([40, 40, 40]));
+            fill_rect(
+                &mut overlay,
+                0,
+                y + 3,
+                filled,
+                SLIDER_HEIGHT - 6,
+                Rgb([0, 160, 220]),
+            );
+            fill_rect(
+                &mut overlay,
+                filled as i32 - 1,
+                y,
+                3,
+                SLIDER_HEIGHT,
+                Rgb([255, 255, 255]),
+            );
+        }
+        overlay
+    }
+
+    /// Moves the slider under `(x, y)`, in pixels of the latest frame rendered, to there.
+    /// Returns whether there was one.
+    pub fn click(&mut self, x: u32, y: u32) -> bool {
+        let (width, _) = self.dimensions;
+        let Some(i) = (0..self.sliders.len())
+            .find(|&i| (self.slider_top(i)..self.slider_top(i) + SLIDER_HEIGHT).contains(&y))
+        else {
+            return false;
+        };
+        self.sliders[i].set_position(x as f32 / width.max(1) as f32);
+        true
+    }
+
+    /// The frame rate over the latest frames rendered, once there are two.
+    pub fn fps(&self) -> Option<f32> {
+        let (first, last) = (self.frames.front()?, self.frames.back()?);
+        let elapsed = last.duration_since(*first);
+        (elapsed > Duration::ZERO).then(|| (self.frames.len() - 1) as f32 / elapsed.as_secs_f32())
+    }
+
+    /// A readout of the frame rate, faces and thresholds, such as for a window title.
+    pub fn title(&self) -> String {
+        let fps = self
+            .fps()
+            .map_or_else(|| "-".to_owned(), |fps| format!("{fps:.1}"));
+        let (faces, kept) = self.counts;
+        let [confidence, size] = &self.sliders;
+        format!(
+            "{fps} fps, {kept} of {faces} faces, {} {:.2}, {} {:.0}%",
+            confidence.name,
+            confidence.value,
+            size.name,
+            size.value * 100.0
+        )
+    }
+
+    fn slider_top(&self, i: usize) -> u32 {
+        let (_, height) = self.dimensions;
+        let below = (self.sliders.len() - i) as u32 * SLIDER_HEIGHT;
+        height.saturating_sub(below)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::face;
+    use crate::Rect;
+
+    #[test]
+    fn greys_out_rejected_faces() {
+        let mut viewer = Viewer::new(DetectorConfig {
+            min_confidence: Some(0.8),
+            ..Default::default()
+        });
+        assert_eq!(Some(0.3), viewer.detector_config().min_confidence);
+        let faces = [
+            face(Rect::with_size(100.0, 100.0, 80.0, 80.0), 0.9),
+            face(Rect::with_size(300.0, 100.0, 80.0, 80.0), 0.6),
+        ];
+        let image = RgbImage::new(640, 480);
+        let start = Instant::now();
+        viewer.render(&image, &faces, start);
+        let overlay = viewer.render(&image, &faces, start + Duration::from_millis(50));
+        assert_eq!(&Rgb([0, 220, 0]), overlay.get_pixel(100, 100));
+        assert_eq!(&Rgb([128, 128, 128]), overlay.get_pixel(300, 100));
+        assert_eq!(
+            "20.0 fps, 1 of 2 faces, min confidence 0.80, min face 0%",
+            viewer.title()
+        );
+
+        // Clicking the left end of the top slider lowers the confidence threshold to its floor.
+        assert!(viewer.click(0, 480 - 2 * SLIDER_HEIGHT + 1));
+        assert!(!viewer.click(0, 10));
+        assert_eq!(Some(0.3), viewer.config().min_confidence);
+        viewer.render(&image, &faces, start + Duration::from_millis(100));
+        assert!(viewer.title().contains("2 of 2 faces"));
+    }
+}
@@ -0,0 +1,68 @@
+//! JavaScript bindings for `wasm32-unknown-unknown` builds, to be processed by
+//! `wasm-bindgen`. Images come straight from a canvas' `getImageData`, as RGBA.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{DetectorConfig, FaceDetector, ImageView, YuNetError};
+
+/// Numbers reported per face by [`Detector::detect_rgba`].
+pub const FACE_STRIDE: usize = 15;
+
+/// A [`FaceDetector`] running the native backend.
+#[wasm_bindgen]
+pub struct Detector {
+    detector: FaceDetector,
+    bgr: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Detector {
+    /// Runs the bundled model, or the bytes of one in the format of
+    /// [`NATIVE_MODEL`](crate::NATIVE_MODEL), such as the response of a `fetch`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(model: Option<Vec<u8>>, max_side: Option<usize>) -> Result<Detector, JsError> {
+        let config = DetectorConfig {
+            max_side,
+            ..DetectorConfig::default()
+        };
+        let model = model.as_deref().unwrap_or(crate::NATIVE_MODEL);
+        Ok(Self {
+            detector: FaceDetector::with_native_model(config, model)?,
+            bgr: Vec::new(),
+        })
+    }
+
+    /// Detects faces in tightly packed RGBA pixels. Each face is reported as
+    /// [`FACE_STRIDE`] numbers: its confidence, the x, y, width and height of its box, then
+    /// the x, y pairs of its right eye, left eye, nose, right and left mouth corners.
+    #[wasm_bindgen(js_name = detectRgba)]
+    pub fn detect_rgba(
+        &mut self,
+        rgba: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<f32>, JsError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("wasm_detect", width, height).entered();
+        if width.checked_mul(height).and_then(|n| n.checked_mul(4)) != Some(rgba.len()) {
+            return Err(YuNetError::InvalidImage.into());
+        }
+        self.bgr.clear();
+        self.bgr
+            .extend(rgba.chunks_exact(4).flat_map(|px| [px[2], px[1], px[0]]));
+        let faces = self
+            .detector
+            .detect_image(&ImageView::new(&self.bgr, width, height)?)?;
+
+        let mut out = Vec::with_capacity(faces.len() * FACE_STRIDE);
+        for face in &faces {
+            let rect = face.rectangle();
+            out.extend([face.confidence(), rect.x, rect.y, rect.w, rect.h]);
+            let landmarks = face.landmarks();
+            for point in landmarks.as_array() {
+                out.extend([point.x, point.y]);
+            }
+        }
+        Ok(out)
+    }
+}
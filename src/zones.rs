@@ -0,0 +1,328 @@
+//! People counting: events when tracked faces enter or leave zones of the frame and cross
+//! counting lines, such as the threshold of a doorway.
+//!
+//! Zones and lines are in normalized frame coordinates (0..1, from the top left), so that
+//! they hold at any resolution. A face is where the center of its rectangle is.
+//!
+//! ```
+//! use glam::Vec2;
+//! use rusty_yunet::zones::{CountingLine, Zone, ZoneCounter};
+//! use rusty_yunet::Rect;
+//!
+//! // People walking down the frame cross the line inwards.
+//! let mut counter = ZoneCounter::new()
+//!     .with_zone(Zone::rect("counter", Rect::with_size(0.6, 0.5, 0.4, 0.5)))
+//!     .with_line(CountingLine::new("door", Vec2::new(0.0, 0.3), Vec2::new(1.0, 0.3)));
+//! # let tracker = rusty_yunet::Tracker::new(Default::default());
+//! // After each tracker update:
+//! for event in counter.update(tracker.tracks()) {
+//!     println!("{event:?}");
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Rect, Track};
+
+/// A named area of the frame.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    pub name: String,
+    /// The corners of a polygon, in order, in normalized frame coordinates.
+    pub polygon: Vec<Vec2>,
+}
+
+impl Zone {
+    pub fn new(name: impl Into<String>, polygon: Vec<Vec2>) -> Self {
+        Self {
+            name: name.into(),
+            polygon,
+        }
+    }
+
+    /// A zone covering `rect`, in normalized frame coordinates.
+    pub fn rect(name: impl Into<String>, rect: Rect) -> Self {
+        let (x0, y0, x1, y1) = (rect.x, rect.y, rect.x + rect.w, rect.y + rect.h);
+        Self::new(
+            name,
+            vec![
+                Vec2::new(x0, y0),
+                Vec2::new(x1, y0),
+                Vec2::new(x1, y1),
+                Vec2::new(x0, y1),
+            ],
+        )
+    }
+
+    /// Whether `point` lies within the polygon, by the even-odd rule.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let edges = self.polygon.iter().zip(self.polygon.iter().cycle().skip(1));
+        edges
+            .filter(|(a, b)| {
+                (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+/// Which way a face crossed a [`CountingLine`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Onto the right-hand side of the line, facing from its start to its end on screen:
+    /// below a line drawn left to right, left of one drawn top to bottom.
+    In,
+    /// Back onto the left-hand side.
+    Out,
+}
+
+/// A named segment of the frame that faces are counted crossing.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountingLine {
+    pub name: String,
+    pub from: Vec2,
+    pub to: Vec2,
+}
+
+impl CountingLine {
+    /// A line from `from` to `to`, in normalized frame coordinates.
+    pub fn new(name: impl Into<String>, from: Vec2, to: Vec2) -> Self {
+        Self {
+            name: name.into(),
+            from,
+            to,
+        }
+    }
+
+    /// Which way moving from `start` to `end` crosses the line, if it does.
+    pub fn crossing(&self, start: Vec2, end: Vec2) -> Option<Direction> {
+        let side = |p: Vec2| (self.to - self.from).perp_dot(p - self.from);
+        let (before, after) = (side(start), side(end));
+        // The path must also pass between the ends of the line.
+        let path = |p: Vec2| (end - start).perp_dot(p - start);
+        if path(self.from) * path(self.to) > 0.0 {
+            return None;
+        }
+        match (before > 0.0, after > 0.0) {
+            (false, true) => Some(Direction::In),
+            (true, false) => Some(Direction::Out),
+            _ => None,
+        }
+    }
+}
+
+/// What a [`ZoneCounter::update`] saw happen.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZoneEvent {
+    Entered {
+        zone: String,
+        track: u64,
+    },
+    /// Also when a track inside the zone ends.
+    Exited {
+        zone: String,
+        track: u64,
+    },
+    CrossedLine {
+        line: String,
+        track: u64,
+        direction: Direction,
+    },
+}
+
+/// Follows the tracks of a [`Tracker`](crate::Tracker) through zones and across counting
+/// lines, keeping the counts of each line.
+#[derive(Debug, Clone, Default)]
+pub struct ZoneCounter {
+    zones: Vec<Zone>,
+    lines: Vec<CountingLine>,
+    /// The count of each line, in and out.
+    counts: Vec<(u64, u64)>,
+    /// The last position of each track, and whether it was in each zone.
+    tracks: HashMap<u64, (Vec2, Vec<bool>)>,
+}
+
+impl ZoneCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_zone(mut self, zone: Zone) -> Self {
+        self.zones.push(zone);
+        self
+    }
+
+    pub fn with_line(mut self, line: CountingLine) -> Self {
+        self.lines.push(line);
+        self.counts.push((0, 0));
+        self
+    }
+
+    /// Compares the tracks alive after a frame, such as [`Tracker::tracks`], with those of
+    /// the previous update. Tracks whose face went undetected keep their last position, so
+    /// a face missed for a frame or two doesn't leave and re-enter.
+    ///
+    /// [`Tracker::tracks`]: crate::Tracker::tracks
+    pub fn update(&mut self, tracks: &[Track]) -> Vec<ZoneEvent> {
+        let mut events = Vec::new();
+        let mut alive = HashMap::with_capacity(tracks.len());
+        for track in tracks {
+            let id = track.id();
+            let position = track.face().normalized_rectangle().center();
+            let inside: Vec<bool> = self.zones.iter().map(|z| z.contains(position)).collect();
+            let (previous, was_inside) = match self.tracks.remove(&id) {
+                Some((previous, was_inside)) => (Some(previous), was_inside),
+                None => (None, vec![false; self.zones.len()]),
+            };
+            if let Some(previous) = previous {
+                for (line, counts) in self.lines.iter().zip(&mut self.counts) {
+                    let Some(direction) = line.crossing(previous, position) else {
+                        continue;
+                    };
+                    match direction {
+                        Direction::In => counts.0 += 1,
+                        Direction::Out => counts.1 += 1,
+                    }
+                    events.push(ZoneEvent::CrossedLine {
+                        line: line.name.clone(),
+                        track: id,
+                        direction,
+                    });
+                }
+            }
+            for (zone, (&now, &before)) in self.zones.iter().zip(inside.iter().zip(&was_inside)) {
+                let name = zone.name.clone();
+                match (before, now) {
+                    (false, true) => events.push(ZoneEvent::Entered {
+                        zone: name,
+                        track: id,
+                    }),
+                    (true, false) => events.push(ZoneEvent::Exited {
+                        zone: name,
+                        track: id,
+                    }),
+                    _ => {}
+                }
+            }
+            alive.insert(id, (position, inside));
+        }
+
+        let mut ended: Vec<(u64, Vec<bool>)> = self
+            .tracks
+            .drain()
+            .map(|(id, (_, inside))| (id, inside))
+            .collect();
+        ended.sort_by_key(|&(id, _)| id);
+        for (id, inside) in ended {
+            for (zone, _) in self.zones.iter().zip(inside).filter(|(_, inside)| *inside) {
+                events.push(ZoneEvent::Exited {
+                    zone: zone.name.clone(),
+                    track: id,
+                });
+            }
+        }
+        self.tracks = alive;
+        events
+    }
+
+    /// How many faces crossed the line called `name` (in, out), if there is one.
+    pub fn line_counts(&self, name: &str) -> Option<(u64, u64)> {
+        let i = self.lines.iter().position(|line| line.name == name)?;
+        Some(self.counts[i])
+    }
+
+    /// The tracks in the zone called `name`, in increasing order.
+    pub fn occupants(&self, name: &str) -> Vec<u64> {
+        let Some(i) = self.zones.iter().position(|zone| zone.name == name) else {
+            return Vec::new();
+        };
+        let mut occupants: Vec<u64> = self
+            .tracks
+            .iter()
+            .filter(|(_, (_, inside))| inside[i])
+            .map(|(&id, _)| id)
+            .collect();
+        occupants.sort_unstable();
+        occupants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::face_in;
+    use crate::tracking::{Tracker, TrackerConfig};
+
+    #[test]
+    fn counts_a_walk_through_a_doorway() {
+        let mut tracker = Tracker::new(TrackerConfig {
+            max_missed: 0,
+            ..Default::default()
+        });
+        let mut counter = ZoneCounter::new()
+            .with_zone(Zone::rect("hall", Rect::with_size(0.0, 0.5, 1.0, 0.5)))
+            .with_line(CountingLine::new(
+                "door",
+                Vec2::new(0.0, 0.4),
+                Vec2::new(1.0, 0.4),
+            ));
+        // A face walking down the frame in steps of 10 of 100 pixels, overlapping enough
+        // to keep its track.
+        let at = |y: f32| face_in(Rect::with_size(40.0, y, 20.0, 40.0), 0.9, (100, 100));
+        let mut events = Vec::new();
+        for y in [0.0, 10.0, 20.0, 30.0, 40.0] {
+            tracker.update(&[at(y)]);
+            events.extend(counter.update(tracker.tracks()));
+        }
+        assert_eq!(
+            vec![
+                ZoneEvent::CrossedLine {
+                    line: "door".to_owned(),
+                    track: 0,
+                    direction: Direction::In,
+                },
+                ZoneEvent::Entered {
+                    zone: "hall".to_owned(),
+                    track: 0,
+                },
+            ],
+            events
+        );
+        assert_eq!(Some((1, 0)), counter.line_counts("door"));
+        assert_eq!(vec![0], counter.occupants("hall"));
+
+        tracker.update(&[]);
+        tracker.update(&[]);
+        assert_eq!(
+            vec![ZoneEvent::Exited {
+                zone: "hall".to_owned(),
+                track: 0,
+            }],
+            counter.update(tracker.tracks())
+        );
+        assert!(counter.occupants("hall").is_empty());
+
+        assert!(
+            !Zone::rect("hall", Rect::with_size(0.0, 0.5, 1.0, 0.5)).contains(Vec2::new(0.5, 0.2))
+        );
+        let line = CountingLine::new("door", Vec2::new(0.0, 0.4), Vec2::new(0.5, 0.4));
+        assert_eq!(
+            None,
+            line.crossing(Vec2::new(0.8, 0.3), Vec2::new(0.8, 0.5))
+        );
+        assert_eq!(
+            Some(Direction::Out),
+            line.crossing(Vec2::new(0.2, 0.5), Vec2::new(0.2, 0.3))
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Pairing up two sets of boxes one to one by how much they overlap, such as faces with the
+//! detections of a person or body detector, or with the tracks of a tracker.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::geometry::{iou_matrix, pairwise, Bounded, Rect};
+
+/// Pairs every item of `a` with at most one of `b` and the other way around, most
+/// overlapping pairs first, leaving out pairs whose IoU is below `min_iou`. Returns the
+/// indices of each pair, in `a` then in `b`.
+pub fn associate<A: Bounded, B: Bounded>(a: &[A], b: &[B], min_iou: f32) -> Vec<(usize, usize)> {
+    pairs(&greedy_matches(&iou_matrix(a, b), min_iou))
+}
+
+/// Like [`associate`], scoring pairs with `score` rather than IoU, higher for better pairs.
+pub fn associate_by<A: Bounded, B: Bounded>(
+    a: &[A],
+    b: &[B],
+    min_score: f32,
+    score: impl Fn(&Rect, &Rect) -> f32,
+) -> Vec<(usize, usize)> {
+    pairs(&greedy_matches(&pairwise(a, b, score), min_score))
+}
+
+/// Pairs up the rows and columns of a matrix of scores, such as an
+/// [`iou_matrix`], one to one, highest scores first, leaving out those below `min_score`.
+/// Returns (row, column, score).
+///
+/// Greedy assignment rather than the Hungarian algorithm: it doesn't maximize the total
+/// score, but for boxes of the same objects, one pair rarely has to give way to two others.
+pub fn greedy_matches(scores: &[Vec<f32>], min_score: f32) -> Vec<(usize, usize, f32)> {
+    let mut candidates: Vec<(f32, usize, usize)> = scores
+        .iter()
+        .enumerate()
+        .flat_map(|(a, row)| row.iter().enumerate().map(move |(b, &score)| (score, a, b)))
+        .filter(|&(score, _, _)| score >= min_score)
+        .collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let columns = scores.first().map_or(0, Vec::len);
+    let (mut rows_taken, mut columns_taken) = (vec![false; scores.len()], vec![false; columns]);
+    candidates
+        .into_iter()
+        .filter(|&(_, a, b)| {
+            let free = !rows_taken[a] && !columns_taken[b];
+            if free {
+                (rows_taken[a], columns_taken[b]) = (true, true);
+            }
+            free
+        })
+        .map(|(score, a, b)| (a, b, score))
+        .collect()
+}
+
+fn pairs(matches: &[(usize, usize, f32)]) -> Vec<(usize, usize)> {
+    matches.iter().map(|&(a, b, _)| (a, b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_the_most_overlapping_pairs() {
+        let a = [
+            Rect::with_size(0.0, 0.0, 10.0, 10.0),
+            Rect::with_size(4.0, 0.0, 10.0, 10.0),
+            Rect::with_size(100.0, 0.0, 10.0, 10.0),
+        ];
+        let b = [
+            Rect::with_size(5.0, 0.0, 10.0, 10.0),
+            Rect::with_size(-2.0, 0.0, 10.0, 10.0),
+        ];
+        // The third box of `a` overlaps nothing. Without the second box of `b`, the first
+        // of `a` goes without, as the second overlaps the first of `b` more.
+        assert_eq!(vec![(1, 0), (0, 1)], associate(&a, &b, 0.3));
+        assert_eq!(vec![(1, 0)], associate(&a, &b[..1], 0.3));
+        assert!(associate(&a, &[] as &[Rect], 0.3).is_empty());
+    }
+}
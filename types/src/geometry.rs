@@ -0,0 +1,224 @@
+use alloc::vec::Vec;
+
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub fn new(pos: Vec2, w: f32, h: f32) -> Self {
+        let Vec2 { x, y } = pos;
+        Self { x, y, w, h }
+    }
+    pub fn with_size(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    /// Distance between the centers of two rectangles.
+    pub fn center_distance(&self, other: &Rect) -> f32 {
+        self.center().distance(other.center())
+    }
+
+    pub fn area(&self) -> f32 {
+        self.w.max(0.0) * self.h.max(0.0)
+    }
+
+    /// Area of the overlap of two rectangles.
+    pub fn intersection(&self, other: &Rect) -> f32 {
+        let w = (self.x + self.w).min(other.x + other.w) - self.x.max(other.x);
+        let h = (self.y + self.h).min(other.y + other.h) - self.y.max(other.y);
+        w.max(0.0) * h.max(0.0)
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn bounding(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let w = (self.x + self.w).max(other.x + other.w) - x;
+        let h = (self.y + self.h).max(other.y + other.h) - y;
+        Rect { x, y, w, h }
+    }
+
+    /// Intersection over union of two rectangles, in 0..1.
+    pub fn iou(&self, other: &Rect) -> f32 {
+        let intersection = self.intersection(other);
+        let union = self.area() + other.area() - intersection;
+        if union > 0.0 {
+            intersection / union
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Where a [`CoordinateConvention`] puts its origin, and which way its y axis points.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// The top-left corner, y down, as images and most UI toolkits.
+    #[default]
+    TopLeft,
+    /// The bottom-left corner, y up, as OpenGL window and texture coordinates.
+    BottomLeft,
+    /// The center, y up, as scene graphs with the image centered, such as Bevy's 2D world.
+    Center,
+}
+
+/// What a [`CoordinateConvention`] measures in.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Units {
+    #[default]
+    Pixels,
+    /// Fractions of the frame: 0..1 from a corner, or -1..1 from the center, as normalized
+    /// device coordinates.
+    Normalized,
+}
+
+/// An origin and units to hand faces over in, such as to a renderer with a bottom-left
+/// origin or a UI laid out in fractions of the screen. The default, top-left pixels, is
+/// what faces are reported in.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CoordinateConvention {
+    pub origin: Origin,
+    pub units: Units,
+}
+
+impl CoordinateConvention {
+    pub const fn new(origin: Origin, units: Units) -> Self {
+        Self { origin, units }
+    }
+
+    /// Converts a point from top-left pixel coordinates of an image of the given
+    /// dimensions (width, height).
+    pub fn point(&self, p: Vec2, (width, height): (usize, usize)) -> Vec2 {
+        let size = Vec2::new(width as f32, height as f32);
+        let p = match self.origin {
+            Origin::TopLeft => p,
+            Origin::BottomLeft => Vec2::new(p.x, size.y - p.y),
+            Origin::Center => Vec2::new(p.x - size.x / 2.0, size.y / 2.0 - p.y),
+        };
+        match (self.units, self.origin) {
+            (Units::Pixels, _) => p,
+            (Units::Normalized, Origin::Center) => 2.0 * p / size,
+            (Units::Normalized, _) => p / size,
+        }
+    }
+
+    /// Converts a rectangle like [`point`](Self::point). Its `x` and `y` remain the
+    /// corner with the smallest coordinates, which is the bottom-left one when y points up.
+    pub fn rect(&self, rect: Rect, dimensions: (usize, usize)) -> Rect {
+        let a = self.point(Vec2::new(rect.x, rect.y), dimensions);
+        let b = self.point(Vec2::new(rect.x + rect.w, rect.y + rect.h), dimensions);
+        let min = a.min(b);
+        Rect::new(min, (a.x - b.x).abs(), (a.y - b.y).abs())
+    }
+}
+
+/// Anything occupying a rectangular region of an image.
+pub trait Bounded {
+    fn bounds(&self) -> Rect;
+}
+
+impl Bounded for Rect {
+    fn bounds(&self) -> Rect {
+        *self
+    }
+}
+
+impl<T: Bounded> Bounded for &T {
+    fn bounds(&self) -> Rect {
+        (*self).bounds()
+    }
+}
+
+/// Intersection over union between every item of `a` (rows) and of `b` (columns).
+pub fn iou_matrix<A: Bounded, B: Bounded>(a: &[A], b: &[B]) -> Vec<Vec<f32>> {
+    pairwise(a, b, |a, b| a.iou(b))
+}
+
+/// Distance between the centers of every item of `a` (rows) and of `b` (columns).
+pub fn center_distance_matrix<A: Bounded, B: Bounded>(a: &[A], b: &[B]) -> Vec<Vec<f32>> {
+    pairwise(a, b, |a, b| a.center_distance(b))
+}
+
+pub(crate) fn pairwise<A: Bounded, B: Bounded>(
+    a: &[A],
+    b: &[B],
+    f: impl Fn(&Rect, &Rect) -> f32,
+) -> Vec<Vec<f32>> {
+    let b: Vec<Rect> = b.iter().map(Bounded::bounds).collect();
+    a.iter()
+        .map(|a| {
+            let a = a.bounds();
+            b.iter().map(|b| f(&a, b)).collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn converts_coordinate_conventions() {
+        let rect = Rect::with_size(10.0, 20.0, 30.0, 40.0);
+        let dimensions = (100, 200);
+        let convention = |origin, units| CoordinateConvention::new(origin, units);
+        assert_eq!(rect, CoordinateConvention::default().rect(rect, dimensions));
+        assert_eq!(
+            Rect::with_size(10.0, 140.0, 30.0, 40.0),
+            convention(Origin::BottomLeft, Units::Pixels).rect(rect, dimensions)
+        );
+        assert_eq!(
+            Rect::with_size(-40.0, 40.0, 30.0, 40.0),
+            convention(Origin::Center, Units::Pixels).rect(rect, dimensions)
+        );
+        let ndc = convention(Origin::Center, Units::Normalized);
+        let close = |expected: [f32; 4], r: Rect| {
+            for (expected, actual) in expected.into_iter().zip([r.x, r.y, r.w, r.h]) {
+                assert!((expected - actual).abs() < 1e-6);
+            }
+        };
+        close([-0.8, 0.4, 0.6, 0.4], ndc.rect(rect, dimensions));
+        assert_eq!(
+            Vec2::new(1.0, 1.0),
+            ndc.point(Vec2::new(100.0, 0.0), dimensions)
+        );
+        let uv = convention(Origin::BottomLeft, Units::Normalized);
+        close([0.1, 0.7, 0.3, 0.2], uv.rect(rect, dimensions));
+    }
+
+    #[test]
+    fn pairwise_matrices() {
+        let a = [
+            Rect::with_size(0.0, 0.0, 10.0, 10.0),
+            Rect::with_size(20.0, 0.0, 10.0, 10.0),
+        ];
+        let b = [Rect::with_size(5.0, 0.0, 10.0, 10.0)];
+
+        let iou = iou_matrix(&a, &b);
+        assert_eq!(2, iou.len());
+        assert!((iou[0][0] - 50.0 / 150.0).abs() < 1e-6);
+        assert_eq!(0.0, iou[1][0]);
+
+        let distance = center_distance_matrix(&a, &b);
+        assert_eq!(vec![vec![5.0], vec![15.0]], distance);
+        assert!(iou_matrix::<Rect, Rect>(&[], &b).is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use glam::Vec2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One of the five landmarks YuNet locates, in the order of [`FaceLandmarks::as_array`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Landmark {
+    RightEye,
+    LeftEye,
+    Nose,
+    MouthRight,
+    MouthLeft,
+}
+
+impl Landmark {
+    pub const ALL: [Landmark; 5] = [
+        Landmark::RightEye,
+        Landmark::LeftEye,
+        Landmark::Nose,
+        Landmark::MouthRight,
+        Landmark::MouthLeft,
+    ];
+
+    /// The name of the field of [`FaceLandmarks`] holding it, such as `right_eye`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Landmark::RightEye => "right_eye",
+            Landmark::LeftEye => "left_eye",
+            Landmark::Nose => "nose",
+            Landmark::MouthRight => "mouth_right",
+            Landmark::MouthLeft => "mouth_left",
+        }
+    }
+}
+
+/// NOTE: "right" and "left" are defined in the natural face sense;
+/// a person's right eye is seen on the left side of the screen.
+///
+/// Note that landmarks may occur outside of screen coordinates, as
+/// YuNet can extrapolate their position from what's actually visible.
+///
+/// Built with [`from_array`](Self::from_array) outside this crate, so that more points can
+/// be added without breaking callers.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FaceLandmarks {
+    pub right_eye: Vec2,
+    pub left_eye: Vec2,
+    pub nose: Vec2,
+    pub mouth_right: Vec2,
+    pub mouth_left: Vec2,
+}
+
+impl FaceLandmarks {
+    pub fn get(&self, landmark: Landmark) -> Vec2 {
+        match landmark {
+            Landmark::RightEye => self.right_eye,
+            Landmark::LeftEye => self.left_eye,
+            Landmark::Nose => self.nose,
+            Landmark::MouthRight => self.mouth_right,
+            Landmark::MouthLeft => self.mouth_left,
+        }
+    }
+
+    pub fn get_mut(&mut self, landmark: Landmark) -> &mut Vec2 {
+        match landmark {
+            Landmark::RightEye => &mut self.right_eye,
+            Landmark::LeftEye => &mut self.left_eye,
+            Landmark::Nose => &mut self.nose,
+            Landmark::MouthRight => &mut self.mouth_right,
+            Landmark::MouthLeft => &mut self.mouth_left,
+        }
+    }
+
+    /// Each landmark with its position, in the order of [`Landmark::ALL`].
+    pub fn iter(&self) -> impl Iterator<Item = (Landmark, Vec2)> + '_ {
+        Landmark::ALL
+            .into_iter()
+            .map(|landmark| (landmark, self.get(landmark)))
+    }
+
+    /// Right eye, left eye, nose, right and left mouth corners.
+    pub fn as_array(&self) -> [Vec2; 5] {
+        Landmark::ALL.map(|landmark| self.get(landmark))
+    }
+
+    /// The landmarks at the positions of [`as_array`](Self::as_array).
+    pub fn from_array(points: [Vec2; 5]) -> Self {
+        let [right_eye, left_eye, nose, mouth_right, mouth_left] = points;
+        Self {
+            right_eye,
+            left_eye,
+            nose,
+            mouth_right,
+            mouth_left,
+        }
+    }
+
+    #[deprecated(note = "renamed to `as_array`")]
+    pub fn points(&self) -> [Vec2; 5] {
+        self.as_array()
+    }
+
+    /// Whether each landmark, in the order of [`as_array`](Self::as_array), lies within an
+    /// image of the given dimensions (width, height) rather than being extrapolated beyond
+    /// its edges.
+    pub fn visibility(&self, (width, height): (usize, usize)) -> [bool; 5] {
+        self.as_array()
+            .map(|p| (0.0..width as f32).contains(&p.x) && (0.0..height as f32).contains(&p.y))
+    }
+
+    /// The landmarks, each moved by `f`, such as into another resolution.
+    pub fn map(&self, f: impl Fn(Vec2) -> Vec2) -> Self {
+        Self::from_array(self.as_array().map(f))
+    }
+}
@@ -0,0 +1,32 @@
+//! The face, landmark and rectangle types of [rusty-yunet], with the geometry and box
+//! association built on them, for receivers of detections that don't run the detector,
+//! such as a microcontroller driving an LED display from faces sent over a serial link.
+//!
+//! The crate is `no_std` and needs only `alloc`. Its `std` feature, on by default, gives
+//! glam its math functions; without it, enable glam's `libm` feature in your own manifest:
+//!
+//! ```toml
+//! [dependencies]
+//! rusty-yunet-types = { version = "0.1", default-features = false, features = ["serde"] }
+//! glam = { version = "0.29", default-features = false, features = ["libm"] }
+//! ```
+//!
+//! rusty-yunet re-exports everything here, so its types are these very ones.
+//!
+//! [rusty-yunet]: https://crates.io/crates/rusty-yunet
+
+#![no_std]
+#![warn(clippy::std_instead_of_core, clippy::std_instead_of_alloc)]
+
+extern crate alloc;
+
+pub mod assoc;
+pub mod geometry;
+mod landmarks;
+mod record;
+
+pub use geometry::{
+    center_distance_matrix, iou_matrix, Bounded, CoordinateConvention, Origin, Rect, Units,
+};
+pub use landmarks::{FaceLandmarks, Landmark};
+pub use record::{FaceBox, FaceRecord};
@@ -0,0 +1,108 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{Bounded, Rect};
+use crate::FaceLandmarks;
+
+/// A detected face as rusty-yunet's `Face` serializes it, without the provenance, context
+/// and annotations only the detector's side has, which are skipped when decoding.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceRecord {
+    /// How confident (0..1) YuNet is that the rectangle represents a valid face.
+    pub confidence: f32,
+    /// Location of the face on absolute pixel coordinates. This may fall outside of screen
+    /// coordinates.
+    pub rectangle: Rect,
+    /// The resolution of the image in which this face was detected (width, height).
+    pub detection_dimensions: (usize, usize),
+    pub landmarks: FaceLandmarks,
+}
+
+/// A detected face without landmarks, for uses that only need where faces are, lighter to
+/// produce and to send at high frame rates.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceBox {
+    /// How confident (0..1) YuNet is that the rectangle represents a valid face.
+    pub confidence: f32,
+    /// Location of the face on absolute pixel coordinates. This may fall outside of screen
+    /// coordinates.
+    pub rectangle: Rect,
+    /// The resolution of the image in which this face was detected (width, height).
+    pub detection_dimensions: (usize, usize),
+}
+
+impl FaceRecord {
+    /// The rectangle in 0..1 of the detection dimensions, to place the face in a frame of
+    /// another resolution, such as the pixels of a display.
+    pub fn normalized_rectangle(&self) -> Rect {
+        normalized(self.rectangle, self.detection_dimensions)
+    }
+}
+
+impl FaceBox {
+    /// Like [`FaceRecord::normalized_rectangle`].
+    pub fn normalized_rectangle(&self) -> Rect {
+        normalized(self.rectangle, self.detection_dimensions)
+    }
+}
+
+impl From<&FaceRecord> for FaceBox {
+    fn from(record: &FaceRecord) -> Self {
+        FaceBox {
+            confidence: record.confidence,
+            rectangle: record.rectangle,
+            detection_dimensions: record.detection_dimensions,
+        }
+    }
+}
+
+impl Bounded for FaceRecord {
+    fn bounds(&self) -> Rect {
+        self.rectangle
+    }
+}
+
+impl Bounded for FaceBox {
+    fn bounds(&self) -> Rect {
+        self.rectangle
+    }
+}
+
+fn normalized(rect: Rect, (width, height): (usize, usize)) -> Rect {
+    let (width, height) = (width as f32, height as f32);
+    Rect::with_size(
+        rect.x / width,
+        rect.y / height,
+        rect.w / width,
+        rect.h / height,
+    )
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_serialized_faces() {
+        // As rusty-yunet serializes a face detected with provenance recorded.
+        let json = r#"{
+            "confidence": 0.9,
+            "rectangle": {"x": 10.0, "y": 20.0, "w": 30.0, "h": 40.0},
+            "detection_dimensions": [100, 200],
+            "landmarks": {
+                "right_eye": [15.0, 30.0], "left_eye": [30.0, 30.0], "nose": [22.0, 40.0],
+                "mouth_right": [17.0, 50.0], "mouth_left": [28.0, 50.0]
+            },
+            "provenance": {"model": "yunet"}
+        }"#;
+        let face: FaceRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(Rect::with_size(10.0, 20.0, 30.0, 40.0), face.bounds());
+        assert_eq!(
+            Rect::with_size(0.1, 0.1, 0.3, 0.2),
+            face.normalized_rectangle()
+        );
+        assert_eq!(22.0, face.landmarks.nose.x);
+    }
+}